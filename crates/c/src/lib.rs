@@ -35,6 +35,36 @@ struct C {
     dtor_funcs: HashMap<TypeId, String>,
     type_names: HashMap<TypeId, String>,
     resources: HashMap<TypeId, ResourceInfo>,
+
+    /// Whether any of the code in `src` uses the statically-allocated return
+    /// area for exports.
+    uses_return_area: bool,
+    /// The header and source of each interface, if they're split into their
+    /// own files.
+    interface_srcs: Vec<InterfaceSource>,
+    /// Definitions shared by all interfaces, such as strings and anonymous
+    /// types of primitives, if interfaces are split into their own files.
+    shared_src: Source,
+    /// The stem of the header that each type is defined in, if interfaces are
+    /// split into their own files.
+    type_files: HashMap<TypeId, String>,
+    /// The stem of the header that each anonymous type of primitives, which
+    /// may be used by several interfaces, is defined in if it refers to named
+    /// types.
+    prim_type_files: HashMap<String, String>,
+    /// Definitions emitted into the files named by `Opts::common`.
+    common_src: Source,
+    /// Packages whose interfaces have been emitted into their own files, if
+    /// `Opts::common` is set.
+    packages: HashSet<PackageId>,
+}
+
+struct InterfaceSource {
+    stem: String,
+    /// Stems of the headers of other interfaces whose types are used here.
+    deps: Vec<String>,
+    src: Source,
+    uses_return_area: bool,
 }
 
 #[derive(Default)]
@@ -99,6 +129,31 @@ pub struct Opts {
     /// Configure the autodropping of borrows in exported functions.
     #[cfg_attr(feature = "clap", arg(long, default_value_t = Enabled::default()))]
     pub autodrop_borrows: Enabled,
+
+    /// Name of the function, with the signature of `realloc`, which
+    /// `cabi_realloc` and all other allocations in the generated code use.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub realloc: Option<String>,
+
+    /// Name of the function, with the signature of `free`, which the generated
+    /// code uses to deallocate memory.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub free: Option<String>,
+
+    /// Emit a header and source file for each interface, along with a header
+    /// for the world which includes all of them.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
+    pub split_interfaces: bool,
+
+    /// Emit the bindings of imported interfaces into files shared with other
+    /// worlds, so that the bindings of several worlds can be linked into one
+    /// module. Each package gets its own header and source file with all of
+    /// its interfaces, which only need to be compiled once. Strings and the
+    /// other definitions that all bindings depend upon are named after `NAME`
+    /// rather than the world and emitted into `NAME.h` and `NAME.c`. Implies
+    /// `--split-interfaces`.
+    #[cfg_attr(feature = "clap", arg(long, name = "NAME"))]
+    pub common: Option<String>,
 }
 
 #[cfg(feature = "clap")]
@@ -168,6 +223,18 @@ impl WorldGenerator for C {
                 }
             }
         }
+
+        // Packages shared with other worlds are generated before anything else
+        // so that the anonymous types they use are named after, and defined
+        // in the files of, the package rather than this world.
+        if self.opts.common.is_some() {
+            for (key, _item) in world.imports.iter() {
+                if let WorldKey::Interface(id) = key {
+                    let pkg = resolve.interfaces[*id].package.unwrap();
+                    self.import_package(resolve, pkg);
+                }
+            }
+        }
     }
 
     fn import_interface(
@@ -177,21 +244,14 @@ impl WorldGenerator for C {
         id: InterfaceId,
         _files: &mut Files,
     ) {
-        let wasm_import_module = resolve.name_world_key(name);
-        let mut gen = self.interface(resolve, true, Some(&wasm_import_module));
-        gen.interface = Some((id, name));
-        gen.define_interface_types(id);
-
-        for (i, (_name, func)) in resolve.interfaces[id].functions.iter().enumerate() {
-            if i == 0 {
-                let name = resolve.name_world_key(name);
-                uwriteln!(gen.src.h_fns, "\n// Imported Functions from `{name}`");
-                uwriteln!(gen.src.c_fns, "\n// Imported Functions from `{name}`");
+        if self.opts.common.is_some() {
+            if let WorldKey::Interface(_) = name {
+                let pkg = resolve.interfaces[id].package.unwrap();
+                self.import_package(resolve, pkg);
+                return;
             }
-            gen.import(Some(name), func);
         }
-
-        gen.gen.src.append(&gen.src);
+        self.import_interface_bindings(resolve, name, id);
     }
 
     fn import_funcs(
@@ -213,7 +273,7 @@ impl WorldGenerator for C {
             gen.import(None, func);
         }
 
-        gen.gen.src.append(&gen.src);
+        gen.finish();
     }
 
     fn export_interface(
@@ -236,7 +296,7 @@ impl WorldGenerator for C {
             gen.export(func, Some(name));
         }
 
-        gen.gen.src.append(&gen.src);
+        gen.finish();
         Ok(())
     }
 
@@ -259,7 +319,7 @@ impl WorldGenerator for C {
             gen.export(func, None);
         }
 
-        gen.gen.src.append(&gen.src);
+        gen.finish();
         Ok(())
     }
 
@@ -276,7 +336,7 @@ impl WorldGenerator for C {
             live.add_type_id(resolve, *id);
         }
         gen.define_live_types(live);
-        gen.gen.src.append(&gen.src);
+        gen.finish();
     }
 
     fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
@@ -297,10 +357,25 @@ impl WorldGenerator for C {
            ",
         );
 
+        // The intrinsics and string helpers are shared by all worlds with the
+        // same common files, in which case the string helpers are always
+        // emitted. Otherwise the string helpers are shared by all interfaces
+        // when they're split into their own files.
+        if self.opts.common.is_some() {
+            self.needs_string = true;
+            mem::swap(&mut self.src, &mut self.common_src);
+        }
         self.print_intrinsics();
-
-        if self.needs_string {
+        if self.opts.common.is_none() && self.split_interfaces() {
+            mem::swap(&mut self.src, &mut self.shared_src);
+        }
+        if self.needs_string && self.opts.string_encoding == StringEncoding::CompactUTF16 {
+            self.c_include("<string.h>");
+            self.print_compact_utf16_string_helpers();
+        } else if self.needs_string {
             self.c_include("<string.h>");
+            let snake = self.common_name().to_snake_case();
+            let free = self.free_fn().to_string();
             let (strlen, size) = match self.opts.string_encoding {
                 StringEncoding::UTF8 => (format!("strlen(s)"), 1),
                 StringEncoding::UTF16 => {
@@ -323,13 +398,13 @@ impl WorldGenerator for C {
                     );
                     (format!("{snake}_string_len(s)"), 2)
                 }
-                StringEncoding::CompactUTF16 => unimplemented!(),
+                StringEncoding::CompactUTF16 => unreachable!(),
             };
             let ty = self.char_type();
             let c_string_ty = match self.opts.string_encoding {
                 StringEncoding::UTF8 => "char",
                 StringEncoding::UTF16 => "char16_t",
-                StringEncoding::CompactUTF16 => panic!("Compact UTF16 unsupported"),
+                StringEncoding::CompactUTF16 => unreachable!(),
             };
             uwrite!(
                 self.src.h_helpers,
@@ -362,7 +437,7 @@ impl WorldGenerator for C {
 
                    void {snake}_string_free({snake}_string_t *ret) {{
                        if (ret->len > 0) {{
-                           free(ret->ptr);
+                           {free}(ret->ptr);
                        }}
                        ret->ptr = NULL;
                        ret->len = 0;
@@ -370,52 +445,42 @@ impl WorldGenerator for C {
                ",
             );
         }
+        if self.opts.common.is_some() {
+            mem::swap(&mut self.src, &mut self.common_src);
+        }
+        if self.split_interfaces() {
+            if self.opts.common.is_none() {
+                mem::swap(&mut self.src, &mut self.shared_src);
+            }
+            // The helpers declared in the shared header are defined along
+            // with those of the world.
+            let c_helpers = mem::take(&mut self.shared_src.c_helpers);
+            self.src.c_helpers.push_str(&c_helpers);
+        }
+        let mut unions = wit_bindgen_core::Source::default();
         if self.needs_union_int32_float {
-            uwriteln!(
-                self.src.c_helpers,
-                "\nunion int32_float {{ int32_t a; float b; }};"
-            );
+            uwriteln!(unions, "\nunion int32_float {{ int32_t a; float b; }};");
         }
         if self.needs_union_float_int32 {
-            uwriteln!(
-                self.src.c_helpers,
-                "\nunion float_int32 {{ float a; int32_t b; }};"
-            );
+            uwriteln!(unions, "\nunion float_int32 {{ float a; int32_t b; }};");
         }
         if self.needs_union_int64_double {
-            uwriteln!(
-                self.src.c_helpers,
-                "\nunion int64_double {{ int64_t a; double b; }};"
-            );
+            uwriteln!(unions, "\nunion int64_double {{ int64_t a; double b; }};");
         }
         if self.needs_union_double_int64 {
-            uwriteln!(
-                self.src.c_helpers,
-                "\nunion double_int64 {{ double a; int64_t b; }};"
-            );
+            uwriteln!(unions, "\nunion double_int64 {{ double a; int64_t b; }};");
         }
+        self.src.c_helpers.push_str(&unions);
         let version = env!("CARGO_PKG_VERSION");
         let mut h_str = wit_bindgen_core::Source::default();
-
-        wit_bindgen_core::generated_preamble(&mut h_str, version);
-
-        uwrite!(
-            h_str,
-            "#ifndef __BINDINGS_{0}_H
-            #define __BINDINGS_{0}_H
-            #ifdef __cplusplus
-            extern \"C\" {{",
-            self.world.to_shouty_snake_case(),
-        );
-
-        // Deindent the extern C { declaration
-        h_str.deindent(1);
-        uwriteln!(h_str, "\n#endif\n");
-
-        uwriteln!(h_str, "#include <stdint.h>");
-        uwriteln!(h_str, "#include <stdbool.h>");
-        for include in self.h_includes.iter() {
-            uwriteln!(h_str, "#include {include}");
+        print_header_start(&mut h_str, &self.world.to_shouty_snake_case());
+        if self.split_interfaces() {
+            uwriteln!(h_str, "#include \"{snake}_types.h\"");
+            for src in self.interface_srcs.iter() {
+                uwriteln!(h_str, "#include \"{}.h\"", src.stem);
+            }
+        } else {
+            self.print_shared_defs(&mut h_str);
         }
 
         let mut c_str = wit_bindgen_core::Source::default();
@@ -424,67 +489,15 @@ impl WorldGenerator for C {
         for include in self.c_includes.iter() {
             uwriteln!(c_str, "#include {include}");
         }
-        c_str.push_str(&self.src.c_defs);
-        c_str.push_str(&self.src.c_fns);
 
-        if self.needs_string {
-            uwriteln!(
-                h_str,
-                "
-                typedef struct {snake}_string_t {{\n\
-                  {ty} *ptr;\n\
-                  size_t len;\n\
-                }} {snake}_string_t;",
-                ty = self.char_type(),
-            );
-        }
-        if self.src.h_defs.len() > 0 {
-            h_str.push_str(&self.src.h_defs);
-        }
-
-        h_str.push_str(&self.src.h_fns);
-
-        if !self.opts.no_helpers && self.src.h_helpers.len() > 0 {
-            uwriteln!(h_str, "\n// Helper Functions");
-            h_str.push_str(&self.src.h_helpers);
-            h_str.push_str("\n");
-        }
-
-        if !self.opts.no_helpers && self.src.c_helpers.len() > 0 {
-            uwriteln!(c_str, "\n// Helper Functions");
-            c_str.push_str(self.src.c_helpers.as_mut_string());
-        }
-
-        uwriteln!(c_str, "\n// Component Adapters");
-
-        // Declare a statically-allocated return area, if needed. We only do
-        // this for export bindings, because import bindings allocate their
-        // return-area on the stack.
-        if self.return_pointer_area_size > 0 {
-            // Automatic indentation avoided due to `extern "C" {` declaration
-            uwrite!(
-                c_str,
-                "
-                __attribute__((__aligned__({})))
-                static uint8_t RET_AREA[{}];
-                ",
-                self.return_pointer_area_align,
-                self.return_pointer_area_size,
-            );
-        }
-        c_str.push_str(&self.src.c_adapters);
-
-        uwriteln!(
-            h_str,
-            "
-            #ifdef __cplusplus
-            }}
-            #endif
-            #endif"
-        );
+        self.print_source(&mut h_str, &mut c_str, &self.src, self.uses_return_area);
+        print_header_end(&mut h_str);
 
         files.push(&format!("{snake}.h"), h_str.as_bytes());
         files.push(&format!("{snake}.c"), c_str.as_bytes());
+        if self.split_interfaces() {
+            self.print_interface_files(&unions, files);
+        }
         if !self.opts.no_object_file {
             files.push(
                 &format!("{snake}_component_type.o",),
@@ -510,6 +523,54 @@ impl WorldGenerator for C {
 }
 
 impl C {
+    fn import_interface_bindings(&mut self, resolve: &Resolve, name: &WorldKey, id: InterfaceId) {
+        let wasm_import_module = resolve.name_world_key(name);
+        let mut gen = self.interface(resolve, true, Some(&wasm_import_module));
+        gen.interface = Some((id, name));
+        gen.define_interface_types(id);
+
+        for (i, (_name, func)) in resolve.interfaces[id].functions.iter().enumerate() {
+            if i == 0 {
+                let name = resolve.name_world_key(name);
+                uwriteln!(gen.src.h_fns, "\n// Imported Functions from `{name}`");
+                uwriteln!(gen.src.c_fns, "\n// Imported Functions from `{name}`");
+            }
+            gen.import(Some(name), func);
+        }
+
+        gen.finish();
+    }
+
+    /// Generates the bindings of every interface in `pkg`, whether or not the
+    /// world imports it, so that they're the same for every world sharing the
+    /// files of the package. The packages it depends upon are generated first.
+    fn import_package(&mut self, resolve: &Resolve, pkg: PackageId) {
+        if !self.packages.insert(pkg) {
+            return;
+        }
+        let interfaces = resolve
+            .interfaces
+            .iter()
+            .filter(|(_, iface)| iface.package == Some(pkg) && iface.name.is_some())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for id in interfaces.iter() {
+            let mut live = LiveTypes::default();
+            live.add_interface(resolve, *id);
+            for ty in live.iter() {
+                if let TypeOwner::Interface(owner) = resolve.types[ty].owner {
+                    let dep = resolve.interfaces[owner].package.unwrap();
+                    if dep != pkg {
+                        self.import_package(resolve, dep);
+                    }
+                }
+            }
+        }
+        for id in interfaces {
+            self.import_interface_bindings(resolve, &WorldKey::Interface(id), id);
+        }
+    }
+
     fn interface<'a>(
         &'a mut self,
         resolve: &'a Resolve,
@@ -523,6 +584,7 @@ impl C {
             interface: None,
             in_import,
             wasm_import_module,
+            uses_return_area: false,
         }
     }
 
@@ -534,14 +596,272 @@ impl C {
         self.c_includes.push(s.to_string());
     }
 
+    /// Prints the includes and definitions that all bindings depend upon,
+    /// such as the string type.
+    fn print_shared_defs(&self, h_str: &mut wit_bindgen_core::Source) {
+        let snake = self.common_name().to_snake_case();
+        uwriteln!(h_str, "#include <stddef.h>");
+        uwriteln!(h_str, "#include <stdint.h>");
+        uwriteln!(h_str, "#include <stdbool.h>");
+        for include in self.h_includes.iter() {
+            uwriteln!(h_str, "#include {include}");
+        }
+
+        if self.opts.realloc.is_some() || self.opts.free.is_some() {
+            uwriteln!(
+                h_str,
+                "\n// Allocator functions used by the generated code, which are defined\n\
+                 // by the application."
+            );
+            if let Some(realloc) = &self.opts.realloc {
+                uwriteln!(h_str, "extern void *{realloc}(void *ptr, size_t size);");
+            }
+            if let Some(free) = &self.opts.free {
+                uwriteln!(h_str, "extern void {free}(void *ptr);");
+            }
+        }
+
+        if self.needs_string && self.opts.string_encoding == StringEncoding::CompactUTF16 {
+            uwriteln!(
+                h_str,
+                "
+                // A `latin1+utf16` string: `ptr` holds Latin-1 bytes unless
+                // `len` is tagged with `{shouty}_STRING_UTF16_TAG`, in which
+                // case it holds `len` UTF-16 code units once the tag is removed.
+                typedef struct {snake}_string_t {{\n\
+                  uint8_t *ptr;\n\
+                  size_t len;\n\
+                }} {snake}_string_t;",
+                shouty = self.common_name().to_shouty_snake_case(),
+            );
+        } else if self.needs_string {
+            uwriteln!(
+                h_str,
+                "
+                typedef struct {snake}_string_t {{\n\
+                  {ty} *ptr;\n\
+                  size_t len;\n\
+                }} {snake}_string_t;",
+                ty = self.char_type(),
+            );
+        }
+    }
+
+    /// Prints the definitions and functions in `src` into the header `h_str`
+    /// and the source file `c_str`.
+    fn print_source(
+        &self,
+        h_str: &mut wit_bindgen_core::Source,
+        c_str: &mut wit_bindgen_core::Source,
+        src: &Source,
+        uses_return_area: bool,
+    ) {
+        c_str.push_str(&src.c_defs);
+        c_str.push_str(&src.c_fns);
+
+        if src.h_defs.len() > 0 {
+            h_str.push_str(&src.h_defs);
+        }
+
+        h_str.push_str(&src.h_fns);
+
+        if !self.opts.no_helpers && src.h_helpers.len() > 0 {
+            uwriteln!(h_str, "\n// Helper Functions");
+            h_str.push_str(&src.h_helpers);
+            h_str.push_str("\n");
+        }
+
+        if !self.opts.no_helpers && src.c_helpers.len() > 0 {
+            uwriteln!(c_str, "\n// Helper Functions");
+            c_str.push_str(&src.c_helpers);
+        }
+
+        uwriteln!(c_str, "\n// Component Adapters");
+
+        // Declare a statically-allocated return area, if needed. We only do
+        // this for export bindings, because import bindings allocate their
+        // return-area on the stack.
+        if uses_return_area {
+            // Automatic indentation avoided due to `extern "C" {` declaration
+            uwrite!(
+                c_str,
+                "
+                __attribute__((__aligned__({})))
+                static uint8_t RET_AREA[{}];
+                ",
+                self.return_pointer_area_align,
+                self.return_pointer_area_size,
+            );
+        }
+        c_str.push_str(&src.c_adapters);
+    }
+
+    /// Emits the header shared by all interfaces along with the header and
+    /// source file of each interface, when they're split into their own files.
+    fn print_interface_files(&mut self, unions: &str, files: &mut Files) {
+        let version = env!("CARGO_PKG_VERSION");
+        let snake = self.world.to_snake_case();
+
+        if let Some(common) = &self.opts.common {
+            let common = common.to_snake_case();
+            let mut h_str = wit_bindgen_core::Source::default();
+            print_header_start(&mut h_str, &common.to_shouty_snake_case());
+            self.print_shared_defs(&mut h_str);
+            let mut c_str = wit_bindgen_core::Source::default();
+            wit_bindgen_core::generated_preamble(&mut c_str, version);
+            uwriteln!(c_str, "#include \"{common}.h\"");
+            for include in self.c_includes.iter() {
+                uwriteln!(c_str, "#include {include}");
+            }
+            self.print_source(&mut h_str, &mut c_str, &self.common_src, false);
+            print_header_end(&mut h_str);
+            files.push(&format!("{common}.h"), h_str.as_bytes());
+            files.push(&format!("{common}.c"), c_str.as_bytes());
+        }
+
+        let mut h_str = wit_bindgen_core::Source::default();
+        print_header_start(
+            &mut h_str,
+            &format!("{}_TYPES", self.world.to_shouty_snake_case()),
+        );
+        match &self.opts.common {
+            Some(common) => uwriteln!(h_str, "#include \"{}.h\"", common.to_snake_case()),
+            None => self.print_shared_defs(&mut h_str),
+        }
+        h_str.push_str(&self.shared_src.h_defs);
+        if !self.opts.no_helpers && !self.shared_src.h_helpers.is_empty() {
+            uwriteln!(h_str, "\n// Helper Functions");
+            h_str.push_str(&self.shared_src.h_helpers);
+            h_str.push_str("\n");
+        }
+        print_header_end(&mut h_str);
+        files.push(&format!("{snake}_types.h"), h_str.as_bytes());
+
+        for mut iface in mem::take(&mut self.interface_srcs) {
+            let stem = &iface.stem;
+            let mut h_str = wit_bindgen_core::Source::default();
+            print_header_start(&mut h_str, &stem.to_shouty_snake_case());
+            for dep in iface.deps.iter() {
+                uwriteln!(h_str, "#include \"{dep}.h\"");
+            }
+
+            let mut c_str = wit_bindgen_core::Source::default();
+            wit_bindgen_core::generated_preamble(&mut c_str, version);
+            uwriteln!(c_str, "#include \"{stem}.h\"");
+            for include in self.c_includes.iter() {
+                uwriteln!(c_str, "#include {include}");
+            }
+
+            iface.src.c_helpers.push_str(unions);
+            self.print_source(&mut h_str, &mut c_str, &iface.src, iface.uses_return_area);
+            print_header_end(&mut h_str);
+
+            files.push(&format!("{stem}.h"), h_str.as_bytes());
+            files.push(&format!("{stem}.c"), c_str.as_bytes());
+        }
+    }
+
     fn char_type(&self) -> &'static str {
         match self.opts.string_encoding {
             StringEncoding::UTF8 => "uint8_t",
             StringEncoding::UTF16 => "uint16_t",
-            StringEncoding::CompactUTF16 => panic!("Compact UTF16 unsupported"),
+            // Latin-1 bytes or UTF-16 code units, depending on the tag in the
+            // length of the string.
+            StringEncoding::CompactUTF16 => "uint8_t",
         }
     }
 
+    /// Prints the helpers for strings using the `latin1+utf16` encoding,
+    /// where the length of a string is tagged when its contents are UTF-16.
+    fn print_compact_utf16_string_helpers(&mut self) {
+        let snake = self.common_name().to_snake_case();
+        let shouty = self.common_name().to_shouty_snake_case();
+        let free = self.free_fn().to_string();
+        self.h_include("<uchar.h>");
+        uwrite!(
+            self.src.h_helpers,
+            "
+               // Set in the `len` of a string whose contents are UTF-16 code
+               // units rather than Latin-1 bytes.
+               #define {shouty}_STRING_UTF16_TAG ((size_t) 1 << 31)
+
+               // Returns whether the contents of `s` are UTF-16 code units.
+               bool {snake}_string_is_utf16(const {snake}_string_t *s);
+
+               // Returns the number of code units in `s`, with the tag removed.
+               size_t {snake}_string_code_units(const {snake}_string_t *s);
+
+               // Transfers ownership of the Latin-1 string `s` into the string `ret`
+               void {snake}_string_set_latin1({snake}_string_t *ret, const char *s);
+
+               // Transfers ownership of the UTF-16 string `s` into the string `ret`
+               void {snake}_string_set_utf16({snake}_string_t *ret, const char16_t *s);
+
+               // Creates a copy of the input nul-terminated Latin-1 string `s`
+               // and stores it into the component model string `ret`.
+               void {snake}_string_dup_latin1({snake}_string_t *ret, const char *s);
+
+               // Creates a copy of the input nul-terminated UTF-16 string `s`
+               // and stores it into the component model string `ret`.
+               void {snake}_string_dup_utf16({snake}_string_t *ret, const char16_t *s);
+
+               // Deallocates the string pointed to by `ret`, deallocating
+               // the memory behind the string.
+               void {snake}_string_free({snake}_string_t *ret);\
+           ",
+        );
+        uwrite!(
+            self.src.c_helpers,
+            "
+               static size_t {snake}_utf16_len(const char16_t *s) {{
+                   const char16_t *c = s;
+                   for (; *c; ++c);
+                   return c - s;
+               }}
+
+               bool {snake}_string_is_utf16(const {snake}_string_t *s) {{
+                   return (s->len & {shouty}_STRING_UTF16_TAG) != 0;
+               }}
+
+               size_t {snake}_string_code_units(const {snake}_string_t *s) {{
+                   return s->len & ~{shouty}_STRING_UTF16_TAG;
+               }}
+
+               void {snake}_string_set_latin1({snake}_string_t *ret, const char *s) {{
+                   ret->ptr = (uint8_t*) s;
+                   ret->len = strlen(s);
+               }}
+
+               void {snake}_string_set_utf16({snake}_string_t *ret, const char16_t *s) {{
+                   ret->ptr = (uint8_t*) s;
+                   ret->len = {snake}_utf16_len(s) | {shouty}_STRING_UTF16_TAG;
+               }}
+
+               void {snake}_string_dup_latin1({snake}_string_t *ret, const char *s) {{
+                   size_t len = strlen(s);
+                   ret->ptr = (uint8_t*) cabi_realloc(NULL, 0, 2, len);
+                   memcpy(ret->ptr, s, len);
+                   ret->len = len;
+               }}
+
+               void {snake}_string_dup_utf16({snake}_string_t *ret, const char16_t *s) {{
+                   size_t len = {snake}_utf16_len(s);
+                   ret->ptr = (uint8_t*) cabi_realloc(NULL, 0, 2, len * 2);
+                   memcpy(ret->ptr, s, len * 2);
+                   ret->len = len | {shouty}_STRING_UTF16_TAG;
+               }}
+
+               void {snake}_string_free({snake}_string_t *ret) {{
+                   if ({snake}_string_code_units(ret) > 0) {{
+                       {free}(ret->ptr);
+                   }}
+                   ret->ptr = NULL;
+                   ret->len = 0;
+               }}
+           ",
+        );
+    }
+
     fn type_name(&mut self, ty: &Type) -> String {
         let mut name = String::new();
         self.push_type_name(ty, &mut name);
@@ -563,7 +883,7 @@ impl C {
             Type::F32 => dst.push_str("float"),
             Type::F64 => dst.push_str("double"),
             Type::String => {
-                dst.push_str(&self.world.to_snake_case());
+                dst.push_str(&self.common_name().to_snake_case());
                 dst.push_str("_");
                 dst.push_str("string_t");
                 self.needs_string = true;
@@ -792,6 +1112,35 @@ pub fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
     }
 }
 
+fn print_header_start(h_str: &mut wit_bindgen_core::Source, guard: &str) {
+    let version = env!("CARGO_PKG_VERSION");
+    wit_bindgen_core::generated_preamble(h_str, version);
+
+    uwrite!(
+        h_str,
+        "#ifndef __BINDINGS_{0}_H
+        #define __BINDINGS_{0}_H
+        #ifdef __cplusplus
+        extern \"C\" {{",
+        guard,
+    );
+
+    // Deindent the extern C { declaration
+    h_str.deindent(1);
+    uwriteln!(h_str, "\n#endif\n");
+}
+
+fn print_header_end(h_str: &mut wit_bindgen_core::Source) {
+    uwriteln!(
+        h_str,
+        "
+        #ifdef __cplusplus
+        }}
+        #endif
+        #endif"
+    );
+}
+
 pub fn owner_namespace<'a>(
     interface: Option<(InterfaceId, &'a WorldKey)>,
     in_import: bool,
@@ -847,33 +1196,39 @@ fn interface_identifier(
                 ns.push_str("exports_");
             }
             let iface = &resolve.interfaces[*id];
-            let pkg = &resolve.packages[iface.package.unwrap()];
-            ns.push_str(&pkg.name.namespace.to_snake_case());
-            ns.push_str("_");
-            ns.push_str(&pkg.name.name.to_snake_case());
+            ns.push_str(&package_identifier(resolve, iface.package.unwrap()));
             ns.push_str("_");
-            let pkg_has_multiple_versions = resolve.packages.iter().any(|(_, p)| {
-                p.name.namespace == pkg.name.namespace
-                    && p.name.name == pkg.name.name
-                    && p.name.version != pkg.name.version
-            });
-            if pkg_has_multiple_versions {
-                if let Some(version) = &pkg.name.version {
-                    let version = version
-                        .to_string()
-                        .replace('.', "_")
-                        .replace('-', "_")
-                        .replace('+', "_");
-                    ns.push_str(&version);
-                    ns.push_str("_");
-                }
-            }
             ns.push_str(&iface.name.as_ref().unwrap().to_snake_case());
             ns
         }
     }
 }
 
+fn package_identifier(resolve: &Resolve, id: PackageId) -> String {
+    let pkg = &resolve.packages[id];
+    let mut ns = String::new();
+    ns.push_str(&pkg.name.namespace.to_snake_case());
+    ns.push_str("_");
+    ns.push_str(&pkg.name.name.to_snake_case());
+    let pkg_has_multiple_versions = resolve.packages.iter().any(|(_, p)| {
+        p.name.namespace == pkg.name.namespace
+            && p.name.name == pkg.name.name
+            && p.name.version != pkg.name.version
+    });
+    if pkg_has_multiple_versions {
+        if let Some(version) = &pkg.name.version {
+            let version = version
+                .to_string()
+                .replace('.', "_")
+                .replace('-', "_")
+                .replace('+', "_");
+            ns.push_str("_");
+            ns.push_str(&version);
+        }
+    }
+    ns
+}
+
 pub fn c_func_name(
     in_import: bool,
     resolve: &Resolve,
@@ -904,6 +1259,7 @@ struct InterfaceGenerator<'a> {
     resolve: &'a Resolve,
     interface: Option<(InterfaceId, &'a WorldKey)>,
     wasm_import_module: Option<&'a str>,
+    uses_return_area: bool,
 }
 
 impl C {
@@ -912,18 +1268,37 @@ impl C {
         // overridden from some other symbol.
         self.src.c_fns("\n// Canonical ABI intrinsics");
         self.src.c_fns("\n");
-        self.src.c_fns(
+        self.src.c_fns(&format!(
             r#"
                 __attribute__((__weak__, __export_name__("cabi_realloc")))
-                void *cabi_realloc(void *ptr, size_t old_size, size_t align, size_t new_size) {
+                void *cabi_realloc(void *ptr, size_t old_size, size_t align, size_t new_size) {{
                     (void) old_size;
                     if (new_size == 0) return (void*) align;
-                    void *ret = realloc(ptr, new_size);
+                    void *ret = {realloc}(ptr, new_size);
                     if (!ret) abort();
                     return ret;
-                }
+                }}
             "#,
-        );
+            realloc = self.realloc_fn(),
+        ));
+    }
+
+    fn split_interfaces(&self) -> bool {
+        self.opts.split_interfaces || self.opts.common.is_some()
+    }
+
+    /// Returns the name that strings and the other definitions which all
+    /// bindings depend upon are prefixed with.
+    fn common_name(&self) -> &str {
+        self.opts.common.as_deref().unwrap_or(&self.world)
+    }
+
+    fn realloc_fn(&self) -> &str {
+        self.opts.realloc.as_deref().unwrap_or("realloc")
+    }
+
+    fn free_fn(&self) -> &str {
+        self.opts.free.as_deref().unwrap_or("free")
     }
 }
 
@@ -1047,12 +1422,22 @@ extern void {ns}_{snake}_drop_own({own} handle);
         };
 
         let drop_fn = format!("__wasm_import_{ns}_{snake}_drop");
-
-        self.src.c_helpers(&format!(
+        let drop_fn_decl = format!(
             r#"
 __attribute__((__import_module__("{import_module}"), __import_name__("[resource-drop]{name}")))
 extern void {drop_fn}(int32_t handle);
+"#
+        );
 
+        // Exports which autodrop borrows call the intrinsic directly, which
+        // must be declared in the header if they're in another source file.
+        if self.gen.split_interfaces() && self.in_import && self.autodrop_enabled() {
+            self.src.h_helpers(&drop_fn_decl);
+        } else {
+            self.src.c_helpers(&drop_fn_decl);
+        }
+        self.src.c_helpers(&format!(
+            r#"
 void {ns}_{snake}_drop_own({own} handle) {{
     {drop_fn}(handle.__handle);
 }}
@@ -1338,6 +1723,14 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs) {
         let _ = (id, name, ty, docs);
     }
+
+    fn type_future(&mut self, _id: TypeId, _name: &str, _ty: &Option<Type>, _docs: &Docs) {
+        unreachable!("`future` types are rejected by `WorldGenerator::generate`")
+    }
+
+    fn type_stream(&mut self, _id: TypeId, _name: &str, _stream: &Stream, _docs: &Docs) {
+        unreachable!("`stream` types are rejected by `WorldGenerator::generate`")
+    }
 }
 
 pub enum CTypeNameInfo<'a> {
@@ -1379,6 +1772,7 @@ impl InterfaceGenerator<'_> {
     }
 
     fn define_live_types(&mut self, live: LiveTypes) {
+        let stem = self.file_stem();
         for ty in live.iter() {
             if self.gen.type_names.contains_key(&ty) {
                 continue;
@@ -1390,13 +1784,20 @@ impl InterfaceGenerator<'_> {
                     let typedef_name = format!("{}_{encoded}_t", self.owner_namespace(ty));
                     let prev = self.gen.type_names.insert(ty, typedef_name.clone());
                     assert!(prev.is_none());
+                    if let Some(stem) = &stem {
+                        self.gen.type_files.insert(ty, stem.clone());
+                    }
 
                     self.define_type(name, ty)
                 }
 
                 CTypeNameInfo::Anonymous { is_prim } => {
                     let (defined, name) = if is_prim {
-                        let namespace = self.gen.world.to_snake_case();
+                        // Those shared with other worlds are named after their
+                        // package instead.
+                        let namespace = self
+                            .package_stem()
+                            .unwrap_or_else(|| self.gen.world.to_snake_case());
                         let name = format!("{namespace}_{encoded}_t");
                         let new_prim = self.gen.prim_names.insert(name.clone());
                         (!new_prim, name)
@@ -1405,13 +1806,39 @@ impl InterfaceGenerator<'_> {
                         (false, format!("{namespace}_{encoded}_t"))
                     };
 
-                    let prev = self.gen.type_names.insert(ty, name);
+                    let prev = self.gen.type_names.insert(ty, name.clone());
                     assert!(prev.is_none());
 
+                    // Anonymous types of primitives are named after the world
+                    // rather than an interface, so when interfaces are split
+                    // into their own files these are defined once in a header
+                    // shared by all of them. Those which refer to named types
+                    // are instead defined by the first interface using them.
+                    let shared = is_prim
+                        && !self.refers_to_interface_types(ty)
+                        && self.package_stem().is_none();
+                    if let (false, Some(stem)) = (shared, &stem) {
+                        let file = if is_prim {
+                            let file = self.gen.prim_type_files.entry(name);
+                            file.or_insert_with(|| stem.clone()).clone()
+                        } else {
+                            stem.clone()
+                        };
+                        self.gen.type_files.insert(ty, file);
+                    }
+
                     if defined {
                         continue;
                     }
 
+                    if shared && self.gen.split_interfaces() {
+                        let src = mem::replace(&mut self.src, mem::take(&mut self.gen.shared_src));
+                        self.define_anonymous_type(ty);
+                        self.define_dtor(ty);
+                        self.gen.shared_src = mem::replace(&mut self.src, src);
+                        continue;
+                    }
+
                     self.define_anonymous_type(ty)
                 }
             }
@@ -1544,7 +1971,8 @@ impl InterfaceGenerator<'_> {
                     .c_helpers("for (size_t i = 0; i < list_len; i++) {\n");
                 self.free(t, "&list_ptr[i]");
                 self.src.c_helpers("}\n");
-                uwriteln!(self.src.c_helpers, "free(list_ptr);");
+                let free = self.gen.free_fn();
+                uwriteln!(self.src.c_helpers, "{free}(list_ptr);");
                 uwriteln!(self.src.c_helpers, "}}");
             }
 
@@ -1604,7 +2032,7 @@ impl InterfaceGenerator<'_> {
                 }
             }
             Type::String => {
-                let snake = self.gen.world.to_snake_case();
+                let snake = self.gen.common_name().to_snake_case();
                 self.src
                     .c_helpers(&format!("{snake}_string_free({expr});\n"));
             }
@@ -1972,6 +2400,115 @@ impl InterfaceGenerator<'_> {
         self.print_typedef_target(id);
     }
 
+    /// Returns whether the anonymous type `id` refers to any named types, such
+    /// as `list<t>` where `t` is a tuple of primitives, or to anonymous types
+    /// defined in the files of an interface.
+    fn refers_to_interface_types(&self, id: TypeId) -> bool {
+        let refers = |ty: &Type| match ty {
+            Type::Id(id) => {
+                self.resolve.types[*id].name.is_some()
+                    || self.gen.type_files.contains_key(id)
+                    || self.refers_to_interface_types(*id)
+            }
+            _ => false,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::List(ty) | TypeDefKind::Option(ty) | TypeDefKind::Type(ty) => refers(ty),
+            TypeDefKind::Tuple(tuple) => tuple.types.iter().any(refers),
+            _ => false,
+        }
+    }
+
+    /// Returns the stem of the header and source file shared with other worlds
+    /// which this interface is emitted into, if `Opts::common` is set and it's
+    /// an imported interface of a package.
+    fn package_stem(&self) -> Option<String> {
+        match self.interface {
+            Some((id, WorldKey::Interface(_)))
+                if self.in_import && self.gen.opts.common.is_some() =>
+            {
+                let pkg = self.resolve.interfaces[id].package.unwrap();
+                Some(package_identifier(self.resolve, pkg))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the stem of the header and source file which this interface is
+    /// emitted into, if interfaces are split into their own files.
+    fn file_stem(&self) -> Option<String> {
+        if let Some(stem) = self.package_stem() {
+            return Some(stem);
+        }
+        match self.interface {
+            Some((_, key)) if self.gen.split_interfaces() => {
+                let mut stem = interface_identifier(
+                    key,
+                    self.resolve,
+                    !self.in_import,
+                    &self.gen.renamed_interfaces,
+                );
+                // Avoid clobbering the files of the world itself.
+                let world = self.gen.world.to_snake_case();
+                let common = self.gen.common_name().to_snake_case();
+                if stem == world || stem == format!("{world}_types") || stem == common {
+                    stem.push_str("_interface");
+                }
+                Some(stem)
+            }
+            _ => None,
+        }
+    }
+
+    /// Adds everything generated here to the bindings of the world, or to the
+    /// files of this interface if interfaces are split into their own files.
+    fn finish(self) {
+        let stem = match self.file_stem() {
+            Some(stem) => stem,
+            None => {
+                self.gen.src.append(&self.src);
+                self.gen.uses_return_area |= self.uses_return_area;
+                return;
+            }
+        };
+
+        // Include the headers of the interfaces that the types used here are
+        // defined in. Those shared with other worlds can't depend on anything
+        // specific to this world.
+        let mut deps = match self.package_stem() {
+            Some(_) => vec![self.gen.common_name().to_snake_case()],
+            None => vec![format!("{}_types", self.gen.world.to_snake_case())],
+        };
+        let mut live = LiveTypes::default();
+        live.add_interface(self.resolve, self.interface.unwrap().0);
+        for ty in live.iter() {
+            if let Some(file) = self.gen.type_files.get(&ty) {
+                if *file != stem && !deps.contains(file) {
+                    deps.push(file.clone());
+                }
+            }
+        }
+
+        // All interfaces of a package share their files.
+        match self.gen.interface_srcs.iter_mut().find(|s| s.stem == stem) {
+            Some(prev) => {
+                for dep in deps {
+                    if !prev.deps.contains(&dep) {
+                        prev.deps.push(dep);
+                    }
+                }
+                prev.src.append(&self.src);
+                prev.uses_return_area |= self.uses_return_area;
+            }
+            None => self.gen.interface_srcs.push(InterfaceSource {
+                stem,
+                deps,
+                src: self.src,
+                uses_return_area: self.uses_return_area,
+            }),
+        }
+    }
+
     fn owner_namespace(&self, id: TypeId) -> String {
         owner_namespace(
             self.interface,
@@ -2077,6 +2614,18 @@ impl InterfaceGenerator<'_> {
 struct DroppableBorrow {
     name: String,
     ty: TypeId,
+    /// The number of blocks this borrow was lifted in.
+    depth: usize,
+}
+
+/// A growable array of the borrows of a resource which were observed in lists
+/// while lifting an export.
+struct DroppableBorrowList {
+    ty: TypeId,
+    ptr: String,
+    len: String,
+    cap: String,
+    depth: usize,
 }
 
 struct FunctionBindgen<'a, 'b> {
@@ -2098,8 +2647,9 @@ struct FunctionBindgen<'a, 'b> {
     /// function exits.
     borrows: Vec<DroppableBorrow>,
 
-    /// Forward declarations for temporary storage of borrow copies.
-    borrow_decls: wit_bindgen_core::Source,
+    /// Borrows observed in lists during lifting an export, that will need to be dropped when the
+    /// guest function exits.
+    borrow_lists: Vec<DroppableBorrowList>,
 }
 
 impl<'a, 'b> FunctionBindgen<'a, 'b> {
@@ -2122,8 +2672,8 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             ret_store_cnt: 0,
             import_return_pointer_area_size: 0,
             import_return_pointer_area_align: 0,
-            borrow_decls: Default::default(),
             borrows: Vec::new(),
+            borrow_lists: Vec::new(),
         }
     }
 
@@ -2169,17 +2719,107 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         self.ret_store_cnt = self.ret_store_cnt + 1;
     }
 
-    fn assert_no_droppable_borrows(&self, context: &str, ty: &Type) {
-        if !self.gen.in_import
-            && self.gen.autodrop_enabled()
-            && self.gen.contains_droppable_borrow(ty)
-        {
-            panic!(
-                "Unable to autodrop borrows in `{}` values, please disable autodrop",
-                context
-            )
+    /// Emits code which adds the borrows in `expr`, a value of type `ty`, to
+    /// the lists of borrows to drop when the export returns.
+    fn push_droppable_borrows(&mut self, ty: &Type, expr: &str) {
+        let id = match ty {
+            Type::Id(id) if self.gen.contains_droppable_borrow(ty) => *id,
+            _ => return,
+        };
+        match &self.gen.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.push_droppable_borrows(t, expr),
+
+            TypeDefKind::Handle(Handle::Borrow(resource)) => {
+                let resource = dealias(self.gen.resolve, *resource);
+                let (ptr, len, cap) = self.borrow_list(resource);
+                let realloc = self.gen.gen.realloc_fn();
+                uwriteln!(self.src, "if ({len} == {cap}) {{");
+                uwriteln!(self.src, "{cap} = {cap} == 0 ? 8 : {cap} * 2;");
+                uwriteln!(
+                    self.src,
+                    "{ptr} = (int32_t *) {realloc}({ptr}, {cap} * sizeof(int32_t));"
+                );
+                uwriteln!(self.src, "}}");
+                uwriteln!(self.src, "{ptr}[{len}++] = ({expr}).__handle;");
+            }
+
+            TypeDefKind::Record(r) => {
+                for field in r.fields.iter() {
+                    let expr = format!("({expr}).{}", to_c_ident(&field.name));
+                    self.push_droppable_borrows(&field.ty, &expr);
+                }
+            }
+
+            TypeDefKind::Tuple(t) => {
+                for (i, ty) in t.types.iter().enumerate() {
+                    self.push_droppable_borrows(ty, &format!("({expr}).f{i}"));
+                }
+            }
+
+            TypeDefKind::Variant(v) => {
+                uwriteln!(self.src, "switch ((int32_t) ({expr}).tag) {{");
+                for (i, case) in v.cases.iter().enumerate() {
+                    if let Some(ty) = &case.ty {
+                        uwriteln!(self.src, "case {i}: {{");
+                        let expr = format!("({expr}).val.{}", to_c_ident(&case.name));
+                        self.push_droppable_borrows(ty, &expr);
+                        uwriteln!(self.src, "break;");
+                        uwriteln!(self.src, "}}");
+                    }
+                }
+                uwriteln!(self.src, "}}");
+            }
+
+            TypeDefKind::Option(t) => {
+                uwriteln!(self.src, "if (({expr}).is_some) {{");
+                self.push_droppable_borrows(t, &format!("({expr}).val"));
+                uwriteln!(self.src, "}}");
+            }
+
+            TypeDefKind::Result(r) => {
+                uwriteln!(self.src, "if (!({expr}).is_err) {{");
+                if let Some(ok) = &r.ok {
+                    self.push_droppable_borrows(ok, &format!("({expr}).val.ok"));
+                }
+                if let Some(err) = &r.err {
+                    uwriteln!(self.src, "}} else {{");
+                    self.push_droppable_borrows(err, &format!("({expr}).val.err"));
+                }
+                uwriteln!(self.src, "}}");
+            }
+
+            TypeDefKind::List(t) => {
+                let i = self.locals.tmp("i");
+                uwriteln!(
+                    self.src,
+                    "for (size_t {i} = 0; {i} < ({expr}).len; {i}++) {{"
+                );
+                self.push_droppable_borrows(t, &format!("({expr}).ptr[{i}]"));
+                uwriteln!(self.src, "}}");
+            }
+
+            _ => {}
         }
     }
+
+    /// Returns the names of the pointer, length and capacity of the list of
+    /// borrows of `resource` to drop when the export returns.
+    fn borrow_list(&mut self, resource: TypeId) -> (String, String, String) {
+        let depth = self.block_storage.len();
+        if let Some(list) = self.borrow_lists.iter().find(|l| l.ty == resource) {
+            return (list.ptr.clone(), list.len.clone(), list.cap.clone());
+        }
+        let list = DroppableBorrowList {
+            ty: resource,
+            ptr: self.locals.tmp("borrows"),
+            len: self.locals.tmp("borrows_len"),
+            cap: self.locals.tmp("borrows_cap"),
+            depth,
+        };
+        let names = (list.ptr.clone(), list.len.clone(), list.cap.clone());
+        self.borrow_lists.push(list);
+        names
+    }
 }
 
 impl Bindgen for FunctionBindgen<'_, '_> {
@@ -2214,6 +2854,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             self.gen.gen.return_pointer_area_size = self.gen.gen.return_pointer_area_size.max(size);
             self.gen.gen.return_pointer_area_align =
                 self.gen.gen.return_pointer_area_align.max(align);
+            self.gen.uses_return_area = true;
             // Declare a statically-allocated return area.
             uwriteln!(self.src, "uint8_t *{} = (uint8_t *) &RET_AREA;", ptr);
         }
@@ -2357,10 +2998,13 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                             let ty = dealias(self.gen.resolve, *id);
 
                             let name = self.locals.tmp("borrow");
-                            uwriteln!(self.borrow_decls, "int32_t {name} = 0;");
                             uwriteln!(self.src, "{name} = {op};");
 
-                            self.borrows.push(DroppableBorrow { name, ty });
+                            self.borrows.push(DroppableBorrow {
+                                name,
+                                ty,
+                                depth: self.block_storage.len(),
+                            });
                         }
                     }
                 }
@@ -2665,16 +3309,6 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 results.push(format!("(uint8_t *) ({}).ptr", operands[0]));
                 results.push(format!("({}).len", operands[0]));
             }
-            Instruction::ListCanonLift { element, ty, .. } => {
-                self.assert_no_droppable_borrows("list", &Type::Id(*ty));
-
-                let list_name = self.gen.gen.type_name(&Type::Id(*ty));
-                let elem_name = self.gen.gen.type_name(element);
-                results.push(format!(
-                    "({}) {{ ({}*)({}), ({}) }}",
-                    list_name, elem_name, operands[0], operands[1]
-                ));
-            }
             Instruction::StringLift { .. } => {
                 let list_name = self.gen.gen.type_name(&Type::String);
                 results.push(format!(
@@ -2692,16 +3326,36 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 results.push(format!("({}).len", operands[0]));
             }
 
-            Instruction::ListLift { element, ty, .. } => {
-                self.assert_no_droppable_borrows("list", &Type::Id(*ty));
+            Instruction::ListCanonLift { element, ty, .. }
+            | Instruction::ListLift { element, ty, .. } => {
+                if let Instruction::ListLift { .. } = inst {
+                    // The lifted elements are discarded in favor of the list
+                    // in linear memory, and so are the borrows observed in them.
+                    let _body = self.blocks.pop().unwrap();
+                    let depth = self.block_storage.len();
+                    self.borrows.retain(|b| b.depth <= depth);
+                    self.borrow_lists.retain(|b| b.depth <= depth);
+                }
 
-                let _body = self.blocks.pop().unwrap();
                 let list_name = self.gen.gen.type_name(&Type::Id(*ty));
                 let elem_name = self.gen.gen.type_name(element);
-                results.push(format!(
+                let list = format!(
                     "({}) {{ ({}*)({}), ({}) }}",
                     list_name, elem_name, operands[0], operands[1]
-                ));
+                );
+                if !self.gen.in_import
+                    && self.gen.autodrop_enabled()
+                    && self.gen.contains_droppable_borrow(element)
+                {
+                    // The borrows in the list are collected now, because the
+                    // function being called owns the list and may free it.
+                    let tmp = self.locals.tmp("list");
+                    uwriteln!(self.src, "{list_name} {tmp} = {list};");
+                    self.push_droppable_borrows(&Type::Id(*ty), &tmp);
+                    results.push(tmp);
+                } else {
+                    results.push(list);
+                }
             }
             Instruction::IterElem { .. } => results.push("e".to_string()),
             Instruction::IterBasePointer => results.push("base".to_string()),
@@ -2925,15 +3579,33 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             },
             Instruction::Return { amt, .. } => {
                 // Emit all temporary borrow decls
-                let src = std::mem::replace(&mut self.src, std::mem::take(&mut self.borrow_decls));
+                let mut borrow_decls = wit_bindgen_core::Source::default();
+                for DroppableBorrow { name, .. } in self.borrows.iter() {
+                    uwriteln!(borrow_decls, "int32_t {name} = 0;");
+                }
+                for DroppableBorrowList { ptr, len, cap, .. } in self.borrow_lists.iter() {
+                    uwriteln!(borrow_decls, "int32_t *{ptr} = NULL;");
+                    uwriteln!(borrow_decls, "size_t {len} = 0;");
+                    uwriteln!(borrow_decls, "size_t {cap} = 0;");
+                }
+                let src = std::mem::replace(&mut self.src, borrow_decls);
                 self.src.append_src(&src);
 
-                for DroppableBorrow { name, ty } in self.borrows.iter() {
+                for DroppableBorrow { name, ty, .. } in self.borrows.iter() {
                     let drop_fn = self.gen.gen.resources[ty].drop_fn.as_str();
                     uwriteln!(self.src, "if ({name} != 0) {{");
                     uwriteln!(self.src, "  {drop_fn}({name});");
                     uwriteln!(self.src, "}}");
                 }
+                for DroppableBorrowList { ty, ptr, len, .. } in self.borrow_lists.iter() {
+                    let drop_fn = self.gen.gen.resources[ty].drop_fn.as_str();
+                    let free = self.gen.gen.free_fn();
+                    let i = self.locals.tmp("i");
+                    uwriteln!(self.src, "for (size_t {i} = 0; {i} < {len}; {i}++) {{");
+                    uwriteln!(self.src, "  {drop_fn}({ptr}[{i}]);");
+                    uwriteln!(self.src, "}}");
+                    uwriteln!(self.src, "{free}({ptr});");
+                }
 
                 assert!(*amt <= 1);
                 if *amt == 1 {
@@ -2972,11 +3644,24 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
 
             Instruction::GuestDeallocate { .. } => {
-                uwriteln!(self.src, "free({});", operands[0]);
+                let free = self.gen.gen.free_fn();
+                uwriteln!(self.src, "{free}({});", operands[0]);
             }
             Instruction::GuestDeallocateString => {
-                uwriteln!(self.src, "if (({}) > 0) {{", operands[1]);
-                uwriteln!(self.src, "free({});", operands[0]);
+                if self.gen.gen.opts.string_encoding == StringEncoding::CompactUTF16 {
+                    // Only the number of code units says whether anything was
+                    // allocated, not the tag.
+                    uwriteln!(
+                        self.src,
+                        "if ((({}) & ~{}_STRING_UTF16_TAG) > 0) {{",
+                        operands[1],
+                        self.gen.gen.common_name().to_shouty_snake_case()
+                    );
+                } else {
+                    uwriteln!(self.src, "if (({}) > 0) {{", operands[1]);
+                }
+                let free = self.gen.gen.free_fn();
+                uwriteln!(self.src, "{free}({});", operands[0]);
                 uwriteln!(self.src, "}}");
             }
             Instruction::GuestDeallocateVariant { blocks } => {
@@ -3009,7 +3694,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 uwriteln!(self.src, "(void) base;");
                 uwrite!(self.src, "{body}");
                 uwriteln!(self.src, "}}");
-                uwriteln!(self.src, "free({ptr});");
+                let free = self.gen.gen.free_fn();
+                uwriteln!(self.src, "{free}({ptr});");
                 uwriteln!(self.src, "}}");
             }
 
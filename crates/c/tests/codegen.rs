@@ -6,6 +6,27 @@ use std::process::Command;
 use wit_parser::{Resolve, UnresolvedPackage};
 
 macro_rules! codegen_test {
+    // todo: support flags wider than 64 bits and then remove this line:
+    (flags_wide $name:tt $test:tt) => {};
+    // `future` and `stream` types aren't supported yet, so make sure they're
+    // reported as an error.
+    (futures_streams $name:tt $test:tt) => {
+        #[test]
+        fn futures_streams() {
+            test_helpers::run_world_codegen_test(
+                "guest-c",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let err = wit_bindgen_c::Opts::default()
+                        .build()
+                        .generate(resolve, world, files)
+                        .unwrap_err();
+                    assert!(err.to_string().contains("aren't supported"), "{err}");
+                },
+                |_, _| {},
+            );
+        }
+    };
     ($id:ident $name:tt $test:tt) => {
         #[test]
         fn $id() {
@@ -30,6 +51,16 @@ macro_rules! codegen_test {
                 },
                 verify,
             );
+            test_helpers::run_world_codegen_test(
+                "guest-c-compact-utf16",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_c::Opts::default();
+                    opts.string_encoding = wit_component::StringEncoding::CompactUTF16;
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
             test_helpers::run_world_codegen_test(
                 "guest-c-autodrop-borrows",
                 $test.as_ref(),
@@ -40,6 +71,37 @@ macro_rules! codegen_test {
                 },
                 verify,
             );
+            test_helpers::run_world_codegen_test(
+                "guest-c-custom-allocator",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_c::Opts::default();
+                    opts.realloc = Some("my_realloc".to_string());
+                    opts.free = Some("my_free".to_string());
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
+            test_helpers::run_world_codegen_test(
+                "guest-c-split-interfaces",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_c::Opts::default();
+                    opts.split_interfaces = true;
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
+            test_helpers::run_world_codegen_test(
+                "guest-c-common",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_c::Opts::default();
+                    opts.common = Some("common".to_string());
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
         }
     };
 }
@@ -68,11 +130,19 @@ fn verify(dir: &Path, name: &str) {
         "-o",
     ];
 
-    let mut cmd = Command::new(sdk_path.join("bin/clang"));
-    cmd.args(&shared_args);
-    cmd.arg(dir.join("obj.o"));
-    cmd.arg(&c_src);
-    test_helpers::run_command(&mut cmd);
+    // Interfaces may be split into their own source files, all of which are
+    // compiled.
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|e| e.to_str()) != Some("c") {
+            continue;
+        }
+        let mut cmd = Command::new(sdk_path.join("bin/clang"));
+        cmd.args(&shared_args);
+        cmd.arg(dir.join("obj.o"));
+        cmd.arg(&path);
+        test_helpers::run_command(&mut cmd);
+    }
 
     let cpp_src = c_src.with_extension("cpp");
     std::fs::write(&cpp_src, format!("#include \"{name}.h\"\n")).unwrap();
@@ -146,3 +216,118 @@ void exports_rename2_f() {}
     verify(&dir, "rename-option");
     Ok(())
 }
+
+#[test]
+fn common_option() -> Result<()> {
+    let dir = test_helpers::test_directory("codegen", "guest-c", "common-option");
+
+    let mut opts = wit_bindgen_c::Opts::default();
+    opts.common = Some("common".to_string());
+
+    let mut resolve = Resolve::default();
+    resolve.push(UnresolvedPackage::parse(
+        "shared.wit".as_ref(),
+        r#"
+            package foo:shared;
+
+            interface a {
+                record point {
+                    x: u32,
+                    y: u32,
+                }
+                make: func(xs: list<u8>) -> option<point>;
+            }
+
+            interface b {
+                use a.{point};
+                resource thing {
+                    constructor(p: point);
+                    get: func() -> list<option<point>>;
+                }
+                hello: func(s: string) -> list<tuple<string, u32>>;
+            }
+        "#,
+    )?)?;
+    let pkg = resolve.push(UnresolvedPackage::parse(
+        "input.wit".as_ref(),
+        r#"
+            package foo:bar;
+
+            interface names {
+                list-names: func() -> list<string>;
+            }
+
+            world one {
+                import foo:shared/a;
+                import foo:shared/b;
+                import local: interface {
+                    greet: func(names: list<string>);
+                }
+                import names;
+                export run: func(s: string) -> list<u8>;
+            }
+
+            world two {
+                import foo:shared/b;
+                import names;
+                export go: func();
+            }
+        "#,
+    )?)?;
+
+    // The files of the shared packages are the same for both worlds, so both
+    // can be linked into the same module. That includes the anonymous types
+    // which a package shares with the interfaces local to a world.
+    let mut one = Default::default();
+    let world = resolve.select_world(pkg, Some("one"))?;
+    opts.build().generate(&resolve, world, &mut one)?;
+    let mut two = Default::default();
+    let world = resolve.select_world(pkg, Some("two"))?;
+    opts.build().generate(&resolve, world, &mut two)?;
+
+    let one = one.iter().collect::<Vec<_>>();
+    let two = two.iter().collect::<Vec<_>>();
+    for file in [
+        "common.h",
+        "common.c",
+        "foo_shared.h",
+        "foo_shared.c",
+        "foo_bar.h",
+        "foo_bar.c",
+    ] {
+        let one = one.iter().find(|(name, _)| *name == file).unwrap();
+        let two = two.iter().find(|(name, _)| *name == file).unwrap();
+        assert_eq!(one.1, two.1, "`{file}` differs between worlds");
+    }
+    for (file, contents) in one.iter().chain(two.iter()) {
+        let dst = dir.join(file);
+        std::fs::create_dir_all(dst.parent().unwrap()).unwrap();
+        std::fs::write(&dst, contents).unwrap();
+    }
+
+    std::fs::write(
+        dir.join("app.c"),
+        r#"
+#include <stddef.h>
+#include "one.h"
+#include "two.h"
+
+void one_run(common_string_t *s, one_list_u8_t *ret) {
+    foo_shared_list_tuple2_string_u32_t list;
+    foo_shared_b_hello(s, &list);
+    foo_shared_list_tuple2_string_u32_free(&list);
+    foo_bar_list_string_t names;
+    foo_bar_names_list_names(&names);
+    local_greet(&names);
+    foo_bar_list_string_free(&names);
+    ret->ptr = NULL;
+    ret->len = 0;
+}
+
+void two_go(void) {}
+        "#,
+    )?;
+
+    verify(&dir, "one");
+    Ok(())
+}
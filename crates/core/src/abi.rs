@@ -1,9 +1,12 @@
 pub use wit_parser::abi::{AbiVariant, WasmSignature, WasmType};
 use wit_parser::{
     Enum, Flags, FlagsRepr, Function, Handle, Int, Record, Resolve, Result_, Results, SizeAlign,
-    Tuple, Type, TypeDefKind, TypeId, Variant,
+    Stream, Tuple, Type, TypeDefKind, TypeId, Variant,
 };
 
+pub mod interp;
+pub mod program;
+
 // Helper macro for defining instructions without having to have tons of
 // exhaustive `match` statements to update
 macro_rules! def_instruction {
@@ -65,7 +68,7 @@ macro_rules! def_instruction {
 }
 
 def_instruction! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub enum Instruction<'a> {
         /// Acquires the specified parameter and places it on the stack.
         /// Depending on the context this may refer to wasm parameters or
@@ -350,6 +353,30 @@ def_instruction! {
             ty: TypeId,
         } : [1] => [1],
 
+        /// Create an `i32` from a future.
+        FutureLower {
+            payload: &'a Option<Type>,
+            ty: TypeId,
+        } : [1] => [1],
+
+        /// Create a future from an `i32`.
+        FutureLift {
+            payload: &'a Option<Type>,
+            ty: TypeId,
+        } : [1] => [1],
+
+        /// Create an `i32` from a stream.
+        StreamLower {
+            stream: &'a Stream,
+            ty: TypeId,
+        } : [1] => [1],
+
+        /// Create a stream from an `i32`.
+        StreamLift {
+            stream: &'a Stream,
+            ty: TypeId,
+        } : [1] => [1],
+
         /// Pops a tuple value off the stack, decomposes the tuple to all of
         /// its fields, and then pushes the fields onto the stack.
         TupleLower {
@@ -522,7 +549,7 @@ def_instruction! {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Bitcast {
     // Upcasts
     F32ToI32,
@@ -734,7 +761,7 @@ fn needs_post_return(resolve: &Resolve, ty: &Type) -> bool {
                 .filter_map(|t| t.as_ref())
                 .any(|t| needs_post_return(resolve, t)),
             TypeDefKind::Flags(_) | TypeDefKind::Enum(_) => false,
-            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unimplemented!(),
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => false,
             TypeDefKind::Unknown => unreachable!(),
         },
 
@@ -1010,6 +1037,12 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         );
     }
 
+    /// Returns the name carried by instructions for the type `id`, which is
+    /// empty for anonymous types.
+    fn type_name(&self, id: TypeId) -> &'a str {
+        self.resolve.types[id].name.as_deref().unwrap_or("")
+    }
+
     fn emit(&mut self, inst: &Instruction<'_>) {
         self.operands.clear();
         self.results.clear();
@@ -1094,7 +1127,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&HandleLower {
                         handle,
                         ty: id,
-                        name: self.resolve.types[*ty].name.as_deref().unwrap(),
+                        name: self.type_name(*ty),
                     });
                 }
                 TypeDefKind::Resource => {
@@ -1104,7 +1137,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&RecordLower {
                         record,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                     let values = self
                         .stack
@@ -1131,7 +1164,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&FlagsLower {
                         flags,
                         ty: id,
-                        name: self.resolve.types[id].name.as_ref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1142,14 +1175,14 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                         variant: v,
                         ty: id,
                         results: &results,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
                 TypeDefKind::Enum(enum_) => {
                     self.emit(&EnumLower {
                         enum_,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
                 TypeDefKind::Option(t) => {
@@ -1168,8 +1201,12 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                         results: &results,
                     });
                 }
-                TypeDefKind::Future(_) => todo!("lower future"),
-                TypeDefKind::Stream(_) => todo!("lower stream"),
+                TypeDefKind::Future(payload) => {
+                    self.emit(&FutureLower { payload, ty: id });
+                }
+                TypeDefKind::Stream(stream) => {
+                    self.emit(&StreamLower { stream, ty: id });
+                }
                 TypeDefKind::Unknown => unreachable!(),
             },
         }
@@ -1278,7 +1315,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&HandleLift {
                         handle,
                         ty: id,
-                        name: self.resolve.types[*ty].name.as_deref().unwrap(),
+                        name: self.type_name(*ty),
                     });
                 }
                 TypeDefKind::Resource => {
@@ -1300,7 +1337,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&RecordLift {
                         record,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
                 TypeDefKind::Tuple(tuple) => {
@@ -1322,7 +1359,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&FlagsLift {
                         flags,
                         ty: id,
-                        name: self.resolve.types[id].name.as_ref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1331,7 +1368,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&VariantLift {
                         variant: v,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1339,7 +1376,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&EnumLift {
                         enum_,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1353,8 +1390,12 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&ResultLift { result: r, ty: id });
                 }
 
-                TypeDefKind::Future(_) => todo!("lift future"),
-                TypeDefKind::Stream(_) => todo!("lift stream"),
+                TypeDefKind::Future(payload) => {
+                    self.emit(&FutureLift { payload, ty: id });
+                }
+                TypeDefKind::Stream(stream) => {
+                    self.emit(&StreamLift { stream, ty: id });
+                }
                 TypeDefKind::Unknown => unreachable!(),
             },
         }
@@ -1430,7 +1471,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&RecordLower {
                         record,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                     self.write_fields_to_memory(record.fields.iter().map(|f| &f.ty), addr, offset);
                 }
@@ -1479,7 +1520,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                         variant: v,
                         ty: id,
                         results: &[],
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1512,8 +1553,9 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.store_intrepr(offset, e.tag());
                 }
 
-                TypeDefKind::Future(_) => todo!("write future to memory"),
-                TypeDefKind::Stream(_) => todo!("write stream to memory"),
+                TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
+                    self.lower_and_emit(ty, addr, &I32Store { offset })
+                }
                 TypeDefKind::Unknown => unreachable!(),
             },
         }
@@ -1625,7 +1667,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&RecordLift {
                         record,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1670,7 +1712,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.emit(&VariantLift {
                         variant,
                         ty: id,
-                        name: self.resolve.types[id].name.as_deref().unwrap(),
+                        name: self.type_name(id),
                     });
                 }
 
@@ -1695,8 +1737,9 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.lift(ty);
                 }
 
-                TypeDefKind::Future(_) => todo!("read future from memory"),
-                TypeDefKind::Stream(_) => todo!("read stream from memory"),
+                TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
+                    self.emit_and_lift(ty, addr, &I32Load { offset })
+                }
                 TypeDefKind::Unknown => unreachable!(),
             },
         }
@@ -1865,8 +1908,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
 
                 TypeDefKind::Enum(_) => {}
 
-                TypeDefKind::Future(_) => todo!("read future from memory"),
-                TypeDefKind::Stream(_) => todo!("read stream from memory"),
+                TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {}
                 TypeDefKind::Unknown => unreachable!(),
             },
         }
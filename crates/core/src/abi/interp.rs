@@ -0,0 +1,1430 @@
+//! An interpreter for the instructions generated by [`call`](super::call)
+//! and [`post_return`](super::post_return).
+//!
+//! Generators translate each [`Instruction`] into source code, which makes it
+//! hard to tell whether a sequence of instructions actually lifts and lowers
+//! values correctly. The interpreter here instead gives each instruction its
+//! meaning directly: interface values are represented by [`Value`], core wasm
+//! values by [`WasmValue`], and linear memory by [`Memory`], a byte vector
+//! with an allocator which checks that everything deallocated was allocated
+//! with the same size and alignment.
+//!
+//! This makes it possible to, for example, lower a value on one side of a
+//! call and lift it on the other side to check that it round-trips, or to
+//! compare the memory written by a generator's glue code against the memory
+//! written here.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use wit_parser::{Function, Resolve, SizeAlign, Type, TypeDefKind};
+
+use super::program::{self, Block, Node, Program};
+use super::{align_to, AbiVariant, Bitcast, Instruction, LiftLower, WasmSignature, WasmType};
+
+/// A value of an interface type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    S8(i8),
+    U16(u16),
+    S16(i16),
+    U32(u32),
+    S32(i32),
+    U64(u64),
+    S64(i64),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    List(Vec<Value>),
+    Record(Vec<Value>),
+    Tuple(Vec<Value>),
+    /// Whether each flag is set.
+    Flags(Vec<bool>),
+    /// The index of the case along with its payload, if it has one.
+    Variant(u32, Option<Box<Value>>),
+    /// The index of the case.
+    Enum(u32),
+    Option(Option<Box<Value>>),
+    Result(Result<Option<Box<Value>>, Option<Box<Value>>>),
+    /// An owned or borrowed handle to a resource.
+    Handle(u32),
+    Future(u32),
+    Stream(u32),
+}
+
+/// A value of a core wasm type.
+///
+/// Memory is 32-bit, so pointers and lengths are `I32`s, and values which
+/// may be either a pointer or an `i64` are `I64`s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WasmValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+/// An argument or result of interpreted code, which is an interface value
+/// where interface types are lowered and a core wasm value where they're
+/// lifted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Val {
+    Value(Value),
+    Wasm(WasmValue),
+}
+
+impl From<Value> for Val {
+    fn from(value: Value) -> Val {
+        Val::Value(value)
+    }
+}
+
+impl From<WasmValue> for Val {
+    fn from(value: WasmValue) -> Val {
+        Val::Wasm(value)
+    }
+}
+
+/// The functions which interpreted code calls.
+///
+/// Code generated with [`LiftLower::LowerArgsLiftResults`] calls core wasm
+/// functions and code generated with [`LiftLower::LiftArgsLowerResults`]
+/// calls interface functions. Both are given the memory the code runs
+/// against, so the callee can interpret code of its own against it.
+pub trait Callee {
+    /// Called for [`Instruction::CallWasm`].
+    fn call_wasm(
+        &mut self,
+        _memory: &mut Memory,
+        name: &str,
+        _sig: &WasmSignature,
+        _args: Vec<WasmValue>,
+    ) -> Result<Vec<WasmValue>> {
+        bail!("unexpected call to the core wasm function `{name}`")
+    }
+
+    /// Called for [`Instruction::CallInterface`].
+    fn call_interface(
+        &mut self,
+        _memory: &mut Memory,
+        func: &Function,
+        _args: Vec<Value>,
+    ) -> Result<Vec<Value>> {
+        bail!("unexpected call to the interface function `{}`", func.name)
+    }
+}
+
+/// A callee which isn't expected to be called.
+struct NoCallee;
+
+impl Callee for NoCallee {}
+
+/// A 32-bit linear memory.
+///
+/// Memory is allocated with a bump allocator, and allocations made through
+/// `cabi_realloc` are tracked so that deallocating memory which wasn't
+/// allocated, or with another size or alignment, traps. Lifting a list
+/// copies it out of memory, and like in languages which take ownership of the
+/// allocation the list was lowered into, the allocation stays live.
+pub struct Memory {
+    bytes: Vec<u8>,
+    allocations: BTreeMap<u32, (usize, usize)>,
+}
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory {
+            // keep null from being handed out as a valid pointer
+            bytes: vec![0; 8],
+            allocations: BTreeMap::new(),
+        }
+    }
+
+    /// Allocates `size` bytes aligned to `align` like `cabi_realloc` does,
+    /// which must be deallocated with [`Memory::free`].
+    pub fn alloc(&mut self, size: usize, align: usize) -> Result<u32> {
+        let ptr = self.reserve(size, align)?;
+        if size > 0 {
+            self.allocations.insert(ptr, (size, align));
+        }
+        Ok(ptr)
+    }
+
+    /// Deallocates memory allocated with [`Memory::alloc`].
+    pub fn free(&mut self, ptr: u32, size: usize, align: usize) -> Result<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        match self.allocations.remove(&ptr) {
+            Some((s, a)) if s == size && a == align => Ok(()),
+            Some((s, a)) => bail!(
+                "deallocation of {ptr:#x} with size {size} and alignment {align} \
+                 doesn't match its allocation with size {s} and alignment {a}"
+            ),
+            None => bail!("deallocation of {ptr:#x} which isn't allocated"),
+        }
+    }
+
+    /// Returns the live allocations made with [`Memory::alloc`], by their
+    /// pointer, along with their size and alignment.
+    pub fn allocations(&self) -> &BTreeMap<u32, (usize, usize)> {
+        &self.allocations
+    }
+
+    /// Reads `len` bytes at `ptr`.
+    pub fn read(&self, ptr: u32, len: usize) -> Result<&[u8]> {
+        let start = ptr as usize;
+        match start.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => Ok(&self.bytes[start..end]),
+            _ => bail!("out of bounds read of {len} bytes at {ptr:#x}"),
+        }
+    }
+
+    /// Writes `bytes` at `ptr`.
+    pub fn write(&mut self, ptr: u32, bytes: &[u8]) -> Result<()> {
+        let start = ptr as usize;
+        match start.checked_add(bytes.len()) {
+            Some(end) if end <= self.bytes.len() => {
+                self.bytes[start..end].copy_from_slice(bytes);
+                Ok(())
+            }
+            _ => bail!("out of bounds write of {} bytes at {ptr:#x}", bytes.len()),
+        }
+    }
+
+    /// Allocates memory which isn't tracked, such as return areas and
+    /// lists which are lowered without transferring their ownership.
+    fn reserve(&mut self, size: usize, align: usize) -> Result<u32> {
+        if size == 0 {
+            return Ok(align as u32);
+        }
+        let start = align_to(self.bytes.len(), align);
+        match start.checked_add(size) {
+            Some(end) if end <= u32::MAX as usize => {
+                self.bytes.resize(end, 0);
+                Ok(start as u32)
+            }
+            _ => bail!("out of memory allocating {size} bytes"),
+        }
+    }
+
+    fn load<const N: usize>(&self, ptr: u32) -> Result<[u8; N]> {
+        Ok(self.read(ptr, N)?.try_into().unwrap())
+    }
+}
+
+/// Executes the instructions generated for functions of a [`Resolve`].
+pub struct Interpreter<'a> {
+    resolve: &'a Resolve,
+    sizes: SizeAlign,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(resolve: &'a Resolve) -> Interpreter<'a> {
+        let mut sizes = SizeAlign::default();
+        sizes.fill(resolve);
+        Interpreter { resolve, sizes }
+    }
+
+    /// Executes the instructions generated by [`call`](super::call) against
+    /// `memory`, returning the values of the final [`Instruction::Return`].
+    ///
+    /// With [`LiftLower::LowerArgsLiftResults`] `args` are interface values
+    /// and the results are as well, and with
+    /// [`LiftLower::LiftArgsLowerResults`] both are core wasm values.
+    pub fn call(
+        &self,
+        variant: AbiVariant,
+        lift_lower: LiftLower,
+        func: &Function,
+        memory: &mut Memory,
+        args: &[Val],
+        callee: &mut dyn Callee,
+    ) -> Result<Vec<Val>> {
+        let program = Program::call(
+            self.resolve,
+            variant,
+            lift_lower,
+            func,
+            &self.sizes,
+            is_list_canonical,
+        );
+        self.run(&program, memory, args, callee)
+    }
+
+    /// Executes the instructions generated by
+    /// [`post_return`](super::post_return) against `memory`, where `args` are
+    /// the results of the exported function.
+    pub fn post_return(&self, func: &Function, memory: &mut Memory, args: &[Val]) -> Result<()> {
+        let program = Program::post_return(self.resolve, func, &self.sizes);
+        self.run(&program, memory, args, &mut NoCallee)?;
+        Ok(())
+    }
+
+    /// Executes `program`, which is useful to check that a [`Program`] still
+    /// behaves the same after it's been optimized.
+    ///
+    /// The program must have been recorded with the sizes of this
+    /// interpreter, and lists may only be canonical if their elements are
+    /// [memcpy-able](program::is_memcpy_able).
+    pub fn run(
+        &self,
+        program: &Program,
+        memory: &mut Memory,
+        args: &[Val],
+        callee: &mut dyn Callee,
+    ) -> Result<Vec<Val>> {
+        let mut exec = Exec {
+            resolve: self.resolve,
+            sizes: &self.sizes,
+            memory,
+            callee,
+            args,
+            registers: vec![None; program.registers()],
+            payload: None,
+            elem: None,
+            base: 0,
+            returned: None,
+        };
+        exec.block(program.body())?;
+        Ok(exec.returned.expect("instructions end with a return"))
+    }
+}
+
+/// What an instruction does, which is all that's left of an [`Instruction`]
+/// once it's recorded.
+enum Op {
+    GetArg(usize),
+    I32Const(i32),
+    Bitcasts(Vec<Bitcast>),
+    ConstZero(Vec<WasmType>),
+    Load(Load, i32),
+    Store(Store, i32),
+    LowerPrim(Type),
+    LiftPrim(Type),
+    ListLower {
+        element: Type,
+        realloc: bool,
+        canonical: bool,
+    },
+    ListLift {
+        element: Type,
+        canonical: bool,
+    },
+    StringLower {
+        realloc: bool,
+    },
+    StringLift,
+    IterElem,
+    IterBasePointer,
+    RecordLower(usize),
+    RecordLift,
+    TupleLower(usize),
+    TupleLift,
+    HandleLower,
+    HandleLift,
+    FutureLower,
+    FutureLift,
+    StreamLower,
+    StreamLift,
+    FlagsLower(usize),
+    FlagsLift(usize),
+    VariantPayloadName,
+    /// Lowers a variant, option or result, whose cases are described by
+    /// whether they have a payload.
+    VariantLower(Kind, Vec<bool>),
+    VariantLift(Kind, Vec<bool>),
+    EnumLower(usize),
+    EnumLift(usize),
+    CallWasm(String, WasmSignature),
+    CallInterface(Function),
+    Return,
+    Malloc {
+        size: usize,
+        align: usize,
+    },
+    /// Allocates the return area requested through
+    /// [`Bindgen::return_pointer`](super::Bindgen::return_pointer).
+    ReturnPointer {
+        size: usize,
+        align: usize,
+    },
+    GuestDeallocate {
+        size: usize,
+        align: usize,
+    },
+    GuestDeallocateString,
+    GuestDeallocateList(Type),
+    GuestDeallocateVariant,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Variant,
+    Option,
+    Result,
+}
+
+#[derive(Clone, Copy)]
+enum Load {
+    I32,
+    I32_8U,
+    I32_8S,
+    I32_16U,
+    I32_16S,
+    I64,
+    F32,
+    F64,
+}
+
+#[derive(Clone, Copy)]
+enum Store {
+    I32,
+    I32_8,
+    I32_16,
+    I64,
+    F32,
+    F64,
+}
+
+impl Op {
+    fn new(inst: &Instruction<'_>) -> Op {
+        use Instruction as I;
+
+        match inst {
+            I::GetArg { nth } => Op::GetArg(*nth),
+            I::I32Const { val } => Op::I32Const(*val),
+            I::Bitcasts { casts } => Op::Bitcasts(casts.to_vec()),
+            I::ConstZero { tys } => Op::ConstZero(tys.to_vec()),
+
+            I::I32Load { offset } | I::PointerLoad { offset } | I::LengthLoad { offset } => {
+                Op::Load(Load::I32, *offset)
+            }
+            I::I32Load8U { offset } => Op::Load(Load::I32_8U, *offset),
+            I::I32Load8S { offset } => Op::Load(Load::I32_8S, *offset),
+            I::I32Load16U { offset } => Op::Load(Load::I32_16U, *offset),
+            I::I32Load16S { offset } => Op::Load(Load::I32_16S, *offset),
+            I::I64Load { offset } => Op::Load(Load::I64, *offset),
+            I::F32Load { offset } => Op::Load(Load::F32, *offset),
+            I::F64Load { offset } => Op::Load(Load::F64, *offset),
+            I::I32Store { offset } | I::PointerStore { offset } | I::LengthStore { offset } => {
+                Op::Store(Store::I32, *offset)
+            }
+            I::I32Store8 { offset } => Op::Store(Store::I32_8, *offset),
+            I::I32Store16 { offset } => Op::Store(Store::I32_16, *offset),
+            I::I64Store { offset } => Op::Store(Store::I64, *offset),
+            I::F32Store { offset } => Op::Store(Store::F32, *offset),
+            I::F64Store { offset } => Op::Store(Store::F64, *offset),
+
+            I::I32FromBool => Op::LowerPrim(Type::Bool),
+            I::I32FromU8 => Op::LowerPrim(Type::U8),
+            I::I32FromS8 => Op::LowerPrim(Type::S8),
+            I::I32FromU16 => Op::LowerPrim(Type::U16),
+            I::I32FromS16 => Op::LowerPrim(Type::S16),
+            I::I32FromU32 => Op::LowerPrim(Type::U32),
+            I::I32FromS32 => Op::LowerPrim(Type::S32),
+            I::I64FromU64 => Op::LowerPrim(Type::U64),
+            I::I64FromS64 => Op::LowerPrim(Type::S64),
+            I::I32FromChar => Op::LowerPrim(Type::Char),
+            I::F32FromFloat32 => Op::LowerPrim(Type::F32),
+            I::F64FromFloat64 => Op::LowerPrim(Type::F64),
+            I::BoolFromI32 => Op::LiftPrim(Type::Bool),
+            I::U8FromI32 => Op::LiftPrim(Type::U8),
+            I::S8FromI32 => Op::LiftPrim(Type::S8),
+            I::U16FromI32 => Op::LiftPrim(Type::U16),
+            I::S16FromI32 => Op::LiftPrim(Type::S16),
+            I::U32FromI32 => Op::LiftPrim(Type::U32),
+            I::S32FromI32 => Op::LiftPrim(Type::S32),
+            I::U64FromI64 => Op::LiftPrim(Type::U64),
+            I::S64FromI64 => Op::LiftPrim(Type::S64),
+            I::CharFromI32 => Op::LiftPrim(Type::Char),
+            I::Float32FromF32 => Op::LiftPrim(Type::F32),
+            I::Float64FromF64 => Op::LiftPrim(Type::F64),
+
+            I::ListCanonLower { element, realloc } => Op::ListLower {
+                element: **element,
+                realloc: realloc.is_some(),
+                canonical: true,
+            },
+            I::ListLower { element, realloc } => Op::ListLower {
+                element: **element,
+                realloc: realloc.is_some(),
+                canonical: false,
+            },
+            I::ListCanonLift { element, .. } => Op::ListLift {
+                element: **element,
+                canonical: true,
+            },
+            I::ListLift { element, .. } => Op::ListLift {
+                element: **element,
+                canonical: false,
+            },
+            I::StringLower { realloc } => Op::StringLower {
+                realloc: realloc.is_some(),
+            },
+            I::StringLift => Op::StringLift,
+            I::IterElem { .. } => Op::IterElem,
+            I::IterBasePointer => Op::IterBasePointer,
+
+            I::RecordLower { record, .. } => Op::RecordLower(record.fields.len()),
+            I::RecordLift { .. } => Op::RecordLift,
+            I::TupleLower { tuple, .. } => Op::TupleLower(tuple.types.len()),
+            I::TupleLift { .. } => Op::TupleLift,
+            I::HandleLower { .. } => Op::HandleLower,
+            I::HandleLift { .. } => Op::HandleLift,
+            I::FutureLower { .. } => Op::FutureLower,
+            I::FutureLift { .. } => Op::FutureLift,
+            I::StreamLower { .. } => Op::StreamLower,
+            I::StreamLift { .. } => Op::StreamLift,
+            I::FlagsLower { flags, .. } => Op::FlagsLower(flags.flags.len()),
+            I::FlagsLift { flags, .. } => Op::FlagsLift(flags.flags.len()),
+
+            I::VariantPayloadName => Op::VariantPayloadName,
+            I::VariantLower { variant, .. } => Op::VariantLower(
+                Kind::Variant,
+                variant.cases.iter().map(|c| c.ty.is_some()).collect(),
+            ),
+            I::VariantLift { variant, .. } => Op::VariantLift(
+                Kind::Variant,
+                variant.cases.iter().map(|c| c.ty.is_some()).collect(),
+            ),
+            I::OptionLower { .. } => Op::VariantLower(Kind::Option, vec![false, true]),
+            I::OptionLift { .. } => Op::VariantLift(Kind::Option, vec![false, true]),
+            I::ResultLower { result, .. } => Op::VariantLower(
+                Kind::Result,
+                vec![result.ok.is_some(), result.err.is_some()],
+            ),
+            I::ResultLift { result, .. } => Op::VariantLift(
+                Kind::Result,
+                vec![result.ok.is_some(), result.err.is_some()],
+            ),
+            I::EnumLower { enum_, .. } => Op::EnumLower(enum_.cases.len()),
+            I::EnumLift { enum_, .. } => Op::EnumLift(enum_.cases.len()),
+
+            I::CallWasm { name, sig } => Op::CallWasm(name.to_string(), (*sig).clone()),
+            I::CallInterface { func } => Op::CallInterface((*func).clone()),
+            I::Return { .. } => Op::Return,
+            I::Malloc { size, align, .. } => Op::Malloc {
+                size: *size,
+                align: *align,
+            },
+            I::GuestDeallocate { size, align } => Op::GuestDeallocate {
+                size: *size,
+                align: *align,
+            },
+            I::GuestDeallocateString => Op::GuestDeallocateString,
+            I::GuestDeallocateList { element } => Op::GuestDeallocateList(**element),
+            I::GuestDeallocateVariant { .. } => Op::GuestDeallocateVariant,
+        }
+    }
+}
+
+/// Whether lists of `element` are lowered and lifted with `ListCanonLower`
+/// and `ListCanonLift`.
+fn is_list_canonical(element: &Type) -> bool {
+    matches!(
+        element,
+        Type::U8
+            | Type::S8
+            | Type::U16
+            | Type::S16
+            | Type::U32
+            | Type::S32
+            | Type::U64
+            | Type::S64
+            | Type::F32
+            | Type::F64
+    )
+}
+
+/// The state of the execution of recorded instructions.
+struct Exec<'a> {
+    resolve: &'a Resolve,
+    sizes: &'a SizeAlign,
+    memory: &'a mut Memory,
+    callee: &'a mut dyn Callee,
+    args: &'a [Val],
+    registers: Vec<Option<Val>>,
+    // the payload of the case of the variant being lowered, which is picked
+    // up by `VariantPayloadName` at the start of the block of the case
+    payload: Option<Value>,
+    // the element of the list being lowered and its address, which are
+    // picked up by `IterElem` and `IterBasePointer` at the start of the block
+    // of the list
+    elem: Option<Value>,
+    base: u32,
+    returned: Option<Vec<Val>>,
+}
+
+impl Exec<'_> {
+    /// Executes `block`, returning its results.
+    fn block(&mut self, block: &Block) -> Result<Vec<Val>> {
+        for node in block.nodes.iter() {
+            self.node(node)?;
+        }
+        block.results.iter().map(|r| self.get(*r)).collect()
+    }
+
+    fn get(&self, register: usize) -> Result<Val> {
+        self.registers[register]
+            .clone()
+            .ok_or_else(|| anyhow!("register {register} is read before it's written"))
+    }
+
+    fn node(&mut self, node: &Node) -> Result<()> {
+        let op = match node.op() {
+            program::Op::Instruction(inst) => Op::new(&inst),
+            program::Op::ReturnPointer { size, align } => Op::ReturnPointer { size, align },
+        };
+        if let Op::VariantPayloadName = op {
+            // cases without a payload don't read it
+            self.registers[node.results[0]] = self.payload.take().map(Val::Value);
+            return Ok(());
+        }
+
+        let mut operands = node
+            .operands
+            .iter()
+            .map(|r| self.get(*r))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+        let mut operand = || operands.next().unwrap();
+
+        let results = match &op {
+            Op::GetArg(nth) => match self.args.get(*nth) {
+                Some(arg) => vec![arg.clone()],
+                None => bail!("argument {nth} is missing"),
+            },
+            Op::I32Const(val) => vec![Val::Wasm(WasmValue::I32(*val))],
+            Op::Bitcasts(casts) => casts
+                .iter()
+                .map(|cast| Ok(Val::Wasm(bitcast(cast, wasm(operand())?)?)))
+                .collect::<Result<_>>()?,
+            Op::ConstZero(tys) => tys
+                .iter()
+                .map(|ty| {
+                    Val::Wasm(match ty {
+                        WasmType::I32 | WasmType::Pointer | WasmType::Length => WasmValue::I32(0),
+                        WasmType::I64 | WasmType::PointerOrI64 => WasmValue::I64(0),
+                        WasmType::F32 => WasmValue::F32(0.0),
+                        WasmType::F64 => WasmValue::F64(0.0),
+                    })
+                })
+                .collect(),
+
+            Op::Load(load, offset) => {
+                let ptr = address(pointer(operand())?, *offset)?;
+                let memory = &self.memory;
+                let value = match load {
+                    Load::I32 => WasmValue::I32(i32::from_le_bytes(memory.load(ptr)?)),
+                    Load::I32_8U => WasmValue::I32(memory.load::<1>(ptr)?[0].into()),
+                    Load::I32_8S => WasmValue::I32((memory.load::<1>(ptr)?[0] as i8).into()),
+                    Load::I32_16U => WasmValue::I32(u16::from_le_bytes(memory.load(ptr)?).into()),
+                    Load::I32_16S => WasmValue::I32(i16::from_le_bytes(memory.load(ptr)?).into()),
+                    Load::I64 => WasmValue::I64(i64::from_le_bytes(memory.load(ptr)?)),
+                    Load::F32 => WasmValue::F32(f32::from_le_bytes(memory.load(ptr)?)),
+                    Load::F64 => WasmValue::F64(f64::from_le_bytes(memory.load(ptr)?)),
+                };
+                vec![Val::Wasm(value)]
+            }
+            Op::Store(store, offset) => {
+                let value = wasm(operand())?;
+                let ptr = address(pointer(operand())?, *offset)?;
+                match (store, value) {
+                    (Store::I32, WasmValue::I32(v)) => self.memory.write(ptr, &v.to_le_bytes())?,
+                    (Store::I32_8, WasmValue::I32(v)) => self.memory.write(ptr, &[v as u8])?,
+                    (Store::I32_16, WasmValue::I32(v)) => {
+                        self.memory.write(ptr, &(v as u16).to_le_bytes())?
+                    }
+                    (Store::I64, WasmValue::I64(v)) => self.memory.write(ptr, &v.to_le_bytes())?,
+                    (Store::F32, WasmValue::F32(v)) => self.memory.write(ptr, &v.to_le_bytes())?,
+                    (Store::F64, WasmValue::F64(v)) => self.memory.write(ptr, &v.to_le_bytes())?,
+                    (_, value) => bail!("can't store {value:?} with this instruction"),
+                }
+                Vec::new()
+            }
+
+            Op::LowerPrim(ty) => vec![Val::Wasm(lower_prim(ty, value(operand())?)?)],
+            Op::LiftPrim(ty) => vec![Val::Value(lift_prim(ty, wasm(operand())?)?)],
+
+            Op::ListLower {
+                element,
+                realloc,
+                canonical,
+            } => {
+                let elems = match value(operand())? {
+                    Value::List(elems) => elems,
+                    value => bail!("expected a list, found {value:?}"),
+                };
+                let size = self.sizes.size(element);
+                let align = self.sizes.align(element);
+                let ptr = self.alloc(elems.len() * size, align, *realloc)?;
+                let len = elems.len();
+                for (i, elem) in elems.into_iter().enumerate() {
+                    let base = ptr + (i * size) as u32;
+                    if *canonical {
+                        self.encode(element, elem, base)?;
+                    } else {
+                        self.elem = Some(elem);
+                        self.base = base;
+                        self.block(&node.blocks[0])?;
+                    }
+                }
+                vec![
+                    Val::Wasm(WasmValue::I32(ptr as i32)),
+                    Val::Wasm(WasmValue::I32(len as i32)),
+                ]
+            }
+            Op::ListLift { element, canonical } => {
+                let ptr = pointer(operand())?;
+                let len = pointer(operand())? as usize;
+                let size = self.list(element, ptr, len)?;
+                let mut elems = Vec::with_capacity(len);
+                for i in 0..len {
+                    let base = ptr + (i * size) as u32;
+                    if *canonical {
+                        elems.push(self.decode(element, base)?);
+                    } else {
+                        self.base = base;
+                        let mut results = self.block(&node.blocks[0])?;
+                        elems.push(value(results.pop().unwrap())?);
+                    }
+                }
+                vec![Val::Value(Value::List(elems))]
+            }
+            Op::StringLower { realloc } => {
+                let string = match value(operand())? {
+                    Value::String(string) => string,
+                    value => bail!("expected a string, found {value:?}"),
+                };
+                let ptr = self.alloc(string.len(), 1, *realloc)?;
+                self.memory.write(ptr, string.as_bytes())?;
+                vec![
+                    Val::Wasm(WasmValue::I32(ptr as i32)),
+                    Val::Wasm(WasmValue::I32(string.len() as i32)),
+                ]
+            }
+            Op::StringLift => {
+                let ptr = pointer(operand())?;
+                let len = pointer(operand())? as usize;
+                let bytes = self.memory.read(ptr, len)?.to_vec();
+                match String::from_utf8(bytes) {
+                    Ok(string) => vec![Val::Value(Value::String(string))],
+                    Err(_) => bail!("string at {ptr:#x} isn't valid utf-8"),
+                }
+            }
+            Op::IterElem => match self.elem.take() {
+                Some(elem) => vec![Val::Value(elem)],
+                None => bail!("`IterElem` outside of the block of a list"),
+            },
+            Op::IterBasePointer => vec![Val::Wasm(WasmValue::I32(self.base as i32))],
+
+            Op::RecordLower(n) => match value(operand())? {
+                Value::Record(fields) if fields.len() == *n => {
+                    fields.into_iter().map(Val::Value).collect()
+                }
+                value => bail!("expected a record with {n} fields, found {value:?}"),
+            },
+            Op::TupleLower(n) => match value(operand())? {
+                Value::Tuple(types) if types.len() == *n => {
+                    types.into_iter().map(Val::Value).collect()
+                }
+                value => bail!("expected a tuple with {n} types, found {value:?}"),
+            },
+            Op::RecordLift => {
+                let fields = operands.map(value).collect::<Result<_>>()?;
+                vec![Val::Value(Value::Record(fields))]
+            }
+            Op::TupleLift => {
+                let types = operands.map(value).collect::<Result<_>>()?;
+                vec![Val::Value(Value::Tuple(types))]
+            }
+
+            Op::HandleLower => match value(operand())? {
+                Value::Handle(handle) => vec![Val::Wasm(WasmValue::I32(handle as i32))],
+                value => bail!("expected a handle, found {value:?}"),
+            },
+            Op::FutureLower => match value(operand())? {
+                Value::Future(handle) => vec![Val::Wasm(WasmValue::I32(handle as i32))],
+                value => bail!("expected a future, found {value:?}"),
+            },
+            Op::StreamLower => match value(operand())? {
+                Value::Stream(handle) => vec![Val::Wasm(WasmValue::I32(handle as i32))],
+                value => bail!("expected a stream, found {value:?}"),
+            },
+            Op::HandleLift => vec![Val::Value(Value::Handle(pointer(operand())?))],
+            Op::FutureLift => vec![Val::Value(Value::Future(pointer(operand())?))],
+            Op::StreamLift => vec![Val::Value(Value::Stream(pointer(operand())?))],
+
+            Op::FlagsLower(n) => {
+                let flags = match value(operand())? {
+                    Value::Flags(flags) if flags.len() == *n => flags,
+                    value => bail!("expected {n} flags, found {value:?}"),
+                };
+                let mut words = vec![0i32; node.results.len()];
+                for (i, set) in flags.into_iter().enumerate() {
+                    if set {
+                        words[i / 32] |= 1 << (i % 32);
+                    }
+                }
+                words
+                    .into_iter()
+                    .map(|w| Val::Wasm(WasmValue::I32(w)))
+                    .collect()
+            }
+            Op::FlagsLift(n) => {
+                let words = operands.map(pointer).collect::<Result<Vec<_>>>()?;
+                let flags = (0..*n).map(|i| words[i / 32] & (1 << (i % 32)) != 0);
+                vec![Val::Value(Value::Flags(flags.collect()))]
+            }
+
+            Op::VariantPayloadName => unreachable!(),
+            Op::VariantLower(kind, cases) => {
+                let (case, payload) = match (kind, value(operand())?) {
+                    (Kind::Variant, Value::Variant(case, payload)) => (case as usize, payload),
+                    (Kind::Option, Value::Option(None)) => (0, None),
+                    (Kind::Option, Value::Option(Some(payload))) => (1, Some(payload)),
+                    (Kind::Result, Value::Result(Ok(payload))) => (0, payload),
+                    (Kind::Result, Value::Result(Err(payload))) => (1, payload),
+                    (_, value) => bail!("expected a variant, found {value:?}"),
+                };
+                match cases.get(case) {
+                    Some(has_payload) if *has_payload == payload.is_some() => {}
+                    _ => bail!("invalid case {case} with payload {payload:?}"),
+                }
+                self.payload = payload.map(|p| *p);
+                self.block(&node.blocks[case])?
+            }
+            Op::VariantLift(kind, cases) => {
+                let case = pointer(operand())?;
+                if case as usize >= cases.len() {
+                    bail!("invalid discriminant {case}");
+                }
+                let mut results = self.block(&node.blocks[case as usize])?;
+                let payload = match results.pop() {
+                    Some(payload) => Some(Box::new(value(payload)?)),
+                    None => None,
+                };
+                let value = match kind {
+                    Kind::Variant => Value::Variant(case, payload),
+                    Kind::Option => Value::Option(payload),
+                    Kind::Result if case == 0 => Value::Result(Ok(payload)),
+                    Kind::Result => Value::Result(Err(payload)),
+                };
+                vec![Val::Value(value)]
+            }
+            Op::EnumLower(n) => match value(operand())? {
+                Value::Enum(case) if (case as usize) < *n => {
+                    vec![Val::Wasm(WasmValue::I32(case as i32))]
+                }
+                value => bail!("expected an enum with {n} cases, found {value:?}"),
+            },
+            Op::EnumLift(n) => {
+                let case = pointer(operand())?;
+                if case as usize >= *n {
+                    bail!("invalid discriminant {case}");
+                }
+                vec![Val::Value(Value::Enum(case))]
+            }
+
+            Op::CallWasm(name, sig) => {
+                let args = operands.map(wasm).collect::<Result<Vec<_>>>()?;
+                check_wasm_types(&args, &sig.params)?;
+                let results = self.callee.call_wasm(self.memory, name, sig, args)?;
+                check_wasm_types(&results, &sig.results)?;
+                results.into_iter().map(Val::Wasm).collect()
+            }
+            Op::CallInterface(func) => {
+                let args = operands.map(value).collect::<Result<Vec<_>>>()?;
+                let results = self.callee.call_interface(self.memory, func, args)?;
+                if results.len() != func.results.len() {
+                    bail!(
+                        "`{}` returned {} results instead of {}",
+                        func.name,
+                        results.len(),
+                        func.results.len()
+                    );
+                }
+                results.into_iter().map(Val::Value).collect()
+            }
+            Op::Return => {
+                self.returned = Some(operands.collect());
+                Vec::new()
+            }
+
+            Op::Malloc { size, align } => {
+                let ptr = self.memory.alloc(*size, *align)?;
+                vec![Val::Wasm(WasmValue::I32(ptr as i32))]
+            }
+            Op::ReturnPointer { size, align } => {
+                let ptr = self.memory.reserve(*size, *align)?;
+                vec![Val::Wasm(WasmValue::I32(ptr as i32))]
+            }
+            Op::GuestDeallocate { size, align } => {
+                self.memory.free(pointer(operand())?, *size, *align)?;
+                Vec::new()
+            }
+            Op::GuestDeallocateString => {
+                let ptr = pointer(operand())?;
+                let len = pointer(operand())?;
+                self.memory.free(ptr, len as usize, 1)?;
+                Vec::new()
+            }
+            Op::GuestDeallocateList(element) => {
+                let ptr = pointer(operand())?;
+                let len = pointer(operand())? as usize;
+                let size = self.list(element, ptr, len)?;
+                for i in 0..len {
+                    self.base = ptr + (i * size) as u32;
+                    self.block(&node.blocks[0])?;
+                }
+                self.memory
+                    .free(ptr, len * size, self.sizes.align(element))?;
+                Vec::new()
+            }
+            Op::GuestDeallocateVariant => {
+                let case = pointer(operand())? as usize;
+                match node.blocks.get(case) {
+                    Some(block) => self.block(block)?,
+                    None => bail!("invalid discriminant {case}"),
+                };
+                Vec::new()
+            }
+        };
+
+        assert_eq!(results.len(), node.results.len());
+        for (register, result) in node.results.iter().zip(results) {
+            self.registers[*register] = Some(result);
+        }
+        Ok(())
+    }
+
+    /// Checks that the list of `len` elements at `ptr` is aligned and in
+    /// bounds, returning the size of its elements.
+    fn list(&self, element: &Type, ptr: u32, len: usize) -> Result<usize> {
+        let size = self.sizes.size(element);
+        let align = self.sizes.align(element);
+        if ptr as usize & (align - 1) != 0 {
+            bail!("list pointer {ptr:#x} isn't aligned to {align}");
+        }
+        match len.checked_mul(size) {
+            Some(bytes) => self.memory.read(ptr, bytes)?,
+            None => bail!("list length {len} is too large"),
+        };
+        Ok(size)
+    }
+
+    /// Writes `value`, an element of a canonical list, to `ptr`.
+    fn encode(&mut self, ty: &Type, value: Value, ptr: u32) -> Result<()> {
+        let fields = match (ty, value) {
+            (Type::U8, Value::U8(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::S8, Value::S8(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::U16, Value::U16(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::S16, Value::S16(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::U32, Value::U32(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::S32, Value::S32(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::U64, Value::U64(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::S64, Value::S64(i)) => return self.memory.write(ptr, &i.to_le_bytes()),
+            (Type::F32, Value::F32(f)) => return self.memory.write(ptr, &f.to_le_bytes()),
+            (Type::F64, Value::F64(f)) => return self.memory.write(ptr, &f.to_le_bytes()),
+            (Type::Id(id), value) => match (&self.resolve.types[*id].kind, value) {
+                (TypeDefKind::Type(t), value) => return self.encode(t, value, ptr),
+                (TypeDefKind::Record(r), Value::Record(fields))
+                    if fields.len() == r.fields.len() =>
+                {
+                    r.fields
+                        .iter()
+                        .map(|f| f.ty)
+                        .zip(fields)
+                        .collect::<Vec<_>>()
+                }
+                (TypeDefKind::Tuple(t), Value::Tuple(types)) if types.len() == t.types.len() => {
+                    t.types.iter().copied().zip(types).collect()
+                }
+                (_, value) => bail!("expected a value of type {ty:?}, found {value:?}"),
+            },
+            (ty, value) => bail!("expected a value of type {ty:?}, found {value:?}"),
+        };
+        let offsets = self.sizes.field_offsets(fields.iter().map(|(ty, _)| ty));
+        let offsets = offsets
+            .into_iter()
+            .map(|(offset, _)| offset)
+            .collect::<Vec<_>>();
+        for ((ty, value), offset) in fields.into_iter().zip(offsets) {
+            self.encode(&ty, value, ptr + offset as u32)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the element of a canonical list at `ptr`.
+    fn decode(&self, ty: &Type, ptr: u32) -> Result<Value> {
+        let memory = &self.memory;
+        Ok(match ty {
+            Type::U8 => Value::U8(u8::from_le_bytes(memory.load(ptr)?)),
+            Type::S8 => Value::S8(i8::from_le_bytes(memory.load(ptr)?)),
+            Type::U16 => Value::U16(u16::from_le_bytes(memory.load(ptr)?)),
+            Type::S16 => Value::S16(i16::from_le_bytes(memory.load(ptr)?)),
+            Type::U32 => Value::U32(u32::from_le_bytes(memory.load(ptr)?)),
+            Type::S32 => Value::S32(i32::from_le_bytes(memory.load(ptr)?)),
+            Type::U64 => Value::U64(u64::from_le_bytes(memory.load(ptr)?)),
+            Type::S64 => Value::S64(i64::from_le_bytes(memory.load(ptr)?)),
+            Type::F32 => Value::F32(f32::from_le_bytes(memory.load(ptr)?)),
+            Type::F64 => Value::F64(f64::from_le_bytes(memory.load(ptr)?)),
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.decode(t, ptr)?,
+                TypeDefKind::Record(r) => {
+                    Value::Record(self.decode_fields(ptr, r.fields.iter().map(|f| &f.ty))?)
+                }
+                TypeDefKind::Tuple(t) => Value::Tuple(self.decode_fields(ptr, &t.types)?),
+                _ => bail!("lists of {ty:?} can't be canonical"),
+            },
+            _ => bail!("lists of {ty:?} can't be canonical"),
+        })
+    }
+
+    fn decode_fields<'b>(
+        &self,
+        ptr: u32,
+        types: impl IntoIterator<Item = &'b Type>,
+    ) -> Result<Vec<Value>> {
+        self.sizes
+            .field_offsets(types)
+            .into_iter()
+            .map(|(offset, ty)| self.decode(ty, ptr + offset as u32))
+            .collect()
+    }
+
+    /// Allocates memory for a list, which is owned by the receiver of the
+    /// list if it's lowered through `realloc`.
+    fn alloc(&mut self, size: usize, align: usize, realloc: bool) -> Result<u32> {
+        if realloc {
+            self.memory.alloc(size, align)
+        } else {
+            self.memory.reserve(size, align)
+        }
+    }
+}
+
+fn value(val: Val) -> Result<Value> {
+    match val {
+        Val::Value(value) => Ok(value),
+        Val::Wasm(value) => bail!("expected an interface value, found {value:?}"),
+    }
+}
+
+fn wasm(val: Val) -> Result<WasmValue> {
+    match val {
+        Val::Wasm(value) => Ok(value),
+        Val::Value(value) => bail!("expected a core wasm value, found {value:?}"),
+    }
+}
+
+/// Returns an `i32`, which pointers, lengths and discriminants are.
+fn pointer(val: Val) -> Result<u32> {
+    match wasm(val)? {
+        WasmValue::I32(i) => Ok(i as u32),
+        value => bail!("expected an i32, found {value:?}"),
+    }
+}
+
+fn address(ptr: u32, offset: i32) -> Result<u32> {
+    match u32::try_from(offset).ok().and_then(|o| ptr.checked_add(o)) {
+        Some(ptr) => Ok(ptr),
+        None => bail!("invalid address {ptr:#x} + {offset}"),
+    }
+}
+
+fn check_wasm_types(values: &[WasmValue], tys: &[WasmType]) -> Result<()> {
+    let matches = values.len() == tys.len()
+        && values.iter().zip(tys).all(|(value, ty)| {
+            matches!(
+                (value, ty),
+                (
+                    WasmValue::I32(_),
+                    WasmType::I32 | WasmType::Pointer | WasmType::Length
+                ) | (WasmValue::I64(_), WasmType::I64 | WasmType::PointerOrI64)
+                    | (WasmValue::F32(_), WasmType::F32)
+                    | (WasmValue::F64(_), WasmType::F64)
+            )
+        });
+    if !matches {
+        bail!("expected values of types {tys:?}, found {values:?}");
+    }
+    Ok(())
+}
+
+fn bitcast(cast: &Bitcast, value: WasmValue) -> Result<WasmValue> {
+    use WasmValue::*;
+
+    Ok(match (cast, value) {
+        (Bitcast::None, value) => value,
+        (Bitcast::Sequence(casts), value) => bitcast(&casts[1], bitcast(&casts[0], value)?)?,
+
+        (Bitcast::F32ToI32, F32(f)) => I32(f.to_bits() as i32),
+        (Bitcast::F64ToI64, F64(f)) => I64(f.to_bits() as i64),
+        (Bitcast::F32ToI64, F32(f)) => I64(f.to_bits().into()),
+        (Bitcast::I32ToF32, I32(i)) => F32(f32::from_bits(i as u32)),
+        (Bitcast::I64ToF64, I64(i)) => F64(f64::from_bits(i as u64)),
+        (Bitcast::I64ToF32, I64(i)) => F32(f32::from_bits(i as u32)),
+
+        // `i32`s are zero-extended when joined with `i64`s
+        (Bitcast::I32ToI64 | Bitcast::PToP64 | Bitcast::LToI64, I32(i)) => I64((i as u32).into()),
+        (Bitcast::I64ToI32 | Bitcast::P64ToP | Bitcast::I64ToL, I64(i)) => I32(i as i32),
+
+        // pointers are `i32`s and pointers or `i64`s are `i64`s
+        (Bitcast::P64ToI64 | Bitcast::I64ToP64, I64(i)) => I64(i),
+        (Bitcast::I32ToP | Bitcast::PToI32 | Bitcast::PToL | Bitcast::LToP, I32(i))
+        | (Bitcast::I32ToL | Bitcast::LToI32, I32(i)) => I32(i),
+
+        (cast, value) => bail!("can't bitcast {value:?} with {cast:?}"),
+    })
+}
+
+fn lower_prim(ty: &Type, value: Value) -> Result<WasmValue> {
+    use WasmValue::*;
+
+    Ok(match (ty, value) {
+        (Type::Bool, Value::Bool(b)) => I32(b.into()),
+        (Type::U8, Value::U8(i)) => I32(i.into()),
+        (Type::S8, Value::S8(i)) => I32(i.into()),
+        (Type::U16, Value::U16(i)) => I32(i.into()),
+        (Type::S16, Value::S16(i)) => I32(i.into()),
+        (Type::U32, Value::U32(i)) => I32(i as i32),
+        (Type::S32, Value::S32(i)) => I32(i),
+        (Type::U64, Value::U64(i)) => I64(i as i64),
+        (Type::S64, Value::S64(i)) => I64(i),
+        (Type::Char, Value::Char(c)) => I32(c as i32),
+        (Type::F32, Value::F32(f)) => F32(f),
+        (Type::F64, Value::F64(f)) => F64(f),
+        (ty, value) => bail!("expected a value of type {ty:?}, found {value:?}"),
+    })
+}
+
+fn lift_prim(ty: &Type, value: WasmValue) -> Result<Value> {
+    use WasmValue::*;
+
+    Ok(match (ty, value) {
+        (Type::Bool, I32(0)) => Value::Bool(false),
+        (Type::Bool, I32(1)) => Value::Bool(true),
+        (Type::Bool, I32(i)) => bail!("invalid bool {i}"),
+        (Type::U8, I32(i)) => Value::U8(i as u8),
+        (Type::S8, I32(i)) => Value::S8(i as i8),
+        (Type::U16, I32(i)) => Value::U16(i as u16),
+        (Type::S16, I32(i)) => Value::S16(i as i16),
+        (Type::U32, I32(i)) => Value::U32(i as u32),
+        (Type::S32, I32(i)) => Value::S32(i),
+        (Type::U64, I64(i)) => Value::U64(i as u64),
+        (Type::S64, I64(i)) => Value::S64(i),
+        (Type::Char, I32(i)) => match char::from_u32(i as u32) {
+            Some(c) => Value::Char(c),
+            None => bail!("invalid char {i:#x}"),
+        },
+        (Type::F32, F32(f)) => Value::F32(f),
+        (Type::F64, F64(f)) => Value::F64(f),
+        (ty, value) => bail!("can't lift {value:?} to {ty:?}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use wit_parser::{Function, Resolve, UnresolvedPackage};
+
+    use super::{is_list_canonical, Callee, Interpreter, Memory, Val, Value, WasmValue};
+    use crate::abi::program::Program;
+    use crate::abi::{AbiVariant, LiftLower, WasmSignature};
+
+    const WIT: &str = r#"
+        package test:interp;
+
+        interface i {
+            record r {
+                a: u8,
+                b: s16,
+                c: list<string>,
+                d: option<char>,
+            }
+            variant v {
+                none,
+                int(u64),
+                float(f64),
+                rec(r),
+            }
+            flags f {
+                a,
+                b,
+                c,
+            }
+            enum e {
+                x,
+                y,
+                z,
+            }
+            record p {
+                x: f32,
+                y: u8,
+                z: u64,
+            }
+
+            echo: func(a: r, b: list<v>, c: tuple<f32, s64, bool>, d: result<list<u32>, e>, e: f, f: list<u8>, g: list<p>)
+                -> (a: r, b: list<v>, c: tuple<f32, s64, bool>, d: result<list<u32>, e>, e: f, f: list<u8>, g: list<p>);
+            greet: func() -> string;
+            check: func(b: bool);
+        }
+
+        world w {
+            import i;
+            export i;
+        }
+    "#;
+
+    fn resolve() -> Resolve {
+        let mut resolve = Resolve::default();
+        resolve
+            .push(UnresolvedPackage::parse("test.wit".as_ref(), WIT).unwrap())
+            .unwrap();
+        resolve
+    }
+
+    fn func<'a>(resolve: &'a Resolve, name: &str) -> &'a Function {
+        let (_, iface) = resolve.interfaces.iter().next().unwrap();
+        &iface.functions[name]
+    }
+
+    fn args() -> Vec<Value> {
+        let r = Value::Record(vec![
+            Value::U8(200),
+            Value::S16(-3),
+            Value::List(vec![
+                Value::String("hello".to_string()),
+                Value::String("wörld".to_string()),
+            ]),
+            Value::Option(Some(Box::new(Value::Char('☃')))),
+        ]);
+        vec![
+            r.clone(),
+            Value::List(vec![
+                Value::Variant(0, None),
+                Value::Variant(1, Some(Box::new(Value::U64(u64::MAX)))),
+                Value::Variant(2, Some(Box::new(Value::F64(-1.5)))),
+                Value::Variant(3, Some(Box::new(r))),
+            ]),
+            Value::Tuple(vec![
+                Value::F32(0.25),
+                Value::S64(i64::MIN),
+                Value::Bool(true),
+            ]),
+            Value::Result(Ok(Some(Box::new(Value::List(vec![
+                Value::U32(1),
+                Value::U32(u32::MAX),
+            ]))))),
+            Value::Flags(vec![true, false, true]),
+            Value::List(vec![Value::U8(1), Value::U8(2), Value::U8(3)]),
+            Value::List(vec![
+                Value::Record(vec![Value::F32(1.5), Value::U8(7), Value::U64(1 << 40)]),
+                Value::Record(vec![Value::F32(-0.0), Value::U8(0), Value::U64(u64::MAX)]),
+            ]),
+        ]
+    }
+
+    /// Implements interface functions by returning their arguments.
+    struct Echo;
+
+    impl Callee for Echo {
+        fn call_interface(
+            &mut self,
+            _memory: &mut Memory,
+            _func: &Function,
+            args: Vec<Value>,
+        ) -> Result<Vec<Value>> {
+            Ok(args)
+        }
+    }
+
+    /// Implements core wasm functions by lifting their arguments, calling
+    /// `Echo`, and lowering its results.
+    struct Wasm<'a> {
+        interp: &'a Interpreter<'a>,
+        func: &'a Function,
+        variant: AbiVariant,
+        optimize: fn(&mut Program, &Resolve),
+        results: Vec<WasmValue>,
+    }
+
+    impl Interpreter<'_> {
+        /// Records the program `call` runs, and then runs `optimize` on it.
+        fn program(
+            &self,
+            optimize: fn(&mut Program, &Resolve),
+            variant: AbiVariant,
+            lift_lower: LiftLower,
+            func: &Function,
+        ) -> Program {
+            let mut program = Program::call(
+                self.resolve,
+                variant,
+                lift_lower,
+                func,
+                &self.sizes,
+                is_list_canonical,
+            );
+            optimize(&mut program, self.resolve);
+            program
+        }
+    }
+
+    impl Callee for Wasm<'_> {
+        fn call_wasm(
+            &mut self,
+            memory: &mut Memory,
+            _name: &str,
+            _sig: &WasmSignature,
+            args: Vec<WasmValue>,
+        ) -> Result<Vec<WasmValue>> {
+            let args = args.into_iter().map(Val::Wasm).collect::<Vec<_>>();
+            let program = self.interp.program(
+                self.optimize,
+                self.variant,
+                LiftLower::LiftArgsLowerResults,
+                self.func,
+            );
+            let results = self.interp.run(&program, memory, &args, &mut Echo)?;
+            self.results = results
+                .into_iter()
+                .map(|result| match result {
+                    Val::Wasm(result) => result,
+                    Val::Value(_) => unreachable!(),
+                })
+                .collect();
+            Ok(self.results.clone())
+        }
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        round_trip_with(|_, _| {})
+    }
+
+    #[test]
+    fn optimized_round_trip() -> Result<()> {
+        round_trip_with(|program, resolve| {
+            program.elide_bitcasts();
+            program.merge_loads();
+            program.memcpy_lists(resolve, |_| true);
+        })
+    }
+
+    fn round_trip_with(optimize: fn(&mut Program, &Resolve)) -> Result<()> {
+        let resolve = resolve();
+        let interp = Interpreter::new(&resolve);
+        let echo = func(&resolve, "echo");
+        let args = args().into_iter().map(Val::Value).collect::<Vec<_>>();
+
+        for variant in [AbiVariant::GuestImport, AbiVariant::GuestExport] {
+            let mut memory = Memory::new();
+            let mut wasm = Wasm {
+                interp: &interp,
+                func: echo,
+                variant,
+                optimize,
+                results: Vec::new(),
+            };
+            let program = interp.program(optimize, variant, LiftLower::LowerArgsLiftResults, echo);
+            let results = interp.run(&program, &mut memory, &args, &mut wasm)?;
+            assert_eq!(results, args);
+
+            if let AbiVariant::GuestExport = variant {
+                // The results are freed after the host is done with them,
+                // which leaves the lists the guest took ownership of.
+                let live = memory.allocations().len();
+                let results = wasm.results.into_iter().map(Val::Wasm).collect::<Vec<_>>();
+                interp.post_return(echo, &mut memory, &results)?;
+                assert!(memory.allocations().len() < live);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn post_return() -> Result<()> {
+        struct Greet;
+
+        impl Callee for Greet {
+            fn call_interface(
+                &mut self,
+                _memory: &mut Memory,
+                _func: &Function,
+                _args: Vec<Value>,
+            ) -> Result<Vec<Value>> {
+                Ok(vec![Value::String("hi".to_string())])
+            }
+        }
+
+        let resolve = resolve();
+        let interp = Interpreter::new(&resolve);
+        let greet = func(&resolve, "greet");
+        let mut memory = Memory::new();
+        let results = interp.call(
+            AbiVariant::GuestExport,
+            LiftLower::LiftArgsLowerResults,
+            greet,
+            &mut memory,
+            &[],
+            &mut Greet,
+        )?;
+        assert_eq!(memory.allocations().len(), 1);
+        interp.post_return(greet, &mut memory, &results)?;
+        assert!(memory.allocations().is_empty());
+
+        // freeing the string again is a double free
+        let err = interp
+            .post_return(greet, &mut memory, &results)
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't allocated"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values() {
+        let resolve = resolve();
+        let interp = Interpreter::new(&resolve);
+        let check = func(&resolve, "check");
+        let mut memory = Memory::new();
+
+        let err = interp
+            .call(
+                AbiVariant::GuestExport,
+                LiftLower::LiftArgsLowerResults,
+                check,
+                &mut memory,
+                &[Val::Wasm(WasmValue::I32(2))],
+                &mut Echo,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid bool"), "{err}");
+
+        let err = interp
+            .call(
+                AbiVariant::GuestImport,
+                LiftLower::LowerArgsLiftResults,
+                check,
+                &mut memory,
+                &[Val::Value(Value::U8(1))],
+                &mut Echo,
+            )
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("expected a value of type Bool"),
+            "{err}"
+        );
+    }
+}
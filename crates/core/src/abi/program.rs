@@ -0,0 +1,1292 @@
+//! A recording of the instructions generated by [`call`] and
+//! [`post_return`] which can be optimized and then replayed.
+//!
+//! [`call`] streams instructions straight into [`Bindgen::emit`], so a
+//! generator only ever sees one instruction at a time and any optimization
+//! across instructions has to be reimplemented by each generator. A
+//! [`Program`] instead records the whole tree of instructions, where each
+//! operand is a register defined by an earlier instruction and the blocks of
+//! an instruction are stored alongside it. Passes such as
+//! [`Program::elide_bitcasts`], [`Program::merge_loads`] and
+//! [`Program::memcpy_lists`] rewrite that tree, and [`Program::replay`] then
+//! feeds the result into any [`Bindgen`] as if it came from [`call`].
+
+use std::collections::HashMap;
+use std::mem::{self, Discriminant};
+
+use wit_parser::{
+    Enum, Flags, Function, Handle, Record, Resolve, Result_, SizeAlign, Stream, Tuple, Type,
+    TypeDefKind, TypeId, Variant,
+};
+
+use super::{
+    call, post_return, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmSignature,
+    WasmType,
+};
+
+/// A recorded tree of instructions.
+pub struct Program {
+    registers: usize,
+    body: Block,
+}
+
+/// A sequence of recorded instructions, and the registers it produces.
+pub struct Block {
+    pub nodes: Vec<Node>,
+    /// The registers passed to [`Bindgen::finish_block`], which are always
+    /// empty for the body of a [`Program`].
+    pub results: Vec<usize>,
+}
+
+/// A recorded instruction, whose operands and results are registers.
+pub struct Node {
+    op: Recorded,
+    pub operands: Vec<usize>,
+    pub results: Vec<usize>,
+    /// The blocks the instruction consumes, in the order they were finished.
+    pub blocks: Vec<Block>,
+}
+
+/// What a [`Node`] does.
+pub enum Op<'a> {
+    Instruction(Instruction<'a>),
+    /// A call to [`Bindgen::return_pointer`].
+    ReturnPointer {
+        size: usize,
+        align: usize,
+    },
+}
+
+impl Program {
+    /// Records the instructions that [`call`] generates for `func`.
+    ///
+    /// `sizes` and `is_list_canonical` stand in for [`Bindgen::sizes`] and
+    /// [`Bindgen::is_list_canonical`] of the generator the program will be
+    /// replayed into.
+    pub fn call(
+        resolve: &Resolve,
+        variant: AbiVariant,
+        lift_lower: LiftLower,
+        func: &Function,
+        sizes: &SizeAlign,
+        is_list_canonical: impl Fn(&Type) -> bool,
+    ) -> Program {
+        let mut recorder = Recorder::new(sizes, is_list_canonical);
+        call(resolve, variant, lift_lower, func, &mut recorder);
+        recorder.finish()
+    }
+
+    /// Records the instructions that [`post_return`] generates for `func`.
+    pub fn post_return(resolve: &Resolve, func: &Function, sizes: &SizeAlign) -> Program {
+        let mut recorder = Recorder::new(sizes, |_: &Type| false);
+        post_return(resolve, func, &mut recorder);
+        recorder.finish()
+    }
+
+    /// The number of registers used by the program, which are numbered from
+    /// zero.
+    pub fn registers(&self) -> usize {
+        self.registers
+    }
+
+    /// The top-level block of the program.
+    pub fn body(&self) -> &Block {
+        &self.body
+    }
+
+    /// Feeds the recorded instructions into `bindgen`, in the same order as
+    /// [`call`] would.
+    pub fn replay<B: Bindgen>(&self, resolve: &Resolve, bindgen: &mut B) {
+        let mut registers = vec![None; self.registers];
+        replay(resolve, bindgen, &self.body.nodes, &mut registers);
+    }
+
+    /// Removes casts which don't change their operand, either because they're
+    /// [`Bitcast::None`] or because they undo an earlier cast, along with
+    /// `Bitcasts` instructions which are left without any casts.
+    pub fn elide_bitcasts(&mut self) {
+        let mut renames = HashMap::new();
+        let mut casts = HashMap::new();
+        elide_bitcasts(&mut self.body, &mut renames, &mut casts);
+    }
+
+    /// Reuses the result of a load for later loads of the same kind from the
+    /// same address, as long as nothing in between may write to memory.
+    pub fn merge_loads(&mut self) {
+        let mut renames = HashMap::new();
+        merge_loads(&mut self.body, &mut renames);
+    }
+
+    /// Lowers and lifts lists of [memcpy-able](is_memcpy_able) elements with
+    /// `ListCanonLower` and `ListCanonLift` instead of a loop over their
+    /// elements, if `memcpy` returns true for the element type.
+    ///
+    /// This is meant for generators whose representation of records and
+    /// tuples has the same layout as the canonical ABI, which is unknown to
+    /// [`Bindgen::is_list_canonical`] if it's specific to a type.
+    pub fn memcpy_lists(&mut self, resolve: &Resolve, memcpy: impl Fn(&Type) -> bool) {
+        memcpy_lists(resolve, &mut self.body, &memcpy);
+    }
+}
+
+impl Node {
+    /// Returns the instruction this node records.
+    pub fn op(&self) -> Op<'_> {
+        use Instruction as I;
+
+        let inst = match &self.op {
+            Recorded::Plain(inst) => inst.clone(),
+            Recorded::ReturnPointer { size, align } => {
+                return Op::ReturnPointer {
+                    size: *size,
+                    align: *align,
+                }
+            }
+            Recorded::Bitcasts { casts } => I::Bitcasts { casts },
+            Recorded::ConstZero { tys } => I::ConstZero { tys },
+            Recorded::ListCanonLower { element, realloc } => I::ListCanonLower {
+                element,
+                realloc: realloc.as_deref(),
+            },
+            Recorded::StringLower { realloc } => I::StringLower {
+                realloc: realloc.as_deref(),
+            },
+            Recorded::ListLower { element, realloc } => I::ListLower {
+                element,
+                realloc: realloc.as_deref(),
+            },
+            Recorded::ListCanonLift { element, ty } => I::ListCanonLift { element, ty: *ty },
+            Recorded::ListLift { element, ty } => I::ListLift { element, ty: *ty },
+            Recorded::IterElem { element } => I::IterElem { element },
+            Recorded::RecordLower { record, name, ty } => I::RecordLower {
+                record,
+                name,
+                ty: *ty,
+            },
+            Recorded::RecordLift { record, name, ty } => I::RecordLift {
+                record,
+                name,
+                ty: *ty,
+            },
+            Recorded::HandleLower { handle, name, ty } => I::HandleLower {
+                handle,
+                name,
+                ty: *ty,
+            },
+            Recorded::HandleLift { handle, name, ty } => I::HandleLift {
+                handle,
+                name,
+                ty: *ty,
+            },
+            Recorded::FutureLower { payload, ty } => I::FutureLower { payload, ty: *ty },
+            Recorded::FutureLift { payload, ty } => I::FutureLift { payload, ty: *ty },
+            Recorded::StreamLower { stream, ty } => I::StreamLower { stream, ty: *ty },
+            Recorded::StreamLift { stream, ty } => I::StreamLift { stream, ty: *ty },
+            Recorded::TupleLower { tuple, ty } => I::TupleLower { tuple, ty: *ty },
+            Recorded::TupleLift { tuple, ty } => I::TupleLift { tuple, ty: *ty },
+            Recorded::FlagsLower { flags, name, ty } => I::FlagsLower {
+                flags,
+                name,
+                ty: *ty,
+            },
+            Recorded::FlagsLift { flags, name, ty } => I::FlagsLift {
+                flags,
+                name,
+                ty: *ty,
+            },
+            Recorded::VariantLower {
+                variant,
+                name,
+                ty,
+                results,
+            } => I::VariantLower {
+                variant,
+                name,
+                ty: *ty,
+                results,
+            },
+            Recorded::VariantLift { variant, name, ty } => I::VariantLift {
+                variant,
+                name,
+                ty: *ty,
+            },
+            Recorded::EnumLower { enum_, name, ty } => I::EnumLower {
+                enum_,
+                name,
+                ty: *ty,
+            },
+            Recorded::EnumLift { enum_, name, ty } => I::EnumLift {
+                enum_,
+                name,
+                ty: *ty,
+            },
+            Recorded::OptionLower {
+                payload,
+                ty,
+                results,
+            } => I::OptionLower {
+                payload,
+                ty: *ty,
+                results,
+            },
+            Recorded::OptionLift { payload, ty } => I::OptionLift { payload, ty: *ty },
+            Recorded::ResultLower {
+                result,
+                ty,
+                results,
+            } => I::ResultLower {
+                result,
+                ty: *ty,
+                results,
+            },
+            Recorded::ResultLift { result, ty } => I::ResultLift { result, ty: *ty },
+            Recorded::CallWasm { name, sig } => I::CallWasm { name, sig },
+            Recorded::CallInterface { func } => I::CallInterface { func },
+            Recorded::Return { amt, func } => I::Return { amt: *amt, func },
+            Recorded::GuestDeallocateList { element } => I::GuestDeallocateList { element },
+        };
+        Op::Instruction(inst)
+    }
+}
+
+/// Returns whether values of `ty` have the same representation in linear
+/// memory as in the language of the generator, assuming it lays out records
+/// and tuples the same way as C, so lists of them can be copied as is.
+///
+/// This is the case for numbers, and records and tuples of them, but not for
+/// `bool` or `char` since lifting them has to validate them.
+pub fn is_memcpy_able(resolve: &Resolve, ty: &Type) -> bool {
+    match ty {
+        Type::U8
+        | Type::S8
+        | Type::U16
+        | Type::S16
+        | Type::U32
+        | Type::S32
+        | Type::U64
+        | Type::S64
+        | Type::F32
+        | Type::F64 => true,
+        Type::Bool | Type::Char | Type::String => false,
+        Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Type(t) => is_memcpy_able(resolve, t),
+            TypeDefKind::Record(r) => r.fields.iter().all(|f| is_memcpy_able(resolve, &f.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().all(|t| is_memcpy_able(resolve, t)),
+            _ => false,
+        },
+    }
+}
+
+/// An owned version of an [`Instruction`].
+///
+/// Instructions without any borrowed data are kept as they are, and the rest
+/// own what they borrow so a [`Program`] can outlive the [`Resolve`] and the
+/// locals of [`call`] they borrow from.
+enum Recorded {
+    Plain(Instruction<'static>),
+    ReturnPointer {
+        size: usize,
+        align: usize,
+    },
+    Bitcasts {
+        casts: Vec<Bitcast>,
+    },
+    ConstZero {
+        tys: Vec<WasmType>,
+    },
+    ListCanonLower {
+        element: Type,
+        realloc: Option<String>,
+    },
+    StringLower {
+        realloc: Option<String>,
+    },
+    ListLower {
+        element: Type,
+        realloc: Option<String>,
+    },
+    ListCanonLift {
+        element: Type,
+        ty: TypeId,
+    },
+    ListLift {
+        element: Type,
+        ty: TypeId,
+    },
+    IterElem {
+        element: Type,
+    },
+    RecordLower {
+        record: Record,
+        name: String,
+        ty: TypeId,
+    },
+    RecordLift {
+        record: Record,
+        name: String,
+        ty: TypeId,
+    },
+    HandleLower {
+        handle: Handle,
+        name: String,
+        ty: TypeId,
+    },
+    HandleLift {
+        handle: Handle,
+        name: String,
+        ty: TypeId,
+    },
+    FutureLower {
+        payload: Option<Type>,
+        ty: TypeId,
+    },
+    FutureLift {
+        payload: Option<Type>,
+        ty: TypeId,
+    },
+    StreamLower {
+        stream: Stream,
+        ty: TypeId,
+    },
+    StreamLift {
+        stream: Stream,
+        ty: TypeId,
+    },
+    TupleLower {
+        tuple: Tuple,
+        ty: TypeId,
+    },
+    TupleLift {
+        tuple: Tuple,
+        ty: TypeId,
+    },
+    FlagsLower {
+        flags: Flags,
+        name: String,
+        ty: TypeId,
+    },
+    FlagsLift {
+        flags: Flags,
+        name: String,
+        ty: TypeId,
+    },
+    VariantLower {
+        variant: Variant,
+        name: String,
+        ty: TypeId,
+        results: Vec<WasmType>,
+    },
+    VariantLift {
+        variant: Variant,
+        name: String,
+        ty: TypeId,
+    },
+    EnumLower {
+        enum_: Enum,
+        name: String,
+        ty: TypeId,
+    },
+    EnumLift {
+        enum_: Enum,
+        name: String,
+        ty: TypeId,
+    },
+    OptionLower {
+        payload: Type,
+        ty: TypeId,
+        results: Vec<WasmType>,
+    },
+    OptionLift {
+        payload: Type,
+        ty: TypeId,
+    },
+    ResultLower {
+        result: Result_,
+        ty: TypeId,
+        results: Vec<WasmType>,
+    },
+    ResultLift {
+        result: Result_,
+        ty: TypeId,
+    },
+    CallWasm {
+        name: String,
+        sig: WasmSignature,
+    },
+    CallInterface {
+        func: Function,
+    },
+    Return {
+        amt: usize,
+        func: Function,
+    },
+    GuestDeallocateList {
+        element: Type,
+    },
+}
+
+impl Recorded {
+    fn new(inst: &Instruction<'_>) -> Recorded {
+        use Instruction as I;
+
+        let plain = match inst {
+            I::GetArg { nth } => I::GetArg { nth: *nth },
+            I::I32Const { val } => I::I32Const { val: *val },
+            I::I32Load { offset } => I::I32Load { offset: *offset },
+            I::I32Load8U { offset } => I::I32Load8U { offset: *offset },
+            I::I32Load8S { offset } => I::I32Load8S { offset: *offset },
+            I::I32Load16U { offset } => I::I32Load16U { offset: *offset },
+            I::I32Load16S { offset } => I::I32Load16S { offset: *offset },
+            I::I64Load { offset } => I::I64Load { offset: *offset },
+            I::F32Load { offset } => I::F32Load { offset: *offset },
+            I::F64Load { offset } => I::F64Load { offset: *offset },
+            I::PointerLoad { offset } => I::PointerLoad { offset: *offset },
+            I::LengthLoad { offset } => I::LengthLoad { offset: *offset },
+            I::I32Store { offset } => I::I32Store { offset: *offset },
+            I::I32Store8 { offset } => I::I32Store8 { offset: *offset },
+            I::I32Store16 { offset } => I::I32Store16 { offset: *offset },
+            I::I64Store { offset } => I::I64Store { offset: *offset },
+            I::F32Store { offset } => I::F32Store { offset: *offset },
+            I::F64Store { offset } => I::F64Store { offset: *offset },
+            I::PointerStore { offset } => I::PointerStore { offset: *offset },
+            I::LengthStore { offset } => I::LengthStore { offset: *offset },
+            I::I32FromChar => I::I32FromChar,
+            I::I64FromU64 => I::I64FromU64,
+            I::I64FromS64 => I::I64FromS64,
+            I::I32FromU32 => I::I32FromU32,
+            I::I32FromS32 => I::I32FromS32,
+            I::I32FromU16 => I::I32FromU16,
+            I::I32FromS16 => I::I32FromS16,
+            I::I32FromU8 => I::I32FromU8,
+            I::I32FromS8 => I::I32FromS8,
+            I::F32FromFloat32 => I::F32FromFloat32,
+            I::F64FromFloat64 => I::F64FromFloat64,
+            I::S8FromI32 => I::S8FromI32,
+            I::U8FromI32 => I::U8FromI32,
+            I::S16FromI32 => I::S16FromI32,
+            I::U16FromI32 => I::U16FromI32,
+            I::S32FromI32 => I::S32FromI32,
+            I::U32FromI32 => I::U32FromI32,
+            I::S64FromI64 => I::S64FromI64,
+            I::U64FromI64 => I::U64FromI64,
+            I::CharFromI32 => I::CharFromI32,
+            I::Float32FromF32 => I::Float32FromF32,
+            I::Float64FromF64 => I::Float64FromF64,
+            I::BoolFromI32 => I::BoolFromI32,
+            I::I32FromBool => I::I32FromBool,
+            I::StringLift => I::StringLift,
+            I::IterBasePointer => I::IterBasePointer,
+            I::VariantPayloadName => I::VariantPayloadName,
+            I::Malloc {
+                realloc,
+                size,
+                align,
+            } => I::Malloc {
+                realloc,
+                size: *size,
+                align: *align,
+            },
+            I::GuestDeallocate { size, align } => I::GuestDeallocate {
+                size: *size,
+                align: *align,
+            },
+            I::GuestDeallocateString => I::GuestDeallocateString,
+            I::GuestDeallocateVariant { blocks } => I::GuestDeallocateVariant { blocks: *blocks },
+
+            I::Bitcasts { casts } => {
+                return Recorded::Bitcasts {
+                    casts: casts.to_vec(),
+                }
+            }
+            I::ConstZero { tys } => return Recorded::ConstZero { tys: tys.to_vec() },
+            I::ListCanonLower { element, realloc } => {
+                return Recorded::ListCanonLower {
+                    element: **element,
+                    realloc: realloc.map(|s| s.to_string()),
+                }
+            }
+            I::StringLower { realloc } => {
+                return Recorded::StringLower {
+                    realloc: realloc.map(|s| s.to_string()),
+                }
+            }
+            I::ListLower { element, realloc } => {
+                return Recorded::ListLower {
+                    element: **element,
+                    realloc: realloc.map(|s| s.to_string()),
+                }
+            }
+            I::ListCanonLift { element, ty } => {
+                return Recorded::ListCanonLift {
+                    element: **element,
+                    ty: *ty,
+                }
+            }
+            I::ListLift { element, ty } => {
+                return Recorded::ListLift {
+                    element: **element,
+                    ty: *ty,
+                }
+            }
+            I::IterElem { element } => return Recorded::IterElem { element: **element },
+            I::RecordLower { record, name, ty } => {
+                return Recorded::RecordLower {
+                    record: (*record).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::RecordLift { record, name, ty } => {
+                return Recorded::RecordLift {
+                    record: (*record).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::HandleLower { handle, name, ty } => {
+                return Recorded::HandleLower {
+                    handle: **handle,
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::HandleLift { handle, name, ty } => {
+                return Recorded::HandleLift {
+                    handle: **handle,
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::FutureLower { payload, ty } => {
+                return Recorded::FutureLower {
+                    payload: **payload,
+                    ty: *ty,
+                }
+            }
+            I::FutureLift { payload, ty } => {
+                return Recorded::FutureLift {
+                    payload: **payload,
+                    ty: *ty,
+                }
+            }
+            I::StreamLower { stream, ty } => {
+                return Recorded::StreamLower {
+                    stream: (*stream).clone(),
+                    ty: *ty,
+                }
+            }
+            I::StreamLift { stream, ty } => {
+                return Recorded::StreamLift {
+                    stream: (*stream).clone(),
+                    ty: *ty,
+                }
+            }
+            I::TupleLower { tuple, ty } => {
+                return Recorded::TupleLower {
+                    tuple: (*tuple).clone(),
+                    ty: *ty,
+                }
+            }
+            I::TupleLift { tuple, ty } => {
+                return Recorded::TupleLift {
+                    tuple: (*tuple).clone(),
+                    ty: *ty,
+                }
+            }
+            I::FlagsLower { flags, name, ty } => {
+                return Recorded::FlagsLower {
+                    flags: (*flags).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::FlagsLift { flags, name, ty } => {
+                return Recorded::FlagsLift {
+                    flags: (*flags).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::VariantLower {
+                variant,
+                name,
+                ty,
+                results,
+            } => {
+                return Recorded::VariantLower {
+                    variant: (*variant).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                    results: results.to_vec(),
+                }
+            }
+            I::VariantLift { variant, name, ty } => {
+                return Recorded::VariantLift {
+                    variant: (*variant).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::EnumLower { enum_, name, ty } => {
+                return Recorded::EnumLower {
+                    enum_: (*enum_).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::EnumLift { enum_, name, ty } => {
+                return Recorded::EnumLift {
+                    enum_: (*enum_).clone(),
+                    name: name.to_string(),
+                    ty: *ty,
+                }
+            }
+            I::OptionLower {
+                payload,
+                ty,
+                results,
+            } => {
+                return Recorded::OptionLower {
+                    payload: **payload,
+                    ty: *ty,
+                    results: results.to_vec(),
+                }
+            }
+            I::OptionLift { payload, ty } => {
+                return Recorded::OptionLift {
+                    payload: **payload,
+                    ty: *ty,
+                }
+            }
+            I::ResultLower {
+                result,
+                ty,
+                results,
+            } => {
+                return Recorded::ResultLower {
+                    result: (*result).clone(),
+                    ty: *ty,
+                    results: results.to_vec(),
+                }
+            }
+            I::ResultLift { result, ty } => {
+                return Recorded::ResultLift {
+                    result: (*result).clone(),
+                    ty: *ty,
+                }
+            }
+            I::CallWasm { name, sig } => {
+                return Recorded::CallWasm {
+                    name: name.to_string(),
+                    sig: (*sig).clone(),
+                }
+            }
+            I::CallInterface { func } => {
+                return Recorded::CallInterface {
+                    func: (*func).clone(),
+                }
+            }
+            I::Return { amt, func } => {
+                return Recorded::Return {
+                    amt: *amt,
+                    func: (*func).clone(),
+                }
+            }
+            I::GuestDeallocateList { element } => {
+                return Recorded::GuestDeallocateList { element: **element }
+            }
+        };
+        Recorded::Plain(plain)
+    }
+
+    /// Returns the kind and offset of a load.
+    fn load(&self) -> Option<(Discriminant<Instruction<'static>>, i32)> {
+        use Instruction as I;
+
+        match self {
+            Recorded::Plain(
+                inst @ (I::I32Load { offset }
+                | I::I32Load8U { offset }
+                | I::I32Load8S { offset }
+                | I::I32Load16U { offset }
+                | I::I32Load16S { offset }
+                | I::I64Load { offset }
+                | I::F32Load { offset }
+                | I::F64Load { offset }
+                | I::PointerLoad { offset }
+                | I::LengthLoad { offset }),
+            ) => Some((mem::discriminant(inst), *offset)),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this may write to memory, or otherwise change what
+    /// a load from it returns, such as by deallocating it.
+    fn clobbers_memory(&self) -> bool {
+        use Instruction as I;
+
+        match self {
+            Recorded::Plain(inst) => matches!(
+                inst,
+                I::I32Store { .. }
+                    | I::I32Store8 { .. }
+                    | I::I32Store16 { .. }
+                    | I::I64Store { .. }
+                    | I::F32Store { .. }
+                    | I::F64Store { .. }
+                    | I::PointerStore { .. }
+                    | I::LengthStore { .. }
+                    | I::StringLift
+                    | I::Malloc { .. }
+                    | I::GuestDeallocate { .. }
+                    | I::GuestDeallocateString
+                    | I::GuestDeallocateVariant { .. }
+            ),
+            Recorded::ReturnPointer { .. }
+            | Recorded::Bitcasts { .. }
+            | Recorded::ConstZero { .. }
+            | Recorded::IterElem { .. }
+            | Recorded::RecordLower { .. }
+            | Recorded::RecordLift { .. }
+            | Recorded::HandleLower { .. }
+            | Recorded::HandleLift { .. }
+            | Recorded::FutureLower { .. }
+            | Recorded::FutureLift { .. }
+            | Recorded::StreamLower { .. }
+            | Recorded::StreamLift { .. }
+            | Recorded::TupleLower { .. }
+            | Recorded::TupleLift { .. }
+            | Recorded::FlagsLower { .. }
+            | Recorded::FlagsLift { .. }
+            | Recorded::EnumLower { .. }
+            | Recorded::EnumLift { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+/// A [`Bindgen`] which records the instructions it's fed into a tree of
+/// blocks, where each operand is a register.
+struct Recorder<'a, F> {
+    sizes: &'a SizeAlign,
+    is_list_canonical: F,
+    registers: usize,
+    blocks: Vec<Vec<Node>>,
+    finished: Vec<Block>,
+}
+
+impl<'a, F> Recorder<'a, F> {
+    fn new(sizes: &'a SizeAlign, is_list_canonical: F) -> Recorder<'a, F> {
+        Recorder {
+            sizes,
+            is_list_canonical,
+            registers: 0,
+            blocks: vec![Vec::new()],
+            finished: Vec::new(),
+        }
+    }
+
+    fn register(&mut self) -> usize {
+        self.registers += 1;
+        self.registers - 1
+    }
+
+    fn push(&mut self, node: Node) {
+        self.blocks.last_mut().unwrap().push(node);
+    }
+
+    fn finish(mut self) -> Program {
+        assert_eq!(self.blocks.len(), 1);
+        assert!(self.finished.is_empty());
+        Program {
+            registers: self.registers,
+            body: Block {
+                nodes: self.blocks.pop().unwrap(),
+                results: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<F: Fn(&Type) -> bool> Bindgen for Recorder<'_, F> {
+    type Operand = usize;
+
+    fn emit(
+        &mut self,
+        _resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<usize>,
+        results: &mut Vec<usize>,
+    ) {
+        let blocks = match inst {
+            Instruction::VariantLower { variant, .. }
+            | Instruction::VariantLift { variant, .. } => variant.cases.len(),
+            Instruction::OptionLower { .. }
+            | Instruction::OptionLift { .. }
+            | Instruction::ResultLower { .. }
+            | Instruction::ResultLift { .. } => 2,
+            Instruction::ListLower { .. }
+            | Instruction::ListLift { .. }
+            | Instruction::GuestDeallocateList { .. } => 1,
+            Instruction::GuestDeallocateVariant { blocks } => *blocks,
+            _ => 0,
+        };
+        let blocks = self.finished.split_off(self.finished.len() - blocks);
+        for _ in 0..inst.results_len() {
+            let register = self.register();
+            results.push(register);
+        }
+        self.push(Node {
+            op: Recorded::new(inst),
+            operands: operands.clone(),
+            results: results.clone(),
+            blocks,
+        });
+    }
+
+    fn return_pointer(&mut self, size: usize, align: usize) -> usize {
+        let register = self.register();
+        self.push(Node {
+            op: Recorded::ReturnPointer { size, align },
+            operands: Vec::new(),
+            results: vec![register],
+            blocks: Vec::new(),
+        });
+        register
+    }
+
+    fn push_block(&mut self) {
+        self.blocks.push(Vec::new());
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<usize>) {
+        let nodes = self.blocks.pop().unwrap();
+        self.finished.push(Block {
+            nodes,
+            results: mem::take(operands),
+        });
+    }
+
+    fn sizes(&self) -> &SizeAlign {
+        self.sizes
+    }
+
+    fn is_list_canonical(&self, _resolve: &Resolve, element: &Type) -> bool {
+        (self.is_list_canonical)(element)
+    }
+}
+
+fn replay<B: Bindgen>(
+    resolve: &Resolve,
+    bindgen: &mut B,
+    nodes: &[Node],
+    registers: &mut Vec<Option<B::Operand>>,
+) {
+    fn get<T: Clone>(registers: &[Option<T>], register: usize) -> T {
+        registers[register]
+            .clone()
+            .unwrap_or_else(|| panic!("register {register} is read before it's written"))
+    }
+
+    for node in nodes {
+        for block in node.blocks.iter() {
+            bindgen.push_block();
+            replay(resolve, bindgen, &block.nodes, registers);
+            let mut operands = block.results.iter().map(|r| get(registers, *r)).collect();
+            bindgen.finish_block(&mut operands);
+        }
+        let results = match node.op() {
+            Op::ReturnPointer { size, align } => vec![bindgen.return_pointer(size, align)],
+            Op::Instruction(inst) => {
+                let mut operands = node.operands.iter().map(|r| get(registers, *r)).collect();
+                let mut results = Vec::new();
+                bindgen.emit(resolve, &inst, &mut operands, &mut results);
+                results
+            }
+        };
+        assert_eq!(results.len(), node.results.len());
+        for (register, result) in node.results.iter().zip(results) {
+            registers[*register] = Some(result);
+        }
+    }
+}
+
+/// Replaces the registers used by `block` according to `renames`, which
+/// maps the results of removed instructions to the registers they're equal
+/// to.
+fn rename(registers: &mut [usize], renames: &HashMap<usize, usize>) {
+    for register in registers {
+        if let Some(renamed) = renames.get(register) {
+            *register = *renamed;
+        }
+    }
+}
+
+fn elide_bitcasts(
+    block: &mut Block,
+    renames: &mut HashMap<usize, usize>,
+    // the register each cast register was cast from, and the cast
+    casts: &mut HashMap<usize, (usize, Bitcast)>,
+) {
+    let mut nodes = Vec::with_capacity(block.nodes.len());
+    for mut node in mem::take(&mut block.nodes) {
+        for block in node.blocks.iter_mut() {
+            elide_bitcasts(block, renames, casts);
+        }
+        rename(&mut node.operands, renames);
+        let Recorded::Bitcasts { casts: node_casts } = &mut node.op else {
+            nodes.push(node);
+            continue;
+        };
+
+        let mut kept = Vec::new();
+        let mut operands = Vec::new();
+        let mut results = Vec::new();
+        for ((cast, operand), result) in mem::take(node_casts)
+            .into_iter()
+            .zip(&node.operands)
+            .zip(&node.results)
+        {
+            let source = match casts.get(operand) {
+                Some((source, prev)) if undoes(prev, &cast) => Some(*source),
+                _ if cast == Bitcast::None => Some(*operand),
+                _ => None,
+            };
+            match source {
+                Some(source) => {
+                    renames.insert(*result, source);
+                }
+                None => {
+                    casts.insert(*result, (*operand, cast.clone()));
+                    kept.push(cast);
+                    operands.push(*operand);
+                    results.push(*result);
+                }
+            }
+        }
+        if kept.is_empty() {
+            continue;
+        }
+        node.op = Recorded::Bitcasts { casts: kept };
+        node.operands = operands;
+        node.results = results;
+        nodes.push(node);
+    }
+    block.nodes = nodes;
+    rename(&mut block.results, renames);
+}
+
+/// Returns whether casting with `first` and then `second` gives back the
+/// original value.
+fn undoes(first: &Bitcast, second: &Bitcast) -> bool {
+    use Bitcast::*;
+
+    matches!(
+        (first, second),
+        (F32ToI32, I32ToF32)
+            | (I32ToF32, F32ToI32)
+            | (F64ToI64, I64ToF64)
+            | (I64ToF64, F64ToI64)
+            | (I32ToI64, I64ToI32)
+            | (F32ToI64, I64ToF32)
+            | (I64ToP64, P64ToI64)
+            | (PToP64, P64ToP)
+            | (I32ToP, PToI32)
+            | (I32ToL, LToI32)
+            | (I64ToL, LToI64)
+    )
+}
+
+fn merge_loads(block: &mut Block, renames: &mut HashMap<usize, usize>) {
+    // Loads aren't reused across blocks since a block may run any number of
+    // times, e.g. once per element of a list.
+    let mut loads = HashMap::new();
+    let mut nodes = Vec::with_capacity(block.nodes.len());
+    for mut node in mem::take(&mut block.nodes) {
+        for block in node.blocks.iter_mut() {
+            merge_loads(block, renames);
+        }
+        rename(&mut node.operands, renames);
+        if let Some((kind, offset)) = node.op.load() {
+            match loads.get(&(kind, node.operands[0], offset)) {
+                Some(result) => {
+                    renames.insert(node.results[0], *result);
+                    continue;
+                }
+                None => {
+                    loads.insert((kind, node.operands[0], offset), node.results[0]);
+                }
+            }
+        } else if !node.blocks.is_empty() || node.op.clobbers_memory() {
+            loads.clear();
+        }
+        nodes.push(node);
+    }
+    block.nodes = nodes;
+    rename(&mut block.results, renames);
+}
+
+fn memcpy_lists(resolve: &Resolve, block: &mut Block, memcpy: &dyn Fn(&Type) -> bool) {
+    for node in block.nodes.iter_mut() {
+        for block in node.blocks.iter_mut() {
+            memcpy_lists(resolve, block, memcpy);
+        }
+        let op = match &node.op {
+            Recorded::ListLower { element, realloc }
+                if is_memcpy_able(resolve, element) && memcpy(element) =>
+            {
+                Recorded::ListCanonLower {
+                    element: *element,
+                    realloc: realloc.clone(),
+                }
+            }
+            Recorded::ListLift { element, ty }
+                if is_memcpy_able(resolve, element) && memcpy(element) =>
+            {
+                Recorded::ListCanonLift {
+                    element: *element,
+                    ty: *ty,
+                }
+            }
+            _ => continue,
+        };
+        node.op = op;
+        node.blocks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wit_parser::{Function, Resolve, SizeAlign, Type, UnresolvedPackage};
+
+    use super::{Block, Node, Op, Program, Recorded};
+    use crate::abi::{call, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower};
+
+    const WIT: &str = r#"
+        package test:program;
+
+        interface i {
+            record point {
+                x: f32,
+                y: f32,
+            }
+            variant v {
+                a(f32),
+                b(u32),
+                c(u64),
+                d(f64),
+                e(tuple<u32, f32>),
+            }
+
+            f: func(a: v, b: list<point>, c: option<string>) -> (a: v, b: list<point>, c: result<u16, string>);
+        }
+
+        world w {
+            import i;
+            export i;
+        }
+    "#;
+
+    fn resolve() -> Resolve {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), WIT).unwrap();
+        resolve.push(pkg).unwrap();
+        resolve
+    }
+
+    fn func(resolve: &Resolve) -> &Function {
+        let (_, iface) = resolve.interfaces.iter().next().unwrap();
+        &iface.functions["f"]
+    }
+
+    fn sizes(resolve: &Resolve) -> SizeAlign {
+        let mut sizes = SizeAlign::default();
+        sizes.fill(resolve);
+        sizes
+    }
+
+    fn programs(resolve: &Resolve, sizes: &SizeAlign) -> Vec<Program> {
+        let func = func(resolve);
+        let mut programs = Vec::new();
+        for variant in [AbiVariant::GuestImport, AbiVariant::GuestExport] {
+            for lift_lower in [
+                LiftLower::LowerArgsLiftResults,
+                LiftLower::LiftArgsLowerResults,
+            ] {
+                programs.push(Program::call(
+                    resolve,
+                    variant,
+                    lift_lower,
+                    func,
+                    sizes,
+                    |_| false,
+                ));
+            }
+        }
+        programs.push(Program::post_return(resolve, func, sizes));
+        programs
+    }
+
+    /// Counts the nodes of `block` for which `f` returns true.
+    fn count(block: &Block, f: &dyn Fn(&Instruction<'_>) -> bool) -> usize {
+        block
+            .nodes
+            .iter()
+            .map(|node| {
+                let matches = match node.op() {
+                    Op::Instruction(inst) => f(&inst) as usize,
+                    Op::ReturnPointer { .. } => 0,
+                };
+                matches + node.blocks.iter().map(|b| count(b, f)).sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// A `Bindgen` which logs everything it's fed.
+    struct Log<'a> {
+        sizes: &'a SizeAlign,
+        log: Vec<String>,
+        operands: usize,
+    }
+
+    impl Bindgen for Log<'_> {
+        type Operand = usize;
+
+        fn emit(
+            &mut self,
+            _resolve: &Resolve,
+            inst: &Instruction<'_>,
+            operands: &mut Vec<usize>,
+            results: &mut Vec<usize>,
+        ) {
+            self.log.push(format!("{inst:?} {operands:?}"));
+            for _ in 0..inst.results_len() {
+                results.push(self.operands);
+                self.operands += 1;
+            }
+        }
+
+        fn return_pointer(&mut self, size: usize, align: usize) -> usize {
+            self.log.push(format!("return_pointer {size} {align}"));
+            self.operands += 1;
+            self.operands - 1
+        }
+
+        fn push_block(&mut self) {
+            self.log.push("push_block".to_string());
+        }
+
+        fn finish_block(&mut self, operands: &mut Vec<usize>) {
+            self.log.push(format!("finish_block {operands:?}"));
+            operands.clear();
+        }
+
+        fn sizes(&self) -> &SizeAlign {
+            self.sizes
+        }
+
+        fn is_list_canonical(&self, _resolve: &Resolve, _element: &Type) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn replay() {
+        let resolve = resolve();
+        let sizes = sizes(&resolve);
+        let func = func(&resolve);
+        for variant in [AbiVariant::GuestImport, AbiVariant::GuestExport] {
+            for lift_lower in [
+                LiftLower::LowerArgsLiftResults,
+                LiftLower::LiftArgsLowerResults,
+            ] {
+                let mut expected = Log {
+                    sizes: &sizes,
+                    log: Vec::new(),
+                    operands: 0,
+                };
+                call(&resolve, variant, lift_lower, func, &mut expected);
+
+                let program = Program::call(&resolve, variant, lift_lower, func, &sizes, |_| false);
+                let mut actual = Log {
+                    sizes: &sizes,
+                    log: Vec::new(),
+                    operands: 0,
+                };
+                program.replay(&resolve, &mut actual);
+                assert_eq!(actual.log, expected.log);
+            }
+        }
+    }
+
+    #[test]
+    fn elide_bitcasts() {
+        let resolve = resolve();
+        let sizes = sizes(&resolve);
+        let is_none = |inst: &Instruction<'_>| match inst {
+            Instruction::Bitcasts { casts } => casts.contains(&Bitcast::None),
+            _ => false,
+        };
+        let mut elided = 0;
+        for mut program in programs(&resolve, &sizes) {
+            elided += count(program.body(), &is_none);
+            program.elide_bitcasts();
+            assert_eq!(count(program.body(), &is_none), 0);
+        }
+        assert!(elided > 0);
+    }
+
+    #[test]
+    fn merge_loads() {
+        let resolve = resolve();
+        let func = func(&resolve);
+        let node = |inst: &Instruction<'_>, operands: &[usize], results: &[usize]| Node {
+            op: Recorded::new(inst),
+            operands: operands.to_vec(),
+            results: results.to_vec(),
+            blocks: Vec::new(),
+        };
+        let mut program = Program {
+            registers: 5,
+            body: Block {
+                nodes: vec![
+                    node(&Instruction::GetArg { nth: 0 }, &[], &[0]),
+                    node(&Instruction::I32Load { offset: 4 }, &[0], &[1]),
+                    node(&Instruction::I32Load { offset: 4 }, &[0], &[2]),
+                    node(&Instruction::I32Load8U { offset: 4 }, &[0], &[3]),
+                    node(&Instruction::I32Store { offset: 0 }, &[2, 0], &[]),
+                    node(&Instruction::I32Load { offset: 4 }, &[0], &[4]),
+                    node(&Instruction::Return { amt: 3, func }, &[1, 2, 4], &[]),
+                ],
+                results: Vec::new(),
+            },
+        };
+        program.merge_loads();
+
+        // the second load reuses the first, but the load of a different kind
+        // and the one after the store don't
+        let nodes = &program.body().nodes;
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(nodes[3].operands, [1, 0]);
+        assert_eq!(nodes[5].operands, [1, 1, 4]);
+    }
+
+    #[test]
+    fn memcpy_lists() {
+        let resolve = resolve();
+        let sizes = sizes(&resolve);
+        let is_list = |inst: &Instruction<'_>| {
+            matches!(
+                inst,
+                Instruction::ListLower { .. } | Instruction::ListLift { .. }
+            )
+        };
+        for mut program in programs(&resolve, &sizes) {
+            let lists = count(program.body(), &is_list);
+            program.memcpy_lists(&resolve, |_| false);
+            assert_eq!(count(program.body(), &is_list), lists);
+            program.memcpy_lists(&resolve, |_| true);
+            assert_eq!(count(program.body(), &is_list), 0);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::fmt::Write;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 pub use wit_parser;
 use wit_parser::*;
 pub mod abi;
@@ -53,6 +53,22 @@ pub struct TypeInfo {
 
     /// Whether or not this type (transitively) has an own handle.
     pub has_own_handle: bool,
+
+    /// Whether or not this type is ever used (transitively) within the
+    /// parameters or results of an imported function.
+    pub imported: bool,
+
+    /// Whether or not this type is ever used (transitively) within the
+    /// parameters or results of an exported function.
+    pub exported: bool,
+
+    /// Whether or not this type is ever used (transitively) within the element
+    /// type of a list, as opposed to only being passed around on its own.
+    pub in_list: bool,
+
+    /// Whether or not this type is ever used (transitively) within the
+    /// signature of a resource's constructor, method or static function.
+    pub resource_method: bool,
 }
 
 impl std::ops::BitOrAssign for TypeInfo {
@@ -64,6 +80,10 @@ impl std::ops::BitOrAssign for TypeInfo {
         self.has_resource |= rhs.has_resource;
         self.has_borrow_handle |= rhs.has_borrow_handle;
         self.has_own_handle |= rhs.has_own_handle;
+        self.imported |= rhs.imported;
+        self.exported |= rhs.exported;
+        self.in_list |= rhs.in_list;
+        self.resource_method |= rhs.resource_method;
     }
 }
 
@@ -110,7 +130,7 @@ impl Types {
             live.add_type(resolve, ty);
         }
         for id in live.iter() {
-            if resolve.types[id].name.is_some() {
+            if is_defined(resolve, id) {
                 let info = self.type_info.get_mut(&id).unwrap();
                 if import {
                     info.borrowed = true;
@@ -125,11 +145,37 @@ impl Types {
             live.add_type(resolve, ty);
         }
         for id in live.iter() {
-            if resolve.types[id].name.is_some() {
+            if is_defined(resolve, id) {
                 self.type_info.get_mut(&id).unwrap().owned = true;
             }
         }
 
+        let mut live = LiveTypes::default();
+        live.add_func(resolve, func);
+        let mut in_list = LiveTypes::default();
+        for id in live.iter() {
+            if let TypeDefKind::List(ty) = &resolve.types[id].kind {
+                in_list.add_type(resolve, ty);
+            }
+        }
+        let resource_method = !matches!(func.kind, FunctionKind::Freestanding);
+        for id in live.iter() {
+            if is_defined(resolve, id) {
+                let info = self.type_info.get_mut(&id).unwrap();
+                if import {
+                    info.imported = true;
+                } else {
+                    info.exported = true;
+                }
+                info.resource_method |= resource_method;
+            }
+        }
+        for id in in_list.iter() {
+            if is_defined(resolve, id) {
+                self.type_info.get_mut(&id).unwrap().in_list = true;
+            }
+        }
+
         for ty in func.results.iter_types() {
             let id = match ty {
                 Type::Id(id) => *id,
@@ -208,12 +254,18 @@ impl Types {
                 info = self.optional_type_info(resolve, r.ok.as_ref());
                 info |= self.optional_type_info(resolve, r.err.as_ref());
             }
+            // Futures and streams are themselves represented as owned handles,
+            // regardless of their payload.
             TypeDefKind::Future(ty) => {
                 info = self.optional_type_info(resolve, ty.as_ref());
+                info.has_own_handle = true;
+                info.has_resource = true;
             }
             TypeDefKind::Stream(stream) => {
                 info = self.optional_type_info(resolve, stream.element.as_ref());
                 info |= self.optional_type_info(resolve, stream.end.as_ref());
+                info.has_own_handle = true;
+                info.has_resource = true;
             }
             TypeDefKind::Unknown => unreachable!(),
         }
@@ -243,6 +295,14 @@ impl Types {
 pub trait WorldGenerator {
     fn generate(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
         let world = &resolve.worlds[id];
+        if !self.supports_async_types() {
+            if let Some(kind) = async_type_used_by(resolve, id) {
+                bail!(
+                    "world `{}` uses `{kind}` types, which aren't supported by this generator",
+                    world.name
+                );
+            }
+        }
         self.preprocess(resolve, id);
 
         fn unwrap_name(key: &WorldKey) -> &str {
@@ -282,7 +342,11 @@ pub trait WorldGenerator {
             match export {
                 WorldItem::Function(f) => funcs.push((unwrap_name(name), f)),
                 WorldItem::Interface(id) => interfaces.push((name, id)),
-                WorldItem::Type(_) => unreachable!(),
+                // `wit-component` can't yet encode worlds which export types.
+                WorldItem::Type(_) => bail!(
+                    "world `{}` exports types, which aren't supported by this generator",
+                    world.name
+                ),
             }
         }
         if !funcs.is_empty() {
@@ -301,6 +365,13 @@ pub trait WorldGenerator {
         let _ = (resolve, world, files);
     }
 
+    /// Whether this generator supports `future` and `stream` types.
+    ///
+    /// If not, `generate` returns an error for worlds which use them.
+    fn supports_async_types(&self) -> bool {
+        false
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let _ = (resolve, world);
     }
@@ -373,6 +444,9 @@ pub trait InterfaceGenerator<'a> {
     fn type_list(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
 
+    fn type_future(&mut self, id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs);
+    fn type_stream(&mut self, id: TypeId, name: &str, stream: &Stream, docs: &Docs);
+
     fn types(&mut self, iface: InterfaceId) {
         let iface = &self.resolve().interfaces[iface];
         for (name, id) in iface.types.iter() {
@@ -393,8 +467,8 @@ pub trait InterfaceGenerator<'a> {
             TypeDefKind::Result(r) => self.type_result(id, name, r, &ty.docs),
             TypeDefKind::List(t) => self.type_list(id, name, t, &ty.docs),
             TypeDefKind::Type(t) => self.type_alias(id, name, t, &ty.docs),
-            TypeDefKind::Future(_) => todo!("generate for future"),
-            TypeDefKind::Stream(_) => todo!("generate for stream"),
+            TypeDefKind::Future(t) => self.type_future(id, name, t, &ty.docs),
+            TypeDefKind::Stream(s) => self.type_stream(id, name, s, &ty.docs),
             TypeDefKind::Handle(_) => todo!("generate for handle"),
             TypeDefKind::Unknown => unreachable!(),
         }
@@ -405,6 +479,67 @@ pub fn generated_preamble(src: &mut Source, version: &str) {
     uwriteln!(src, "// Generated by `wit-bindgen` {version}. DO NOT EDIT!")
 }
 
+/// Returns whether `id` is a type that generators emit a definition for,
+/// namely a named type or an anonymous type which is nominal in most
+/// languages, such as a record built in memory without a name.
+fn is_defined(resolve: &Resolve, id: TypeId) -> bool {
+    let ty = &resolve.types[id];
+    ty.name.is_some()
+        || matches!(
+            ty.kind,
+            TypeDefKind::Record(_)
+                | TypeDefKind::Variant(_)
+                | TypeDefKind::Enum(_)
+                | TypeDefKind::Flags(_)
+                | TypeDefKind::Resource
+        )
+}
+
+/// Returns the name of the first kind of `future` or `stream` type used by
+/// `world`, if any.
+pub fn async_type_used_by(resolve: &Resolve, world: WorldId) -> Option<&'static str> {
+    let mut live = LiveTypes::default();
+    live.add_world(resolve, world);
+    let kind = live.iter().find_map(|id| match resolve.types[id].kind {
+        TypeDefKind::Future(_) => Some("future"),
+        TypeDefKind::Stream(_) => Some("stream"),
+        _ => None,
+    });
+    kind
+}
+
+/// Returns the constructors, methods and static functions of the resource `id`.
+pub fn resource_functions(resolve: &Resolve, id: TypeId) -> Vec<&Function> {
+    let funcs: Box<dyn Iterator<Item = &Function>> = match resolve.types[id].owner {
+        TypeOwner::Interface(interface) => {
+            Box::new(resolve.interfaces[interface].functions.values())
+        }
+        TypeOwner::World(world) => {
+            let world = &resolve.worlds[world];
+            Box::new(
+                world
+                    .imports
+                    .values()
+                    .chain(world.exports.values())
+                    .filter_map(|item| match item {
+                        WorldItem::Function(func) => Some(func),
+                        _ => None,
+                    }),
+            )
+        }
+        TypeOwner::None => unreachable!(),
+    };
+
+    funcs
+        .filter(|func| match func.kind {
+            FunctionKind::Method(resource)
+            | FunctionKind::Static(resource)
+            | FunctionKind::Constructor(resource) => resource == id,
+            FunctionKind::Freestanding => false,
+        })
+        .collect()
+}
+
 pub fn dealias(resolve: &Resolve, mut id: TypeId) -> TypeId {
     loop {
         match &resolve.types[id].kind {
@@ -413,3 +548,108 @@ pub fn dealias(resolve: &Resolve, mut id: TypeId) -> TypeId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wit_parser::{Resolve, UnresolvedPackage};
+
+    use super::{TypeInfo, Types};
+
+    const WIT: &str = r#"
+        package test:types;
+
+        interface shared {
+            record both { a: u32 }
+            record unused { a: u32 }
+        }
+
+        interface a {
+            use shared.{both};
+
+            record imported-only { a: u32 }
+            record element { a: string }
+            record inner { a: u32 }
+            record wrapper { a: inner }
+            record direct { a: u32 }
+
+            f: func(a: imported-only, b: both, c: list<element>, d: direct) -> list<wrapper>;
+        }
+
+        interface b {
+            use shared.{both};
+
+            record exported-only { a: u32 }
+            record method-arg { a: u32 }
+            record free-arg { a: u32 }
+
+            resource r {
+                constructor();
+                m: func(a: method-arg);
+            }
+
+            g: func(a: exported-only, b: both, c: free-arg);
+        }
+
+        world w {
+            import a;
+            export b;
+        }
+    "#;
+
+    fn analyze() -> (Resolve, Types) {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), WIT).unwrap();
+        resolve.push(pkg).unwrap();
+        let mut types = Types::default();
+        types.analyze(&resolve);
+        (resolve, types)
+    }
+
+    fn info(resolve: &Resolve, types: &Types, iface: &str, name: &str) -> TypeInfo {
+        let (_, iface) = resolve
+            .interfaces
+            .iter()
+            .find(|(_, i)| i.name.as_deref() == Some(iface))
+            .unwrap();
+        types.get(iface.types[name])
+    }
+
+    #[test]
+    fn direction() {
+        let (resolve, types) = analyze();
+
+        let i = info(&resolve, &types, "a", "imported-only");
+        assert!(i.imported && !i.exported);
+        let i = info(&resolve, &types, "b", "exported-only");
+        assert!(!i.imported && i.exported);
+        let i = info(&resolve, &types, "shared", "both");
+        assert!(i.imported && i.exported);
+        let i = info(&resolve, &types, "shared", "unused");
+        assert!(!i.imported && !i.exported);
+
+        // Types reached through a list or another record count too.
+        assert!(info(&resolve, &types, "a", "element").imported);
+        assert!(info(&resolve, &types, "a", "inner").imported);
+    }
+
+    #[test]
+    fn in_list() {
+        let (resolve, types) = analyze();
+
+        assert!(info(&resolve, &types, "a", "element").in_list);
+        assert!(info(&resolve, &types, "a", "wrapper").in_list);
+        assert!(info(&resolve, &types, "a", "inner").in_list);
+        assert!(!info(&resolve, &types, "a", "direct").in_list);
+        assert!(!info(&resolve, &types, "a", "imported-only").in_list);
+    }
+
+    #[test]
+    fn resource_method() {
+        let (resolve, types) = analyze();
+
+        assert!(info(&resolve, &types, "b", "method-arg").resource_method);
+        assert!(info(&resolve, &types, "b", "r").resource_method);
+        assert!(!info(&resolve, &types, "b", "free-arg").resource_method);
+        assert!(!info(&resolve, &types, "b", "exported-only").resource_method);
+    }
+}
@@ -0,0 +1,1471 @@
+use crate::{Cpp, ResourceInfo};
+use heck::*;
+use std::fmt::Write;
+use wit_bindgen_c::{c_func_name, gen_type_name, int_repr, is_arg_by_pointer, CTypeNameInfo};
+use wit_bindgen_core::{dealias, uwrite, uwriteln, wit_parser::*, Direction, Source};
+
+/// Names of the members which the classes of imported resources define
+/// themselves, and which methods from WIT therefore can't use.
+const RESOURCE_MEMBERS: &[&str] = &["from_handle", "borrow", "handle", "release"];
+
+pub(crate) struct InterfaceGenerator<'a> {
+    gen: &'a mut Cpp,
+    resolve: &'a Resolve,
+    interface: Option<(InterfaceId, &'a WorldKey)>,
+    in_import: bool,
+    /// The namespace, such as `exports::wasi::cli::run`, these bindings are
+    /// placed in.
+    namespace: String,
+
+    /// Forward declarations of the types defined in this namespace, such that
+    /// resource classes can refer to types defined after them.
+    fwd: Source,
+    types: Source,
+    funcs: Source,
+    /// Definitions of imported functions and methods, placed within the
+    /// namespace in the `.cpp` file.
+    impls: Source,
+    /// Definitions of the C functions for exports, which call into the C++
+    /// implementations of the application.
+    glue: Source,
+
+    /// Whether types are printed without going through their aliases, which
+    /// is done for the members of resource classes since those may precede
+    /// the definition of aliases they refer to.
+    dealias_names: bool,
+}
+
+/// How the C signature of a function returns its results.
+enum CRet {
+    Void,
+    Scalar(Type),
+    Pointers(Vec<Type>),
+}
+
+impl<'a> InterfaceGenerator<'a> {
+    pub(crate) fn new(
+        gen: &'a mut Cpp,
+        resolve: &'a Resolve,
+        in_import: bool,
+        interface: Option<(InterfaceId, &'a WorldKey)>,
+        namespace: String,
+    ) -> InterfaceGenerator<'a> {
+        InterfaceGenerator {
+            gen,
+            resolve,
+            interface,
+            in_import,
+            namespace,
+            fwd: Source::default(),
+            types: Source::default(),
+            funcs: Source::default(),
+            impls: Source::default(),
+            glue: Source::default(),
+            dealias_names: false,
+        }
+    }
+
+    pub(crate) fn define_interface_types(&mut self, id: InterfaceId) {
+        let mut live = LiveTypes::default();
+        live.add_interface(self.resolve, id);
+        self.define_live_types(live);
+    }
+
+    pub(crate) fn define_function_types(&mut self, funcs: &[(&str, &Function)]) {
+        let mut live = LiveTypes::default();
+        for (_, func) in funcs {
+            live.add_func(self.resolve, func);
+        }
+        self.define_live_types(live);
+    }
+
+    /// Defines the C++ types for `live`, naming their C counterparts exactly
+    /// the way the C generator does.
+    pub(crate) fn define_live_types(&mut self, live: LiveTypes) {
+        let types = live
+            .iter()
+            .filter(|ty| !self.gen.c_type_names.contains_key(ty))
+            .collect::<Vec<_>>();
+
+        // Resources are declared first since the members of their classes
+        // may refer to resources defined after them.
+        for ty in types.iter() {
+            if let TypeDefKind::Resource = self.resolve.types[*ty].kind {
+                self.declare_resource(*ty);
+            }
+        }
+
+        for ty in types {
+            let (info, encoded) = gen_type_name(self.resolve, ty);
+            match info {
+                CTypeNameInfo::Named { name } => {
+                    let c_name = format!("{}_{encoded}_t", self.owner_namespace(ty));
+                    self.gen.c_type_names.insert(ty, c_name);
+                    wit_bindgen_core::InterfaceGenerator::define_type(self, name, ty);
+                }
+                CTypeNameInfo::Anonymous { is_prim } => {
+                    let c_name = if is_prim {
+                        let name = format!("{}_{encoded}_t", self.gen.world.to_snake_case());
+                        self.gen.prim_names.insert(name.clone());
+                        name
+                    } else {
+                        format!("{}_{encoded}_t", self.owner_namespace(ty))
+                    };
+                    self.gen.c_type_names.insert(ty, c_name);
+                }
+            }
+            self.define_converters(ty);
+        }
+    }
+
+    /// Writes out the bindings generated for this interface.
+    pub(crate) fn finish(self) {
+        let ns = &self.namespace;
+        if !self.fwd.is_empty() || !self.types.is_empty() || !self.funcs.is_empty() {
+            uwriteln!(self.gen.h_src, "\nnamespace {ns} {{");
+            self.gen.h_src.push_str(&self.fwd);
+            self.gen.h_src.push_str(&self.types);
+            self.gen.h_src.push_str(&self.funcs);
+            uwriteln!(self.gen.h_src, "}}");
+        }
+        if !self.impls.is_empty() {
+            uwriteln!(self.gen.cpp_fns, "\nnamespace {ns} {{");
+            self.gen.cpp_fns.push_str(&self.impls);
+            uwriteln!(self.gen.cpp_fns, "}}");
+        }
+        self.gen.cpp_fns.push_str(&self.glue);
+    }
+
+    fn owner_namespace(&self, id: TypeId) -> String {
+        wit_bindgen_c::owner_namespace(
+            self.interface,
+            self.in_import,
+            self.gen.world.clone(),
+            self.resolve,
+            id,
+            &self.gen.renamed_interfaces,
+        )
+    }
+
+    fn c_func_name(&self, interface: Option<&WorldKey>, func: &Function) -> String {
+        c_func_name(
+            self.in_import,
+            self.resolve,
+            &self.gen.world,
+            interface,
+            func,
+            &self.gen.renamed_interfaces,
+        )
+    }
+
+    /// Records the C++ name of the type `id` defined in this namespace.
+    fn register(&mut self, id: TypeId, name: &str) {
+        let qualified = format!("::{}::{name}", self.namespace);
+        self.gen.cpp_type_names.insert(id, qualified);
+    }
+
+    /// Returns the C type used for `ty`.
+    fn c_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::Char => "uint32_t".into(),
+            Type::U8 => "uint8_t".into(),
+            Type::S8 => "int8_t".into(),
+            Type::U16 => "uint16_t".into(),
+            Type::S16 => "int16_t".into(),
+            Type::U32 => "uint32_t".into(),
+            Type::S32 => "int32_t".into(),
+            Type::U64 => "uint64_t".into(),
+            Type::S64 => "int64_t".into(),
+            Type::F32 => "float".into(),
+            Type::F64 => "double".into(),
+            Type::String => format!("{}_string_t", self.gen.world.to_snake_case()),
+            Type::Id(id) => self.gen.c_type_names[id].clone(),
+        }
+    }
+
+    /// Returns the prefix of the C names derived from the name of the C type
+    /// `id`, such as its `_free` function.
+    fn c_prefix(&self, id: TypeId) -> String {
+        let name = &self.gen.c_type_names[&id];
+        name.strip_suffix("_t").unwrap().to_string()
+    }
+
+    /// Returns the C++ type owning a value of `ty`.
+    fn type_name(&self, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::Char => "char32_t".into(),
+            Type::U8 => "uint8_t".into(),
+            Type::S8 => "int8_t".into(),
+            Type::U16 => "uint16_t".into(),
+            Type::S16 => "int16_t".into(),
+            Type::U32 => "uint32_t".into(),
+            Type::S32 => "int32_t".into(),
+            Type::U64 => "uint64_t".into(),
+            Type::S64 => "int64_t".into(),
+            Type::F32 => "float".into(),
+            Type::F64 => "double".into(),
+            Type::String => "std::string".into(),
+            Type::Id(id) => {
+                let kind = &self.resolve.types[*id].kind;
+                let nominal = matches!(
+                    kind,
+                    TypeDefKind::Record(_)
+                        | TypeDefKind::Variant(_)
+                        | TypeDefKind::Enum(_)
+                        | TypeDefKind::Flags(_)
+                        | TypeDefKind::Resource
+                );
+                if nominal || !self.dealias_names {
+                    if let Some(name) = self.gen.cpp_type_names.get(id) {
+                        return name.clone();
+                    }
+                }
+                match kind {
+                    TypeDefKind::Type(t) => self.type_name(t),
+                    TypeDefKind::List(t) => format!("std::vector<{}>", self.type_name(t)),
+                    TypeDefKind::Option(t) => format!("std::optional<{}>", self.type_name(t)),
+                    TypeDefKind::Result(r) => {
+                        let ok = match &r.ok {
+                            Some(ty) => self.type_name(ty),
+                            None => "void".into(),
+                        };
+                        let err = match &r.err {
+                            Some(ty) => self.type_name(ty),
+                            None => "std::monostate".into(),
+                        };
+                        format!("std::expected<{ok}, {err}>")
+                    }
+                    TypeDefKind::Tuple(t) => {
+                        let types = t
+                            .types
+                            .iter()
+                            .map(|ty| self.type_name(ty))
+                            .collect::<Vec<_>>();
+                        format!("std::tuple<{}>", types.join(", "))
+                    }
+                    TypeDefKind::Handle(Handle::Own(resource)) => {
+                        let info = self.resource(*resource);
+                        match info.direction {
+                            Direction::Import => info.cpp.clone(),
+                            Direction::Export => format!("std::unique_ptr<{}>", info.cpp),
+                        }
+                    }
+                    TypeDefKind::Handle(Handle::Borrow(resource)) => {
+                        let info = self.resource(*resource);
+                        match info.direction {
+                            Direction::Import => info.cpp.clone(),
+                            Direction::Export => {
+                                format!("std::reference_wrapper<{}>", info.cpp)
+                            }
+                        }
+                    }
+                    TypeDefKind::Record(_)
+                    | TypeDefKind::Variant(_)
+                    | TypeDefKind::Enum(_)
+                    | TypeDefKind::Flags(_)
+                    | TypeDefKind::Resource => {
+                        panic!("failed to find type name for {id:?}")
+                    }
+                    TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                        "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+                    ),
+                    TypeDefKind::Unknown => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Returns the C++ type of a parameter of type `ty`, which borrows the
+    /// argument unless ownership of some handle within it is passed along.
+    fn param_type(&self, ty: &Type) -> String {
+        let id = match ty {
+            Type::String => return "std::string_view".into(),
+            Type::Id(id) => *id,
+            _ => return self.type_name(ty),
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.param_type(t),
+            TypeDefKind::List(t) if !self.has_own(t) => {
+                format!("std::span<const {}>", self.type_name(t))
+            }
+            TypeDefKind::Handle(Handle::Borrow(resource)) => {
+                let info = self.resource(*resource);
+                match info.direction {
+                    Direction::Import => format!("const {}&", info.cpp),
+                    Direction::Export => format!("{}&", info.cpp),
+                }
+            }
+            TypeDefKind::Enum(_) | TypeDefKind::Flags(_) | TypeDefKind::Handle(_) => {
+                self.type_name(ty)
+            }
+            _ if self.has_own(ty) => self.type_name(ty),
+            _ => format!("const {}&", self.type_name(ty)),
+        }
+    }
+
+    /// Returns whether values of `ty` own some handle, in which case they
+    /// can only be moved.
+    fn has_own(&self, ty: &Type) -> bool {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return false,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Handle(Handle::Own(_)) => true,
+            TypeDefKind::Handle(Handle::Borrow(_))
+            | TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_) => false,
+            TypeDefKind::Type(t) | TypeDefKind::List(t) | TypeDefKind::Option(t) => self.has_own(t),
+            TypeDefKind::Record(r) => r.fields.iter().any(|f| self.has_own(&f.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().any(|ty| self.has_own(ty)),
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .any(|c| c.ty.iter().any(|ty| self.has_own(ty))),
+            TypeDefKind::Result(r) => {
+                r.ok.iter().any(|ty| self.has_own(ty)) || r.err.iter().any(|ty| self.has_own(ty))
+            }
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) | TypeDefKind::Unknown => false,
+        }
+    }
+
+    /// Returns whether `ty` is a scalar which C and C++ represent the same
+    /// way, such that lists of it can be passed along without copying.
+    fn is_scalar(&self, ty: &Type) -> bool {
+        match ty {
+            Type::String => false,
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.is_scalar(t),
+                _ => false,
+            },
+            _ => true,
+        }
+    }
+
+    fn resource(&self, id: TypeId) -> &ResourceInfo {
+        &self.gen.resources[&dealias(self.resolve, id)]
+    }
+
+    /// Returns the function freeing the memory owned by a C value of `ty`,
+    /// if the C generator defines one.
+    fn free_fn(&self, ty: &Type) -> Option<String> {
+        match ty {
+            Type::String => Some(format!("{}_string_free", self.gen.world.to_snake_case())),
+            Type::Id(id) => {
+                if self.needs_free(ty) {
+                    Some(format!("{}_free", self.c_prefix(*id)))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn needs_free(&self, ty: &Type) -> bool {
+        let id = match ty {
+            Type::String => return true,
+            Type::Id(id) => *id,
+            _ => return false,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.needs_free(t),
+            TypeDefKind::Record(r) => r.fields.iter().any(|f| self.needs_free(&f.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().any(|ty| self.needs_free(ty)),
+            TypeDefKind::List(_)
+            | TypeDefKind::Variant(_)
+            | TypeDefKind::Option(_)
+            | TypeDefKind::Result(_) => true,
+            TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Handle(_) => false,
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+            ),
+            TypeDefKind::Unknown => unreachable!(),
+        }
+    }
+
+    /// Returns an expression converting the C value `expr` of `ty` into its
+    /// C++ representation.
+    fn lift(&mut self, ty: &Type, expr: &str) -> String {
+        let id = match ty {
+            Type::Char => return format!("static_cast<char32_t>({expr})"),
+            Type::String => {
+                self.gen.needs_string = true;
+                return format!("::lift_string({expr})");
+            }
+            Type::Id(id) => *id,
+            _ => return expr.to_string(),
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.lift(t, expr),
+            TypeDefKind::Enum(_) | TypeDefKind::Flags(_) => {
+                format!("static_cast<{}>({expr})", self.type_name(ty))
+            }
+            TypeDefKind::Handle(handle) => {
+                let (Handle::Own(resource) | Handle::Borrow(resource)) = handle;
+                let info = self.resource(*resource);
+                match (handle, info.direction) {
+                    (_, Direction::Import) => {
+                        format!("{}::from_handle(({expr}).__handle)", info.cpp)
+                    }
+                    (Handle::Own(_), Direction::Export) => {
+                        format!("::take_{}({expr})", info.prefix)
+                    }
+                    (Handle::Borrow(_), Direction::Export) => {
+                        format!("std::ref(*static_cast<{}*>({expr}))", info.cpp)
+                    }
+                }
+            }
+            TypeDefKind::Record(_)
+            | TypeDefKind::Tuple(_)
+            | TypeDefKind::Variant(_)
+            | TypeDefKind::Option(_)
+            | TypeDefKind::Result(_)
+            | TypeDefKind::List(_) => format!("::lift_{}({expr})", self.c_prefix(id)),
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+            ),
+            TypeDefKind::Resource | TypeDefKind::Unknown => unreachable!(),
+        }
+    }
+
+    /// Returns an expression converting the C++ value `expr` of `ty` into its
+    /// C representation, transferring ownership of any handles in it.
+    fn lower(&mut self, ty: &Type, expr: &str) -> String {
+        let id = match ty {
+            Type::Char => return format!("static_cast<uint32_t>({expr})"),
+            Type::String => {
+                self.gen.needs_string = true;
+                return format!("::lower_string({expr})");
+            }
+            Type::Id(id) => *id,
+            _ => return expr.to_string(),
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.lower(t, expr),
+            TypeDefKind::Enum(_) | TypeDefKind::Flags(_) => {
+                format!("static_cast<{}>({expr})", self.c_type(ty))
+            }
+            TypeDefKind::Handle(handle) => {
+                let (Handle::Own(resource) | Handle::Borrow(resource)) = handle;
+                let info = self.resource(*resource);
+                match (handle, info.direction) {
+                    (Handle::Own(_), Direction::Import) => {
+                        format!("{}{{ ({expr}).release() }}", info.own)
+                    }
+                    (Handle::Borrow(_), Direction::Import) => {
+                        format!("{}{{ ({expr}).handle() }}", info.borrow)
+                    }
+                    (Handle::Own(_), Direction::Export) => {
+                        format!("{}_new(({expr}).release())", info.prefix)
+                    }
+                    (Handle::Borrow(_), Direction::Export) => {
+                        format!("static_cast<{}*>(&({expr}).get())", info.rep)
+                    }
+                }
+            }
+            TypeDefKind::Record(_)
+            | TypeDefKind::Tuple(_)
+            | TypeDefKind::Variant(_)
+            | TypeDefKind::Option(_)
+            | TypeDefKind::Result(_)
+            | TypeDefKind::List(_) => format!("::lower_{}({expr})", self.c_prefix(id)),
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+            ),
+            TypeDefKind::Resource | TypeDefKind::Unknown => unreachable!(),
+        }
+    }
+
+    /// Emits the `lift_*` and `lower_*` functions converting between the C
+    /// and C++ representations of the aggregate type `id`.
+    fn define_converters(&mut self, id: TypeId) {
+        let kind = &self.resolve.types[id].kind;
+        if !matches!(
+            kind,
+            TypeDefKind::Record(_)
+                | TypeDefKind::Tuple(_)
+                | TypeDefKind::Variant(_)
+                | TypeDefKind::Option(_)
+                | TypeDefKind::Result(_)
+                | TypeDefKind::List(_)
+        ) {
+            return;
+        }
+        let c_name = self.gen.c_type_names[&id].clone();
+        if !self.gen.converters.insert(c_name.clone()) {
+            return;
+        }
+        let prefix = self.c_prefix(id);
+        let cpp = self.type_name(&Type::Id(id));
+
+        let mut lift = Source::default();
+        let mut lower = Source::default();
+        uwriteln!(lower, "{c_name} r{{}};");
+        match kind {
+            TypeDefKind::Record(record) => {
+                uwriteln!(lift, "return {cpp}{{");
+                for field in record.fields.iter() {
+                    let name = to_cpp_ident(&field.name);
+                    let lifted = self.lift(&field.ty, &format!("v.{name}"));
+                    uwriteln!(lift, "{lifted},");
+                    let lowered = self.lower(&field.ty, &format!("v.{name}"));
+                    uwriteln!(lower, "r.{name} = {lowered};");
+                }
+                uwriteln!(lift, "}};");
+                if record.fields.is_empty() {
+                    uwriteln!(lift, "(void) v;");
+                    uwriteln!(lower, "(void) v;");
+                }
+            }
+            TypeDefKind::Tuple(tuple) => {
+                let mut lifted = Vec::new();
+                for (i, ty) in tuple.types.iter().enumerate() {
+                    lifted.push(self.lift(ty, &format!("v.f{i}")));
+                    let lowered = self.lower(ty, &format!("std::get<{i}>(v)"));
+                    uwriteln!(lower, "r.f{i} = {lowered};");
+                }
+                if tuple.types.is_empty() {
+                    uwriteln!(lift, "(void) v;");
+                    uwriteln!(lower, "(void) v;");
+                }
+                uwriteln!(lift, "return {cpp}({});", lifted.join(", "));
+            }
+            TypeDefKind::List(ty) => {
+                let elem = self.c_type(ty);
+                let lifted = self.lift(ty, "v.ptr[i]");
+                let lowered = self.lower(ty, "v[i]");
+                lift.push_str(&format!(
+                    "
+                        {cpp} r;
+                        r.reserve(v.len);
+                        for (size_t i = 0; i < v.len; i++) {{
+                            r.push_back({lifted});
+                        }}
+                        return r;
+                    "
+                ));
+                lower.push_str(&format!(
+                    "
+                        r.len = v.size();
+                        if (r.len > 0) {{
+                            r.ptr = static_cast<{elem}*>(malloc(sizeof({elem}) * r.len));
+                            for (size_t i = 0; i < r.len; i++) {{
+                                r.ptr[i] = {lowered};
+                            }}
+                        }}
+                    "
+                ));
+            }
+            TypeDefKind::Option(ty) => {
+                let lifted = self.lift(ty, "v.val");
+                let lowered = self.lower(ty, "*v");
+                lift.push_str(&format!(
+                    "
+                        if (v.is_some) {{
+                            return {cpp}({lifted});
+                        }}
+                        return std::nullopt;
+                    "
+                ));
+                lower.push_str(&format!(
+                    "
+                        if (v) {{
+                            r.is_some = true;
+                            r.val = {lowered};
+                        }}
+                    "
+                ));
+            }
+            TypeDefKind::Result(result) => {
+                uwriteln!(lift, "if (v.is_err) {{");
+                match &result.err {
+                    Some(ty) => {
+                        let lifted = self.lift(ty, "v.val.err");
+                        uwriteln!(lift, "return {cpp}(std::unexpect, {lifted});");
+                    }
+                    None => uwriteln!(lift, "return {cpp}(std::unexpect);"),
+                }
+                uwriteln!(lift, "}}");
+                match &result.ok {
+                    Some(ty) => {
+                        let lifted = self.lift(ty, "v.val.ok");
+                        uwriteln!(lift, "return {cpp}(std::in_place, {lifted});");
+                    }
+                    None => uwriteln!(lift, "return {cpp}();"),
+                }
+
+                uwriteln!(lower, "if (v) {{");
+                uwriteln!(lower, "r.is_err = false;");
+                if let Some(ty) = &result.ok {
+                    let lowered = self.lower(ty, "*v");
+                    uwriteln!(lower, "r.val.ok = {lowered};");
+                }
+                uwriteln!(lower, "}} else {{");
+                uwriteln!(lower, "r.is_err = true;");
+                if let Some(ty) = &result.err {
+                    let lowered = self.lower(ty, "v.error()");
+                    uwriteln!(lower, "r.val.err = {lowered};");
+                }
+                uwriteln!(lower, "}}");
+            }
+            TypeDefKind::Variant(variant) => {
+                uwriteln!(lift, "switch (v.tag) {{");
+                uwriteln!(lower, "switch (v.variant.index()) {{");
+                for (i, case) in variant.cases.iter().enumerate() {
+                    let case_name = variant_case_name(&cpp, &case.name);
+                    let c_case = to_cpp_ident(&case.name);
+                    let payload = match &case.ty {
+                        Some(ty) => self.lift(ty, &format!("v.val.{c_case}")),
+                        None => String::new(),
+                    };
+                    uwriteln!(lift, "case {i}: {{");
+                    uwriteln!(lift, "return {cpp}{{{cpp}::{case_name}{{{payload}}}}};");
+                    uwriteln!(lift, "}}");
+
+                    uwriteln!(lower, "case {i}: {{");
+                    uwriteln!(lower, "r.tag = {i};");
+                    if let Some(ty) = &case.ty {
+                        uwriteln!(lower, "auto& payload = std::get<{i}>(v.variant);");
+                        let lowered = self.lower(ty, "payload.value");
+                        uwriteln!(lower, "r.val.{c_case} = {lowered};");
+                    }
+                    uwriteln!(lower, "break;");
+                    uwriteln!(lower, "}}");
+                }
+                uwriteln!(lift, "}}");
+                uwriteln!(lift, "abort();");
+                uwriteln!(lower, "}}");
+            }
+            _ => unreachable!(),
+        }
+        uwriteln!(lower, "return r;");
+
+        let lower_param = if self.has_own(&Type::Id(id)) {
+            format!("{cpp}& v")
+        } else if let TypeDefKind::List(_) = kind {
+            // Lists are lowered from views such that parameters can be lowered
+            // without copying them into a `std::vector` first.
+            format!("{} v", self.param_type(&Type::Id(id)))
+        } else {
+            format!("const {cpp}& v")
+        };
+        uwriteln!(
+            self.gen.cpp_converters,
+            "\n[[maybe_unused]] static {cpp} lift_{prefix}(const {c_name}& v) {{"
+        );
+        self.gen.cpp_converters.push_str(&lift);
+        uwriteln!(self.gen.cpp_converters, "}}");
+        uwriteln!(
+            self.gen.cpp_converters,
+            "\n[[maybe_unused]] static {c_name} lower_{prefix}({lower_param}) {{"
+        );
+        self.gen.cpp_converters.push_str(&lower);
+        uwriteln!(self.gen.cpp_converters, "}}");
+    }
+
+    fn classify_ret(&self, func: &Function) -> CRet {
+        match func.results.len() {
+            0 => CRet::Void,
+            1 => {
+                let ty = *func.results.iter_types().next().unwrap();
+                if self.returns_scalar(&ty) {
+                    CRet::Scalar(ty)
+                } else {
+                    CRet::Pointers(vec![ty])
+                }
+            }
+            _ => CRet::Pointers(func.results.iter_types().cloned().collect()),
+        }
+    }
+
+    /// Returns whether the C function returns `ty` directly rather than
+    /// through a return pointer.
+    fn returns_scalar(&self, ty: &Type) -> bool {
+        match ty {
+            Type::String => false,
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.returns_scalar(t),
+                TypeDefKind::Flags(_) | TypeDefKind::Enum(_) | TypeDefKind::Handle(_) => true,
+                _ => false,
+            },
+            _ => true,
+        }
+    }
+
+    /// Returns the C++ return type of `func`.
+    fn ret_type(&self, func: &Function) -> String {
+        match func.results.len() {
+            0 => "void".into(),
+            1 => self.type_name(func.results.iter_types().next().unwrap()),
+            _ => {
+                let types = func
+                    .results
+                    .iter_types()
+                    .map(|ty| self.type_name(ty))
+                    .collect::<Vec<_>>();
+                format!("std::tuple<{}>", types.join(", "))
+            }
+        }
+    }
+
+    /// Returns the C++ parameters of `func`, leaving out `self` for methods.
+    fn params(&self, func: &Function) -> String {
+        let skip = usize::from(matches!(func.kind, FunctionKind::Method(_)));
+        func.params
+            .iter()
+            .skip(skip)
+            .map(|(name, ty)| format!("{} {}", self.param_type(ty), to_cpp_ident(name)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns the name of the C++ function or member for `func`.
+    fn func_name(&self, func: &Function) -> String {
+        let name = to_cpp_ident(&func.item_name().to_snake_case());
+        match func.kind {
+            FunctionKind::Freestanding if !self.in_import && name == "instance" => {
+                format!("{name}_")
+            }
+            FunctionKind::Method(_) | FunctionKind::Static(_)
+                if self.in_import && RESOURCE_MEMBERS.contains(&name.as_str()) =>
+            {
+                format!("{name}_")
+            }
+            FunctionKind::Static(id) if !self.in_import => {
+                let resource = self.resolve.types[id].name.as_ref().unwrap();
+                format!("{}_{name}", resource.to_snake_case())
+            }
+            FunctionKind::Constructor(id) if !self.in_import => {
+                let resource = self.resolve.types[id].name.as_ref().unwrap();
+                format!("make_{}", resource.to_snake_case())
+            }
+            _ => name,
+        }
+    }
+
+    /// Returns the functions of the interface being generated which belong
+    /// to the resource `id`.
+    fn resource_funcs(&self, id: TypeId) -> Vec<&'a Function> {
+        let funcs: Vec<&'a Function> = match self.interface {
+            Some((iface, _)) => self.resolve.interfaces[iface].functions.values().collect(),
+            None => {
+                let world = &self.resolve.worlds[self.gen.world_id.unwrap()];
+                let items = if self.in_import {
+                    &world.imports
+                } else {
+                    &world.exports
+                };
+                items
+                    .values()
+                    .filter_map(|item| match item {
+                        WorldItem::Function(func) => Some(func),
+                        _ => None,
+                    })
+                    .collect()
+            }
+        };
+        funcs
+            .into_iter()
+            .filter(|func| match func.kind {
+                FunctionKind::Method(r)
+                | FunctionKind::Static(r)
+                | FunctionKind::Constructor(r) => r == id,
+                FunctionKind::Freestanding => false,
+            })
+            .collect()
+    }
+
+    /// Generates the C++ wrapper of the imported function `func`, which
+    /// lowers its arguments to call the C function and lifts its results.
+    pub(crate) fn import(&mut self, interface: Option<&WorldKey>, func: &Function) {
+        let c_name = self.c_func_name(interface, func);
+        let name = self.func_name(func);
+        let params = self.params(func);
+        let ret = self.ret_type(func);
+
+        match func.kind {
+            FunctionKind::Freestanding => {
+                self.funcs.push_str("\n");
+                push_docs(&mut self.funcs, &func.docs);
+                uwriteln!(self.funcs, "{ret} {name}({params});");
+                uwriteln!(self.impls, "\n{ret} {name}({params}) {{");
+            }
+            FunctionKind::Method(id) | FunctionKind::Static(id) | FunctionKind::Constructor(id) => {
+                let class = self.resolve.types[id].name.as_ref().unwrap();
+                let class = class.to_upper_camel_case();
+                match func.kind {
+                    FunctionKind::Method(_) => {
+                        uwriteln!(self.impls, "\n{ret} {class}::{name}({params}) const {{")
+                    }
+                    FunctionKind::Constructor(_) => {
+                        uwriteln!(self.impls, "\n{class}::{class}({params}) {{")
+                    }
+                    _ => uwriteln!(self.impls, "\n{ret} {class}::{name}({params}) {{"),
+                }
+            }
+        }
+
+        let mut args = Vec::new();
+        let mut frees = Vec::new();
+        for (i, (param, ty)) in func.params.iter().enumerate() {
+            let expr = if i == 0 && matches!(func.kind, FunctionKind::Method(_)) {
+                "*this".to_string()
+            } else {
+                to_cpp_ident(param)
+            };
+            let by_pointer = is_arg_by_pointer(self.resolve, ty);
+            if !by_pointer {
+                args.push(self.lower(ty, &expr));
+                continue;
+            }
+            let c_ty = self.c_type(ty);
+            let local = format!("wit_arg{i}");
+            match self.borrowed_view(ty) {
+                // Strings and lists of scalars are passed to C without
+                // copying them.
+                Some(elem) => uwriteln!(
+                    self.impls,
+                    "{c_ty} {local}{{const_cast<{elem}*>(reinterpret_cast<const {elem}*>({expr}.data())), {expr}.size()}};"
+                ),
+                None => {
+                    let lowered = self.lower(ty, &expr);
+                    uwriteln!(self.impls, "{c_ty} {local} = {lowered};");
+                    if let Some(free) = self.free_fn(ty) {
+                        frees.push(format!("{free}(&{local});"));
+                    }
+                }
+            }
+            args.push(format!("&{local}"));
+        }
+
+        let ret = self.classify_ret(func);
+        let mut results = Vec::new();
+        match &ret {
+            CRet::Void => {}
+            CRet::Scalar(ty) => {
+                uwrite!(self.impls, "{} wit_ret = ", self.c_type(ty));
+                results.push((*ty, "wit_ret".to_string()));
+            }
+            CRet::Pointers(tys) => {
+                for (i, ty) in tys.iter().enumerate() {
+                    let c_ty = self.c_type(ty);
+                    uwriteln!(self.impls, "{c_ty} wit_ret{i};");
+                    args.push(format!("&wit_ret{i}"));
+                    results.push((*ty, format!("wit_ret{i}")));
+                }
+                // The call is made on a line of its own.
+            }
+        }
+        uwriteln!(self.impls, "::{c_name}({});", args.join(", "));
+        for free in frees {
+            uwriteln!(self.impls, "{free}");
+        }
+
+        let mut lifted = Vec::new();
+        for (i, (ty, ret)) in results.iter().enumerate() {
+            let value = self.lift(ty, ret);
+            if matches!(func.kind, FunctionKind::Constructor(_)) {
+                uwriteln!(self.impls, "*this = {value};");
+                continue;
+            }
+            uwriteln!(self.impls, "auto wit_result{i} = {value};");
+            if let Some(free) = self.free_fn(ty).filter(|_| !self.returns_scalar(ty)) {
+                uwriteln!(self.impls, "{free}(&{ret});");
+            }
+            lifted.push(format!("wit_result{i}"));
+        }
+        match lifted.len() {
+            0 => {}
+            1 => uwriteln!(self.impls, "return {};", lifted[0]),
+            _ => {
+                let moved = lifted
+                    .iter()
+                    .map(|r| format!("std::move({r})"))
+                    .collect::<Vec<_>>();
+                uwriteln!(
+                    self.impls,
+                    "return {}({});",
+                    self.ret_type(func),
+                    moved.join(", ")
+                );
+            }
+        }
+        uwriteln!(self.impls, "}}");
+    }
+
+    /// Returns the C type of the elements of a string or list of scalars of
+    /// type `ty` if it can be passed to C by pointing at its C++ contents.
+    fn borrowed_view(&self, ty: &Type) -> Option<String> {
+        match ty {
+            Type::String => Some("uint8_t".into()),
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.borrowed_view(t),
+                TypeDefKind::List(t) if self.is_scalar(t) => Some(self.c_type(t)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the element type of `ty` if it's a list, following aliases.
+    fn list_elem(&self, ty: &Type) -> Option<Type> {
+        match ty {
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.list_elem(t),
+                TypeDefKind::List(t) => Some(*t),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Generates the abstract `Guest` class for the exported functions
+    /// `funcs` along with the C functions calling into it.
+    pub(crate) fn export(&mut self, interface: Option<&WorldKey>, funcs: &[&Function]) {
+        let mut guest = Source::default();
+        for func in funcs {
+            if !matches!(func.kind, FunctionKind::Method(_)) {
+                let ret = match func.kind {
+                    FunctionKind::Constructor(id) => self.type_name(&Type::Id(id)),
+                    _ => self.ret_type(func),
+                };
+                let ret = match func.kind {
+                    FunctionKind::Constructor(_) => format!("std::unique_ptr<{ret}>"),
+                    _ => ret,
+                };
+                push_docs(&mut guest, &func.docs);
+                uwriteln!(
+                    guest,
+                    "virtual {ret} {}({}) = 0;",
+                    self.func_name(func),
+                    self.params(func)
+                );
+            }
+            self.export_glue(interface, func);
+        }
+        if !guest.is_empty() {
+            let name = match interface {
+                Some(key) => self.resolve.name_world_key(key),
+                None => self.resolve.worlds[self.gen.world_id.unwrap()].name.clone(),
+            };
+            let class = self.guest_class();
+            self.funcs.push_str(&format!(
+                "
+                    // Implemented by the application to provide the exports of
+                    // `{name}`.
+                    class {class} {{
+                    public:
+                        virtual ~{class}() = default;
+                "
+            ));
+            self.funcs.push_str(&guest);
+            self.funcs.push_str(&format!(
+                "
+                        // Returns the implementation of these exports, which
+                        // the application defines.
+                        static {class}& instance();
+                    }};
+                "
+            ));
+        }
+    }
+
+    /// Returns the name of the class implementing the exports, which is
+    /// `Guest` unless the interface has a type of that name.
+    fn guest_class(&self) -> &'static str {
+        let collides = match self.interface {
+            Some((id, _)) => self.resolve.interfaces[id]
+                .types
+                .keys()
+                .any(|name| name.to_upper_camel_case() == "Guest"),
+            None => false,
+        };
+        if collides {
+            "Guest_"
+        } else {
+            "Guest"
+        }
+    }
+
+    /// Defines the C function declared by the C bindings for the export
+    /// `func`, which lifts the arguments, calls the C++ implementation and
+    /// lowers its results.
+    fn export_glue(&mut self, interface: Option<&WorldKey>, func: &Function) {
+        let c_name = self.c_func_name(interface, func);
+        let ret = self.classify_ret(func);
+
+        let mut c_params = Vec::new();
+        for (i, (_, ty)) in func.params.iter().enumerate() {
+            let c_ty = self.c_type(ty);
+            let ptr = if is_arg_by_pointer(self.resolve, ty) {
+                "*"
+            } else {
+                ""
+            };
+            c_params.push(format!("{c_ty} {ptr}wit_arg{i}"));
+        }
+        if let CRet::Pointers(tys) = &ret {
+            for (i, ty) in tys.iter().enumerate() {
+                c_params.push(format!("{} *wit_ret{i}", self.c_type(ty)));
+            }
+        }
+        let c_ret = match &ret {
+            CRet::Scalar(ty) => self.c_type(ty),
+            _ => "void".to_string(),
+        };
+        uwriteln!(self.glue, "\n{c_ret} {c_name}({}) {{", c_params.join(", "));
+
+        let mut args = Vec::new();
+        let mut frees = Vec::new();
+        for (i, (_, ty)) in func.params.iter().enumerate() {
+            let by_pointer = is_arg_by_pointer(self.resolve, ty);
+            let c_arg = if by_pointer {
+                format!("(*wit_arg{i})")
+            } else {
+                format!("wit_arg{i}")
+            };
+            if by_pointer {
+                if let Some(free) = self.free_fn(ty) {
+                    frees.push(format!("{free}(wit_arg{i});"));
+                }
+            }
+            let local = format!("wit_param{i}");
+
+            if self.borrowed_view(ty).is_some() {
+                let param_ty = self.param_type(ty);
+                let elem = match self.list_elem(ty) {
+                    Some(elem) => self.type_name(&elem),
+                    None => "char".to_string(),
+                };
+                uwriteln!(
+                    self.glue,
+                    "{param_ty} {local}(reinterpret_cast<{elem}*>({c_arg}.ptr), {c_arg}.len);"
+                );
+                args.push(local);
+                continue;
+            }
+
+            let borrow = match ty {
+                Type::Id(id) => match &self.resolve.types[dealias(self.resolve, *id)].kind {
+                    TypeDefKind::Handle(Handle::Borrow(resource)) => Some(*resource),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match borrow.map(|r| self.resource(r)) {
+                Some(info) if info.direction == Direction::Export => {
+                    let cpp = info.cpp.clone();
+                    if i == 0 && matches!(func.kind, FunctionKind::Method(_)) {
+                        args.push(format!("static_cast<{cpp}*>({c_arg})"));
+                    } else {
+                        args.push(format!("*static_cast<{cpp}*>({c_arg})"));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let lifted = self.lift(ty, &c_arg);
+            uwriteln!(self.glue, "auto {local} = {lifted};");
+            if self.has_own(ty) {
+                args.push(format!("std::move({local})"));
+            } else {
+                args.push(local);
+            }
+        }
+
+        let call = match func.kind {
+            FunctionKind::Method(_) => {
+                let this = args.remove(0);
+                format!("{this}->{}({})", self.func_name(func), args.join(", "))
+            }
+            _ => format!(
+                "::{}::{}::instance().{}({})",
+                self.namespace,
+                self.guest_class(),
+                self.func_name(func),
+                args.join(", ")
+            ),
+        };
+        match &ret {
+            CRet::Void => uwriteln!(self.glue, "{call};"),
+            _ => uwriteln!(self.glue, "auto wit_result = {call};"),
+        }
+        for free in frees {
+            uwriteln!(self.glue, "{free}");
+        }
+        match &ret {
+            CRet::Void => {}
+            CRet::Scalar(ty) => {
+                let lowered = self.lower(ty, "wit_result");
+                uwriteln!(self.glue, "return {lowered};");
+            }
+            CRet::Pointers(tys) if tys.len() == 1 => {
+                let lowered = self.lower(&tys[0], "wit_result");
+                uwriteln!(self.glue, "*wit_ret0 = {lowered};");
+            }
+            CRet::Pointers(tys) => {
+                for (i, ty) in tys.iter().enumerate() {
+                    let lowered = self.lower(ty, &format!("std::get<{i}>(wit_result)"));
+                    uwriteln!(self.glue, "*wit_ret{i} = {lowered};");
+                }
+            }
+        }
+        uwriteln!(self.glue, "}}");
+    }
+
+    /// Records the names of the resource `id` and of its C helpers.
+    fn declare_resource(&mut self, id: TypeId) {
+        let name = self.resolve.types[id].name.as_ref().unwrap();
+        let class = name.to_upper_camel_case();
+        self.register(id, &class);
+
+        let ns = self.owner_namespace(id);
+        let snake = name.to_snake_case();
+        let info = ResourceInfo {
+            direction: if self.in_import {
+                Direction::Import
+            } else {
+                Direction::Export
+            },
+            cpp: self.gen.cpp_type_names[&id].clone(),
+            prefix: format!("{ns}_{snake}"),
+            own: format!("{ns}_own_{snake}_t"),
+            borrow: format!("{ns}_borrow_{snake}_t"),
+            rep: format!("{ns}_{snake}_t"),
+        };
+        self.gen.resources.insert(id, info);
+    }
+
+    fn define_imported_resource(&mut self, id: TypeId, class: &str, info: &ResourceInfo) {
+        let own = &info.own;
+        let prefix = &info.prefix;
+        self.types.push_str(&format!(
+            "class {class} {{
+                public:
+                    ~{class}() {{
+                        if (wit_owned_) {{
+                            ::{prefix}_drop_own({own}{{wit_handle_}});
+                        }}
+                    }}
+                    {class}({class}&& other) noexcept
+                        : wit_handle_(std::exchange(other.wit_handle_, -1)),
+                          wit_owned_(std::exchange(other.wit_owned_, false)) {{}}
+                    {class}& operator=({class}&& other) noexcept {{
+                        std::swap(wit_handle_, other.wit_handle_);
+                        std::swap(wit_owned_, other.wit_owned_);
+                        return *this;
+                    }}
+                    {class}(const {class}&) = delete;
+                    {class}& operator=(const {class}&) = delete;
+
+                    // Takes ownership of `handle`, dropping it along with this
+                    // object.
+                    static {class} from_handle(int32_t handle) noexcept {{
+                        return {class}(FromHandle{{}}, handle, true);
+                    }}
+                    // Returns a view of this resource which doesn't drop the
+                    // handle, such as for passing it along as a `borrow`.
+                    {class} borrow() const noexcept {{
+                        return {class}(FromHandle{{}}, wit_handle_, false);
+                    }}
+                    int32_t handle() const noexcept {{
+                        return wit_handle_;
+                    }}
+                    // Gives up ownership of the handle, returning it.
+                    int32_t release() noexcept {{
+                        wit_owned_ = false;
+                        return std::exchange(wit_handle_, -1);
+                    }}
+            "
+        ));
+
+        self.dealias_names = true;
+        for func in self.resource_funcs(id) {
+            self.types.push_str("\n");
+            push_docs(&mut self.types, &func.docs);
+            let params = self.params(func);
+            match func.kind {
+                FunctionKind::Constructor(_) => {
+                    uwriteln!(self.types, "explicit {class}({params});")
+                }
+                FunctionKind::Method(_) => uwriteln!(
+                    self.types,
+                    "{} {}({params}) const;",
+                    self.ret_type(func),
+                    self.func_name(func)
+                ),
+                _ => uwriteln!(
+                    self.types,
+                    "static {} {}({params});",
+                    self.ret_type(func),
+                    self.func_name(func)
+                ),
+            }
+        }
+        self.dealias_names = false;
+
+        self.types.push_str(&format!(
+            "
+                private:
+                    struct FromHandle {{}};
+                    {class}(FromHandle, int32_t handle, bool owned) noexcept
+                        : wit_handle_(handle), wit_owned_(owned) {{}}
+
+                    int32_t wit_handle_ = -1;
+                    bool wit_owned_ = false;
+                }};
+            "
+        ));
+    }
+
+    fn define_exported_resource(&mut self, id: TypeId, class: &str, info: &ResourceInfo) {
+        let rep = &info.rep;
+        let prefix = &info.prefix;
+        let own = &info.own;
+        let cpp = &info.cpp;
+        uwriteln!(self.gen.h_reps, "struct {rep} {{}};");
+
+        self.types.push_str(&format!(
+            "// Implemented by the application, which hands out instances of
+                // this resource as `std::unique_ptr`s.
+                class {class} : public ::{rep} {{
+                public:
+                    virtual ~{class}() = default;
+            "
+        ));
+        self.dealias_names = true;
+        for func in self.resource_funcs(id) {
+            if let FunctionKind::Method(_) = func.kind {
+                push_docs(&mut self.types, &func.docs);
+                uwriteln!(
+                    self.types,
+                    "virtual {} {}({}) = 0;",
+                    self.ret_type(func),
+                    self.func_name(func),
+                    self.params(func)
+                );
+            }
+        }
+        self.dealias_names = false;
+        uwriteln!(self.types, "}};");
+
+        // Taking an owned handle drops it, which runs the destructor below
+        // before the representation can be handed out as a `std::unique_ptr`.
+        self.gen.cpp_converters.push_str(&format!(
+            "
+                static {rep} *wit_taking_{prefix} = nullptr;
+
+                [[maybe_unused]] static std::unique_ptr<{cpp}> take_{prefix}({own} handle) {{
+                    {rep} *rep = {prefix}_rep(handle);
+                    wit_taking_{prefix} = rep;
+                    {prefix}_drop_own(handle);
+                    wit_taking_{prefix} = nullptr;
+                    return std::unique_ptr<{cpp}>(static_cast<{cpp}*>(rep));
+                }}
+
+                void {prefix}_destructor({rep} *rep) {{
+                    if (rep != wit_taking_{prefix}) {{
+                        delete static_cast<{cpp}*>(rep);
+                    }}
+                }}
+            "
+        ));
+    }
+}
+
+impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn type_record(&mut self, id: TypeId, name: &str, record: &Record, docs: &Docs) {
+        let camel = name.to_upper_camel_case();
+        self.register(id, &camel);
+        uwriteln!(self.fwd, "struct {camel};");
+
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        uwriteln!(self.types, "struct {camel} {{");
+        for field in record.fields.iter() {
+            push_docs(&mut self.types, &field.docs);
+            let ty = self.type_name(&field.ty);
+            uwriteln!(self.types, "{ty} {};", to_cpp_ident(&field.name));
+        }
+        uwriteln!(self.types, "}};");
+    }
+
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        let class = name.to_upper_camel_case();
+        uwriteln!(self.fwd, "class {class};");
+
+        let info = self.gen.resources[&id].clone();
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        if self.in_import {
+            self.define_imported_resource(id, &class, &info);
+        } else {
+            self.define_exported_resource(id, &class, &info);
+        }
+    }
+
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let camel = name.to_upper_camel_case();
+        self.register(id, &camel);
+        let repr = int_repr(wit_bindgen_c::flags_repr(flags));
+        uwriteln!(self.fwd, "enum class {camel} : {repr};");
+
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        uwriteln!(self.types, "enum class {camel} : {repr} {{");
+        for (i, flag) in flags.flags.iter().enumerate() {
+            push_docs(&mut self.types, &flag.docs);
+            uwriteln!(
+                self.types,
+                "{} = {repr}{{1}} << {i},",
+                flag.name.to_upper_camel_case()
+            );
+        }
+        uwriteln!(self.types, "}};");
+        for op in ["|", "&", "^"] {
+            self.types.push_str(&format!(
+                "constexpr {camel} operator{op}({camel} a, {camel} b) {{
+                    return static_cast<{camel}>(static_cast<{repr}>(a) {op} static_cast<{repr}>(b));
+                }}\n"
+            ));
+        }
+        self.types.push_str(&format!(
+            "constexpr {camel} operator~({camel} a) {{
+                return static_cast<{camel}>(~static_cast<{repr}>(a));
+            }}\n"
+        ));
+    }
+
+    fn type_tuple(&mut self, id: TypeId, name: &str, _tuple: &Tuple, docs: &Docs) {
+        self.type_using(id, name, docs);
+    }
+
+    fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+        let camel = name.to_upper_camel_case();
+        self.register(id, &camel);
+        uwriteln!(self.fwd, "struct {camel};");
+
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        uwriteln!(self.types, "struct {camel} {{");
+        let mut cases = Vec::new();
+        for case in variant.cases.iter() {
+            let case_name = variant_case_name(&camel, &case.name);
+            push_docs(&mut self.types, &case.docs);
+            match &case.ty {
+                Some(ty) => {
+                    let ty = self.type_name(ty);
+                    uwriteln!(self.types, "struct {case_name} {{");
+                    uwriteln!(self.types, "{ty} value;");
+                    uwriteln!(self.types, "}};");
+                }
+                None => uwriteln!(self.types, "struct {case_name} {{}};"),
+            }
+            cases.push(case_name);
+        }
+        uwriteln!(self.types, "std::variant<{}> variant;", cases.join(", "));
+        uwriteln!(self.types, "}};");
+    }
+
+    fn type_option(&mut self, id: TypeId, name: &str, _payload: &Type, docs: &Docs) {
+        self.type_using(id, name, docs);
+    }
+
+    fn type_result(&mut self, id: TypeId, name: &str, _result: &Result_, docs: &Docs) {
+        self.type_using(id, name, docs);
+    }
+
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        let camel = name.to_upper_camel_case();
+        self.register(id, &camel);
+        let repr = int_repr(enum_.tag());
+        uwriteln!(self.fwd, "enum class {camel} : {repr};");
+
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        uwriteln!(self.types, "enum class {camel} : {repr} {{");
+        for case in enum_.cases.iter() {
+            push_docs(&mut self.types, &case.docs);
+            uwriteln!(self.types, "{},", case.name.to_upper_camel_case());
+        }
+        uwriteln!(self.types, "}};");
+    }
+
+    fn type_alias(&mut self, id: TypeId, name: &str, _ty: &Type, docs: &Docs) {
+        self.type_using(id, name, docs);
+    }
+
+    fn type_list(&mut self, id: TypeId, name: &str, _ty: &Type, docs: &Docs) {
+        self.type_using(id, name, docs);
+    }
+
+    fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs) {
+        let _ = (id, name, ty, docs);
+    }
+
+    fn type_future(&mut self, _id: TypeId, _name: &str, _ty: &Option<Type>, _docs: &Docs) {
+        unreachable!("`future` types are rejected by `WorldGenerator::generate`")
+    }
+
+    fn type_stream(&mut self, _id: TypeId, _name: &str, _stream: &Stream, _docs: &Docs) {
+        unreachable!("`stream` types are rejected by `WorldGenerator::generate`")
+    }
+}
+
+impl InterfaceGenerator<'_> {
+    /// Defines `name` as an alias of the C++ type for the structural type
+    /// `id`, such as a `std::vector` for lists.
+    fn type_using(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        let target = self.type_name(&Type::Id(id));
+        let camel = name.to_upper_camel_case();
+        self.register(id, &camel);
+
+        self.types.push_str("\n");
+        push_docs(&mut self.types, docs);
+        uwriteln!(self.types, "using {camel} = {target};");
+    }
+}
+
+/// Returns the name of the struct for the case `case` of the variant
+/// `variant`, which mustn't be the name of the variant itself.
+fn variant_case_name(variant: &str, case: &str) -> String {
+    let name = case.to_upper_camel_case();
+    if variant.rsplit("::").next() == Some(name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+fn push_docs(src: &mut Source, docs: &Docs) {
+    let docs = match &docs.contents {
+        Some(docs) => docs,
+        None => return,
+    };
+    for line in docs.trim().lines() {
+        src.push_str("// ");
+        src.push_str(line);
+        src.push_str("\n");
+    }
+}
+
+/// Escapes C++ keywords, along with the names of namespaces which the
+/// generated code refers to.
+pub(crate) fn to_cpp_ident(name: &str) -> String {
+    match name {
+        "std" => "std_".into(),
+        s => wit_bindgen_c::to_c_ident(s),
+    }
+}
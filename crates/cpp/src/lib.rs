@@ -0,0 +1,370 @@
+mod interface;
+
+use anyhow::Result;
+use heck::*;
+use interface::InterfaceGenerator;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use wit_bindgen_core::{uwriteln, wit_parser::*, Direction, Files, Source, WorldGenerator};
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Skip generating an object file which contains type information for the
+    /// world that is being generated.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
+    pub no_object_file: bool,
+
+    /// Rename the interface `K` to `V` in the generated source code.
+    #[cfg_attr(feature = "clap", arg(long, name = "K=V", value_parser = parse_rename))]
+    pub rename: Vec<(String, String)>,
+
+    /// Rename the world in the generated source code and file names.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub rename_world: Option<String>,
+
+    /// Add the specified suffix to the name of the custom section containing
+    /// the component type.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub type_section_suffix: Option<String>,
+}
+
+#[cfg(feature = "clap")]
+fn parse_rename(name: &str) -> Result<(String, String)> {
+    let mut parts = name.splitn(2, '=');
+    let to_rename = parts.next().unwrap();
+    match parts.next() {
+        Some(part) => Ok((to_rename.to_string(), part.to_string())),
+        None => anyhow::bail!("`--rename` option must have an `=` in it (e.g. `--rename a=b`)"),
+    }
+}
+
+impl Opts {
+    pub fn build(&self) -> Box<dyn WorldGenerator> {
+        // All lifting and lowering is done by the C bindings, which the C++
+        // bindings wrap. Signature flattening is disabled so `option` and
+        // `result` values always travel through their C structs.
+        let c = wit_bindgen_c::Opts {
+            no_sig_flattening: true,
+            no_object_file: self.no_object_file,
+            rename: self.rename.clone(),
+            rename_world: self.rename_world.clone(),
+            type_section_suffix: self.type_section_suffix.clone(),
+            ..Default::default()
+        };
+        Box::new(Cpp {
+            c: c.build(),
+            opts: self.clone(),
+            world: String::new(),
+            world_id: None,
+            renamed_interfaces: HashMap::new(),
+            c_type_names: HashMap::new(),
+            cpp_type_names: HashMap::new(),
+            prim_names: HashSet::new(),
+            resources: HashMap::new(),
+            converters: HashSet::new(),
+            needs_string: false,
+            h_reps: Source::default(),
+            h_src: Source::default(),
+            cpp_converters: Source::default(),
+            cpp_fns: Source::default(),
+        })
+    }
+}
+
+struct Cpp {
+    /// The C generator producing the `.h`/`.c` pair which the C++ bindings
+    /// call into.
+    c: Box<dyn WorldGenerator>,
+    opts: Opts,
+    world: String,
+    world_id: Option<WorldId>,
+    renamed_interfaces: HashMap<WorldKey, String>,
+
+    /// Names of the types in the C bindings, kept in sync with the C
+    /// generator's own `type_names`.
+    c_type_names: HashMap<TypeId, String>,
+    /// Fully qualified names of the C++ types defined for named types.
+    cpp_type_names: HashMap<TypeId, String>,
+    prim_names: HashSet<String>,
+    resources: HashMap<TypeId, ResourceInfo>,
+    /// C types for which `lift_*` and `lower_*` functions have been emitted.
+    converters: HashSet<String>,
+    needs_string: bool,
+
+    /// Definitions of the representations of exported resources, which live
+    /// outside of any namespace.
+    h_reps: Source,
+    h_src: Source,
+    cpp_converters: Source,
+    cpp_fns: Source,
+}
+
+#[derive(Clone)]
+struct ResourceInfo {
+    direction: Direction,
+    /// Fully qualified name of the C++ class for this resource.
+    cpp: String,
+    /// Prefix of the C helpers for this resource, such as `{prefix}_drop_own`.
+    prefix: String,
+    own: String,
+    borrow: String,
+    /// The C type of the representation of an exported resource.
+    rep: String,
+}
+
+impl WorldGenerator for Cpp {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+        self.c.preprocess(resolve, world);
+        self.world = self
+            .opts
+            .rename_world
+            .clone()
+            .unwrap_or_else(|| resolve.worlds[world].name.clone());
+        self.world_id = Some(world);
+
+        // Interfaces are renamed the same way as in the C generator, which
+        // already warns about renames that don't match anything.
+        let mut interfaces = HashMap::new();
+        let world = &resolve.worlds[world];
+        for (key, _item) in world.imports.iter().chain(world.exports.iter()) {
+            interfaces.insert(resolve.name_world_key(key), key.clone());
+        }
+        for (from, to) in self.opts.rename.iter() {
+            if let Some(key) = interfaces.get(from) {
+                self.renamed_interfaces.insert(key.clone(), to.clone());
+            }
+        }
+    }
+
+    fn import_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        files: &mut Files,
+    ) {
+        self.c.import_interface(resolve, name, id, files);
+
+        let mut gen = self.interface(resolve, true, Some((id, name)));
+        gen.define_interface_types(id);
+        for (_name, func) in resolve.interfaces[id].functions.iter() {
+            gen.import(Some(name), func);
+        }
+        gen.finish();
+    }
+
+    fn import_funcs(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        funcs: &[(&str, &Function)],
+        files: &mut Files,
+    ) {
+        self.c.import_funcs(resolve, world, funcs, files);
+
+        let mut gen = self.interface(resolve, true, None);
+        gen.define_function_types(funcs);
+        for (_name, func) in funcs.iter() {
+            gen.import(None, func);
+        }
+        gen.finish();
+    }
+
+    fn export_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        files: &mut Files,
+    ) -> Result<()> {
+        self.c.export_interface(resolve, name, id, files)?;
+
+        let mut gen = self.interface(resolve, false, Some((id, name)));
+        gen.define_interface_types(id);
+        let funcs = resolve.interfaces[id]
+            .functions
+            .values()
+            .collect::<Vec<_>>();
+        gen.export(Some(name), &funcs);
+        gen.finish();
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        funcs: &[(&str, &Function)],
+        files: &mut Files,
+    ) -> Result<()> {
+        self.c.export_funcs(resolve, world, funcs, files)?;
+
+        let mut gen = self.interface(resolve, false, None);
+        gen.define_function_types(funcs);
+        let funcs = funcs.iter().map(|(_, func)| *func).collect::<Vec<_>>();
+        gen.export(None, &funcs);
+        gen.finish();
+        Ok(())
+    }
+
+    fn import_types(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        types: &[(&str, TypeId)],
+        files: &mut Files,
+    ) {
+        self.c.import_types(resolve, world, types, files);
+
+        let mut gen = self.interface(resolve, true, None);
+        let mut live = LiveTypes::default();
+        for (_, id) in types {
+            live.add_type_id(resolve, *id);
+        }
+        gen.define_live_types(live);
+        gen.finish();
+    }
+
+    fn pre_export_interface(&mut self, resolve: &Resolve, files: &mut Files) -> Result<()> {
+        self.c.pre_export_interface(resolve, files)?;
+
+        // Mirror the C generator, which redefines all types of exported
+        // interfaces except for those imported types which exports use.
+        let live_import_types = wit_bindgen_c::imported_types_used_by_exported_interfaces(
+            resolve,
+            self.world_id.unwrap(),
+        );
+        self.c_type_names
+            .retain(|k, _| live_import_types.contains(k));
+        self.cpp_type_names
+            .retain(|k, _| live_import_types.contains(k));
+        self.resources.retain(|k, _| live_import_types.contains(k));
+        Ok(())
+    }
+
+    fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        self.c.finish(resolve, id, files)?;
+
+        let version = env!("CARGO_PKG_VERSION");
+        let snake = self.world.to_snake_case();
+
+        let mut h_str = Source::default();
+        wit_bindgen_core::generated_preamble(&mut h_str, version);
+        h_str.push_str(&format!(
+            "#ifndef __BINDINGS_{shouty}_HPP
+             #define __BINDINGS_{shouty}_HPP
+
+             #include <cstddef>
+             #include <cstdint>
+             #include <expected>
+             #include <functional>
+             #include <memory>
+             #include <optional>
+             #include <span>
+             #include <string>
+             #include <string_view>
+             #include <tuple>
+             #include <utility>
+             #include <variant>
+             #include <vector>
+
+             #include \"{snake}.h\"
+            ",
+            shouty = self.world.to_shouty_snake_case(),
+        ));
+        if !self.h_reps.is_empty() {
+            uwriteln!(
+                h_str,
+                "\n// Representations of exported resources, from which the\n\
+                 // classes implementing them derive."
+            );
+            h_str.push_str(&self.h_reps);
+        }
+        h_str.push_str(&self.h_src);
+        uwriteln!(h_str, "\n#endif");
+
+        let mut cpp_str = Source::default();
+        wit_bindgen_core::generated_preamble(&mut cpp_str, version);
+        uwriteln!(cpp_str, "#include \"{snake}.hpp\"");
+        uwriteln!(cpp_str, "#include <cstdlib>");
+        uwriteln!(cpp_str, "#include <cstring>");
+        if self.needs_string {
+            cpp_str.push_str(&format!(
+                "
+                    [[maybe_unused]] static std::string lift_string(const {snake}_string_t& v) {{
+                        return std::string(reinterpret_cast<const char*>(v.ptr), v.len);
+                    }}
+
+                    [[maybe_unused]] static {snake}_string_t lower_string(std::string_view v) {{
+                        {snake}_string_t r{{}};
+                        r.len = v.size();
+                        if (r.len > 0) {{
+                            r.ptr = static_cast<uint8_t*>(malloc(r.len));
+                            memcpy(r.ptr, v.data(), r.len);
+                        }}
+                        return r;
+                    }}
+                ",
+            ));
+        }
+        cpp_str.push_str(&self.cpp_converters);
+        cpp_str.push_str(&self.cpp_fns);
+
+        files.push(&format!("{snake}.hpp"), h_str.as_bytes());
+        files.push(&format!("{snake}.cpp"), cpp_str.as_bytes());
+        Ok(())
+    }
+}
+
+impl Cpp {
+    fn interface<'a>(
+        &'a mut self,
+        resolve: &'a Resolve,
+        in_import: bool,
+        interface: Option<(InterfaceId, &'a WorldKey)>,
+    ) -> InterfaceGenerator<'a> {
+        let namespace = self.namespace(resolve, in_import, interface.map(|(_, key)| key));
+        InterfaceGenerator::new(self, resolve, in_import, interface, namespace)
+    }
+
+    /// Returns the C++ namespace, such as `exports::wasi::cli::run`, that
+    /// bindings for `key` are placed in, or those for the world itself if
+    /// `key` is `None`.
+    fn namespace(&self, resolve: &Resolve, in_import: bool, key: Option<&WorldKey>) -> String {
+        let mut segments = Vec::new();
+        if !in_import {
+            segments.push("exports".to_string());
+        }
+        match key {
+            None => segments.push(self.world.to_snake_case()),
+            Some(key) => match (self.renamed_interfaces.get(key), key) {
+                (Some(rename), _) => segments.push(rename.to_snake_case()),
+                (None, WorldKey::Name(name)) => segments.push(name.to_snake_case()),
+                (None, WorldKey::Interface(id)) => {
+                    let iface = &resolve.interfaces[*id];
+                    let pkg = &resolve.packages[iface.package.unwrap()];
+                    segments.push(pkg.name.namespace.to_snake_case());
+                    segments.push(pkg.name.name.to_snake_case());
+                    let pkg_has_multiple_versions = resolve.packages.iter().any(|(_, p)| {
+                        p.name.namespace == pkg.name.namespace
+                            && p.name.name == pkg.name.name
+                            && p.name.version != pkg.name.version
+                    });
+                    if pkg_has_multiple_versions {
+                        if let Some(version) = &pkg.name.version {
+                            let version = version.to_string().replace(['.', '-', '+'], "_");
+                            segments.push(format!("v{version}"));
+                        }
+                    }
+                    segments.push(iface.name.as_ref().unwrap().to_snake_case());
+                }
+            },
+        }
+        segments
+            .iter()
+            .map(|s| interface::to_cpp_ident(s))
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+}
@@ -0,0 +1,82 @@
+use heck::*;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+macro_rules! codegen_test {
+    // todo: support flags wider than 64 bits and then remove this line:
+    (flags_wide $name:tt $test:tt) => {};
+
+    // `future` and `stream` types aren't supported yet, so make sure they're
+    // reported as an error.
+    (futures_streams $name:tt $test:tt) => {
+        #[test]
+        fn futures_streams() {
+            test_helpers::run_world_codegen_test(
+                "guest-cpp",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let err = wit_bindgen_cpp::Opts::default()
+                        .build()
+                        .generate(resolve, world, files)
+                        .unwrap_err();
+                    assert!(err.to_string().contains("aren't supported"), "{err}");
+                },
+                |_, _| {},
+            );
+        }
+    };
+
+    ($id:ident $name:tt $test:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "guest-cpp",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_cpp::Opts::default()
+                        .build()
+                        .generate(resolve, world, files)
+                        .unwrap()
+                },
+                verify,
+            );
+        }
+    };
+}
+
+test_helpers::codegen_tests!();
+
+fn verify(dir: &Path, name: &str) {
+    let name = name.to_snake_case();
+    let sdk_path = PathBuf::from(
+        env::var_os("WASI_SDK_PATH").expect("environment variable WASI_SDK_PATH should be set"),
+    );
+    let sysroot = sdk_path.join("share/wasi-sysroot");
+
+    let shared_args = vec![
+        "--sysroot",
+        sysroot.to_str().unwrap(),
+        "-I",
+        dir.to_str().unwrap(),
+        "-Wall",
+        "-Wextra",
+        "-Werror",
+        "-Wno-unused-parameter",
+        "-c",
+        "-o",
+    ];
+
+    let mut cmd = Command::new(sdk_path.join("bin/clang"));
+    cmd.args(&shared_args);
+    cmd.arg(dir.join("c.o"));
+    cmd.arg(dir.join(format!("{name}.c")));
+    test_helpers::run_command(&mut cmd);
+
+    let mut cmd = Command::new(sdk_path.join("bin/clang++"));
+    cmd.arg("-std=c++23");
+    cmd.args(&shared_args);
+    cmd.arg(dir.join("cpp.o"));
+    cmd.arg(dir.join(format!("{name}.cpp")));
+    test_helpers::run_command(&mut cmd);
+}
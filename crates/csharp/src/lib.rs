@@ -9,16 +9,17 @@ use std::{
     ops::Deref,
 };
 use wit_bindgen_core::{
-    abi::{self, AbiVariant, Bindgen, Instruction, LiftLower, WasmType},
+    abi::{self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType},
+    dealias, resource_functions,
     wit_parser::LiveTypes,
     Direction,
 };
 use wit_bindgen_core::{
     uwrite, uwriteln,
     wit_parser::{
-        Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Int, InterfaceId, Record, Resolve,
-        Result_, SizeAlign, Tuple, Type, TypeDefKind, TypeId, TypeOwner, Variant, WorldId,
-        WorldKey,
+        Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle, Int, InterfaceId, Record,
+        Resolve, Result_, SizeAlign, Stream, Tuple, Type, TypeDefKind, TypeId, TypeOwner, Variant,
+        WorldId, WorldKey,
     },
     Files, InterfaceGenerator as _, Ns, WorldGenerator,
 };
@@ -103,12 +104,13 @@ pub struct CSharp {
     return_area_align: usize,
     tuple_counts: HashSet<usize>,
     needs_result: bool,
-    needs_interop_string: bool,
+    needs_rep_table: bool,
     interface_fragments: HashMap<String, InterfaceTypeAndFragments>,
     world_fragments: Vec<InterfaceFragment>,
     sizes: SizeAlign,
     interface_names: HashMap<InterfaceId, String>,
     anonymous_type_owners: HashMap<TypeId, TypeOwner>,
+    resource_directions: HashMap<TypeId, Direction>,
 }
 
 impl CSharp {
@@ -121,6 +123,7 @@ impl CSharp {
         &'a mut self,
         resolve: &'a Resolve,
         name: &'a str,
+        key: Option<&'a WorldKey>,
         direction: Direction,
         function_level: FunctionLevel,
     ) -> InterfaceGenerator<'a> {
@@ -131,6 +134,7 @@ impl CSharp {
             gen: self,
             resolve,
             name,
+            key,
             direction,
             function_level,
         }
@@ -167,7 +171,13 @@ impl WorldGenerator for CSharp {
     ) {
         let name = interface_name(self, resolve, key, Direction::Import);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name, Direction::Import, FunctionLevel::Interface);
+        let mut gen = self.interface(
+            resolve,
+            &name,
+            Some(key),
+            Direction::Import,
+            FunctionLevel::Interface,
+        );
 
         gen.types(id);
 
@@ -178,7 +188,6 @@ impl WorldGenerator for CSharp {
         // for anonymous types
         gen.define_interface_types(id);
 
-        gen.add_import_return_area();
         gen.add_interface_fragment(false);
     }
 
@@ -193,6 +202,7 @@ impl WorldGenerator for CSharp {
         let mut gen = self.interface(
             resolve,
             name,
+            None,
             Direction::Import,
             FunctionLevel::FreeStanding,
         );
@@ -213,7 +223,13 @@ impl WorldGenerator for CSharp {
     ) -> Result<()> {
         let name = interface_name(self, resolve, key, Direction::Export);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name, Direction::Export, FunctionLevel::Interface);
+        let mut gen = self.interface(
+            resolve,
+            &name,
+            Some(key),
+            Direction::Export,
+            FunctionLevel::Interface,
+        );
 
         gen.types(id);
 
@@ -240,6 +256,7 @@ impl WorldGenerator for CSharp {
         let mut gen = self.interface(
             resolve,
             name,
+            None,
             Direction::Export,
             FunctionLevel::FreeStanding,
         );
@@ -260,7 +277,13 @@ impl WorldGenerator for CSharp {
         _files: &mut Files,
     ) {
         let name = &format!("{}-world", resolve.worlds[world].name);
-        let mut gen = self.interface(resolve, name, Direction::Import, FunctionLevel::Interface);
+        let mut gen = self.interface(
+            resolve,
+            name,
+            None,
+            Direction::Import,
+            FunctionLevel::Interface,
+        );
 
         for (ty_name, ty) in types {
             gen.define_type(ty_name, *ty);
@@ -306,12 +329,12 @@ impl WorldGenerator for CSharp {
             env!("CARGO_PKG_VERSION"),
         );
 
+        src.push_str("}\n");
+
         if self.needs_result {
             src.push_str(
                 r#"
-                using System.Runtime.InteropServices;
-
-                namespace Wit.Interop;
+                public readonly struct None {}
 
                 [StructLayout(LayoutKind.Sequential)]
                 public readonly struct Result<Ok, Err>
@@ -366,19 +389,47 @@ impl WorldGenerator for CSharp {
                 "#,
             )
         }
-        src.push_str("}\n");
 
-        if self.needs_interop_string {
+        if self.needs_rep_table {
             src.push_str(
                 r#"
-                public static class InteropString
-                {
-                    public static IntPtr FromString(string input, out int length)
-                    {
-                        var utf8Bytes = Encoding.UTF8.GetBytes(input);
-                        length = utf8Bytes.Length;
-                        var gcHandle = GCHandle.Alloc(utf8Bytes, GCHandleType.Pinned);
-                        return gcHandle.AddrOfPinnedObject();
+                internal class RepTable<T> {
+                    private List<object> list = new List<object>();
+                    private int? firstVacant = null;
+
+                    private class Vacant {
+                        internal int? next;
+
+                        internal Vacant(int? next) {
+                            this.next = next;
+                        }
+                    }
+
+                    internal int Add(T v) {
+                        int rep;
+                        if (firstVacant.HasValue) {
+                            rep = firstVacant.Value;
+                            firstVacant = ((Vacant) list[rep]).next;
+                            list[rep] = v!;
+                        } else {
+                            rep = list.Count;
+                            list.Add(v!);
+                        }
+                        return rep;
+                    }
+
+                    internal T Get(int rep) {
+                        if (list[rep] is Vacant) {
+                            throw new ArgumentException("invalid rep");
+                        }
+                        return (T) list[rep];
+                    }
+
+                    internal T Remove(int rep) {
+                        var val = Get(rep);
+                        list[rep] = new Vacant(firstVacant);
+                        firstVacant = rep;
+                        return val;
                     }
                 }
                 "#,
@@ -407,27 +458,6 @@ impl WorldGenerator for CSharp {
                     {{
                         private byte buffer;
 
-                        private int GetS32(int offset)
-                        {{
-                            ReadOnlySpan<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            return BitConverter.ToInt32(span.Slice(offset, 4));
-                        }}
-
-                        public void SetS32(int offset, int value)
-                        {{
-                            Span<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
-                        public void SetF32(int offset, float value)
-                        {{
-                            Span<byte> span = this;
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
                         internal unsafe int AddrOfBuffer()
                         {{
                             fixed(byte* ptr = &buffer)
@@ -435,11 +465,6 @@ impl WorldGenerator for CSharp {
                                 return (int)ptr;
                             }}
                         }}
-
-                        public unsafe string GetUTF8String(int p0, int p1)
-                        {{
-                            return Encoding.UTF8.GetString((byte*)p0, p1);
-                        }}
                     }}
 
                     [ThreadStatic]
@@ -666,6 +691,7 @@ struct InterfaceGenerator<'a> {
     gen: &'a mut CSharp,
     resolve: &'a Resolve,
     name: &'a str,
+    key: Option<&'a WorldKey>,
     direction: Direction,
     function_level: FunctionLevel,
 }
@@ -731,6 +757,10 @@ impl InterfaceGenerator<'_> {
             TypeDefKind::Tuple(t) => self.type_tuple(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::Option(t) => self.type_option(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::Record(t) => self.type_record(type_id, typedef_name, t, &type_def.docs),
+            // `own<T>` and `borrow<T>` are spelled as the class generated for `T`
+            TypeDefKind::Handle(_) => {}
+            // lists and results are spelled as `List<T>` (or `T[]`) and `Result<Ok, Err>`
+            TypeDefKind::List(_) | TypeDefKind::Result(_) => {}
             _ => unreachable!(),
         }
     }
@@ -744,12 +774,20 @@ impl InterfaceGenerator<'_> {
             type_def.owner
         };
 
-        if let TypeOwner::Interface(id) = owner {
-            if let Some(name) = self.gen.interface_names.get(&id) {
-                if name != self.name {
-                    return format!("{}.", name);
+        match owner {
+            TypeOwner::Interface(id) => {
+                if let Some(name) = self.gen.interface_names.get(&id) {
+                    if name != self.name {
+                        return format!("{}.", name);
+                    }
                 }
             }
+            // types defined by the world live in the `I{World}World` interface
+            TypeOwner::World(_) if when => {
+                let world = self.gen.name.to_upper_camel_case();
+                return format!("{}I{world}World.", self.gen.qualifier());
+            }
+            _ => {}
         }
 
         if when {
@@ -773,59 +811,6 @@ impl InterfaceGenerator<'_> {
             });
     }
 
-    fn add_import_return_area(&mut self) {
-        let mut ret_struct_type = String::new();
-        if self.gen.return_area_size > 0 {
-            uwrite!(
-                ret_struct_type,
-                r#"
-                private unsafe struct ReturnArea
-                {{
-                    public static byte GetU8(IntPtr ptr)
-                    {{
-                        var span = new Span<byte>((void*)ptr, 1);
-
-                        return span[0];
-                    }}
-
-                    public static ushort GetU16(IntPtr ptr)
-                    {{
-                        var span = new Span<byte>((void*)ptr, 2);
-
-                        return BitConverter.ToUInt16(span);
-                    }}
-
-                    public static int GetS32(IntPtr ptr)
-                    {{
-                        var span = new Span<byte>((void*)ptr, 4);
-
-                        return BitConverter.ToInt32(span);
-                    }}
-
-                    internal static float GetF32(IntPtr ptr, int offset)
-                    {{
-                        var span = new Span<byte>((void*)ptr, 4);
-                        return BitConverter.ToSingle(span.Slice(offset, 4));
-                    }}
-
-                    public static string GetUTF8String(IntPtr ptr)
-                    {{
-                        return Encoding.UTF8.GetString((byte*)GetS32(ptr), GetS32(ptr + 4));
-                    }}
-
-                }}
-            "#
-            );
-        }
-
-        uwrite!(
-            self.csharp_interop_src,
-            r#"
-                {ret_struct_type}
-            "#
-        );
-    }
-
     fn add_export_return_area(&mut self) {
         // Declare a statically-allocated return area, if needed. We only do
         // this for export bindings, because import bindings allocate their
@@ -842,41 +827,6 @@ impl InterfaceGenerator<'_> {
                     {{
                         private byte buffer;
 
-                        private int GetS32(int offset)
-                        {{
-                            ReadOnlySpan<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            return BitConverter.ToInt32(span.Slice(offset, 4));
-                        }}
-
-                        public void SetS8(int offset, int value)
-                        {{
-                            Span<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
-                        public void SetS16(int offset, short value)
-                        {{
-                            Span<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
-                        public void SetS32(int offset, int value)
-                        {{
-                            Span<byte> span = MemoryMarshal.CreateSpan(ref buffer, {0});
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
-                        public void SetF32(int offset, float value)
-                        {{
-                            Span<byte> span = this;
-
-                            BitConverter.TryWriteBytes(span.Slice(offset), value);
-                        }}
-
                         internal unsafe int AddrOfBuffer()
                         {{
                             fixed(byte* ptr = &buffer)
@@ -884,11 +834,6 @@ impl InterfaceGenerator<'_> {
                                 return (int)ptr;
                             }}
                         }}
-
-                        public unsafe string GetUTF8String(int p0, int p1)
-                        {{
-                            return Encoding.UTF8.GetString((byte*)p0, p1);
-                        }}
                     }}
 
                     [ThreadStatic]
@@ -911,10 +856,6 @@ impl InterfaceGenerator<'_> {
     }
 
     fn import(&mut self, import_module_name: &str, func: &Function) {
-        if func.kind != FunctionKind::Freestanding {
-            todo!("resources");
-        }
-
         let sig = self.resolve.wasm_signature(AbiVariant::GuestImport, func);
 
         let wasm_result_type = match &sig.results[..] {
@@ -940,7 +881,7 @@ impl InterfaceGenerator<'_> {
             }
         };
 
-        let camel_name = func.name.to_upper_camel_case();
+        let camel_name = camel_func_name(&func.name);
 
         let wasm_params = sig
             .params
@@ -970,7 +911,13 @@ impl InterfaceGenerator<'_> {
             &mut bindgen,
         );
 
-        let src = bindgen.src;
+        // Memory handed to the import is only borrowed for the duration of the
+        // call and released right after it, see `CallWasm`.
+        let src = if bindgen.needs_cleanup_list {
+            format!("var cleanups = new List<Action>();\n{}", bindgen.src)
+        } else {
+            bindgen.src
+        };
 
         let params = func
             .params
@@ -978,7 +925,7 @@ impl InterfaceGenerator<'_> {
             .enumerate()
             .map(|(_i, param)| {
                 let ty = self.type_name_with_qualifier(&param.1, true);
-                let param_name = &param.0;
+                let param_name = param.0.to_csharp_ident();
                 format!("{ty} {param_name}")
             })
             .collect::<Vec<_>>()
@@ -1003,7 +950,6 @@ impl InterfaceGenerator<'_> {
                 internal static unsafe {result_type} {camel_name}({params})
                 {{
                     {src}
-                    //TODO: free alloc handle (interopString) if exists
                 }}
             "#
         );
@@ -1050,7 +996,7 @@ impl InterfaceGenerator<'_> {
             }
         };
 
-        let camel_name = func.name.to_upper_camel_case();
+        let camel_name = camel_func_name(&func.name);
 
         let wasm_params = sig
             .params
@@ -1068,6 +1014,7 @@ impl InterfaceGenerator<'_> {
             .iter()
             .map(|(name, ty)| {
                 let ty = self.type_name(ty);
+                let name = name.to_csharp_ident();
 
                 format!("{ty} {name}")
             })
@@ -1082,24 +1029,37 @@ impl InterfaceGenerator<'_> {
             self.csharp_interop_src,
             r#"
             [UnmanagedCallersOnly(EntryPoint = "{export_name}")]
-            public static {wasm_result_type} {interop_name}({wasm_params}) {{
+            public static unsafe {wasm_result_type} {interop_name}({wasm_params}) {{
                 {src}
             }}
             "#
         );
 
-        if !sig.results.is_empty() {
+        if abi::guest_export_needs_post_return(self.resolve, func) {
+            let mut bindgen =
+                FunctionBindgen::new(self, &func.name, Box::new(["returnValue".to_owned()]));
+
+            abi::post_return(bindgen.gen.resolve, func, &mut bindgen);
+
+            let src = bindgen.src;
+
             uwrite!(
                 self.csharp_interop_src,
                 r#"
                 [UnmanagedCallersOnly(EntryPoint = "cabi_post_{export_name}")]
-                public static void cabi_post_{interop_name}({wasm_result_type} returnValue) {{
-                    Console.WriteLine("cabi_post_{export_name}");
+                public static unsafe void cabi_post_{interop_name}({wasm_result_type} returnValue) {{
+                    {src}
                 }}
                 "#
             );
         }
 
+        // Methods of exported resources are abstract members of the resource's
+        // class instead, see `type_resource`.
+        if let FunctionKind::Method(_) = func.kind {
+            return;
+        }
+
         uwrite!(
             self.src,
             r#"static abstract {result_type} {camel_name}({params});
@@ -1188,11 +1148,13 @@ impl InterfaceGenerator<'_> {
                     }
                     TypeDefKind::Option(base_ty) => {
                         // TODO: investigate a generic Option<> class.
-                        if let Some(_name) = &ty.name {
+                        // Named options are spelled after their typedef, since
+                        // several typedefs may share a payload type.
+                        if let Some(name) = &ty.name {
                             format!(
-                                "{}Option{}",
+                                "{}{}",
                                 self.qualifier(qualifier, id),
-                                self.type_name_with_qualifier(base_ty, false)
+                                name.to_upper_camel_case()
                             )
                         } else {
                             format!(
@@ -1204,15 +1166,19 @@ impl InterfaceGenerator<'_> {
                     }
                     TypeDefKind::Result(result) => {
                         self.gen.needs_result = true;
+                        let world_qualifier = self.gen.qualifier();
                         let mut name = |ty: &Option<Type>| {
                             ty.as_ref()
                                 .map(|ty| self.type_name_boxed(ty, qualifier))
-                                .unwrap_or_else(|| "void".to_owned())
+                                .unwrap_or_else(|| format!("{world_qualifier}None"))
                         };
                         let ok = name(&result.ok);
                         let err = name(&result.err);
 
-                        format!("{}Result<{ok}, {err}>", self.gen.qualifier())
+                        format!("{world_qualifier}Result<{ok}, {err}>")
+                    }
+                    TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => {
+                        self.type_name_with_qualifier(&Type::Id(*id), qualifier)
                     }
                     _ => {
                         if let Some(name) = &ty.name {
@@ -1292,8 +1258,270 @@ impl InterfaceGenerator<'_> {
         }
     }
 
+    /// The core wasm module that the functions of this interface are imported
+    /// from (or exported under).
+    fn wasm_module_name(&self) -> String {
+        match self.key {
+            Some(key) => self.resolve.name_world_key(key),
+            None => "$root".to_owned(),
+        }
+    }
+
+    /// The fully qualified name of the class holding the interop glue for the
+    /// functions of `id`'s owner.
+    fn interop_class_name(&self, id: TypeId) -> String {
+        match self.resolve.types[id].owner {
+            TypeOwner::Interface(_) => {
+                let (namespace, interface_name) =
+                    CSharp::get_class_name_from_qualified_name(self.name.to_string());
+                let class_name = interface_name.strip_prefix("I").unwrap();
+                format!("global::{namespace}.{class_name}Interop")
+            }
+            TypeOwner::World(_) => format!(
+                "global::{}exports.{}World",
+                self.gen.qualifier(),
+                self.gen.name.to_upper_camel_case()
+            ),
+            TypeOwner::None => unreachable!(),
+        }
+    }
+
+    /// Returns the result type, parameter list and argument names of `func` as
+    /// a member of its resource's class, i.e. without the `self` parameter of
+    /// methods.
+    fn resource_member_sig(&mut self, func: &Function) -> (String, String, Vec<String>) {
+        let result_type = match func.results.len() {
+            0 => "void".to_owned(),
+            1 => self.type_name(func.results.iter_types().next().unwrap()),
+            _ => {
+                let types = func
+                    .results
+                    .iter_types()
+                    .map(|ty| self.type_name(ty))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("({})", types)
+            }
+        };
+
+        let skip = match func.kind {
+            FunctionKind::Method(_) => 1,
+            _ => 0,
+        };
+
+        let (params, args): (Vec<_>, Vec<_>) = func
+            .params
+            .iter()
+            .skip(skip)
+            .map(|(name, ty)| {
+                let ty = self.type_name(ty);
+                let name = name.to_csharp_ident();
+                (format!("{ty} {name}"), name)
+            })
+            .unzip();
+
+        (result_type, params.join(", "), args)
+    }
+
+    fn import_resource(&mut self, id: TypeId, name: &str) {
+        let camel_name = name.to_upper_camel_case();
+        let module = self.wasm_module_name();
+        let interop = self.interop_class_name(id);
+
+        let mut members = String::new();
+        for func in resource_functions(self.resolve, id) {
+            let (result_type, params, mut args) = self.resource_member_sig(func);
+            let target = camel_func_name(&func.name);
+            let member_name = func.item_name().to_upper_camel_case();
+            let ret = if result_type == "void" { "" } else { "return " };
+
+            match func.kind {
+                FunctionKind::Constructor(_) => {
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        r#"
+                        public {camel_name}({params}) {{
+                            var __resource = {interop}.{target}({args});
+                            this.Handle = __resource.Handle;
+                            __resource.Handle = 0;
+                        }}
+                        "#
+                    );
+                }
+                FunctionKind::Method(_) => {
+                    args.insert(0, "this".to_owned());
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        r#"
+                        public {result_type} {member_name}({params}) {{
+                            {ret}{interop}.{target}({args});
+                        }}
+                        "#
+                    );
+                }
+                FunctionKind::Static(_) => {
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        r#"
+                        public static {result_type} {member_name}({params}) {{
+                            {ret}{interop}.{target}({args});
+                        }}
+                        "#
+                    );
+                }
+                FunctionKind::Freestanding => unreachable!(),
+            }
+        }
+
+        uwrite!(
+            self.src,
+            r#"
+            public class {camel_name} : IDisposable {{
+                internal int Handle {{ get; set; }}
+
+                public readonly record struct THandle(int Handle);
+
+                public {camel_name}(THandle handle) {{
+                    Handle = handle.Handle;
+                }}
+
+                ~{camel_name}() {{
+                    Dispose(false);
+                }}
+
+                public void Dispose() {{
+                    Dispose(true);
+                    GC.SuppressFinalize(this);
+                }}
+
+                protected virtual void Dispose(bool disposing) {{
+                    if (Handle != 0) {{
+                        var handle = Handle;
+                        Handle = 0;
+                        WasmInterop.wasmImportResourceDrop(handle);
+                    }}
+                }}
+
+                internal static class WasmInterop
+                {{
+                    [DllImport("{module}", EntryPoint = "[resource-drop]{name}"), WasmImportLinkage]
+                    internal static extern void wasmImportResourceDrop(int p0);
+                }}
+
+                {members}
+            }}
+            "#
+        );
+    }
+
+    fn export_resource(&mut self, id: TypeId, name: &str) {
+        self.gen.needs_rep_table = true;
+
+        let camel_name = name.to_upper_camel_case();
+        let module = self.wasm_module_name();
+
+        let mut members = String::new();
+        let mut stub_members = String::new();
+        for func in resource_functions(self.resolve, id) {
+            // Constructors and static functions are implemented as static members
+            // of the interface, see `export`.
+            if let FunctionKind::Method(_) = func.kind {
+                let (result_type, params, _) = self.resource_member_sig(func);
+                let member_name = func.item_name().to_upper_camel_case();
+
+                uwrite!(
+                    members,
+                    r#"
+                    public abstract {result_type} {member_name}({params});
+                    "#
+                );
+                uwrite!(
+                    stub_members,
+                    r#"
+                    public override {result_type} {member_name}({params}) {{
+                        throw new NotImplementedException();
+                    }}
+                    "#
+                );
+            }
+        }
+
+        uwrite!(
+            self.src,
+            r#"
+            public abstract class {camel_name} : IDisposable {{
+                internal static RepTable<{camel_name}> repTable = new ();
+                internal int Handle {{ get; set; }}
+                internal int? Rep {{ get; set; }}
+
+                ~{camel_name}() {{
+                    Dispose(false);
+                }}
+
+                public void Dispose() {{
+                    Dispose(true);
+                    GC.SuppressFinalize(this);
+                }}
+
+                protected virtual void Dispose(bool disposing) {{
+                    if (Handle != 0) {{
+                        var handle = Handle;
+                        Handle = 0;
+                        WasmInterop.wasmImportResourceDrop(handle);
+                    }}
+                }}
+
+                internal static class WasmInterop
+                {{
+                    [DllImport("[export]{module}", EntryPoint = "[resource-drop]{name}"), WasmImportLinkage]
+                    internal static extern void wasmImportResourceDrop(int p0);
+
+                    [DllImport("[export]{module}", EntryPoint = "[resource-new]{name}"), WasmImportLinkage]
+                    internal static extern int wasmImportResourceNew(int p0);
+
+                    [DllImport("[export]{module}", EntryPoint = "[resource-rep]{name}"), WasmImportLinkage]
+                    internal static extern int wasmImportResourceRep(int p0);
+                }}
+
+                {members}
+            }}
+            "#
+        );
+
+        let qualified_name = self.type_name_with_qualifier(&Type::Id(id), true);
+
+        // The host drops the last handle to a resource through its destructor,
+        // at which point the instance is released from the rep table.
+        uwrite!(
+            self.csharp_interop_src,
+            r#"
+            [UnmanagedCallersOnly(EntryPoint = "{module}#[dtor]{name}")]
+            public static void wasmExportResourceDtor{camel_name}(int rep) {{
+                var val = global::{qualified_name}.repTable.Remove(rep);
+                val.Handle = 0;
+                val.Rep = null;
+                val.Dispose();
+            }}
+            "#
+        );
+
+        if self.gen.opts.generate_stub {
+            uwrite!(
+                self.stub,
+                r#"
+                public class {camel_name} : global::{qualified_name} {{
+                    {stub_members}
+                }}
+                "#
+            );
+        }
+    }
+
     fn sig_string(&mut self, func: &Function, qualifier: bool) -> String {
-        let name = func.name.to_csharp_ident();
+        let camel_case = camel_func_name(&func.name);
 
         let result_type = match func.results.len() {
             0 => "void".into(),
@@ -1334,7 +1562,6 @@ impl InterfaceGenerator<'_> {
             .collect::<Vec<_>>()
             .join(", ");
 
-        let camel_case = name.to_upper_camel_case();
         format!("public static {result_type} {camel_case}({params})")
     }
 }
@@ -1490,7 +1717,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                                if (this.tag == {tag}) {{
                                    return ({ty}) this.value;
                                }} else {{
-                                   throw new RuntimeException("expected {tag}, got " + this.tag);
+                                   throw new ArgumentException("expected {tag}, got " + this.tag);
                                }}
                            }}
                         "#
@@ -1506,7 +1733,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .enumerate()
             .map(|(i, case)| {
                 let tag = case.name.to_shouty_snake_case();
-                format!("public readonly {tag_type} {tag} = {i};")
+                format!("public const {tag_type} {tag} = {i};")
             })
             .collect::<Vec<_>>()
             .join("\n");
@@ -1514,11 +1741,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         uwrite!(
             self.src,
             "
-            public static class {name} {{
+            public class {name} {{
                 public readonly {tag_type} tag;
-                private readonly Object value;
+                private readonly object? value;
 
-                private {name}({tag_type} tag, Object value) {{
+                private {name}({tag_type} tag, object? value) {{
                     this.tag = tag;
                     this.value = value;
                 }}
@@ -1602,6 +1829,14 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         unimplemented!();
     }
 
+    fn type_future(&mut self, _id: TypeId, _name: &str, _ty: &Option<Type>, _docs: &Docs) {
+        unreachable!("`future` types are rejected by `WorldGenerator::generate`")
+    }
+
+    fn type_stream(&mut self, _id: TypeId, _name: &str, _stream: &Stream, _docs: &Docs) {
+        unreachable!("`stream` types are rejected by `WorldGenerator::generate`")
+    }
+
     fn define_type(&mut self, name: &str, id: TypeId) {
         let ty = &self.resolve().types[id];
         match &ty.kind {
@@ -1614,16 +1849,23 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             TypeDefKind::Result(r) => self.type_result(id, name, r, &ty.docs),
             TypeDefKind::List(t) => self.type_list(id, name, t, &ty.docs),
             TypeDefKind::Type(t) => self.type_alias(id, name, t, &ty.docs),
-            TypeDefKind::Future(_) => todo!("generate for future"),
-            TypeDefKind::Stream(_) => todo!("generate for stream"),
-            TypeDefKind::Resource => todo!("generate for resource"),
-            TypeDefKind::Handle(_) => todo!("generate for handle"),
+            TypeDefKind::Future(t) => self.type_future(id, name, t, &ty.docs),
+            TypeDefKind::Stream(s) => self.type_stream(id, name, s, &ty.docs),
+            TypeDefKind::Resource => self.type_resource(id, name, &ty.docs),
+            TypeDefKind::Handle(_) => {}
             TypeDefKind::Unknown => unreachable!(),
         }
     }
 
-    fn type_resource(&mut self, _id: TypeId, _name: &str, _docs: &Docs) {
-        todo!()
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        self.print_docs(docs);
+
+        self.gen.resource_directions.insert(id, self.direction);
+
+        match self.direction {
+            Direction::Import => self.import_resource(id, name),
+            Direction::Export => self.export_resource(id, name),
+        }
     }
 }
 
@@ -1635,8 +1877,8 @@ enum Stubs<'a> {
 struct Block {
     body: String,
     results: Vec<String>,
-    _xelement: String,
-    _xbase: String,
+    element: String,
+    base: String,
 }
 
 struct BlockStorage {
@@ -1704,51 +1946,81 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             })),
             Instruction::I32Load { offset }
             | Instruction::PointerLoad { offset }
-            | Instruction::LengthLoad { offset } => match self.gen.direction {
-                Direction::Import => results.push(format!("ReturnArea.GetS32(ptr + {offset})")),
-                Direction::Export => results.push(format!("returnArea.GetS32({offset})")),
-            },
-            Instruction::I32Load8U { offset } => match self.gen.direction {
-                Direction::Import => results.push(format!("ReturnArea.GetU8(ptr + {offset})")),
-                Direction::Export => results.push(format!("returnArea.GetU8({offset})")),
-            },
+            | Instruction::LengthLoad { offset } => {
+                results.push(format!("*(int*)({} + {offset})", operands[0]))
+            }
+            Instruction::I32Load8U { offset } => {
+                results.push(format!("(int)*(byte*)({} + {offset})", operands[0]))
+            }
             Instruction::I32Load8S { offset } => {
-                results.push(format!("returnArea.GetS8({offset})"))
+                results.push(format!("(int)*(sbyte*)({} + {offset})", operands[0]))
+            }
+            Instruction::I32Load16U { offset } => {
+                results.push(format!("(int)*(ushort*)({} + {offset})", operands[0]))
             }
-            Instruction::I32Load16U { offset } => match self.gen.direction {
-                Direction::Import => results.push(format!("ReturnArea.GetU16(ptr + {offset})")),
-                Direction::Export => results.push(format!("returnArea.GetU16({offset})")),
-            },
             Instruction::I32Load16S { offset } => {
-                results.push(format!("returnArea.GetS16({offset})"))
+                results.push(format!("(int)*(short*)({} + {offset})", operands[0]))
+            }
+            Instruction::I64Load { offset } => {
+                results.push(format!("*(long*)({} + {offset})", operands[0]))
             }
-            Instruction::I64Load { offset } => results.push(format!("ReturnArea.GetS64({offset})")),
             Instruction::F32Load { offset } => {
-                results.push(format!("ReturnArea.GetF32(ptr, {offset})"))
+                results.push(format!("*(float*)({} + {offset})", operands[0]))
+            }
+            Instruction::F64Load { offset } => {
+                results.push(format!("*(double*)({} + {offset})", operands[0]))
             }
-            Instruction::F64Load { offset } => results.push(format!("ReturnArea.GetF64({offset})")),
 
             Instruction::I32Store { offset }
             | Instruction::PointerStore { offset }
             | Instruction::LengthStore { offset } => {
-                uwriteln!(self.src, "returnArea.SetS32({}, {});", offset, operands[0])
+                uwriteln!(
+                    self.src,
+                    "*(int*)({} + {offset}) = {};",
+                    operands[1],
+                    operands[0]
+                )
             }
             Instruction::I32Store8 { offset } => {
-                uwriteln!(self.src, "returnArea.SetS8({}, {});", offset, operands[0])
+                uwriteln!(
+                    self.src,
+                    "*(byte*)({} + {offset}) = unchecked((byte)({}));",
+                    operands[1],
+                    operands[0]
+                )
             }
             Instruction::I32Store16 { offset } => {
                 uwriteln!(
                     self.src,
-                    "returnArea.SetS16({}, unchecked((short){}));",
-                    offset,
+                    "*(short*)({} + {offset}) = unchecked((short)({}));",
+                    operands[1],
+                    operands[0]
+                )
+            }
+            Instruction::I64Store { offset } => {
+                uwriteln!(
+                    self.src,
+                    "*(long*)({} + {offset}) = {};",
+                    operands[1],
                     operands[0]
                 )
             }
-            Instruction::I64Store { .. } => todo!("I64Store"),
             Instruction::F32Store { offset } => {
-                uwriteln!(self.src, "returnArea.SetF32({}, {});", offset, operands[0])
+                uwriteln!(
+                    self.src,
+                    "*(float*)({} + {offset}) = {};",
+                    operands[1],
+                    operands[0]
+                )
+            }
+            Instruction::F64Store { offset } => {
+                uwriteln!(
+                    self.src,
+                    "*(double*)({} + {offset}) = {};",
+                    operands[1],
+                    operands[0]
+                )
             }
-            Instruction::F64Store { .. } => todo!("F64Store"),
 
             Instruction::I64FromU64 => results.push(format!("unchecked((long)({}))", operands[0])),
             Instruction::I32FromChar => results.push(format!("((int){})", operands[0])),
@@ -1775,7 +2047,11 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             | Instruction::S64FromI64
             | Instruction::Float64FromF64 => results.push(operands[0].clone()),
 
-            Instruction::Bitcasts { .. } => todo!("Bitcasts"),
+            Instruction::Bitcasts { casts } => {
+                for (cast, op) in casts.iter().zip(operands) {
+                    results.push(perform_cast(op, cast));
+                }
+            }
 
             Instruction::I32FromBool => {
                 results.push(format!("({} ? 1 : 0)", operands[0]));
@@ -1865,9 +2141,133 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 self.payloads.push(payload);
             }
 
-            Instruction::VariantLower { .. } => todo!("VariantLift"),
+            Instruction::VariantLower {
+                variant,
+                results: lowered_types,
+                ..
+            } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let payloads = self
+                    .payloads
+                    .drain(self.payloads.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
 
-            Instruction::VariantLift { .. } => todo!("VariantLift"),
+                let lowered = lowered_types
+                    .iter()
+                    .map(|_| self.locals.tmp("lowered"))
+                    .collect::<Vec<_>>();
+
+                results.extend(lowered.iter().cloned());
+
+                let declarations = lowered
+                    .iter()
+                    .zip(lowered_types.iter())
+                    .map(|(lowered, ty)| format!("{} {lowered};", wasm_type(*ty)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                let op = &operands[0];
+
+                let cases = variant
+                    .cases
+                    .iter()
+                    .zip(blocks)
+                    .zip(payloads)
+                    .enumerate()
+                    .map(|(i, ((case, Block { body, results, .. }), payload))| {
+                        let payload = if self.gen.non_empty_type(case.ty.as_ref()).is_some() {
+                            let case_name = case.name.to_upper_camel_case();
+                            format!("var {payload} = ({op}).get{case_name}();")
+                        } else {
+                            String::new()
+                        };
+
+                        let assignments = lowered
+                            .iter()
+                            .zip(&results)
+                            .map(|(lowered, result)| format!("{lowered} = {result};\n"))
+                            .collect::<Vec<_>>()
+                            .concat();
+
+                        format!(
+                            "case {i}: {{
+                                 {payload}
+                                 {body}
+                                 {assignments}
+                                 break;
+                             }}"
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                uwrite!(
+                    self.src,
+                    r#"
+                    {declarations}
+
+                    switch (({op}).tag) {{
+                        {cases}
+
+                        default: throw new ArgumentException("invalid discriminant: " + ({op}).tag);
+                    }}
+                    "#
+                );
+            }
+
+            Instruction::VariantLift { variant, ty, .. } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+
+                let ty = self.gen.type_name_with_qualifier(&Type::Id(*ty), true);
+                let lifted = self.locals.tmp("lifted");
+                let op = &operands[0];
+
+                let cases = variant
+                    .cases
+                    .iter()
+                    .zip(blocks)
+                    .enumerate()
+                    .map(|(i, (case, Block { body, results, .. }))| {
+                        let payload = if self.gen.non_empty_type(case.ty.as_ref()).is_some() {
+                            results.into_iter().next().unwrap()
+                        } else {
+                            String::new()
+                        };
+
+                        let case_name = case.name.to_csharp_ident();
+
+                        format!(
+                            "case {i}: {{
+                                 {body}
+                                 {lifted} = {ty}.{case_name}({payload});
+                                 break;
+                             }}"
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                uwrite!(
+                    self.src,
+                    r#"
+                    {ty} {lifted};
+
+                    switch ({op}) {{
+                        {cases}
+
+                        default: throw new ArgumentException("invalid discriminant: " + ({op}));
+                    }}
+                    "#
+                );
+
+                results.push(lifted);
+            }
 
             Instruction::OptionLower {
                 results: lowered_types,
@@ -1974,9 +2374,122 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 results.push(lifted);
             }
 
-            Instruction::ResultLower { .. } => todo!("ResultLower"),
+            Instruction::ResultLower {
+                results: lowered_types,
+                result,
+                ..
+            } => {
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
+                let err_payload = self.payloads.pop().unwrap();
+                let ok_payload = self.payloads.pop().unwrap();
+
+                let lowered = lowered_types
+                    .iter()
+                    .map(|_| self.locals.tmp("lowered"))
+                    .collect::<Vec<_>>();
+
+                results.extend(lowered.iter().cloned());
+
+                let declarations = lowered
+                    .iter()
+                    .zip(lowered_types.iter())
+                    .map(|(lowered, ty)| format!("{} {lowered};", wasm_type(*ty)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                let op = &operands[0];
+
+                let block = |ty: Option<&Type>, Block { body, results, .. }, payload, accessor| {
+                    let payload = if ty.is_some() {
+                        format!("var {payload} = ({op}).{accessor};")
+                    } else {
+                        String::new()
+                    };
+
+                    let assignments = lowered
+                        .iter()
+                        .zip(&results)
+                        .map(|(lowered, result)| format!("{lowered} = {result};\n"))
+                        .collect::<Vec<_>>()
+                        .concat();
+
+                    format!(
+                        "{payload}
+                         {body}
+                         {assignments}"
+                    )
+                };
+
+                let ok = block(result.ok.as_ref(), ok, ok_payload, "AsOk");
+                let err = block(result.err.as_ref(), err, err_payload, "AsErr");
+
+                uwrite!(
+                    self.src,
+                    r#"
+                    {declarations}
+
+                    switch (({op}).Tag) {{
+                        case 0: {{
+                            {ok}
+                            break;
+                        }}
+                        case 1: {{
+                            {err}
+                            break;
+                        }}
+
+                        default: throw new ArgumentException("invalid discriminant: " + ({op}).Tag);
+                    }}
+                    "#
+                );
+            }
+
+            Instruction::ResultLift { result, ty } => {
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
 
-            Instruction::ResultLift { .. } => todo!("ResultLift"),
+                let ty = self.gen.type_name_with_qualifier(&Type::Id(*ty), true);
+                let lifted = self.locals.tmp("lifted");
+                let op = &operands[0];
+
+                let payload = |ty: Option<&Type>, results: Vec<String>| {
+                    if ty.is_some() {
+                        results.into_iter().next().unwrap()
+                    } else {
+                        format!("new {}None()", self.gen.gen.qualifier())
+                    }
+                };
+
+                let ok_payload = payload(result.ok.as_ref(), ok.results);
+                let err_payload = payload(result.err.as_ref(), err.results);
+                let ok = ok.body;
+                let err = err.body;
+
+                uwrite!(
+                    self.src,
+                    r#"
+                    {ty} {lifted};
+
+                    switch ({op}) {{
+                        case 0: {{
+                            {ok}
+                            {lifted} = {ty}.ok({ok_payload});
+                            break;
+                        }}
+                        case 1: {{
+                            {err}
+                            {lifted} = {ty}.err({err_payload});
+                            break;
+                        }}
+
+                        default: throw new ArgumentException("invalid discriminant: " + ({op}));
+                    }}
+                    "#
+                );
+
+                results.push(lifted);
+            }
 
             Instruction::EnumLower { .. } => results.push(format!("(int){}", operands[0])),
 
@@ -1993,48 +2506,192 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 );
             }
 
-            Instruction::ListCanonLower { .. } => todo!("ListCanonLower"),
+            Instruction::ListCanonLower { element, realloc } => {
+                let op = &operands[0];
+                let ty = self.gen.type_name(element);
+                let size = self.gen.gen.sizes.size(element);
 
-            Instruction::ListCanonLift { .. } => todo!("ListCanonLift"),
+                if realloc.is_none() {
+                    // The import only borrows the list, so pin it in place for
+                    // the duration of the call.
+                    let handle = self.locals.tmp("gcHandle");
+                    uwriteln!(
+                        self.src,
+                        "var {handle} = GCHandle.Alloc({op}, GCHandleType.Pinned);
+                         cleanups.Add(() => {handle}.Free());"
+                    );
+                    self.needs_cleanup_list = true;
+
+                    results.push(format!("(int){handle}.AddrOfPinnedObject()"));
+                } else {
+                    let address = self.locals.tmp("address");
+                    uwriteln!(
+                        self.src,
+                        "var {address} = NativeMemory.Alloc((nuint)(({op}).Length * {size}));
+                         ({op}).AsSpan().CopyTo(new Span<{ty}>({address}, ({op}).Length));"
+                    );
+
+                    results.push(format!("(int){address}"));
+                }
+                results.push(format!("({op}).Length"));
+            }
+
+            Instruction::ListCanonLift { element, .. } => {
+                let ty = self.gen.type_name(element);
+                let address = self.locals.tmp("address");
+                let length = self.locals.tmp("length");
+                let array = self.locals.tmp("array");
 
-            Instruction::StringLower { realloc } => {
-                let op = &operands[0];
-                let interop_string = self.locals.tmp("interopString");
-                let result_var = self.locals.tmp("result");
                 uwriteln!(
                     self.src,
-                    "
-                    var {result_var} = {op};
-                    IntPtr {interop_string} = InteropString.FromString({result_var}, out int length{result_var});"
+                    "var {address} = {};
+                     var {length} = {};
+                     var {array} = new Span<{ty}>((void*)({address}), {length}).ToArray();
+                     if ({length} > 0) {{
+                         NativeMemory.Free((void*)({address}));
+                     }}",
+                    operands[0],
+                    operands[1]
                 );
 
+                results.push(array);
+            }
+
+            Instruction::StringLower { realloc } => {
+                let op = &operands[0];
+                let bytes = self.locals.tmp("bytes");
+
+                uwriteln!(self.src, "var {bytes} = Encoding.UTF8.GetBytes({op});");
+
                 if realloc.is_none() {
-                    results.push(format!("{interop_string}.ToInt32()"));
+                    // The import only borrows the string, so pin it in place for
+                    // the duration of the call.
+                    let handle = self.locals.tmp("gcHandle");
+                    uwriteln!(
+                        self.src,
+                        "var {handle} = GCHandle.Alloc({bytes}, GCHandleType.Pinned);
+                         cleanups.Add(() => {handle}.Free());"
+                    );
+                    self.needs_cleanup_list = true;
+
+                    results.push(format!("(int){handle}.AddrOfPinnedObject()"));
                 } else {
-                    results.push(format!("{interop_string}.ToInt32()"));
+                    let address = self.locals.tmp("address");
+                    uwriteln!(
+                        self.src,
+                        "var {address} = NativeMemory.Alloc((nuint){bytes}.Length);
+                         {bytes}.CopyTo(new Span<byte>({address}, {bytes}.Length));"
+                    );
+
+                    results.push(format!("(int){address}"));
                 }
-                results.push(format!("length{result_var}"));
+                results.push(format!("{bytes}.Length"));
+            }
 
-                self.gen.gen.needs_interop_string = true;
+            Instruction::StringLift { .. } => {
+                let address = self.locals.tmp("address");
+                let length = self.locals.tmp("length");
+                let lifted = self.locals.tmp("str");
+
+                uwriteln!(
+                    self.src,
+                    "var {address} = {};
+                     var {length} = {};
+                     var {lifted} = Encoding.UTF8.GetString((byte*)({address}), {length});
+                     if ({length} > 0) {{
+                         NativeMemory.Free((void*)({address}));
+                     }}",
+                    operands[0],
+                    operands[1]
+                );
+
+                results.push(lifted);
             }
 
-            Instruction::StringLift { .. } => match self.gen.direction {
-                Direction::Import => results.push(format!("ReturnArea.GetUTF8String(ptr)")),
-                Direction::Export => {
-                    let address = &operands[0];
-                    let length = &operands[1];
+            Instruction::ListLower { element, realloc } => {
+                let Block {
+                    body,
+                    results: block_results,
+                    element: block_element,
+                    base,
+                } = self.blocks.pop().unwrap();
+                assert!(block_results.is_empty());
 
-                    results.push(format!("returnArea.GetUTF8String({address}, {length})"));
+                let op = &operands[0];
+                let size = self.gen.gen.sizes.size(element);
+                let list = self.locals.tmp("list");
+                let address = self.locals.tmp("address");
+                let index = self.locals.tmp("index");
+
+                uwrite!(
+                    self.src,
+                    "
+                    var {list} = {op};
+                    var {address} = (int)NativeMemory.Alloc((nuint)({list}.Count * {size}));
+                    for (int {index} = 0; {index} < {list}.Count; ++{index}) {{
+                        var {block_element} = {list}[{index}];
+                        int {base} = {address} + ({index} * {size});
+                        {body}
+                    }}
+                    "
+                );
+
+                if realloc.is_none() {
+                    uwriteln!(
+                        self.src,
+                        "cleanups.Add(() => NativeMemory.Free((void*){address}));"
+                    );
+                    self.needs_cleanup_list = true;
                 }
-            },
 
-            Instruction::ListLower { .. } => todo!("ListLower"),
+                results.push(address);
+                results.push(format!("{list}.Count"));
+            }
 
-            Instruction::ListLift { .. } => todo!("ListLift"),
+            Instruction::ListLift { element, .. } => {
+                let Block {
+                    body,
+                    results: block_results,
+                    base,
+                    ..
+                } = self.blocks.pop().unwrap();
+                let ty = self.gen.type_name_boxed(element, true);
+                let size = self.gen.gen.sizes.size(element);
+                let address = self.locals.tmp("address");
+                let length = self.locals.tmp("length");
+                let array = self.locals.tmp("array");
+                let index = self.locals.tmp("index");
+                let result = &block_results[0];
 
-            Instruction::IterElem { .. } => todo!("IterElem"),
+                uwrite!(
+                    self.src,
+                    "
+                    var {address} = {};
+                    var {length} = {};
+                    var {array} = new List<{ty}>({length});
+                    for (int {index} = 0; {index} < {length}; ++{index}) {{
+                        int {base} = {address} + ({index} * {size});
+                        {body}
+                        {array}.Add({result});
+                    }}
+                    if ({length} > 0) {{
+                        NativeMemory.Free((void*)({address}));
+                    }}
+                    ",
+                    operands[0],
+                    operands[1]
+                );
 
-            Instruction::IterBasePointer => todo!("IterBasePointer"),
+                results.push(array);
+            }
+
+            Instruction::IterElem { .. } => {
+                results.push(self.block_storage.last().unwrap().element.clone())
+            }
+
+            Instruction::IterBasePointer => {
+                results.push(self.block_storage.last().unwrap().base.clone())
+            }
 
             Instruction::CallWasm { sig, name } => {
                 let assignment = match &sig.results[..] {
@@ -2050,8 +2707,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     _ => unreachable!(),
                 };
 
-                let func_name = self.func_name.to_upper_camel_case();
-                let name = name.to_upper_camel_case();
+                let func_name = camel_func_name(self.func_name);
+                let name = camel_func_name(name);
 
                 let operands = operands.join(", ");
 
@@ -2059,11 +2716,20 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     self.src,
                     "{assignment} {name}WasmInterop.wasmImport{func_name}({operands});"
                 );
+
+                if self.needs_cleanup_list {
+                    uwriteln!(
+                        self.src,
+                        "foreach (var cleanup in cleanups) {{
+                             cleanup();
+                         }}"
+                    );
+                }
             }
 
             Instruction::CallInterface { func } => {
                 let module = self.gen.name.to_string();
-                let func_name = self.func_name.to_upper_camel_case();
+                let func_name = camel_func_name(self.func_name);
                 let interface_name = CSharp::get_class_name_from_qualified_name(module).1;
 
                 let class_name_root = (match self.gen.function_level {
@@ -2075,6 +2741,20 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 })
                 .to_upper_camel_case();
 
+                // Methods of exported resources are dispatched on the instance
+                // passed as `self`.
+                let (target, operands) = match func.kind {
+                    FunctionKind::Method(_) => (
+                        format!(
+                            "({}).{}",
+                            operands[0],
+                            func.item_name().to_upper_camel_case()
+                        ),
+                        &operands[1..],
+                    ),
+                    _ => (format!("{class_name_root}Impl.{func_name}"), &operands[..]),
+                };
+
                 let mut oper = String::new();
 
                 for (i, param) in operands.iter().enumerate() {
@@ -2086,23 +2766,15 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 }
 
                 match func.results.len() {
-                    0 => self
-                        .src
-                        .push_str(&format!("{class_name_root}Impl.{func_name}({oper});")),
+                    0 => self.src.push_str(&format!("{target}({oper});")),
                     1 => {
                         let ret = self.locals.tmp("ret");
-                        uwriteln!(
-                            self.src,
-                            "var {ret} = {class_name_root}Impl.{func_name}({oper});"
-                        );
+                        uwriteln!(self.src, "var {ret} = {target}({oper});");
                         results.push(ret);
                     }
                     _ => {
                         let ret = self.locals.tmp("ret");
-                        uwriteln!(
-                            self.src,
-                            "var {ret} = {class_name_root}Impl.{func_name}({oper});"
-                        );
+                        uwriteln!(self.src, "var {ret} = {target}({oper});");
                         let mut i = 1;
                         for _ in func.results.iter_types() {
                             results.push(format!("{}.Item{}", ret, i));
@@ -2112,7 +2784,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 }
             }
 
-            Instruction::Return { amt: _, func } => match func.results.len() {
+            Instruction::Return { amt, .. } => match amt {
                 0 => (),
                 1 => uwriteln!(self.src, "return {};", operands[0]),
                 _ => {
@@ -2123,23 +2795,153 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::Malloc { .. } => unimplemented!(),
 
-            Instruction::GuestDeallocate { .. } => todo!("GuestDeallocate"),
+            Instruction::GuestDeallocate { .. } => {
+                uwriteln!(self.src, "NativeMemory.Free((void*)({}));", operands[0]);
+            }
+
+            Instruction::GuestDeallocateString => {
+                uwriteln!(self.src, "NativeMemory.Free((void*)({}));", operands[0]);
+            }
+
+            Instruction::GuestDeallocateVariant { blocks } => {
+                let cases = self
+                    .blocks
+                    .drain(self.blocks.len() - blocks..)
+                    .enumerate()
+                    .map(|(i, Block { body, .. })| {
+                        format!(
+                            "case {i}: {{
+                                 {body}
+                                 break;
+                             }}"
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let op = &operands[0];
 
-            Instruction::GuestDeallocateString => todo!("GuestDeallocateString"),
+                uwrite!(
+                    self.src,
+                    "
+                    switch ({op}) {{
+                        {cases}
+                    }}
+                    "
+                );
+            }
 
-            Instruction::GuestDeallocateVariant { .. } => todo!("GuestDeallocateString"),
+            Instruction::GuestDeallocateList { element } => {
+                let Block { body, base, .. } = self.blocks.pop().unwrap();
+                let address = &operands[0];
+                let length = &operands[1];
+                let size = self.gen.gen.sizes.size(element);
+                let index = self.locals.tmp("index");
+
+                if !body.trim().is_empty() {
+                    uwrite!(
+                        self.src,
+                        "
+                        for (int {index} = 0; {index} < {length}; ++{index}) {{
+                            int {base} = {address} + ({index} * {size});
+                            {body}
+                        }}
+                        "
+                    );
+                }
 
-            Instruction::GuestDeallocateList { .. } => todo!("GuestDeallocateList"),
-            Instruction::HandleLower {
-                handle: _,
-                name: _,
-                ty: _,
-            } => todo!(),
-            Instruction::HandleLift {
-                handle: _,
-                name: _,
-                ty: _dir,
-            } => todo!("HandleLeft"),
+                uwriteln!(self.src, "NativeMemory.Free((void*)({address}));");
+            }
+            Instruction::HandleLower { handle, .. } => {
+                let (Handle::Own(ty) | Handle::Borrow(ty)) = handle;
+                let is_own = matches!(handle, Handle::Own(_));
+                let id = dealias(self.gen.resolve, *ty);
+                let op = &operands[0];
+                let lowered = self.locals.tmp("handle");
+
+                uwriteln!(self.src, "var {lowered} = ({op}).Handle;");
+
+                match self.gen.gen.resource_directions[&id] {
+                    Direction::Import => {
+                        // Clearing `Handle` keeps `Dispose` from dropping a
+                        // handle that now belongs to the callee.
+                        if is_own {
+                            uwriteln!(self.src, "({op}).Handle = 0;");
+                        }
+                    }
+                    Direction::Export => {
+                        let class = self.gen.type_name_with_qualifier(&Type::Id(id), true);
+                        let rep = self.locals.tmp("rep");
+
+                        uwrite!(
+                            self.src,
+                            "
+                            if ({lowered} == 0) {{
+                                if (({op}).Rep.HasValue) {{
+                                    throw new InvalidOperationException(\"resource is owned by another component\");
+                                }}
+                                var {rep} = global::{class}.repTable.Add({op});
+                                ({op}).Rep = {rep};
+                                {lowered} = global::{class}.WasmInterop.wasmImportResourceNew({rep});
+                            "
+                        );
+                        // Instances are only registered in `repTable` the first
+                        // time they're passed out, and stay registered until
+                        // their destructor runs. An instance without a handle
+                        // which is still registered has had its `own` handle
+                        // passed out already, so it can't be passed out again.
+                        // For an `own` the host holds the handle from now on,
+                        // whereas a `borrow` caches it on the instance for
+                        // later calls.
+                        if is_own {
+                            uwriteln!(self.src, "}}\n({op}).Handle = 0;");
+                        } else {
+                            uwriteln!(self.src, "({op}).Handle = {lowered};\n}}");
+                        }
+                    }
+                }
+
+                results.push(lowered);
+            }
+
+            Instruction::HandleLift { handle, .. } => {
+                let (Handle::Own(ty) | Handle::Borrow(ty)) = handle;
+                let is_own = matches!(handle, Handle::Own(_));
+                let id = dealias(self.gen.resolve, *ty);
+                let class = self.gen.type_name_with_qualifier(&Type::Id(id), true);
+                let op = &operands[0];
+
+                match self.gen.gen.resource_directions[&id] {
+                    Direction::Import => {
+                        results.push(format!(
+                            "new global::{class}(new global::{class}.THandle({op}))"
+                        ));
+                    }
+                    Direction::Export => {
+                        if is_own {
+                            let resource = self.locals.tmp("resource");
+                            uwriteln!(
+                                self.src,
+                                "var {resource} = global::{class}.repTable.Get(global::{class}.WasmInterop.wasmImportResourceRep({op}));
+                                {resource}.Handle = {op};"
+                            );
+                            results.push(resource);
+                        } else {
+                            // Borrows of resources defined by this component are
+                            // passed as the rep directly.
+                            results.push(format!("global::{class}.repTable.Get({op})"));
+                        }
+                    }
+                }
+            }
+
+            Instruction::FutureLower { .. }
+            | Instruction::FutureLift { .. }
+            | Instruction::StreamLower { .. }
+            | Instruction::StreamLift { .. } => {
+                unreachable!(
+                    "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+                )
+            }
         }
     }
 
@@ -2150,9 +2952,6 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         // their return area to be live until the post-return call.
         match self.gen.direction {
             Direction::Import => {
-                self.gen.gen.return_area_size = size;
-                self.gen.gen.return_area_align = align;
-
                 uwrite!(
                     self.src,
                     "
@@ -2187,7 +2986,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         self.block_storage.push(BlockStorage {
             body: mem::take(&mut self.src),
             element: self.locals.tmp("element"),
-            base: self.locals.tmp("base"),
+            base: self.locals.tmp("baseAddress"),
         });
     }
 
@@ -2201,8 +3000,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         self.blocks.push(Block {
             body: mem::replace(&mut self.src, body),
             results: mem::take(operands),
-            _xelement: element,
-            _xbase: base,
+            element,
+            base,
         });
     }
 
@@ -2215,6 +3014,32 @@ impl Bindgen for FunctionBindgen<'_, '_> {
     }
 }
 
+fn perform_cast(op: &str, cast: &Bitcast) -> String {
+    match cast {
+        Bitcast::I32ToF32 => format!("BitConverter.Int32BitsToSingle({op})"),
+        Bitcast::I64ToF32 => format!("BitConverter.Int32BitsToSingle((int){op})"),
+        Bitcast::F32ToI32 => format!("BitConverter.SingleToInt32Bits({op})"),
+        Bitcast::F32ToI64 => format!("(long)BitConverter.SingleToInt32Bits({op})"),
+        Bitcast::I64ToF64 => format!("BitConverter.Int64BitsToDouble({op})"),
+        Bitcast::F64ToI64 => format!("BitConverter.DoubleToInt64Bits({op})"),
+        Bitcast::I32ToI64 | Bitcast::LToI64 | Bitcast::PToP64 => format!("(long)({op})"),
+        Bitcast::I64ToI32 | Bitcast::I64ToL | Bitcast::P64ToP => format!("(int)({op})"),
+        Bitcast::I64ToP64
+        | Bitcast::P64ToI64
+        | Bitcast::I32ToP
+        | Bitcast::PToI32
+        | Bitcast::I32ToL
+        | Bitcast::LToI32
+        | Bitcast::LToP
+        | Bitcast::PToL
+        | Bitcast::None => op.to_owned(),
+        Bitcast::Sequence(sequence) => {
+            let [first, second] = &**sequence;
+            perform_cast(&perform_cast(op, first), second)
+        }
+    }
+}
+
 fn int_type(int: Int) -> &'static str {
     match int {
         Int::U8 => "byte",
@@ -2321,6 +3146,11 @@ fn interface_name(
     )
 }
 
+/// Converts a core function name, e.g. `[method]x.get`, into an UpperCamelCase C# identifier.
+fn camel_func_name(name: &str) -> String {
+    name.replace('.', "-").to_upper_camel_case()
+}
+
 fn is_primitive(ty: &Type) -> bool {
     matches!(
         ty,
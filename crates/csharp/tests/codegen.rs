@@ -17,9 +17,8 @@ macro_rules! codegen_test {
                 |resolve, world, files| {
                     if [
                         "conventions",
+                        "flags-wide",
                         "guest-name",
-                        "import-and-export-resource",
-                        "import-and-export-resource-alias",
                         "import-func",
                         "integers",
                         "issue544",
@@ -31,35 +30,19 @@ macro_rules! codegen_test {
                         "just-export",
                         "keywords",
                         "lift-lower-foreign",
-                        "lists",
                         "many-arguments",
-                        "option-result",
                         "rename-interface",
-                        "resource-alias",
-                        "resource-borrow-in-record",
-                        "resource-borrow-in-record-export",
-                        "resource-local-alias",
-                        "resource-local-alias-borrow",
-                        "resource-local-alias-borrow-import",
-                        "resource-own-in-other-interface",
-                        "resources",
-                        "resources-in-aggregates",
-                        "resources-with-lists",
-                        "result-empty",
                         "ret-areas",
-                        "return-resource-from-export",
                         "same-names5",
                         "simple-functions",
                         "simple-http",
-                        "simple-lists",
-                        "small-anonymous",
                         "unused-import",
                         "use-across-interfaces",
-                        "variants",
                         "variants-unioning-types",
                         "worlds-with-types",
                         "zero-size-tuple",
                         "go_params",
+                        "futures-streams",
                     ]
                     .contains(&$name)
                     {
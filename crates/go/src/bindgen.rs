@@ -317,9 +317,12 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                             self.lower_src.push_str("}\n");
                         }
                     }
-                    TypeDefKind::Future(_) => todo!("impl future"),
-                    TypeDefKind::Stream(_) => todo!("impl stream"),
-                    TypeDefKind::Resource => todo!("impl resource"),
+                    TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                        "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+                    ),
+                    // Resources are only ever passed around through an
+                    // `own` or `borrow` handle, which is handled below.
+                    TypeDefKind::Resource => unreachable!(),
                     TypeDefKind::Handle(h) => {
                         match self.interface.direction {
                             Direction::Import => {
@@ -607,9 +610,12 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                             self.lift_src.push_str("}\n");
                         }
                     }
-                    TypeDefKind::Future(_) => todo!("impl future"),
-                    TypeDefKind::Stream(_) => todo!("impl stream"),
-                    TypeDefKind::Resource => todo!("impl resource"),
+                    TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                        "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+                    ),
+                    // Resources are only ever passed around through an
+                    // `own` or `borrow` handle, which is handled below.
+                    TypeDefKind::Resource => unreachable!(),
                     TypeDefKind::Handle(h) => {
                         match self.interface.direction {
                             Direction::Import => {
@@ -673,6 +679,14 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                                         self.lift_src,
                                         "{lift_name} := {resource_name}({param}.__handle)",
                                     );
+                                    // Borrows of imported resources must be dropped before
+                                    // the exported function returns, wherever they appear in
+                                    // the arguments. Deferring the drop works for borrows
+                                    // nested in records, variants and lists alike since it
+                                    // runs when the whole function returns.
+                                    if let Borrow(_) = h {
+                                        uwriteln!(self.lift_src, "defer {lift_name}.Drop()");
+                                    }
                                 }
                             }
                         }
@@ -1,7 +1,14 @@
+use std::collections::BTreeMap;
 use std::fmt::Write as _;
 
 use wit_bindgen_core::{uwriteln, Files, Source};
 
+// identifiers which the generated code declares in functions, or imports,
+// which imported packages can't be named after
+const RESERVED: &[&str] = &[
+    "e", "i", "base", "ok", "self", "handle", "rep", "exports", "fmt", "sync",
+];
+
 #[derive(Default)]
 pub(crate) struct ImportRequirements {
     // whether the generated code needs to import result and option
@@ -16,27 +23,75 @@ pub(crate) struct ImportRequirements {
     // whether the generated code needs to import "sync"
     pub(crate) needs_sync_import: bool,
 
+    // whether the generated code needs to import "math"
+    pub(crate) needs_math_import: bool,
+
+    // the generated packages imported by the generated code, by their path
+    // relative to the package of the world, along with their names in it
+    pub(crate) packages: BTreeMap<String, String>,
+
     pub(crate) src: Source,
 }
 
 impl ImportRequirements {
     pub(crate) fn generate(&mut self, snake: String, files: &mut Files, file_name: String) {
+        self.print_imports("");
+
+        if self.needs_result_option {
+            let mut result_option_src = Source::default();
+            uwriteln!(result_option_src, "package {snake}");
+            print_result_option(&mut result_option_src);
+            files.push(&file_name, result_option_src.as_bytes());
+        }
+    }
+
+    /// Imports the generated package at `path`, which is relative to the
+    /// package of the world, and returns its name in the importing package.
+    ///
+    /// Packages are named after the last segment of their path, unless
+    /// another imported package already has that name.
+    pub(crate) fn import_package(&mut self, path: &str) -> String {
+        if let Some(name) = self.packages.get(path) {
+            return name.clone();
+        }
+        let mut name = path.rsplit('/').next().unwrap().to_string();
+        if RESERVED.contains(&name.as_str()) || self.packages.values().any(|n| *n == name) {
+            name = path.replace('/', "_");
+        }
+        self.packages.insert(path.to_string(), name.clone());
+        name
+    }
+
+    /// Prints the imports of the generated code, where `module` is the import
+    /// path of the package of the world.
+    pub(crate) fn print_imports(&mut self, module: &str) {
         if self.needs_import_unsafe {
             self.src.push_str("import \"unsafe\"\n");
         }
         if self.needs_fmt_import {
             self.src.push_str("import \"fmt\"\n");
         }
+        if self.needs_math_import {
+            self.src.push_str("import \"math\"\n");
+        }
         if self.needs_sync_import {
             self.src.push_str("import \"sync\"\n\n");
         }
+        for (path, name) in self.packages.iter() {
+            if path.rsplit('/').next() == Some(name.as_str()) {
+                uwriteln!(self.src, "import \"{module}/{path}\"");
+            } else {
+                uwriteln!(self.src, "import {name} \"{module}/{path}\"");
+            }
+        }
+    }
+}
 
-        if self.needs_result_option {
-            let mut result_option_src = Source::default();
-            uwriteln!(
-                result_option_src,
-                "package {snake}
-
+/// Prints the `Option` and `Result` types used by the generated code.
+pub(crate) fn print_result_option(src: &mut Source) {
+    uwriteln!(
+        src,
+        "
             // inspired from https://github.com/moznion/go-optional
 
             type optionKind int
@@ -165,8 +220,5 @@ impl ImportRequirements {
                 }}
             }}
             "
-            );
-            files.push(&file_name, result_option_src.as_bytes());
-        }
-    }
+    );
 }
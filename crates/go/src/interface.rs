@@ -1,18 +1,20 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
+use std::mem;
 
 use heck::{ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
 use wit_bindgen_c::{
     c_func_name, gen_type_name, is_arg_by_pointer, owner_namespace as c_owner_namespace,
     CTypeNameInfo,
 };
+use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
 use wit_bindgen_core::wit_parser::{
     Field, Function, FunctionKind, Handle, InterfaceId, LiveTypes, Resolve, Type, TypeDefKind,
     TypeId, TypeOwner, WorldKey,
 };
 use wit_bindgen_core::{uwriteln, Direction, InterfaceGenerator as _, Source};
 
-use super::{avoid_keyword, bindgen, TinyGo};
+use super::{avoid_keyword, bindgen, wasm, TinyGo, CABI};
 
 pub(crate) struct InterfaceGenerator<'a> {
     pub(crate) src: Source,
@@ -23,12 +25,19 @@ pub(crate) struct InterfaceGenerator<'a> {
     pub(crate) direction: Direction,
     pub(crate) export_funcs: Vec<(String, String)>,
     pub(crate) methods: HashMap<TypeId, Vec<(String, String)>>,
+    // the signatures of the exported functions and methods in the stub
+    // implementation, if one is generated
+    pub(crate) stub_funcs: Vec<String>,
+    pub(crate) stub_methods: HashMap<TypeId, Vec<String>>,
     // tracking all the exported resources used in generating the
     // resource interface and the resource destructors
     // this interface-level tracking is needed to prevent duplicated
     // resource declaration which has been declared in other interfaces
     pub(crate) exported_resources: HashSet<TypeId>,
     pub(crate) wasm_import_module: Option<&'a str>,
+    // the path of the Go package generated into, relative to the package of
+    // the world
+    pub(crate) package: String,
 }
 
 impl InterfaceGenerator<'_> {
@@ -74,6 +83,7 @@ impl InterfaceGenerator<'_> {
 
             let prev = self.gen.type_names.insert(ty, name.clone());
             assert!(prev.is_none());
+            self.gen.type_packages.insert(ty, self.package.clone());
 
             // define Go types
             match &self.resolve.types[ty].name {
@@ -84,7 +94,13 @@ impl InterfaceGenerator<'_> {
     }
 
     /// Given a type ID, returns the namespace of the type.
+    ///
+    /// Types aren't prefixed with a namespace when each interface has a
+    /// package of its own.
     pub(crate) fn owner_namespace(&self, id: TypeId) -> String {
+        if self.gen.opts.packages {
+            return String::new();
+        }
         let ty = &self.resolve.types[id];
         match (ty.owner, self.interface) {
             // If this type is owned by an interface, then we must be generating
@@ -112,7 +128,7 @@ impl InterfaceGenerator<'_> {
             self.gen.world.clone(),
             self.resolve,
             id,
-            &Default::default(),
+            &self.gen.renamed_interfaces,
         )
     }
 
@@ -120,6 +136,9 @@ impl InterfaceGenerator<'_> {
     ///
     /// If self is not an interface, returns the namespace of the world.
     pub(crate) fn namespace(&self) -> String {
+        if self.gen.opts.packages {
+            return String::new();
+        }
         match self.interface {
             Some((_, key)) => self.interface_identifier(key),
             None => self.gen.world.to_upper_camel_case(),
@@ -132,6 +151,15 @@ impl InterfaceGenerator<'_> {
 
     /// Returns the identifier of the given interface.
     pub(crate) fn interface_identifier(&self, key: &WorldKey) -> String {
+        if let Some(rename) = self.gen.renamed_interfaces.get(key) {
+            let mut name = String::new();
+            if matches!(self.direction, Direction::Export) && matches!(key, WorldKey::Interface(_))
+            {
+                name.push_str("Exports");
+            }
+            name.push_str(&rename.to_upper_camel_case());
+            return name;
+        }
         match key {
             WorldKey::Name(k) => k.to_upper_camel_case(),
             WorldKey::Interface(id) => {
@@ -154,6 +182,17 @@ impl InterfaceGenerator<'_> {
         }
     }
 
+    /// Returns the name of the parameter `name` of a function, which doesn't
+    /// shadow any generated package.
+    pub(crate) fn param_name(&self, name: &str) -> String {
+        let name = avoid_keyword(&name.to_snake_case());
+        if self.gen.package_names.contains(&name) {
+            format!("{name}_")
+        } else {
+            name
+        }
+    }
+
     /// Returns the function name of the given function.
     pub(crate) fn func_name(&self, func: &Function) -> String {
         match func.kind {
@@ -191,11 +230,71 @@ impl InterfaceGenerator<'_> {
         name
     }
 
+    /// Returns the name of the Go interface which the guest implements for
+    /// the functions of an exported interface.
+    pub(crate) fn export_interface_name(&self) -> String {
+        if self.gen.opts.packages {
+            "Exports".to_string()
+        } else {
+            self.namespace()
+        }
+    }
+
     /// A special variable generated for exported interfaces.
     ///
     /// This variable is used to store the exported interface.
     pub(crate) fn get_interface_var_name(&self) -> String {
-        self.namespace().to_snake_case()
+        self.export_interface_name().to_snake_case()
+    }
+
+    /// Qualifies `name`, which is defined along with the type `id`, with the
+    /// package of the type if it's not the package generated into.
+    pub(crate) fn qualify(&mut self, id: TypeId, name: &str) -> String {
+        let package = &self.gen.type_packages[&id];
+        if *package == self.package {
+            return name.to_string();
+        }
+        let package = package.clone();
+        let package = self.gen.import_package(&package);
+        format!("{package}.{name}")
+    }
+
+    /// Returns the name of a helper of the runtime of pure Go bindings, such
+    /// as `Alloc`, which lives in a package of its own if each interface has
+    /// a package of its own.
+    pub(crate) fn runtime(&mut self, name: &str) -> String {
+        if self.gen.opts.packages {
+            let package = self.gen.import_package(CABI);
+            format!("{package}.{}", wasm::runtime_name(name, true))
+        } else {
+            wasm::runtime_name(name, false)
+        }
+    }
+
+    /// Returns the prefix of the `Option` and `Result` types.
+    fn result_option_prefix(&mut self) -> String {
+        if self.gen.opts.packages {
+            let package = self.gen.import_package(CABI);
+            format!("{package}.")
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the name of the helper named `helper` of the exported resource
+    /// `id`, which other packages call when each interface has a package of
+    /// its own.
+    pub(crate) fn exported_resource_helper(&mut self, id: TypeId, helper: &str) -> String {
+        let name = self.resource_helper_name(&self.gen.type_names[&id], helper);
+        self.qualify(id, &name)
+    }
+
+    fn resource_helper_name(&self, type_name: &str, helper: &str) -> String {
+        if self.gen.opts.packages {
+            format!("{}{type_name}", helper.to_upper_camel_case())
+        } else {
+            format!("{helper}{type_name}")
+        }
     }
 
     /// Returns the type representation of the given type.
@@ -229,17 +328,22 @@ impl InterfaceGenerator<'_> {
                     }
                     wit_bindgen_core::wit_parser::TypeDefKind::Option(o) => {
                         self.gen.with_result_option(true);
-                        format!("Option[{}]", self.get_ty(o))
+                        let prefix = self.result_option_prefix();
+                        format!("{prefix}Option[{}]", self.get_ty(o))
                     }
                     wit_bindgen_core::wit_parser::TypeDefKind::Result(r) => {
                         self.gen.with_result_option(true);
+                        let prefix = self.result_option_prefix();
                         format!(
-                            "Result[{}, {}]",
+                            "{prefix}Result[{}, {}]",
                             self.optional_ty(r.ok.as_ref()),
                             self.optional_ty(r.err.as_ref())
                         )
                     }
-                    _ => self.gen.type_names.get(id).unwrap().to_owned(),
+                    _ => {
+                        let name = self.gen.type_names.get(id).unwrap().to_owned();
+                        self.qualify(*id, &name)
+                    }
                 }
             }
         }
@@ -301,7 +405,7 @@ impl InterfaceGenerator<'_> {
                         let mut src = String::new();
                         src.push_str("Result");
                         src.push_str(&self.optional_ty_name(r.ok.as_ref()));
-                        src.push_str(&self.optional_ty_name(r.ok.as_ref()));
+                        src.push_str(&self.optional_ty_name(r.err.as_ref()));
                         src.push('T');
                         src
                     }
@@ -388,7 +492,7 @@ impl InterfaceGenerator<'_> {
         if i > 0 {
             params.push_str(", ");
         }
-        params.push_str(&avoid_keyword(&name.to_snake_case()));
+        params.push_str(&self.param_name(name));
         params.push(' ');
         params.push_str(&self.get_ty(param));
     }
@@ -542,7 +646,7 @@ impl InterfaceGenerator<'_> {
                 &self.gen.world,
                 self.interface.map(|(_, key)| key),
                 func,
-                &Default::default(),
+                &self.gen.renamed_interfaces,
             )
         } else {
             // do not want to generate public functions
@@ -676,8 +780,9 @@ impl InterfaceGenerator<'_> {
                 // although handles are anonymous types, they are generated in the
                 // `type_resource` function as part of the resource type generation.
             }
-            TypeDefKind::Future(_) => todo!("anonymous_type for future"),
-            TypeDefKind::Stream(_) => todo!("anonymous_type for stream"),
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => unreachable!(
+                "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+            ),
             TypeDefKind::Unknown => unreachable!(),
         }
     }
@@ -742,6 +847,10 @@ impl InterfaceGenerator<'_> {
     }
 
     pub(crate) fn import(&mut self, resolve: &Resolve, func: &Function) {
+        if self.gen.opts.pure_go {
+            return self.import_wasm(resolve, func);
+        }
+
         let mut func_bindgen = bindgen::FunctionBindgen::new(self, func);
         func_bindgen.process_args();
         func_bindgen.process_returns();
@@ -763,6 +872,69 @@ impl InterfaceGenerator<'_> {
         self.src.push_str("}\n\n");
     }
 
+    /// Generates an imported function which calls its core wasm import
+    /// directly, without going through cgo.
+    fn import_wasm(&mut self, resolve: &Resolve, func: &Function) {
+        let sig = resolve.wasm_signature(AbiVariant::GuestImport, func);
+        let wasm_func = format!(
+            "_{}{}",
+            self.namespace(),
+            func.name.replace('.', " ").to_upper_camel_case()
+        );
+
+        let params = func
+            .params
+            .iter()
+            .map(|(name, _)| match func.kind {
+                FunctionKind::Method(_) if name == "self" => "self".to_string(),
+                _ => self.param_name(name),
+            })
+            .collect();
+        let mut f = wasm::FunctionBindgen::new(self, params, wasm_func.clone());
+        abi::call(
+            resolve,
+            AbiVariant::GuestImport,
+            LiftLower::LowerArgsLiftResults,
+            func,
+            &mut f,
+        );
+        let body = f.src;
+        let needs_unpin = f.needs_unpin;
+
+        // declaration of the core wasm import
+        let module = self.wasm_import_module.unwrap();
+        let params = sig
+            .params
+            .iter()
+            .map(|ty| wasm::wasm_type(*ty))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let result = sig
+            .results
+            .first()
+            .map(|ty| format!(" {}", wasm::wasm_type(*ty)))
+            .unwrap_or_default();
+        uwriteln!(
+            self.src,
+            "//go:wasmimport {module} {}
+            func {wasm_func}({params}){result}
+            ",
+            func.name
+        );
+
+        self.func_sig(func);
+        if needs_unpin {
+            let unpin = self.runtime("Unpin");
+            uwriteln!(self.src, "defer {unpin}()");
+        }
+        self.src.push_str(&body);
+        self.src.push_str("}\n\n");
+        // the core wasm signature and memory accesses go through `unsafe`
+        if self.src.contains("unsafe.") {
+            self.gen.with_import_unsafe(true);
+        }
+    }
+
     pub(crate) fn import_invoke(
         &mut self,
         resolve: &Resolve,
@@ -811,6 +983,40 @@ impl InterfaceGenerator<'_> {
     }
 
     pub(crate) fn export(&mut self, resolve: &Resolve, func: &Function) {
+        // This variable holds the declaration functions in the exported interface that user
+        // needs to implement.
+        let interface_method_decl = self.func_sig_with_no_namespace(func);
+        if self.gen.opts.generate_stub {
+            let stub_decl = self.stub_sig(func);
+            match func.kind {
+                FunctionKind::Method(id) => {
+                    self.stub_methods.entry(id).or_default().push(stub_decl);
+                }
+                _ => self.stub_funcs.push(stub_decl),
+            }
+        }
+        let export_func = if self.gen.opts.pure_go {
+            self.export_wasm(resolve, func)
+        } else {
+            self.export_cgo(resolve, func)
+        };
+
+        match func.kind {
+            FunctionKind::Method(id) => {
+                self.methods
+                    .entry(id)
+                    .or_default()
+                    .push((interface_method_decl, export_func));
+            }
+            _ => {
+                self.export_funcs.push((interface_method_decl, export_func));
+            }
+        }
+    }
+
+    /// Generates an exported function which is called by its C binding
+    /// through cgo.
+    fn export_cgo(&mut self, resolve: &Resolve, func: &Function) -> String {
         let mut func_bindgen = bindgen::FunctionBindgen::new(self, func);
         func_bindgen.process_args();
         func_bindgen.process_returns();
@@ -820,10 +1026,7 @@ impl InterfaceGenerator<'_> {
         let lift_src = func_bindgen.lift_src;
         let lower_src = func_bindgen.lower_src;
 
-        // This variable holds the declaration functions in the exported interface that user
-        // needs to implement.
-        let interface_method_decl = self.func_sig_with_no_namespace(func);
-        let export_func = {
+        {
             let mut src = String::new();
             // header
             src.push_str("//export ");
@@ -833,7 +1036,7 @@ impl InterfaceGenerator<'_> {
                 &self.gen.world,
                 self.interface.map(|(_, key)| key),
                 func,
-                &Default::default(),
+                &self.gen.renamed_interfaces,
             );
             src.push_str(&name);
             src.push('\n');
@@ -921,25 +1124,94 @@ impl InterfaceGenerator<'_> {
 
             src.push_str("\n}\n");
             src
+        }
+    }
+
+    /// Generates an exported function which is called by core wasm directly,
+    /// without going through cgo, along with its post-return function if it
+    /// needs one.
+    fn export_wasm(&mut self, resolve: &Resolve, func: &Function) -> String {
+        let sig = resolve.wasm_signature(AbiVariant::GuestExport, func);
+        let export_name = func.core_export_name(self.wasm_import_module);
+        let name = if self.gen.opts.packages {
+            format!("export{}", self.func_name(func))
+        } else {
+            format!("{}{}", self.namespace(), self.func_name(func)).to_lower_camel_case()
         };
 
-        match func.kind {
-            FunctionKind::Method(id) => {
-                self.methods
-                    .entry(id)
-                    .or_default()
-                    .push((interface_method_decl, export_func));
-            }
-            _ => {
-                self.export_funcs.push((interface_method_decl, export_func));
-            }
+        let params = (0..sig.params.len())
+            .map(|i| format!("arg{i}"))
+            .collect::<Vec<_>>();
+        let mut f = wasm::FunctionBindgen::new(self, params.clone(), String::new());
+        abi::call(
+            resolve,
+            AbiVariant::GuestExport,
+            LiftLower::LiftArgsLowerResults,
+            func,
+            &mut f,
+        );
+        let body = f.src;
+
+        // The `self` of a method is typed as a pointer in the signature, but
+        // it's the representation of the resource, which is an `int32` here.
+        let params = params
+            .iter()
+            .zip(&sig.params)
+            .enumerate()
+            .map(|(i, (name, ty))| match func.kind {
+                FunctionKind::Method(_) if i == 0 && !sig.indirect_params => {
+                    format!("{name} int32")
+                }
+                _ => format!("{name} {}", wasm::wasm_type(*ty)),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let result = sig
+            .results
+            .first()
+            .map(|ty| format!(" {}", wasm::wasm_type(*ty)))
+            .unwrap_or_default();
+
+        let mut src = Source::default();
+        uwriteln!(
+            src,
+            "//go:wasmexport {export_name}
+            func {name}({params}){result} {{"
+        );
+        src.push_str(&body);
+        src.push_str("}\n\n");
+
+        // Memory which has been handed to the host for the results is kept
+        // alive until the host is done with it.
+        if abi::guest_export_needs_post_return(resolve, func) {
+            let params = sig
+                .results
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| format!("arg{i} {}", wasm::wasm_type(*ty)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let unpin = self.runtime("Unpin");
+            uwriteln!(
+                src,
+                "//go:wasmexport cabi_post_{export_name}
+                func {name}PostReturn({params}) {{
+                    {unpin}()
+                }}
+                "
+            );
         }
+        // the core wasm signature and memory accesses go through `unsafe`
+        if src.contains("unsafe.") {
+            self.gen.with_import_unsafe(true);
+        }
+        src.into()
     }
 
     pub(crate) fn finish(&mut self) {
         if !self.export_funcs.is_empty() {
             let interface_var_name = &self.get_interface_var_name();
-            let interface_name = &self.namespace();
+            let interface_name = &self.export_interface_name();
 
             self.src
                 .push_str(format!("var {interface_var_name} {interface_name} = nil\n").as_str());
@@ -988,11 +1260,83 @@ impl InterfaceGenerator<'_> {
             for (_, export_func) in &self.export_funcs {
                 self.src.push_str(export_func);
             }
+
+            if self.gen.opts.generate_stub {
+                self.print_stub();
+            }
+        }
+    }
+
+    /// Returns the signature of `func` in the stub implementation, whose
+    /// imports are tracked apart from the ones of the bindings as it's
+    /// written to a file of its own.
+    fn stub_sig(&mut self, func: &Function) -> String {
+        let stub = self.gen.stubs.entry(self.package.clone()).or_default();
+        mem::swap(
+            &mut self.gen.import_requirements,
+            &mut stub.import_requirements,
+        );
+        let sig = self.func_sig_with_no_namespace(func);
+        let stub = self.gen.stubs.get_mut(&self.package).unwrap();
+        mem::swap(
+            &mut self.gen.import_requirements,
+            &mut stub.import_requirements,
+        );
+        sig
+    }
+
+    /// Prints a stub implementation of the exported interface and its
+    /// resources, which registers itself with `Set{interface_name}`.
+    fn print_stub(&mut self) {
+        let interface_name = self.export_interface_name();
+        let mut src = String::new();
+        uwriteln!(
+            src,
+            "// {interface_name}Impl is a stub implementation of the `{interface_name}` interface.
+            type {interface_name}Impl struct{{}}
+
+            func init() {{
+                Set{interface_name}({interface_name}Impl{{}})
+            }}
+            "
+        );
+        for decl in &self.stub_funcs {
+            src.push_str(&format!(
+                "func ({interface_name}Impl) {decl}{{
+                    panic(\"not implemented\")
+                }}
+
+                "
+            ));
+        }
+
+        for id in &self.exported_resources {
+            let ty_name = &self.gen.type_names[id];
+            src.push_str(&format!(
+                "// {ty_name}Impl is a stub implementation of the `{ty_name}` resource.
+                type {ty_name}Impl struct{{}}
+
+                var _ {ty_name} = (*{ty_name}Impl)(nil)
+
+                "
+            ));
+            for decl in self.stub_methods.get(id).into_iter().flatten() {
+                src.push_str(&format!(
+                    "func (*{ty_name}Impl) {decl}{{
+                        panic(\"not implemented\")
+                    }}
+
+                    "
+                ));
+            }
         }
+
+        let stub = self.gen.stubs.entry(self.package.clone()).or_default();
+        stub.src.push_str(&src);
     }
 
     pub(crate) fn print_export_interface(&mut self) {
-        let interface_name = &self.namespace();
+        let interface_name = &self.export_interface_name();
         self.src
             .push_str(format!("type {interface_name} interface {{\n").as_str());
         for (interface_func_declaration, _) in &self.export_funcs {
@@ -1001,6 +1345,111 @@ impl InterfaceGenerator<'_> {
         }
         self.src.push_str("}\n");
     }
+
+    /// Generates the destructor and drop function of an exported resource,
+    /// which go through its C bindings.
+    fn export_resource_cgo(&mut self, id: TypeId, name: &str) {
+        let type_name = self.type_name(name, true);
+        let private_type_name = type_name.to_snake_case();
+        let c_typedef_target = self.gen.c_type_names[&id].clone();
+        let ns = self.c_namespace_of_resource(id);
+        let snake = name.to_snake_case();
+        let own = format!("{ns}_own_{snake}_t");
+
+        // generate [dtor] function for exported resources
+        let namespace = self.c_owner_namespace(id);
+        let func_name = format!("{}_{}", namespace, snake).to_lower_camel_case();
+        self.src
+            .push_str(&format!("//export {namespace}_{snake}_destructor\n"));
+        uwriteln!(
+            self.src,
+            "func {func_name}Destructor(self *C.{c_typedef_target}) {{
+                {private_type_name} := {private_type_name}_pointers[int32(self.__handle)]
+                {private_type_name}_to_own_handlers.Delete({private_type_name})
+                delete({private_type_name}_pointers, int32(self.__handle))
+                C.free(unsafe.Pointer(self))
+            }}
+            ",
+        );
+
+        self.gen.with_import_unsafe(true);
+
+        // generate [resource-drop] function
+        uwriteln!(
+            self.src,
+            "func Drop{type_name}(self {type_name}) {{
+                owningHandler := get{type_name}OwningHandler(self)
+                var cOwningHandler C.{own}
+                cOwningHandler.__handle = C.int32_t(owningHandler)
+                C.{ns}_{snake}_drop_own(cOwningHandler)
+            }}
+            ",
+        );
+    }
+
+    /// Generates the destructor and drop function of an exported resource,
+    /// along with the intrinsics used to manage its handles, which are
+    /// imported from core wasm directly, and the helpers which lower and lift
+    /// its handles.
+    fn export_resource_wasm(&mut self, id: TypeId, name: &str) {
+        let type_name = self.type_name(name, true);
+        let private_type_name = type_name.to_snake_case();
+        let module = self.wasm_import_module.unwrap();
+        let func_name = format!("{}_{}", self.owner_namespace(id), name).to_lower_camel_case();
+        let lower = self.resource_helper_name(&type_name, "lower");
+        let lift = self.resource_helper_name(&type_name, "lift");
+
+        // the representation of the resource is the key of the instance in
+        // the pointers map
+        self.src.push_str(&format!(
+            "//go:wasmexport {module}#[dtor]{name}
+            func {func_name}Destructor(rep int32) {{
+                {private_type_name} := {private_type_name}_pointers[rep]
+                {private_type_name}_to_own_handlers.Delete({private_type_name})
+                delete({private_type_name}_pointers, rep)
+            }}
+
+            //go:wasmimport [export]{module} [resource-new]{name}
+            func _{type_name}_new(rep int32) int32
+
+            //go:wasmimport [export]{module} [resource-rep]{name}
+            func _{type_name}_rep(handle int32) int32
+
+            //go:wasmimport [export]{module} [resource-drop]{name}
+            func _{type_name}_drop(handle int32)
+
+            func Drop{type_name}(self {type_name}) {{
+                _{type_name}_drop(get{type_name}OwningHandler(self))
+            }}
+
+            // {lower} hands out a new handle to the instance to the host.
+            func {lower}(self {type_name}) int32 {{
+                {private_type_name}_mu.Lock()
+                {private_type_name}_next_id += 1
+                {private_type_name}_pointers[{private_type_name}_next_id] = self
+                handle := _{type_name}_new({private_type_name}_next_id)
+                {private_type_name}_mu.Unlock()
+                set{type_name}OwningHandler(self, handle)
+                return handle
+            }}
+
+            // {lift} returns the instance which an owned handle refers to.
+            func {lift}(handle int32) {type_name} {{
+                return {lift}Borrow(_{type_name}_rep(handle))
+            }}
+
+            // {lift}Borrow returns the instance with the given representation,
+            // which is what borrowed handles are passed as.
+            func {lift}Borrow(rep int32) {type_name} {{
+                self, ok := {private_type_name}_pointers[rep]
+                if !ok {{
+                    panic(\"internal error: invalid handle\")
+                }}
+                return self
+            }}
+            ",
+        ));
+    }
 }
 
 impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
@@ -1057,32 +1506,22 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 );
             }
             Direction::Export => {
-                // generate a typedef struct for export resource
-                let c_typedef_target = self.gen.c_type_names[&id].clone();
-                let ns = self.c_namespace_of_resource(id);
-                let snake = self.resolve.types[id]
-                    .name
-                    .as_ref()
-                    .unwrap()
-                    .to_snake_case();
-                let mut own = ns.clone();
-                own.push_str("_own_");
-                own.push_str(&snake);
-                own.push_str("_t");
-
-                // generate a typedef struct for export resource
-                // the typedef struct is a dummy struct that contains a
-                // Go binding specific handle field. This handle field is used
-                // to retrieve the exported Go struct from the exported C struct.
-                self.preamble
-                    .push_str(&format!("// typedef struct {c_typedef_target} "));
-                self.preamble.push_str("{");
-                self.preamble.push_str("\n");
-                self.preamble.push_str("//  int32_t __handle; \n");
-                self.preamble.push_str("// ");
-                self.preamble.push_str("} ");
-                self.preamble.push_str(&c_typedef_target);
-                self.preamble.push_str(";\n");
+                if !self.gen.opts.pure_go {
+                    // generate a typedef struct for export resource
+                    // the typedef struct is a dummy struct that contains a
+                    // Go binding specific handle field. This handle field is used
+                    // to retrieve the exported Go struct from the exported C struct.
+                    let c_typedef_target = &self.gen.c_type_names[&id];
+                    self.preamble
+                        .push_str(&format!("// typedef struct {c_typedef_target} "));
+                    self.preamble.push_str("{");
+                    self.preamble.push_str("\n");
+                    self.preamble.push_str("//  int32_t __handle; \n");
+                    self.preamble.push_str("// ");
+                    self.preamble.push_str("} ");
+                    self.preamble.push_str(c_typedef_target);
+                    self.preamble.push_str(";\n");
+                }
 
                 // import "sync" for Mutex
                 self.gen.with_sync_import(true);
@@ -1128,36 +1567,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                     "
                 );
 
-                // generate [dtor] function for exported resources
-                let namespace = self.c_owner_namespace(id);
-                let snake = name.to_snake_case();
-                let func_name = format!("{}_{}", namespace, snake).to_lower_camel_case();
-                self.src
-                    .push_str(&format!("//export {namespace}_{snake}_destructor\n"));
-                uwriteln!(
-                    self.src,
-                    "func {func_name}Destructor(self *C.{c_typedef_target}) {{
-                        {private_type_name} := {private_type_name}_pointers[int32(self.__handle)]
-                        {private_type_name}_to_own_handlers.Delete({private_type_name})
-                        delete({private_type_name}_pointers, int32(self.__handle))
-                        C.free(unsafe.Pointer(self))
-                    }}
-                    ",
-                );
-
-                self.gen.with_import_unsafe(true);
-
-                // generate [resource-drop] function
-                uwriteln!(
-                    self.src,
-                    "func Drop{type_name}(self {type_name}) {{
-                        owningHandler := get{type_name}OwningHandler(self)
-                        var cOwningHandler C.{own}
-                        cOwningHandler.__handle = C.int32_t(owningHandler)
-                        C.{ns}_{snake}_drop_own(cOwningHandler)
-                    }}
-                    ",
-                );
+                if self.gen.opts.pure_go {
+                    self.export_resource_wasm(id, name);
+                } else {
+                    self.export_resource_cgo(id, name);
+                }
 
                 // book keep the exported resource type
                 self.exported_resources.insert(id);
@@ -1251,22 +1665,26 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_option(
         &mut self,
-        id: wit_bindgen_core::wit_parser::TypeId,
+        _id: wit_bindgen_core::wit_parser::TypeId,
         _name: &str,
         _payload: &wit_bindgen_core::wit_parser::Type,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        self.get_ty(&Type::Id(id));
+        // no type is defined since `Option[T]` is used directly, which
+        // only needs to be generated
+        self.gen.with_result_option(true);
     }
 
     fn type_result(
         &mut self,
-        id: wit_bindgen_core::wit_parser::TypeId,
+        _id: wit_bindgen_core::wit_parser::TypeId,
         _name: &str,
         _result: &wit_bindgen_core::wit_parser::Result_,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        self.get_ty(&Type::Id(id));
+        // no type is defined since `Result[T, E]` is used directly, which
+        // only needs to be generated
+        self.gen.with_result_option(true);
     }
 
     fn type_enum(
@@ -1321,16 +1739,36 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     ) {
         let name = self.type_name(name, true);
         let ty = self.get_ty(ty);
-        self.src.push_str(&format!("type {name} = {ty}\n"));
+        self.src.push_str(&format!("type {name} = []{ty}\n"));
     }
 
     fn type_builtin(
+        &mut self,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        name: &str,
+        ty: &wit_bindgen_core::wit_parser::Type,
+        docs: &wit_bindgen_core::wit_parser::Docs,
+    ) {
+        self.type_alias(id, name, ty, docs);
+    }
+
+    fn type_future(
+        &mut self,
+        _id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
+        _ty: &Option<wit_bindgen_core::wit_parser::Type>,
+        _docs: &wit_bindgen_core::wit_parser::Docs,
+    ) {
+        unreachable!("`future` types are rejected by `WorldGenerator::generate`")
+    }
+
+    fn type_stream(
         &mut self,
         _id: wit_bindgen_core::wit_parser::TypeId,
         _name: &str,
-        _ty: &wit_bindgen_core::wit_parser::Type,
+        _stream: &wit_bindgen_core::wit_parser::Stream,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        todo!("type_builtin")
+        unreachable!("`stream` types are rejected by `WorldGenerator::generate`")
     }
 }
@@ -1,19 +1,28 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{Read, Write};
 use std::mem;
 use std::process::Stdio;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use heck::{ToKebabCase, ToSnakeCase};
 use wit_bindgen_c::imported_types_used_by_exported_interfaces;
 use wit_bindgen_core::wit_parser::{
-    Function, InterfaceId, LiveTypes, Resolve, SizeAlign, Type, TypeId, WorldId, WorldKey,
+    Function, InterfaceId, LiveTypes, Resolve, SizeAlign, Type, TypeId, WorldId, WorldItem,
+    WorldKey,
 };
 use wit_bindgen_core::{Direction, Files, Source, WorldGenerator};
 
+use path::GoPath;
+
 mod bindgen;
 mod imports;
 mod interface;
+mod path;
+mod wasm;
+
+/// The path of the package which holds the parts of the bindings shared by
+/// all generated packages, relative to the package of the world.
+const CABI: &str = "cabi";
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -21,18 +30,77 @@ pub struct Opts {
     /// Whether or not `gofmt` is executed to format generated code.
     #[cfg_attr(feature = "clap", arg(long))]
     pub gofmt: bool,
+
+    /// Whether to generate bindings in pure Go, which call and are called by
+    /// core wasm directly through `//go:wasmimport` and `//go:wasmexport`
+    /// instead of going through cgo and the C bindings.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub pure_go: bool,
+
+    /// Whether to generate a Go package for each interface, in a directory
+    /// tree mirroring the WIT packages, instead of a single Go package for
+    /// the whole world. This implies `--pure-go`.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub packages: bool,
+
+    /// The name of the Go package of the world, which defaults to the name of
+    /// the world.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub package_name: Option<String>,
+
+    /// The import path of the Go package of the world, which the packages of
+    /// interfaces are imported relative to with `--packages`. Defaults to the
+    /// name of the world.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub module_path: Option<String>,
+
+    /// The prefix of the names of the Go files of the package of the world,
+    /// which defaults to the name of the world.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub file_prefix: Option<String>,
+
+    /// Rename the interface `K` to `V` in the generated source code.
+    #[cfg_attr(feature = "clap", arg(long, name = "K=V", value_parser = parse_rename))]
+    pub rename: Vec<(String, String)>,
+
+    /// Whether or not to generate a stub implementation of exported
+    /// interfaces and resources, in a file of its own.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub generate_stub: bool,
 }
 
 impl Default for Opts {
     fn default() -> Self {
-        Self { gofmt: true } // Set the default value of gofmt to true
+        Self {
+            gofmt: true, // Set the default value of gofmt to true
+            pure_go: false,
+            packages: false,
+            package_name: None,
+            module_path: None,
+            file_prefix: None,
+            rename: Vec::new(),
+            generate_stub: false,
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+fn parse_rename(name: &str) -> Result<(String, String)> {
+    let mut parts = name.splitn(2, '=');
+    let to_rename = parts.next().unwrap();
+    match parts.next() {
+        Some(part) => Ok((to_rename.to_string(), part.to_string())),
+        None => bail!("`--rename` option must have an `=` in it (e.g. `--rename a=b`)"),
     }
 }
 
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
+        let mut opts = self.clone();
+        // packages import each other in Go, which cgo can't go through
+        opts.pure_go |= opts.packages;
         Box::new(TinyGo {
-            opts: self.clone(),
+            opts,
             ..TinyGo::default()
         })
     }
@@ -56,6 +124,13 @@ pub struct TinyGo {
     // mapping from interface ID to the name of the interface
     interface_names: HashMap<InterfaceId, WorldKey>,
 
+    // the names given to interfaces through `--rename`
+    renamed_interfaces: HashMap<WorldKey, String>,
+
+    // the interfaces passed to `--rename` which aren't part of the world,
+    // reported as an error once generation finishes
+    unmatched_renames: Vec<String>,
+
     // C type names
     c_type_names: HashMap<TypeId, String>,
 
@@ -65,12 +140,39 @@ pub struct TinyGo {
     // Go type names
     type_names: HashMap<TypeId, String>,
 
+    // the paths of the packages which Go types are defined in, relative to
+    // the package of the world
+    type_packages: HashMap<TypeId, String>,
+
+    // the generated packages other than the package of the world, by their
+    // path relative to it
+    packages: BTreeMap<String, Package>,
+
+    // the names of the generated packages, which parameters avoid so that
+    // they don't shadow them
+    package_names: HashSet<String>,
+
+    // the stub implementations of exported interfaces, by the path of the
+    // package they are generated into
+    stubs: BTreeMap<String, Package>,
+
     // tracking all the exported resources used in generating the
     // resource interface and the resource destructors
     exported_resources: HashSet<TypeId>,
 
     // the world ID
     world_id: Option<WorldId>,
+
+    // the size of the area that exported functions return values in when
+    // generating pure Go bindings
+    return_pointer_area_size: usize,
+}
+
+/// A Go package of the bindings.
+#[derive(Default)]
+struct Package {
+    src: Source,
+    import_requirements: imports::ImportRequirements,
 }
 
 impl TinyGo {
@@ -90,10 +192,90 @@ impl TinyGo {
             export_funcs: Default::default(),
             exported_resources: Default::default(),
             methods: Default::default(),
+            stub_funcs: Default::default(),
+            stub_methods: Default::default(),
             wasm_import_module,
+            package: String::new(),
+        }
+    }
+
+    /// Returns the path of the package generated for an interface, relative
+    /// to the package of the world, which is empty for the package of the
+    /// world itself.
+    fn package_path(&self, resolve: &Resolve, name: &WorldKey, direction: Direction) -> String {
+        if !self.opts.packages {
+            return String::new();
+        }
+        match self.renamed_interfaces.get(name) {
+            Some(rename) => {
+                let rename = avoid_keyword(&rename.to_snake_case());
+                match direction {
+                    Direction::Import => rename,
+                    Direction::Export => format!("exports/{rename}"),
+                }
+            }
+            None => name.to_path(resolve, direction).join("/"),
+        }
+    }
+
+    /// Returns the import path of the package of the world.
+    fn module(&self) -> String {
+        match &self.opts.module_path {
+            Some(path) => path.clone(),
+            None => self.world.to_kebab_case(),
         }
     }
 
+    /// Returns the name of the package of the world.
+    fn package_name(&self) -> String {
+        match &self.opts.package_name {
+            Some(name) => name.clone(),
+            None => self.world.to_snake_case(),
+        }
+    }
+
+    /// Returns the prefix of the names of the files of the package of the
+    /// world.
+    fn file_prefix(&self) -> String {
+        match &self.opts.file_prefix {
+            Some(prefix) => prefix.clone(),
+            None => self.world.to_kebab_case(),
+        }
+    }
+
+    /// Starts generating code into the package at `path`, whose imports are
+    /// tracked until `leave_package` is called.
+    fn enter_package(&mut self, path: &str) {
+        if !path.is_empty() {
+            let package = self.packages.entry(path.to_string()).or_default();
+            mem::swap(
+                &mut self.import_requirements,
+                &mut package.import_requirements,
+            );
+        }
+    }
+
+    /// Appends `src` to the package at `path`, and stops generating code into
+    /// it.
+    fn leave_package(&mut self, path: &str, src: &str) {
+        if path.is_empty() {
+            self.src.push_str(src);
+        } else {
+            let package = self.packages.get_mut(path).unwrap();
+            mem::swap(
+                &mut self.import_requirements,
+                &mut package.import_requirements,
+            );
+            package.src.push_str(src);
+        }
+    }
+
+    /// Imports the generated package at `path` into the package which code is
+    /// currently generated into, returning its name there.
+    fn import_package(&mut self, path: &str) -> String {
+        self.import_requirements.import_package(path)
+    }
+
     fn get_c_ty(&self, ty: &Type) -> String {
         let res = match ty {
             Type::Bool => "bool".into(),
@@ -143,6 +325,10 @@ impl TinyGo {
     pub fn with_sync_import(&mut self, needs_sync_import: bool) {
         self.import_requirements.needs_sync_import = needs_sync_import;
     }
+
+    fn with_math_import(&mut self, needs_math_import: bool) {
+        self.import_requirements.needs_math_import = needs_math_import;
+    }
 }
 
 impl WorldGenerator for TinyGo {
@@ -151,6 +337,35 @@ impl WorldGenerator for TinyGo {
         self.world = name.to_string();
         self.sizes.fill(resolve);
         self.world_id = Some(world);
+        let world = &resolve.worlds[world];
+
+        let mut interfaces = HashMap::new();
+        for (key, _item) in world.imports.iter().chain(world.exports.iter()) {
+            let name = resolve.name_world_key(key);
+            interfaces.insert(name, key.clone());
+        }
+
+        for (from, to) in self.opts.rename.iter() {
+            match interfaces.get(from) {
+                Some(key) => {
+                    self.renamed_interfaces.insert(key.clone(), to.clone());
+                }
+                None => self.unmatched_renames.push(from.clone()),
+            }
+        }
+
+        if self.opts.packages {
+            let imports = world.imports.iter().map(|i| (i, Direction::Import));
+            let exports = world.exports.iter().map(|e| (e, Direction::Export));
+            for ((key, item), direction) in imports.chain(exports) {
+                if let WorldItem::Interface(_) = item {
+                    let path = self.package_path(resolve, key, direction);
+                    let name = path.rsplit('/').next().unwrap();
+                    self.package_names.insert(name.to_string());
+                }
+            }
+            self.package_names.insert(CABI.to_string());
+        }
     }
 
     fn import_interface(
@@ -161,12 +376,15 @@ impl WorldGenerator for TinyGo {
         _files: &mut Files,
     ) {
         let name_raw = &resolve.name_world_key(name);
-        self.src
-            .push_str(&format!("// Import functions from {name_raw}\n"));
         self.interface_names.insert(id, name.clone());
+        let package = self.package_path(resolve, name, Direction::Import);
+        self.enter_package(&package);
 
         let mut gen = self.interface(resolve, Direction::Import, Some(name_raw));
         gen.interface = Some((id, name));
+        gen.package = package.clone();
+        gen.src
+            .push_str(&format!("// Import functions from {name_raw}\n"));
         gen.define_interface_types(id);
 
         for (_name, func) in resolve.interfaces[id].functions.iter() {
@@ -175,7 +393,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         let preamble = mem::take(&mut gen.preamble);
-        self.src.push_str(&src);
+        self.leave_package(&package, &src);
         self.preamble.append_src(&preamble);
     }
 
@@ -210,6 +428,8 @@ impl WorldGenerator for TinyGo {
         self.c_type_names
             .retain(|k, _| live_import_types.contains(k));
         self.type_names.retain(|k, _| live_import_types.contains(k));
+        self.type_packages
+            .retain(|k, _| live_import_types.contains(k));
         Ok(())
     }
 
@@ -222,11 +442,14 @@ impl WorldGenerator for TinyGo {
     ) -> Result<()> {
         self.interface_names.insert(id, name.clone());
         let name_raw = &resolve.name_world_key(name);
-        self.src
-            .push_str(&format!("// Export functions from {name_raw}\n"));
+        let package = self.package_path(resolve, name, Direction::Export);
+        self.enter_package(&package);
 
-        let mut gen = self.interface(resolve, Direction::Export, None);
+        let mut gen = self.interface(resolve, Direction::Export, Some(name_raw));
         gen.interface = Some((id, name));
+        gen.package = package.clone();
+        gen.src
+            .push_str(&format!("// Export functions from {name_raw}\n"));
         gen.define_interface_types(id);
 
         for (_name, func) in resolve.interfaces[id].functions.iter() {
@@ -237,7 +460,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         let preamble = mem::take(&mut gen.preamble);
-        self.src.push_str(&src);
+        self.leave_package(&package, &src);
         self.preamble.append_src(&preamble);
         Ok(())
     }
@@ -287,6 +510,15 @@ impl WorldGenerator for TinyGo {
     }
 
     fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        if let Some(from) = self.unmatched_renames.first() {
+            bail!("rename of `{from}` did not match any interfaces");
+        }
+
+        if self.opts.packages {
+            self.finish_packages(files);
+            return Ok(());
+        }
+
         // make sure all types are defined on top of the file
         let src = mem::take(&mut self.src);
         self.src.push_str(&src);
@@ -294,62 +526,52 @@ impl WorldGenerator for TinyGo {
         // prepend package and imports header
         let src = mem::take(&mut self.src);
         wit_bindgen_core::generated_preamble(&mut self.src, env!("CARGO_PKG_VERSION"));
-        let snake = self.world.to_snake_case();
+        let package_name = self.package_name();
         // add package
         self.src.push_str("package ");
-        self.src.push_str(&snake);
+        self.src.push_str(&package_name);
         self.src.push_str("\n\n");
 
-        // import C
-        self.src.push_str("// #include \"");
-        self.src.push_str(self.world.to_snake_case().as_str());
-        self.src.push_str(".h\"\n");
-        self.src.push_str("// #include <stdlib.h>\n");
-        if self.preamble.len() > 0 {
-            self.src.append_src(&self.preamble);
+        if self.opts.pure_go {
+            // memory is shared with the host through `unsafe`
+            self.with_import_unsafe(true);
+        } else {
+            // import C
+            self.src.push_str("// #include \"");
+            self.src.push_str(self.world.to_snake_case().as_str());
+            self.src.push_str(".h\"\n");
+            self.src.push_str("// #include <stdlib.h>\n");
+            if self.preamble.len() > 0 {
+                self.src.append_src(&self.preamble);
+            }
+            self.src.push_str("import \"C\"\n");
         }
-        self.src.push_str("import \"C\"\n");
-        let world = &resolve.worlds[id];
-
-        self.import_requirements.generate(
-            snake,
-            files,
-            format!("{}_types.go", world.name.to_kebab_case()),
-        );
+        let file_prefix = self.file_prefix();
+
+        self.import_requirements
+            .generate(package_name, files, format!("{file_prefix}_types.go"));
         self.src.push_str(&self.import_requirements.src);
 
         self.src.push_str(&src);
 
+        if self.opts.pure_go {
+            wasm::print_runtime(&mut self.src, self.return_pointer_area_size, false);
+        }
+
         if self.opts.gofmt {
-            let mut child = std::process::Command::new("gofmt")
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()
-                .expect("failed to spawn gofmt");
-            child
-                .stdin
-                .take()
-                .unwrap()
-                .write_all(self.src.as_bytes())
-                .expect("failed to write to gofmt");
-            self.src.as_mut_string().truncate(0);
-            child
-                .stdout
-                .take()
-                .unwrap()
-                .read_to_string(self.src.as_mut_string())
-                .expect("failed to read from gofmt");
-            let status = child.wait().expect("failed to wait on gofmt");
-            assert!(status.success());
+            gofmt(&mut self.src);
+        }
+        files.push(&format!("{file_prefix}.go"), self.src.as_bytes());
+        self.finish_stubs(files);
+
+        if self.opts.pure_go {
+            return Ok(());
         }
-        files.push(
-            &format!("{}.go", world.name.to_kebab_case()),
-            self.src.as_bytes(),
-        );
 
         let mut opts = wit_bindgen_c::Opts::default();
         opts.no_sig_flattening = true;
         opts.no_object_file = true;
+        opts.rename = self.opts.rename.clone();
         opts.build()
             .generate(resolve, id, files)
             .expect("C generator should be infallible");
@@ -358,6 +580,118 @@ impl WorldGenerator for TinyGo {
     }
 }
 
+impl TinyGo {
+    /// Writes the bindings as a Go package per interface, along with the
+    /// package shared by all of them.
+    fn finish_packages(&mut self, files: &mut Files) {
+        let module = self.module();
+
+        // The runtime of the bindings exports `cabi_realloc`, so it's always
+        // linked in through the package of the world.
+        self.import_requirements
+            .packages
+            .entry(CABI.to_string())
+            .or_insert_with(|| "_".to_string());
+        let mut packages = mem::take(&mut self.packages);
+        let root = Package {
+            src: mem::take(&mut self.src),
+            import_requirements: mem::take(&mut self.import_requirements),
+        };
+        packages.insert(String::new(), root);
+
+        let mut needs_result_option = false;
+        for (path, mut package) in packages {
+            let requirements = &mut package.import_requirements;
+            needs_result_option |= requirements.needs_result_option;
+            let (name, file) = self.package_file(&path);
+
+            let mut src = Source::default();
+            wit_bindgen_core::generated_preamble(&mut src, env!("CARGO_PKG_VERSION"));
+            src.push_str(&format!("package {name}\n\n"));
+            requirements.print_imports(&module);
+            src.push_str(&requirements.src);
+            src.push_str(&package.src);
+            if self.opts.gofmt {
+                gofmt(&mut src);
+            }
+            files.push(&format!("{file}.go"), src.as_bytes());
+        }
+        self.finish_stubs(files);
+
+        let mut src = Source::default();
+        wit_bindgen_core::generated_preamble(&mut src, env!("CARGO_PKG_VERSION"));
+        src.push_str(&format!("package {CABI}\n\n"));
+        src.push_str("import \"unsafe\"\n");
+        wasm::print_runtime(&mut src, self.return_pointer_area_size, true);
+        if needs_result_option {
+            imports::print_result_option(&mut src);
+        }
+        if self.opts.gofmt {
+            gofmt(&mut src);
+        }
+        files.push(&format!("{CABI}/{CABI}.go"), src.as_bytes());
+    }
+
+    /// Writes the stub implementations of exported interfaces, each next to
+    /// the file of the package it's part of.
+    fn finish_stubs(&mut self, files: &mut Files) {
+        let module = self.module();
+        for (path, mut stub) in mem::take(&mut self.stubs) {
+            if stub.src.is_empty() {
+                continue;
+            }
+            let (name, file) = self.package_file(&path);
+            let requirements = &mut stub.import_requirements;
+
+            // stubs are meant to be edited, so they don't have the preamble
+            // of generated files
+            let mut src = Source::default();
+            src.push_str(&format!("package {name}\n\n"));
+            requirements.print_imports(&module);
+            src.push_str(&requirements.src);
+            src.push_str(&stub.src);
+            if self.opts.gofmt {
+                gofmt(&mut src);
+            }
+            files.push(&format!("{file}_stub.go"), src.as_bytes());
+        }
+    }
+
+    /// Returns the name of the package at `path` along with the path of its
+    /// file, without the extension.
+    fn package_file(&self, path: &str) -> (String, String) {
+        if path.is_empty() {
+            (self.package_name(), self.file_prefix())
+        } else {
+            let name = path.rsplit('/').next().unwrap();
+            (name.to_string(), format!("{path}/{name}"))
+        }
+    }
+}
+
+fn gofmt(src: &mut Source) {
+    let mut child = std::process::Command::new("gofmt")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn gofmt");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(src.as_bytes())
+        .expect("failed to write to gofmt");
+    src.as_mut_string().truncate(0);
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(src.as_mut_string())
+        .expect("failed to read from gofmt");
+    let status = child.wait().expect("failed to wait on gofmt");
+    assert!(status.success());
+}
+
 fn avoid_keyword(s: &str) -> String {
     if GOKEYWORDS.contains(&s) {
         format!("{s}_")
@@ -367,7 +701,7 @@ fn avoid_keyword(s: &str) -> String {
 }
 
 // a list of Go keywords
-const GOKEYWORDS: [&str; 26] = [
+const GOKEYWORDS: [&str; 33] = [
     "break",
     "default",
     "func",
@@ -396,4 +730,13 @@ const GOKEYWORDS: [&str; 26] = [
     // not a Go keyword but needs to escape due to
     // it's used as a variable name that passes to C
     "ret",
+    // not Go keywords either, but these are predeclared identifiers and
+    // packages which are used by the generated code
+    "len",
+    "make",
+    "copy",
+    "string",
+    "panic",
+    "unsafe",
+    "math",
 ];
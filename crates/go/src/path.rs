@@ -1,7 +1,14 @@
 use heck::ToSnakeCase;
-use wit_bindgen_core::{wit_parser::{WorldKey, Resolve, PackageId}, Direction};
+use wit_bindgen_core::{
+    wit_parser::{PackageId, Resolve, WorldKey},
+    Direction,
+};
+
+use super::avoid_keyword;
 
 pub(crate) trait GoPath {
+    /// Returns the segments of the path of the Go package generated for this
+    /// interface, relative to the package of the world.
     fn to_path(&self, resolve: &Resolve, direction: Direction) -> Vec<String>;
 }
 
@@ -11,18 +18,19 @@ impl GoPath for WorldKey {
         if matches!(direction, Direction::Export) {
             path.push("exports".to_string());
         }
-        match self {   
+        match self {
             WorldKey::Name(n) => path.push(n.to_snake_case()),
             WorldKey::Interface(id) => {
-            let iface = &resolve.interfaces[*id];
-            let pkg = iface.package.unwrap();
-            let pkgname = resolve.packages[pkg].name.clone();
-            path.push(pkgname.namespace.to_snake_case());
-            path.push(name_package_module(resolve, pkg));
-            path.push(iface.name.as_ref().unwrap().to_snake_case());
+                let iface = &resolve.interfaces[*id];
+                let pkg = iface.package.unwrap();
+                let pkgname = resolve.packages[pkg].name.clone();
+                path.push(pkgname.namespace.to_snake_case());
+                path.push(name_package_module(resolve, pkg));
+                path.push(iface.name.as_ref().unwrap().to_snake_case());
             }
         }
-        path
+        // each segment may end up as the name of a package
+        path.iter().map(|s| avoid_keyword(s)).collect()
     }
 }
 
@@ -57,16 +65,14 @@ pub fn name_package_module(resolve: &Resolve, id: PackageId) -> String {
     };
 
     // Here there's multiple packages with the same name that differ only in
-    // version, so the version needs to be mangled into the Rust module name
+    // version, so the version needs to be mangled into the Go package name
     // that we're generating. This in theory could look at all of
     // `versions_with_same_name` and produce a minimal diff, e.g. for 0.1.0
     // and 0.2.0 this could generate "foo1" and "foo2", but for now
     // a simpler path is chosen to generate "foo0_1_0" and "foo0_2_0".
     let version = version
         .to_string()
-        .replace('.', "_")
-        .replace('-', "_")
-        .replace('+', "_")
+        .replace(['.', '-', '+'], "_")
         .to_snake_case();
     format!("{base}{version}")
 }
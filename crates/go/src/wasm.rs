@@ -0,0 +1,888 @@
+use std::fmt::Write as _;
+use std::mem;
+
+use heck::ToUpperCamelCase;
+use wit_bindgen_core::abi::{AbiVariant, Bindgen, Bitcast, Instruction, WasmType};
+use wit_bindgen_core::wit_parser::{
+    FunctionKind, Handle, Resolve, SizeAlign, Type, TypeDefKind, TypeId,
+};
+use wit_bindgen_core::{dealias, uwriteln, Direction, Source};
+
+use crate::interface;
+
+/// Generates the body of a function which calls, or is called by, core wasm
+/// directly from Go through `//go:wasmimport` and `//go:wasmexport`.
+///
+/// Values are lowered into and lifted from linear memory with `unsafe`, so
+/// this needs neither cgo nor the C bindings.
+pub(crate) struct FunctionBindgen<'a, 'b> {
+    pub(crate) interface: &'a mut interface::InterfaceGenerator<'b>,
+    pub(crate) params: Vec<String>,
+    pub(crate) src: Source,
+    // whether memory has been pinned for the host which needs to be released
+    // once the call is done
+    pub(crate) needs_unpin: bool,
+    // the name of the Go declaration of the core wasm import to call
+    wasm_func: String,
+    tmp: usize,
+    block_storage: Vec<Source>,
+    blocks: Vec<(String, Vec<String>)>,
+    payloads: Vec<String>,
+}
+
+impl<'a, 'b> FunctionBindgen<'a, 'b> {
+    pub(crate) fn new(
+        interface: &'a mut interface::InterfaceGenerator<'b>,
+        params: Vec<String>,
+        wasm_func: String,
+    ) -> Self {
+        Self {
+            interface,
+            params,
+            src: Source::default(),
+            needs_unpin: false,
+            wasm_func,
+            tmp: 0,
+            block_storage: Vec::new(),
+            blocks: Vec::new(),
+            payloads: Vec::new(),
+        }
+    }
+
+    fn tmp(&mut self, name: &str) -> String {
+        let ret = format!("{name}{}", self.tmp);
+        self.tmp += 1;
+        ret
+    }
+
+    fn load(&mut self, ty: &str, offset: i32, operands: &[String], results: &mut Vec<String>) {
+        results.push(format!("*(*{ty})(unsafe.Add({}, {offset}))", operands[0]));
+    }
+
+    fn load_ext(&mut self, ty: &str, offset: i32, operands: &[String], results: &mut Vec<String>) {
+        results.push(format!(
+            "int32(*(*{ty})(unsafe.Add({}, {offset})))",
+            operands[0]
+        ));
+    }
+
+    fn store(&mut self, ty: &str, offset: i32, operands: &[String]) {
+        self.src.push_str(&format!(
+            "*(*{ty})(unsafe.Add({}, {offset})) = {}\n",
+            operands[1], operands[0]
+        ));
+    }
+
+    fn store_narrow(&mut self, ty: &str, offset: i32, operands: &[String]) {
+        self.src.push_str(&format!(
+            "*(*{ty})(unsafe.Add({}, {offset})) = {ty}({})\n",
+            operands[1], operands[0]
+        ));
+    }
+
+    /// Declares variables for the results of a block-based instruction, which
+    /// are assigned by each of its blocks.
+    fn declare_results(&mut self, name: &str, tys: &[WasmType], results: &mut Vec<String>) {
+        for ty in tys {
+            let tmp = self.tmp(name);
+            uwriteln!(self.src, "var {tmp} {}", wasm_type(*ty));
+            results.push(tmp);
+        }
+    }
+
+    /// Returns the resource behind `handle` if it's exported by this
+    /// component, in which case values of it are tracked by Go.
+    fn exported_resource(&self, handle: &Handle) -> Option<TypeId> {
+        let (Handle::Own(resource) | Handle::Borrow(resource)) = handle;
+        let resource = dealias(self.interface.resolve, *resource);
+        if self.interface.gen.exported_resources.contains(&resource) {
+            Some(resource)
+        } else {
+            None
+        }
+    }
+}
+
+impl Bindgen for FunctionBindgen<'_, '_> {
+    type Operand = String;
+
+    fn sizes(&self) -> &SizeAlign {
+        &self.interface.gen.sizes
+    }
+
+    fn push_block(&mut self) {
+        let prev = mem::take(&mut self.src);
+        self.block_storage.push(prev);
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<String>) {
+        let to_restore = self.block_storage.pop().unwrap();
+        let src = mem::replace(&mut self.src, to_restore);
+        self.blocks.push((src.into(), mem::take(operands)));
+    }
+
+    fn return_pointer(&mut self, size: usize, _align: usize) -> String {
+        let ptr = self.tmp("ptr");
+
+        // Imports get their own return area, which is pinned for the duration
+        // of the call since the host writes to it. Exports use a statically
+        // allocated return area, because it has to be live until the
+        // post-return call.
+        if matches!(self.interface.direction, Direction::Import) {
+            self.needs_unpin = true;
+            let alloc = self.interface.runtime("Alloc");
+            uwriteln!(self.src, "{ptr} := {alloc}({size})");
+        } else {
+            let gen = &mut self.interface.gen;
+            gen.return_pointer_area_size = gen.return_pointer_area_size.max(size);
+            let area = self.interface.runtime("ReturnArea");
+            uwriteln!(self.src, "{ptr} := unsafe.Pointer(&{area})");
+        }
+        ptr
+    }
+
+    fn is_list_canonical(&self, resolve: &Resolve, ty: &Type) -> bool {
+        // Only Go's numeric types have the same representation in Go and in
+        // linear memory.
+        match ty {
+            Type::U8
+            | Type::U16
+            | Type::U32
+            | Type::U64
+            | Type::S8
+            | Type::S16
+            | Type::S32
+            | Type::S64
+            | Type::F32
+            | Type::F64 => true,
+            Type::Id(id) => match &resolve.types[*id].kind {
+                TypeDefKind::Type(t) => self.is_list_canonical(resolve, t),
+                _ => false,
+            },
+            Type::Bool | Type::Char | Type::String => false,
+        }
+    }
+
+    fn emit(
+        &mut self,
+        resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<String>,
+        results: &mut Vec<String>,
+    ) {
+        match inst {
+            Instruction::GetArg { nth } => results.push(self.params[*nth].clone()),
+            Instruction::I32Const { val } => results.push(format!("int32({val})")),
+            Instruction::ConstZero { tys } => {
+                for ty in tys.iter() {
+                    results.push(match ty {
+                        WasmType::Pointer => "unsafe.Pointer(nil)".to_string(),
+                        ty => format!("{}(0)", wasm_type(*ty)),
+                    });
+                }
+            }
+
+            Instruction::U8FromI32 => results.push(format!("uint8({})", operands[0])),
+            Instruction::S8FromI32 => results.push(format!("int8({})", operands[0])),
+            Instruction::U16FromI32 => results.push(format!("uint16({})", operands[0])),
+            Instruction::S16FromI32 => results.push(format!("int16({})", operands[0])),
+            Instruction::U32FromI32 => results.push(format!("uint32({})", operands[0])),
+            Instruction::U64FromI64 => results.push(format!("uint64({})", operands[0])),
+            Instruction::CharFromI32 => results.push(format!("rune({})", operands[0])),
+            Instruction::S32FromI32 | Instruction::S64FromI64 => results.push(operands[0].clone()),
+
+            Instruction::I32FromU8
+            | Instruction::I32FromS8
+            | Instruction::I32FromU16
+            | Instruction::I32FromS16
+            | Instruction::I32FromU32
+            | Instruction::I32FromChar => {
+                results.push(format!("int32({})", operands[0]));
+            }
+            Instruction::I64FromU64 => results.push(format!("int64({})", operands[0])),
+            Instruction::I32FromS32 | Instruction::I64FromS64 => results.push(operands[0].clone()),
+
+            // f32/f64 have the same representation in core wasm and in Go, so
+            // no conversions are necessary.
+            Instruction::F32FromFloat32
+            | Instruction::F64FromFloat64
+            | Instruction::Float32FromF32
+            | Instruction::Float64FromF64 => {
+                results.push(operands[0].clone());
+            }
+
+            Instruction::Bitcasts { casts } => {
+                for (cast, op) in casts.iter().zip(operands) {
+                    let op = self.perform_cast(op, cast);
+                    results.push(op);
+                }
+            }
+
+            Instruction::BoolFromI32 => results.push(format!("({} != 0)", operands[0])),
+            Instruction::I32FromBool => {
+                let tmp = self.tmp("bool");
+                self.src.push_str(&format!(
+                    "var {tmp} int32
+                    if {} {{
+                        {tmp} = 1
+                    }}\n",
+                    operands[0]
+                ));
+                results.push(tmp);
+            }
+
+            Instruction::RecordLower { record, .. } => {
+                let op = &operands[0];
+                for field in record.fields.iter() {
+                    let name = self.interface.field_name(field);
+                    results.push(format!("{op}.{name}"));
+                }
+            }
+            Instruction::RecordLift { record, ty, .. } => {
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let mut result = format!("{name}{{\n");
+                for (field, op) in record.fields.iter().zip(operands.iter()) {
+                    let field = self.interface.field_name(field);
+                    uwriteln!(result, "{field}: {op},");
+                }
+                result.push('}');
+                results.push(result);
+            }
+
+            Instruction::TupleLower { tuple, .. } => {
+                let op = &operands[0];
+                for i in 0..tuple.types.len() {
+                    results.push(format!("{op}.F{i}"));
+                }
+            }
+            Instruction::TupleLift { ty, .. } => {
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let mut result = format!("{name}{{\n");
+                for (i, op) in operands.iter().enumerate() {
+                    uwriteln!(result, "F{i}: {op},");
+                }
+                result.push('}');
+                results.push(result);
+            }
+
+            Instruction::HandleLower { handle, .. } => {
+                let op = &operands[0];
+                match self.exported_resource(handle) {
+                    // An exported resource is handed out to the host by
+                    // creating a handle to a newly registered representation.
+                    Some(resource) => {
+                        let lower = self.interface.exported_resource_helper(resource, "lower");
+                        results.push(format!("{lower}({op})"));
+                    }
+                    None => results.push(format!("int32({op})")),
+                }
+            }
+
+            Instruction::HandleLift { handle, .. } => {
+                let op = &operands[0];
+                match self.exported_resource(handle) {
+                    // Exported resources are looked up by their representation,
+                    // which the host passes directly for borrows.
+                    Some(resource) => {
+                        let lift = self.interface.exported_resource_helper(resource, "lift");
+                        match handle {
+                            Handle::Own(_) => results.push(format!("{lift}({op})")),
+                            Handle::Borrow(_) => results.push(format!("{lift}Borrow({op})")),
+                        }
+                    }
+                    None => {
+                        let (Handle::Own(resource) | Handle::Borrow(resource)) = handle;
+                        let resource = dealias(resolve, *resource);
+                        let name = self.interface.get_ty(&Type::Id(resource));
+                        // Borrows of imported resources must be dropped before
+                        // the exported function returns, wherever they appear
+                        // in the arguments, which deferring the drop takes care
+                        // of.
+                        if matches!(handle, Handle::Borrow(_))
+                            && matches!(self.interface.direction, Direction::Export)
+                        {
+                            let lifted = self.tmp("lift");
+                            self.src.push_str(&format!(
+                                "{lifted} := {name}({op})
+                                defer {lifted}.Drop()\n"
+                            ));
+                            results.push(lifted);
+                        } else {
+                            results.push(format!("{name}({op})"));
+                        }
+                    }
+                }
+            }
+
+            Instruction::FlagsLower { flags, .. } => {
+                let op = &operands[0];
+                for i in 0..flags.repr().count() {
+                    if i == 0 {
+                        results.push(format!("int32({op})"));
+                    } else {
+                        results.push(format!("int32({op} >> {})", i * 32));
+                    }
+                }
+            }
+            Instruction::FlagsLift { ty, .. } => {
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let result = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, op)| {
+                        if i == 0 {
+                            format!("{name}(uint32({op}))")
+                        } else {
+                            format!("{name}(uint32({op}))<<{}", i * 32)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                results.push(result);
+            }
+
+            Instruction::EnumLower { .. } => results.push(format!("int32({}.Kind())", operands[0])),
+            Instruction::EnumLift { enum_, ty, .. } => {
+                // The kind of an enum is private to its package, so it's
+                // created through the constructors of its cases.
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let result = self.tmp("enum");
+                uwriteln!(self.src, "var {result} {name}");
+                uwriteln!(self.src, "switch {} {{", operands[0]);
+                for (i, case) in enum_.cases.iter().enumerate() {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "case {i}:");
+                    uwriteln!(self.src, "{result} = {name}{case_name}()");
+                }
+                self.src.push_str(
+                    "default:
+                        panic(\"invalid enum discriminant\")
+                    }\n",
+                );
+                results.push(result);
+            }
+
+            Instruction::VariantPayloadName => {
+                let name = self.tmp("payload");
+                results.push(name.clone());
+                self.payloads.push(name);
+            }
+
+            Instruction::VariantLower {
+                variant,
+                ty,
+                results: result_types,
+                ..
+            } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let payloads = self
+                    .payloads
+                    .drain(self.payloads.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let op = &operands[0];
+
+                let mut variant_results = Vec::new();
+                self.declare_results("variant", result_types, &mut variant_results);
+                uwriteln!(self.src, "switch {op}.Kind() {{");
+                for ((case, (block, block_results)), payload) in
+                    variant.cases.iter().zip(blocks).zip(payloads)
+                {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "case {name}Kind{case_name}:");
+                    if case.ty.is_some() && uses_ident(&block, &block_results, &payload) {
+                        uwriteln!(self.src, "{payload} := {op}.Get{case_name}()");
+                    }
+                    self.src.push_str(&block);
+                    for (name, result) in variant_results.iter().zip(&block_results) {
+                        uwriteln!(self.src, "{name} = {result}");
+                    }
+                }
+                self.src.push_str("}\n");
+                results.extend(variant_results);
+            }
+
+            Instruction::VariantLift { variant, ty, .. } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let name = self.interface.get_ty(&Type::Id(*ty));
+                let result = self.tmp("variant");
+                uwriteln!(self.src, "var {result} {name}");
+                uwriteln!(self.src, "switch {} {{", operands[0]);
+                for (i, (case, (block, block_results))) in
+                    variant.cases.iter().zip(blocks).enumerate()
+                {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "case {i}:");
+                    self.src.push_str(&block);
+                    assert!(block_results.len() == (case.ty.is_some() as usize));
+                    let value = block_results.first().map(|s| s.as_str()).unwrap_or("");
+                    uwriteln!(self.src, "{result} = {name}{case_name}({value})");
+                }
+                self.src.push_str(
+                    "default:
+                        panic(\"invalid variant discriminant\")
+                    }\n",
+                );
+                results.push(result);
+            }
+
+            Instruction::OptionLower {
+                results: result_types,
+                ..
+            } => {
+                let (some, some_results) = self.blocks.pop().unwrap();
+                let (none, none_results) = self.blocks.pop().unwrap();
+                let some_payload = self.payloads.pop().unwrap();
+                let _none_payload = self.payloads.pop().unwrap();
+                let op = &operands[0];
+
+                let mut option_results = Vec::new();
+                self.declare_results("option", result_types, &mut option_results);
+                uwriteln!(self.src, "if {op}.IsSome() {{");
+                if uses_ident(&some, &some_results, &some_payload) {
+                    uwriteln!(self.src, "{some_payload} := {op}.Unwrap()");
+                }
+                self.src.push_str(&some);
+                for (name, result) in option_results.iter().zip(&some_results) {
+                    uwriteln!(self.src, "{name} = {result}");
+                }
+                self.src.push_str("} else {\n");
+                self.src.push_str(&none);
+                for (name, result) in option_results.iter().zip(&none_results) {
+                    uwriteln!(self.src, "{name} = {result}");
+                }
+                self.src.push_str("}\n");
+                results.extend(option_results);
+            }
+
+            Instruction::OptionLift { ty, .. } => {
+                let (some, some_results) = self.blocks.pop().unwrap();
+                let (none, none_results) = self.blocks.pop().unwrap();
+                assert!(none_results.is_empty());
+                assert!(some_results.len() == 1);
+
+                let ty = self.interface.get_ty(&Type::Id(*ty));
+                let result = self.tmp("option");
+                uwriteln!(self.src, "var {result} {ty}");
+                uwriteln!(self.src, "switch {} {{", operands[0]);
+                self.src.push_str("case 0:\n");
+                self.src.push_str(&none);
+                self.src.push_str("case 1:\n");
+                self.src.push_str(&some);
+                uwriteln!(self.src, "{result}.Set({})", some_results[0]);
+                self.src.push_str(
+                    "default:
+                        panic(\"invalid option discriminant\")
+                    }\n",
+                );
+                results.push(result);
+            }
+
+            Instruction::ResultLower {
+                result,
+                results: result_types,
+                ..
+            } => {
+                let (err, err_results) = self.blocks.pop().unwrap();
+                let (ok, ok_results) = self.blocks.pop().unwrap();
+                let err_payload = self.payloads.pop().unwrap();
+                let ok_payload = self.payloads.pop().unwrap();
+                let op = &operands[0];
+
+                let mut result_results = Vec::new();
+                self.declare_results("result", result_types, &mut result_results);
+                uwriteln!(self.src, "if {op}.IsErr() {{");
+                if result.err.is_some() && uses_ident(&err, &err_results, &err_payload) {
+                    uwriteln!(self.src, "{err_payload} := {op}.UnwrapErr()");
+                }
+                self.src.push_str(&err);
+                for (name, result) in result_results.iter().zip(&err_results) {
+                    uwriteln!(self.src, "{name} = {result}");
+                }
+                self.src.push_str("} else {\n");
+                if result.ok.is_some() && uses_ident(&ok, &ok_results, &ok_payload) {
+                    uwriteln!(self.src, "{ok_payload} := {op}.Unwrap()");
+                }
+                self.src.push_str(&ok);
+                for (name, result) in result_results.iter().zip(&ok_results) {
+                    uwriteln!(self.src, "{name} = {result}");
+                }
+                self.src.push_str("}\n");
+                results.extend(result_results);
+            }
+
+            Instruction::ResultLift { result, ty, .. } => {
+                let (err, err_results) = self.blocks.pop().unwrap();
+                assert!(err_results.len() == (result.err.is_some() as usize));
+                let (ok, ok_results) = self.blocks.pop().unwrap();
+                assert!(ok_results.len() == (result.ok.is_some() as usize));
+
+                let ty = self.interface.get_ty(&Type::Id(*ty));
+                let result = self.tmp("result");
+                uwriteln!(self.src, "var {result} {ty}");
+                uwriteln!(self.src, "switch {} {{", operands[0]);
+                self.src.push_str("case 0:\n");
+                self.src.push_str(&ok);
+                let ok_value = ok_results
+                    .first()
+                    .map(|s| s.as_str())
+                    .unwrap_or("struct{}{}");
+                uwriteln!(self.src, "{result}.Set({ok_value})");
+                self.src.push_str("case 1:\n");
+                self.src.push_str(&err);
+                let err_value = err_results
+                    .first()
+                    .map(|s| s.as_str())
+                    .unwrap_or("struct{}{}");
+                uwriteln!(self.src, "{result}.SetErr({err_value})");
+                self.src.push_str(
+                    "default:
+                        panic(\"invalid result discriminant\")
+                    }\n",
+                );
+                results.push(result);
+            }
+
+            Instruction::StringLower { .. } => {
+                let ptr = self.tmp("ptr");
+                self.src.push_str(&format!(
+                    "{ptr} := unsafe.Pointer(unsafe.StringData({}))\n",
+                    operands[0]
+                ));
+                if matches!(self.interface.direction, Direction::Import) {
+                    self.needs_unpin = true;
+                }
+                let pin = self.interface.runtime("Pin");
+                uwriteln!(self.src, "{pin}({ptr})");
+                results.push(ptr);
+                results.push(format!("uint32(len({}))", operands[0]));
+            }
+            Instruction::ListCanonLower { .. } => {
+                let ptr = self.tmp("ptr");
+                self.src.push_str(&format!(
+                    "{ptr} := unsafe.Pointer(unsafe.SliceData({}))\n",
+                    operands[0]
+                ));
+                if matches!(self.interface.direction, Direction::Import) {
+                    self.needs_unpin = true;
+                }
+                let pin = self.interface.runtime("Pin");
+                uwriteln!(self.src, "{pin}({ptr})");
+                results.push(ptr);
+                results.push(format!("uint32(len({}))", operands[0]));
+            }
+            Instruction::ListLower { element, .. } => {
+                let (body, body_results) = self.blocks.pop().unwrap();
+                assert!(body_results.is_empty());
+                let size = self.interface.gen.sizes.size(element);
+                let vec = self.tmp("vec");
+                let ptr = self.tmp("ptr");
+                if matches!(self.interface.direction, Direction::Import) {
+                    self.needs_unpin = true;
+                }
+                let alloc = self.interface.runtime("Alloc");
+                self.src.push_str(&format!(
+                    "{vec} := {}
+                    {ptr} := {alloc}(uintptr(len({vec})) * {size})
+                    for i := range {vec} {{\n",
+                    operands[0]
+                ));
+                if uses_ident(&body, &body_results, "e") {
+                    uwriteln!(self.src, "e := {vec}[i]");
+                }
+                if uses_ident(&body, &body_results, "base") {
+                    uwriteln!(self.src, "base := unsafe.Add({ptr}, i*{size})");
+                }
+                self.src.push_str(&body);
+                self.src.push_str("}\n");
+                results.push(ptr);
+                results.push(format!("uint32(len({vec}))"));
+            }
+
+            Instruction::StringLift => {
+                self.needs_unpin = true;
+                results.push(format!(
+                    "string(unsafe.Slice((*byte)({}), {}))",
+                    operands[0], operands[1]
+                ));
+            }
+            Instruction::ListCanonLift { element, .. } => {
+                self.needs_unpin = true;
+                let elem = self.interface.get_ty(element);
+                let list = self.tmp("list");
+                self.src.push_str(&format!(
+                    "{list} := make([]{elem}, {len})
+                    copy({list}, unsafe.Slice((*{elem})({ptr}), {len}))\n",
+                    ptr = operands[0],
+                    len = operands[1],
+                ));
+                results.push(list);
+            }
+            Instruction::ListLift { element, .. } => {
+                self.needs_unpin = true;
+                let (body, body_results) = self.blocks.pop().unwrap();
+                assert!(body_results.len() == 1);
+                let size = self.interface.gen.sizes.size(element);
+                let elem = self.interface.get_ty(element);
+                let list = self.tmp("list");
+                self.src.push_str(&format!(
+                    "{list} := make([]{elem}, {})
+                    for i := range {list} {{\n",
+                    operands[1]
+                ));
+                if uses_ident(&body, &body_results, "base") {
+                    uwriteln!(self.src, "base := unsafe.Add({}, i*{size})", operands[0]);
+                }
+                self.src.push_str(&body);
+                uwriteln!(self.src, "{list}[i] = {}", body_results[0]);
+                self.src.push_str("}\n");
+                results.push(list);
+            }
+            Instruction::IterElem { .. } => results.push("e".to_string()),
+            Instruction::IterBasePointer => results.push("base".to_string()),
+
+            Instruction::CallWasm { sig, .. } => {
+                let args = operands.join(", ");
+                match sig.results.len() {
+                    0 => uwriteln!(self.src, "{}({args})", self.wasm_func),
+                    1 => {
+                        let ret = self.tmp("ret");
+                        uwriteln!(self.src, "{ret} := {}({args})", self.wasm_func);
+                        results.push(ret);
+                    }
+                    // Results which don't fit in a single core wasm value are
+                    // returned through memory instead.
+                    _ => unreachable!("core wasm signatures have at most one result"),
+                }
+            }
+
+            Instruction::CallInterface { func } => {
+                let func_name = self.interface.func_name(func);
+                let call = match func.kind {
+                    FunctionKind::Method(_) => {
+                        format!("{}.{func_name}({})", operands[0], operands[1..].join(", "))
+                    }
+                    _ => {
+                        let var = self.interface.get_interface_var_name();
+                        format!("{var}.{func_name}({})", operands.join(", "))
+                    }
+                };
+                if func.results.len() == 0 {
+                    uwriteln!(self.src, "{call}");
+                } else {
+                    let rets = (0..func.results.len())
+                        .map(|_| self.tmp("result"))
+                        .collect::<Vec<_>>();
+                    uwriteln!(self.src, "{} := {call}", rets.join(", "));
+                    results.extend(rets);
+                }
+
+                // The arguments have been copied out of any memory the host
+                // allocated for them, so it doesn't need to be kept alive any
+                // more.
+                let sig = resolve.wasm_signature(AbiVariant::GuestExport, func);
+                if self.needs_unpin || sig.indirect_params {
+                    let unpin = self.interface.runtime("Unpin");
+                    uwriteln!(self.src, "{unpin}()");
+                }
+            }
+
+            Instruction::Return { amt, .. } => {
+                if *amt > 0 {
+                    uwriteln!(self.src, "return {}", operands.join(", "));
+                }
+            }
+
+            Instruction::I32Load { offset } => self.load("int32", *offset, operands, results),
+            Instruction::I64Load { offset } => self.load("int64", *offset, operands, results),
+            Instruction::F32Load { offset } => self.load("float32", *offset, operands, results),
+            Instruction::F64Load { offset } => self.load("float64", *offset, operands, results),
+            Instruction::LengthLoad { offset } => self.load("uint32", *offset, operands, results),
+            Instruction::PointerLoad { offset } => {
+                // Pointers in linear memory are always 32 bits wide, which
+                // isn't necessarily the case for `unsafe.Pointer`.
+                results.push(format!(
+                    "unsafe.Pointer(uintptr(*(*uint32)(unsafe.Add({}, {offset}))))",
+                    operands[0]
+                ));
+            }
+            Instruction::I32Load8U { offset } => self.load_ext("uint8", *offset, operands, results),
+            Instruction::I32Load8S { offset } => self.load_ext("int8", *offset, operands, results),
+            Instruction::I32Load16U { offset } => {
+                self.load_ext("uint16", *offset, operands, results)
+            }
+            Instruction::I32Load16S { offset } => {
+                self.load_ext("int16", *offset, operands, results)
+            }
+
+            Instruction::I32Store { offset } => self.store("int32", *offset, operands),
+            Instruction::I32Store8 { offset } => self.store_narrow("int8", *offset, operands),
+            Instruction::I32Store16 { offset } => self.store_narrow("int16", *offset, operands),
+            Instruction::I64Store { offset } => self.store("int64", *offset, operands),
+            Instruction::F32Store { offset } => self.store("float32", *offset, operands),
+            Instruction::F64Store { offset } => self.store("float64", *offset, operands),
+            Instruction::LengthStore { offset } => self.store("uint32", *offset, operands),
+            Instruction::PointerStore { offset } => {
+                self.src.push_str(&format!(
+                    "*(*uint32)(unsafe.Add({}, {offset})) = uint32(uintptr({}))\n",
+                    operands[1], operands[0]
+                ));
+            }
+
+            // Memory is garbage collected in Go, and memory handed to Go by
+            // the host is released by the runtime's `Unpin` instead.
+            Instruction::GuestDeallocate { .. }
+            | Instruction::GuestDeallocateString
+            | Instruction::GuestDeallocateList { .. }
+            | Instruction::GuestDeallocateVariant { .. } => {}
+
+            // Only emitted when lowering the arguments of a call to an
+            // export from outside of the component.
+            Instruction::Malloc { .. } => {
+                unreachable!("guest bindings never call their own exports")
+            }
+
+            Instruction::FutureLower { .. }
+            | Instruction::FutureLift { .. }
+            | Instruction::StreamLower { .. }
+            | Instruction::StreamLift { .. } => unreachable!(
+                "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+            ),
+        }
+    }
+}
+
+impl FunctionBindgen<'_, '_> {
+    fn perform_cast(&mut self, op: &str, cast: &Bitcast) -> String {
+        match cast {
+            Bitcast::I32ToF32 | Bitcast::I64ToF32 => {
+                self.interface.gen.with_math_import(true);
+                format!("math.Float32frombits(uint32({op}))")
+            }
+            Bitcast::F32ToI32 => {
+                self.interface.gen.with_math_import(true);
+                format!("int32(math.Float32bits({op}))")
+            }
+            Bitcast::F32ToI64 => {
+                self.interface.gen.with_math_import(true);
+                format!("int64(math.Float32bits({op}))")
+            }
+            Bitcast::I64ToF64 => {
+                self.interface.gen.with_math_import(true);
+                format!("math.Float64frombits(uint64({op}))")
+            }
+            Bitcast::F64ToI64 => {
+                self.interface.gen.with_math_import(true);
+                format!("int64(math.Float64bits({op}))")
+            }
+            Bitcast::I32ToI64 | Bitcast::LToI64 => format!("int64({op})"),
+            Bitcast::I64ToI32 | Bitcast::LToI32 => format!("int32({op})"),
+            Bitcast::I32ToL | Bitcast::I64ToL => format!("uint32({op})"),
+            Bitcast::PToI32 => format!("int32(uintptr({op}))"),
+            Bitcast::PToL => format!("uint32(uintptr({op}))"),
+            Bitcast::PToP64 => format!("int64(uintptr({op}))"),
+            Bitcast::I32ToP | Bitcast::LToP | Bitcast::P64ToP => {
+                format!("unsafe.Pointer(uintptr({op}))")
+            }
+            Bitcast::I64ToP64 | Bitcast::P64ToI64 | Bitcast::None => op.to_string(),
+            Bitcast::Sequence(sequence) => {
+                let [first, second] = &**sequence;
+                let inner = self.perform_cast(op, first);
+                self.perform_cast(&inner, second)
+            }
+        }
+    }
+}
+
+/// Returns the Go type of a core wasm type.
+pub(crate) fn wasm_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "int32",
+        WasmType::I64 => "int64",
+        WasmType::F32 => "float32",
+        WasmType::F64 => "float64",
+        WasmType::Pointer => "unsafe.Pointer",
+        WasmType::Length => "uint32",
+        WasmType::PointerOrI64 => "int64",
+    }
+}
+
+/// Returns the name of a helper of the runtime of the bindings, which is
+/// exported from its package if the runtime lives in a package of its own.
+pub(crate) fn runtime_name(name: &str, exported: bool) -> String {
+    if exported {
+        name.to_string()
+    } else {
+        format!("wasm{name}")
+    }
+}
+
+/// Prints the helpers used by the generated functions to share memory with
+/// the host, along with the return area of exported functions.
+pub(crate) fn print_runtime(src: &mut Source, return_pointer_area_size: usize, exported: bool) {
+    let alloc = runtime_name("Alloc", exported);
+    let pin = runtime_name("Pin", exported);
+    let unpin = runtime_name("Unpin", exported);
+    src.push_str(&format!(
+        "
+        // wasmPinned keeps memory which is shared with the host alive until the
+        // bindings are done with it, since the host only refers to it by address.
+        var wasmPinned []unsafe.Pointer
+
+        // {alloc} allocates memory which is kept alive until the next call to
+        // {unpin}. The memory is allocated as 64-bit words so that it is aligned
+        // for every canonical ABI type.
+        func {alloc}(size uintptr) unsafe.Pointer {{
+            ptr := unsafe.Pointer(unsafe.SliceData(make([]uint64, (size+7)/8)))
+            wasmPinned = append(wasmPinned, ptr)
+            return ptr
+        }}
+
+        // {pin} keeps the memory at ptr alive until the next call to {unpin}.
+        func {pin}(ptr unsafe.Pointer) {{
+            wasmPinned = append(wasmPinned, ptr)
+        }}
+
+        // {unpin} releases all memory kept alive by {alloc} and {pin}.
+        func {unpin}() {{
+            for i := range wasmPinned {{
+                wasmPinned[i] = nil
+            }}
+            wasmPinned = wasmPinned[:0]
+        }}
+
+        //go:wasmexport cabi_realloc
+        func wasmRealloc(ptr unsafe.Pointer, oldSize, align, newSize uint32) unsafe.Pointer {{
+            newPtr := {alloc}(uintptr(newSize))
+            copy(unsafe.Slice((*byte)(newPtr), newSize), unsafe.Slice((*byte)(ptr), oldSize))
+            return newPtr
+        }}
+        "
+    ));
+    if return_pointer_area_size > 0 {
+        let words = return_pointer_area_size.div_ceil(8);
+        let area = runtime_name("ReturnArea", exported);
+        src.push_str(&format!(
+            "
+            // {area} holds the results of exported functions until their
+            // post-return function is called.
+            var {area} [{words}]uint64
+            "
+        ));
+    }
+}
+
+/// Returns whether the identifier `ident` is used by a block, either in its
+/// code or in the expressions it results in.
+fn uses_ident(src: &str, results: &[String], ident: &str) -> bool {
+    std::iter::once(src)
+        .chain(results.iter().map(|s| s.as_str()))
+        .flat_map(|s| s.split(|c: char| !c.is_alphanumeric() && c != '_'))
+        .any(|word| word == ident)
+}
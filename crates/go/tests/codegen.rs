@@ -6,8 +6,31 @@ use std::process::Command;
 use heck::*;
 
 macro_rules! codegen_test {
+    // todo: support flags wider than 64 bits and then remove this line:
+    (flags_wide $name:tt $test:tt) => {};
     (issue668 $name:tt $test:tt) => {};
     (multiversion $name:tt $test:tt) => {};
+
+    // `future` and `stream` types aren't supported yet, so make sure they're
+    // reported as an error.
+    (futures_streams $name:tt $test:tt) => {
+        #[test]
+        fn futures_streams() {
+            test_helpers::run_world_codegen_test(
+                "guest-go",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let err = wit_bindgen_go::Opts::default()
+                        .build()
+                        .generate(resolve, world, files)
+                        .unwrap_err();
+                    assert!(err.to_string().contains("aren't supported"), "{err}");
+                },
+                |_, _| {},
+            );
+        }
+    };
+
     ($id:ident $name:tt $test:tt) => {
         #[test]
         fn $id() {
@@ -21,7 +44,27 @@ macro_rules! codegen_test {
                         .unwrap()
                 },
                 verify,
-            )
+            );
+            test_helpers::run_world_codegen_test(
+                "guest-go-pure",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_go::Opts::default();
+                    opts.pure_go = true;
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
+            test_helpers::run_world_codegen_test(
+                "guest-go-packages",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_go::Opts::default();
+                    opts.packages = true;
+                    opts.build().generate(resolve, world, files).unwrap()
+                },
+                verify,
+            );
         }
     };
 }
@@ -87,3 +130,218 @@ fn verify(dir: &Path, name: &str) {
     cmd.current_dir(dir);
     test_helpers::run_command(&mut cmd);
 }
+
+#[test]
+fn package_options() {
+    use wit_bindgen_core::wit_parser::{Resolve, UnresolvedPackage};
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push(
+            UnresolvedPackage::parse(
+                "input.wit".as_ref(),
+                r#"
+                    package foo:bar;
+
+                    interface b {
+                        record point {
+                            x: u32,
+                        }
+                        f: func() -> point;
+                    }
+
+                    world the-world {
+                        use b.{point};
+                        export run: func() -> point;
+                    }
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+
+    let mut opts = wit_bindgen_go::Opts::default();
+    opts.gofmt = false;
+    opts.package_name = Some("bindings".to_string());
+    opts.module_path = Some("example.com/app/bindings".to_string());
+    opts.file_prefix = Some("wit".to_string());
+    opts.rename
+        .push(("foo:bar/b".to_string(), "renamed".to_string()));
+
+    // All interfaces are in the package of the world.
+    opts.pure_go = true;
+    let mut files = Default::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let files = files.iter().collect::<Vec<_>>();
+    let names = files.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(names, ["wit.go"]);
+    let src = std::str::from_utf8(files[0].1).unwrap();
+    assert!(src.contains("package bindings\n"));
+    assert!(src.contains("func RenamedF() RenamedPoint {"));
+
+    // Each interface has a package of its own, which are imported through
+    // the module path.
+    opts.packages = true;
+    let mut files = Default::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let files = files.iter().collect::<Vec<_>>();
+    let names = files.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(names, ["cabi/cabi.go", "renamed/renamed.go", "wit.go"]);
+    let src = std::str::from_utf8(files[2].1).unwrap();
+    assert!(src.contains("package bindings\n"));
+    assert!(src.contains("import \"example.com/app/bindings/renamed\"\n"));
+    assert!(src.contains("type Point = renamed.Point\n"));
+
+    // Renaming an interface which isn't part of the world is an error.
+    opts.rename
+        .push(("foo:bar/missing".to_string(), "missing".to_string()));
+    let err = opts
+        .build()
+        .generate(&resolve, world, &mut Default::default())
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("`foo:bar/missing` did not match"),
+        "{err}"
+    );
+}
+
+#[test]
+fn generate_stub() {
+    use wit_bindgen_core::wit_parser::{Resolve, UnresolvedPackage};
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push(
+            UnresolvedPackage::parse(
+                "input.wit".as_ref(),
+                r#"
+                    package foo:bar;
+
+                    interface b {
+                        resource thing {
+                            constructor(x: u32);
+                            get: func() -> option<u32>;
+                        }
+                    }
+
+                    world the-world {
+                        export b;
+                        export run: func(s: string) -> string;
+                    }
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+
+    let mut opts = wit_bindgen_go::Opts::default();
+    opts.gofmt = false;
+    opts.pure_go = true;
+    opts.generate_stub = true;
+
+    // The stubs of all interfaces are in the package of the world.
+    let mut files = Default::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let files = files.iter().collect::<Vec<_>>();
+    let names = files.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["the-world.go", "the-world_stub.go", "the-world_types.go"]
+    );
+    let src = std::str::from_utf8(files[1].1).unwrap();
+    assert!(src.starts_with("package the_world\n"));
+    assert!(src.contains("SetTheWorld(TheWorldImpl{})"));
+    assert!(src.contains("func (TheWorldImpl) Run(s string) string {"));
+    assert!(src.contains("SetExportsFooBarB(ExportsFooBarBImpl{})"));
+    assert!(
+        src.contains("func (ExportsFooBarBImpl) ConstructorThing(x uint32) ExportsFooBarBThing {")
+    );
+    assert!(src.contains("var _ ExportsFooBarBThing = (*ExportsFooBarBThingImpl)(nil)"));
+    assert!(src.contains("func (*ExportsFooBarBThingImpl) MethodThingGet() Option[uint32] {"));
+
+    // Each stub is next to the package it implements, along with the imports
+    // its signatures need.
+    opts.packages = true;
+    let mut files = Default::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let files = files.iter().collect::<Vec<_>>();
+    let names = files.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "cabi/cabi.go",
+            "exports/foo/bar/b/b.go",
+            "exports/foo/bar/b/b_stub.go",
+            "the-world.go",
+            "the-world_stub.go",
+        ]
+    );
+    let src = std::str::from_utf8(files[2].1).unwrap();
+    assert!(src.starts_with("package b\n"));
+    assert!(src.contains("import \"the-world/cabi\"\n"));
+    assert!(src.contains("SetExports(ExportsImpl{})"));
+    assert!(src.contains("func (*ThingImpl) MethodThingGet() cabi.Option[uint32] {"));
+    let src = std::str::from_utf8(files[4].1).unwrap();
+    assert!(!src.contains("import"));
+    assert!(src.contains("func (ExportsImpl) Run(s string) string {"));
+}
+
+#[test]
+fn pure_go_list_results() {
+    use wit_bindgen_core::wit_parser::{Resolve, UnresolvedPackage};
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push(
+            UnresolvedPackage::parse(
+                "input.wit".as_ref(),
+                r#"
+                    package foo:bar;
+
+                    interface b {
+                        record point {
+                            x: u32,
+                            name: string,
+                        }
+                        points: func() -> list<point>;
+                        names: func() -> list<string>;
+                    }
+
+                    world the-world {
+                        import b;
+                        export b;
+                    }
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+
+    let mut opts = wit_bindgen_go::Opts::default();
+    opts.gofmt = false;
+    opts.pure_go = true;
+    let mut files = Default::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let files = files.iter().collect::<Vec<_>>();
+    let src = std::str::from_utf8(files[0].1).unwrap();
+
+    // Imports pass a return area to the host, and lift each element out of
+    // the list it points to.
+    assert!(src.contains("func _FooBarBPoints(unsafe.Pointer)\n"));
+    assert!(src.contains("func FooBarBPoints() []FooBarBPoint {"));
+    assert!(src.contains("list1 := make([]FooBarBPoint, *(*uint32)(unsafe.Add(ptr0, 4)))"));
+    assert!(src.contains("func FooBarBNames() []string {"));
+    assert!(src.contains("list1 := make([]string, *(*uint32)(unsafe.Add(ptr0, 4)))"));
+
+    // Exports lower each element into memory which is kept alive until the
+    // post-return function is called, and return a pointer to the list.
+    assert!(src.contains("func exportsFooBarBPoints() unsafe.Pointer {"));
+    assert!(src.contains("ptr4 := wasmAlloc(uintptr(len(vec3)) * 12)"));
+    assert!(src.contains("func exportsFooBarBNames() unsafe.Pointer {"));
+    assert!(src.contains("ptr4 := wasmAlloc(uintptr(len(vec3)) * 8)"));
+    assert!(src.contains("func exportsFooBarBNamesPostReturn(arg0 unsafe.Pointer) {"));
+    assert!(src.contains("var wasmReturnArea [1]uint64"));
+}
@@ -113,6 +113,12 @@ impl Parse for Config {
                     Opt::PubExportMacro(enable) => {
                         opts.pub_export_macro = enable.value();
                     }
+                    Opt::AsyncExports(enable) => {
+                        opts.async_exports = enable.value();
+                    }
+                    Opt::UnstableAsyncTypes(enable) => {
+                        opts.unstable_async_types = enable.value();
+                    }
                 }
             }
         } else {
@@ -231,6 +237,8 @@ mod kw {
     syn::custom_keyword!(default_bindings_module);
     syn::custom_keyword!(export_macro_name);
     syn::custom_keyword!(pub_export_macro);
+    syn::custom_keyword!(async_exports);
+    syn::custom_keyword!(unstable_async_types);
 }
 
 #[derive(Clone)]
@@ -280,6 +288,8 @@ enum Opt {
     DefaultBindingsModule(syn::LitStr),
     ExportMacroName(syn::LitStr),
     PubExportMacro(syn::LitBool),
+    AsyncExports(syn::LitBool),
+    UnstableAsyncTypes(syn::LitBool),
 }
 
 impl Parse for Opt {
@@ -398,6 +408,14 @@ impl Parse for Opt {
             input.parse::<kw::pub_export_macro>()?;
             input.parse::<Token![:]>()?;
             Ok(Opt::PubExportMacro(input.parse()?))
+        } else if l.peek(kw::async_exports) {
+            input.parse::<kw::async_exports>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::AsyncExports(input.parse()?))
+        } else if l.peek(kw::unstable_async_types) {
+            input.parse::<kw::unstable_async_types>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::UnstableAsyncTypes(input.parse()?))
         } else {
             Err(l.error())
         }
@@ -0,0 +1,42 @@
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use core::future::Future;
+use core::pin::pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+/// Waker used by `block_on` which records whether it was woken.
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Runs `future` to completion on the current thread, returning its output.
+///
+/// wit-bindgen-rust generates calls to this function from the export glue of
+/// `async fn` exports. Component model imports are currently all synchronous
+/// so there is no event loop to wait on; instead the future is polled again
+/// each time it wakes itself up. A future which returns `Poll::Pending`
+/// without arranging to be woken can never make progress, and in that
+/// situation this function panics rather than spinning forever.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        if !flag.0.swap(false, Ordering::SeqCst) {
+            panic!("future is pending but was not woken, and can never complete");
+        }
+    }
+}
@@ -11,6 +11,9 @@ pub use bitflags;
 #[cfg(not(target_env = "p2"))]
 mod cabi_realloc;
 
+mod async_support;
+pub use async_support::block_on;
+
 /// This function is called from generated bindings and will be deleted by
 /// the linker. The purpose of this function is to force a reference to the
 /// symbol `cabi_realloc` to make its way through to the final linker
@@ -785,6 +785,19 @@
 ///     // Disable a workaround to force wasm constructors to be run only once
 ///     // when exported functions are called.
 ///     disable_run_ctors_once_workaround: false,
+///
+///     // Generates exported functions as `async fn` in their traits. Each call
+///     // to an export is run to completion before returning to the caller.
+///     //
+///     // This defaults to `false`.
+///     async_exports: false,
+///
+///     // Generates bindings for worlds which use `future` and `stream` types.
+///     // This is unstable and the bindings can't yet be compiled for wasm,
+///     // since these types can't be encoded in a component yet.
+///     //
+///     // This defaults to `false`, which rejects such worlds.
+///     unstable_async_types: false,
 /// });
 /// ```
 ///
@@ -818,5 +831,7 @@ pub mod rt {
     #[cfg(all(feature = "realloc", not(target_env = "p2")))]
     pub use wit_bindgen_rt::cabi_realloc;
 
+    pub use wit_bindgen_rt::block_on;
+
     pub use crate::pre_wit_bindgen_0_20_0::*;
 }
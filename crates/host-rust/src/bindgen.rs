@@ -0,0 +1,699 @@
+use crate::interface::InterfaceGenerator;
+use crate::wasm_type;
+use heck::*;
+use std::fmt::Write as _;
+use std::mem;
+use wit_bindgen_core::abi::{Bindgen, Instruction, LiftLower, WasmType};
+use wit_bindgen_core::{uwrite, uwriteln, wit_parser::*, Source};
+use wit_bindgen_rust::common::{RustFunctionGenerator, RustGenerator};
+
+pub(super) struct FunctionBindgen<'a, 'b> {
+    pub gen: &'b mut InterfaceGenerator<'a>,
+    params: Vec<String>,
+    pub src: Source,
+    blocks: Vec<String>,
+    block_storage: Vec<Source>,
+    tmp: usize,
+    /// The name of the variable holding the store, either the `Caller` of an
+    /// import or the store passed to a call of an export.
+    store: &'static str,
+    /// The host trait method implementing an import, or the name of the
+    /// guest's export being called.
+    callee: String,
+    /// The post-return function of the export being called, if any.
+    pub post_return: Option<String>,
+    /// The core wasm results of the export being called, which are passed
+    /// to its post-return function.
+    wasm_results: Vec<(String, WasmType)>,
+    pub needs_memory: bool,
+    pub needs_realloc: bool,
+}
+
+impl<'a, 'b> FunctionBindgen<'a, 'b> {
+    pub(super) fn new(
+        gen: &'b mut InterfaceGenerator<'a>,
+        params: Vec<String>,
+        store: &'static str,
+        callee: String,
+    ) -> FunctionBindgen<'a, 'b> {
+        FunctionBindgen {
+            gen,
+            params,
+            src: Default::default(),
+            blocks: Vec::new(),
+            block_storage: Vec::new(),
+            tmp: 0,
+            store,
+            callee,
+            post_return: None,
+            wasm_results: Vec::new(),
+            needs_memory: false,
+            needs_realloc: false,
+        }
+    }
+
+    /// Binds the value loaded from linear memory as a `ty` to a new local,
+    /// converted to the core wasm type `wasm` if necessary.
+    fn load(&mut self, ty: &str, wasm: &str, offset: i32, operands: &[String]) -> String {
+        self.needs_memory = true;
+        let tmp = self.tmp();
+        let rt = self.gen.path_to_rt();
+        let store = self.store;
+        let ptr = &operands[0];
+        let load = format!("{rt}::load::<{ty}>(&{store}, memory, {ptr}, {offset})?");
+        if ty == wasm {
+            uwriteln!(self.src, "let l{tmp} = {load};");
+        } else {
+            uwriteln!(self.src, "let l{tmp} = {wasm}::from({load});");
+        }
+        format!("l{tmp}")
+    }
+
+    /// Stores the first operand into linear memory at the address in the
+    /// second, truncated to `ty` if necessary.
+    fn store(&mut self, ty: Option<&str>, offset: i32, operands: &[String]) {
+        self.needs_memory = true;
+        let rt = self.gen.path_to_rt();
+        let store = self.store;
+        let ptr = &operands[1];
+        let val = match ty {
+            Some(ty) => format!("({}) as {ty}", operands[0]),
+            None => operands[0].clone(),
+        };
+        uwriteln!(
+            self.src,
+            "{rt}::store(&mut {store}, memory, {ptr}, {offset}, {val})?;"
+        );
+    }
+}
+
+impl RustFunctionGenerator for FunctionBindgen<'_, '_> {
+    fn push_str(&mut self, s: &str) {
+        self.src.push_str(s);
+    }
+
+    fn tmp(&mut self) -> usize {
+        let ret = self.tmp;
+        self.tmp += 1;
+        ret
+    }
+
+    /// Hosts implement imports by lifting their arguments and call exports
+    /// by lowering them, the reverse of guests.
+    fn lift_lower(&self) -> LiftLower {
+        if self.gen.in_import {
+            LiftLower::LiftArgsLowerResults
+        } else {
+            LiftLower::LowerArgsLiftResults
+        }
+    }
+
+    fn type_path(&self, id: TypeId, owned: bool) -> String {
+        self.gen.type_path(id, owned)
+    }
+}
+
+impl Bindgen for FunctionBindgen<'_, '_> {
+    type Operand = String;
+
+    fn push_block(&mut self) {
+        let prev_src = mem::take(&mut self.src);
+        self.block_storage.push(prev_src);
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<String>) {
+        let prev_src = self.block_storage.pop().unwrap();
+        let src = mem::replace(&mut self.src, prev_src);
+        let expr = match operands.len() {
+            0 => "()".to_string(),
+            1 => operands[0].clone(),
+            _ => format!("({})", operands.join(", ")),
+        };
+        if src.is_empty() {
+            self.blocks.push(expr);
+        } else if operands.is_empty() {
+            self.blocks.push(format!("{{\n{}}}", &src[..]));
+        } else {
+            self.blocks.push(format!("{{\n{}\n{}\n}}", &src[..], expr));
+        }
+    }
+
+    fn return_pointer(&mut self, _size: usize, _align: usize) -> String {
+        unreachable!("hosts never allocate return areas")
+    }
+
+    fn sizes(&self) -> &SizeAlign {
+        &self.gen.sizes
+    }
+
+    fn is_list_canonical(&self, _resolve: &Resolve, ty: &Type) -> bool {
+        primitive(ty).is_some()
+    }
+
+    fn emit(
+        &mut self,
+        _resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<String>,
+        results: &mut Vec<String>,
+    ) {
+        let store = self.store;
+        let mut top_as = |cvt: &str| {
+            let mut s = operands.pop().unwrap();
+            s.push_str(" as ");
+            s.push_str(cvt);
+            results.push(s);
+        };
+
+        match inst {
+            Instruction::GetArg { nth } => results.push(self.params[*nth].clone()),
+            Instruction::I32Const { val } => results.push(format!("{}i32", val)),
+            Instruction::ConstZero { tys } => {
+                for ty in tys.iter() {
+                    match ty {
+                        WasmType::I32 | WasmType::Pointer | WasmType::Length => {
+                            results.push("0i32".to_string())
+                        }
+                        WasmType::I64 | WasmType::PointerOrI64 => results.push("0i64".to_string()),
+                        WasmType::F32 => results.push("0.0f32".to_string()),
+                        WasmType::F64 => results.push("0.0f64".to_string()),
+                    }
+                }
+            }
+
+            Instruction::I64FromU64 | Instruction::I64FromS64 => {
+                let rt = self.gen.path_to_rt();
+                let s = operands.pop().unwrap();
+                results.push(format!("{rt}::as_i64({s})"));
+            }
+            Instruction::I32FromChar
+            | Instruction::I32FromU8
+            | Instruction::I32FromS8
+            | Instruction::I32FromU16
+            | Instruction::I32FromS16
+            | Instruction::I32FromU32
+            | Instruction::I32FromS32 => {
+                let rt = self.gen.path_to_rt();
+                let s = operands.pop().unwrap();
+                results.push(format!("{rt}::as_i32({s})"));
+            }
+
+            Instruction::F32FromFloat32 => {
+                let rt = self.gen.path_to_rt();
+                let s = operands.pop().unwrap();
+                results.push(format!("{rt}::as_f32({s})"));
+            }
+            Instruction::F64FromFloat64 => {
+                let rt = self.gen.path_to_rt();
+                let s = operands.pop().unwrap();
+                results.push(format!("{rt}::as_f64({s})"));
+            }
+            Instruction::Float32FromF32
+            | Instruction::Float64FromF64
+            | Instruction::S32FromI32
+            | Instruction::S64FromI64 => {
+                results.push(operands.pop().unwrap());
+            }
+            Instruction::S8FromI32 => top_as("i8"),
+            Instruction::U8FromI32 => top_as("u8"),
+            Instruction::S16FromI32 => top_as("i16"),
+            Instruction::U16FromI32 => top_as("u16"),
+            Instruction::U32FromI32 => top_as("u32"),
+            Instruction::U64FromI64 => top_as("u64"),
+            Instruction::CharFromI32 => {
+                let rt = self.gen.path_to_rt();
+                results.push(format!("{rt}::char_lift({} as u32)?", operands[0]));
+            }
+
+            Instruction::Bitcasts { casts } => crate::bitcast(casts, operands, results),
+
+            Instruction::I32FromBool => {
+                results.push(format!("match {} {{ true => 1, false => 0 }}", operands[0]));
+            }
+            Instruction::BoolFromI32 => {
+                results.push(format!("{} != 0", operands[0]));
+            }
+
+            Instruction::FlagsLower { flags, .. } => {
+                let tmp = self.tmp();
+                self.push_str(&format!("let flags{} = {};\n", tmp, operands[0]));
+                for i in 0..flags.repr().count() {
+                    results.push(format!("flags{tmp}.bits()[{i}] as i32"));
+                }
+            }
+
+            Instruction::FlagsLift { ty, .. } => {
+                let name = self.gen.type_path(*ty, true);
+                let bits = operands
+                    .iter()
+                    .map(|op| format!("{op} as u32"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                results.push(format!("{name}::from_bits_retain([{bits}])"));
+            }
+
+            // Handles are identified by the representation of their resource,
+            // see `add_resource_intrinsics_to_linker`.
+            Instruction::HandleLower { .. } => {
+                results.push(format!("({}).rep() as i32", operands[0]));
+            }
+            Instruction::HandleLift { .. } => {
+                let rt = self.gen.path_to_rt();
+                results.push(format!("{rt}::Resource::new({} as u32)", operands[0]));
+            }
+
+            Instruction::FutureLower { .. } | Instruction::StreamLower { .. } => {
+                let rt = self.gen.path_to_rt();
+                results.push(format!("{rt}::as_i32({})", operands[0]));
+            }
+            Instruction::FutureLift { .. } | Instruction::StreamLift { .. } => {
+                results.push(format!("{} as u32", operands[0]));
+            }
+
+            Instruction::RecordLower { ty, record, .. } => {
+                self.record_lower(*ty, record, &operands[0], results);
+            }
+            Instruction::RecordLift { ty, record, .. } => {
+                self.record_lift(*ty, record, operands, results);
+            }
+
+            Instruction::TupleLower { tuple, .. } => {
+                self.tuple_lower(tuple, &operands[0], results);
+            }
+            Instruction::TupleLift { .. } => {
+                self.tuple_lift(operands, results);
+            }
+
+            Instruction::VariantPayloadName => results.push("e".to_string()),
+
+            Instruction::VariantLower {
+                variant,
+                results: result_types,
+                ty,
+                ..
+            } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let name = self.typename_lower(*ty);
+                self.let_results(result_types.len(), results);
+                let op0 = &operands[0];
+                self.push_str(&format!("match {op0} {{\n"));
+                for (case, block) in variant.cases.iter().zip(blocks) {
+                    let case_name = case.name.to_upper_camel_case();
+                    self.push_str(&format!("{name}::{case_name}"));
+                    if case.ty.is_some() {
+                        self.push_str(&format!("(e) => {block},\n"));
+                    } else {
+                        self.push_str(&format!(" => {{\n{block}\n}}\n"));
+                    }
+                }
+                if results.is_empty() {
+                    self.push_str("}\n");
+                } else {
+                    self.push_str("};\n");
+                }
+            }
+
+            Instruction::VariantLift { variant, ty, .. } => {
+                let rt = self.gen.path_to_rt();
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let op0 = &operands[0];
+                let tmp = self.tmp();
+                let name = self.typename_lift(*ty);
+                uwriteln!(self.src, "let v{tmp} = match {op0} {{");
+                for (i, (case, block)) in variant.cases.iter().zip(blocks).enumerate() {
+                    uwriteln!(self.src, "{i} => {{");
+                    let case_name = case.name.to_upper_camel_case();
+                    if case.ty.is_none() {
+                        uwriteln!(self.src, "{name}::{case_name}");
+                    } else {
+                        uwriteln!(self.src, "let e{tmp} = {block};");
+                        uwriteln!(self.src, "{name}::{case_name}(e{tmp})");
+                    }
+                    uwriteln!(self.src, "}}");
+                }
+                uwriteln!(self.src, "_ => return Err({rt}::invalid_discriminant()),");
+                uwriteln!(self.src, "}};");
+                results.push(format!("v{tmp}"));
+            }
+
+            Instruction::OptionLower {
+                results: result_types,
+                ..
+            } => {
+                let some = self.blocks.pop().unwrap();
+                let none = self.blocks.pop().unwrap();
+                self.let_results(result_types.len(), results);
+                let operand = &operands[0];
+                self.push_str(&format!(
+                    "match {operand} {{
+                        Some(e) => {some},
+                        None => {{\n{none}\n}},
+                    }};"
+                ));
+            }
+
+            Instruction::OptionLift { .. } => {
+                let rt = self.gen.path_to_rt();
+                let some = self.blocks.pop().unwrap();
+                let none = self.blocks.pop().unwrap();
+                assert_eq!(none, "()");
+                let operand = &operands[0];
+                results.push(format!(
+                    "match {operand} {{
+                        0 => None,
+                        1 => {{
+                            let e = {some};
+                            Some(e)
+                        }}
+                        _ => return Err({rt}::invalid_discriminant()),
+                    }}"
+                ));
+            }
+
+            Instruction::ResultLower {
+                results: result_types,
+                result,
+                ..
+            } => {
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
+                self.let_results(result_types.len(), results);
+                let operand = &operands[0];
+                let ok_binding = if result.ok.is_some() { "e" } else { "_" };
+                let err_binding = if result.err.is_some() { "e" } else { "_" };
+                self.push_str(&format!(
+                    "match {operand} {{
+                        Ok({ok_binding}) => {{ {ok} }},
+                        Err({err_binding}) => {{ {err} }},
+                    }};"
+                ));
+            }
+
+            Instruction::ResultLift { .. } => {
+                let rt = self.gen.path_to_rt();
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
+                let operand = &operands[0];
+                results.push(format!(
+                    "match {operand} {{
+                        0 => {{
+                            let e = {ok};
+                            Ok(e)
+                        }}
+                        1 => {{
+                            let e = {err};
+                            Err(e)
+                        }}
+                        _ => return Err({rt}::invalid_discriminant()),
+                    }}"
+                ));
+            }
+
+            Instruction::EnumLower { .. } => {
+                results.push(format!("{}.clone() as i32", operands[0]));
+            }
+
+            Instruction::EnumLift { enum_, ty, .. } => {
+                let rt = self.gen.path_to_rt();
+                let name = self.gen.type_path(*ty, true);
+                let op = &operands[0];
+                let tmp = self.tmp();
+                uwriteln!(self.src, "let enum{tmp} = match {op} {{");
+                for (i, case) in enum_.cases.iter().enumerate() {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "{i} => {name}::{case_name},");
+                }
+                uwriteln!(self.src, "_ => return Err({rt}::invalid_discriminant()),");
+                uwriteln!(self.src, "}};");
+                results.push(format!("enum{tmp}"));
+            }
+
+            Instruction::ListCanonLower { element, .. } => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                self.needs_realloc = true;
+                let tmp = self.tmp();
+                let ty = primitive(element).unwrap();
+                uwriteln!(
+                    self.src,
+                    "let (ptr{tmp}, len{tmp}) = {rt}::store_list::<{ty}>(&mut {store}, memory, &realloc, &{})?;",
+                    operands[0]
+                );
+                results.push(format!("ptr{tmp}"));
+                results.push(format!("len{tmp}"));
+            }
+
+            Instruction::ListCanonLift { element, .. } => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                let tmp = self.tmp();
+                let ty = primitive(element).unwrap();
+                uwriteln!(
+                    self.src,
+                    "let list{tmp} = {rt}::load_list::<{ty}>(&{store}, memory, {}, {})?;",
+                    operands[0],
+                    operands[1]
+                );
+                results.push(format!("list{tmp}"));
+            }
+
+            Instruction::StringLower { .. } => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                self.needs_realloc = true;
+                let tmp = self.tmp();
+                uwriteln!(
+                    self.src,
+                    "let (ptr{tmp}, len{tmp}) = {rt}::store_string(&mut {store}, memory, &realloc, &{})?;",
+                    operands[0]
+                );
+                results.push(format!("ptr{tmp}"));
+                results.push(format!("len{tmp}"));
+            }
+
+            Instruction::StringLift => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                let tmp = self.tmp();
+                uwriteln!(
+                    self.src,
+                    "let string{tmp} = {rt}::load_string(&{store}, memory, {}, {})?;",
+                    operands[0],
+                    operands[1]
+                );
+                results.push(format!("string{tmp}"));
+            }
+
+            Instruction::ListLower { element, .. } => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                self.needs_realloc = true;
+                let body = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let size = self.gen.sizes.size(element);
+                let align = self.gen.sizes.align(element);
+                uwriteln!(self.src, "let vec{tmp} = {};", operands[0]);
+                uwriteln!(self.src, "let len{tmp} = vec{tmp}.len();");
+                uwriteln!(
+                    self.src,
+                    "let result{tmp} = {rt}::alloc(&mut {store}, &realloc, len{tmp} * {size}, {align})?;"
+                );
+                uwriteln!(self.src, "for (i, e) in vec{tmp}.iter().enumerate() {{");
+                uwriteln!(
+                    self.src,
+                    "let base = result{tmp}.wrapping_add((i * {size}) as i32);"
+                );
+                uwriteln!(self.src, "{body}");
+                uwriteln!(self.src, "}}");
+                results.push(format!("result{tmp}"));
+                results.push(format!("len{tmp} as i32"));
+            }
+
+            Instruction::ListLift { element, .. } => {
+                let body = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let size = self.gen.sizes.size(element);
+                let len = format!("len{tmp}");
+                let base = format!("base{tmp}");
+                let result = format!("result{tmp}");
+                uwriteln!(self.src, "let {base} = {};", operands[0]);
+                uwriteln!(self.src, "let {len} = {};", operands[1]);
+                uwriteln!(self.src, "let mut {result} = Vec::new();");
+                uwriteln!(self.src, "for i in 0..{len} as u32 {{");
+                uwriteln!(
+                    self.src,
+                    "let base = {base}.wrapping_add((i as i32).wrapping_mul({size}));"
+                );
+                uwriteln!(self.src, "let e{tmp} = {body};");
+                uwriteln!(self.src, "{result}.push(e{tmp});");
+                uwriteln!(self.src, "}}");
+                results.push(result);
+            }
+
+            Instruction::IterElem { .. } => results.push("e".to_string()),
+
+            Instruction::IterBasePointer => results.push("base".to_string()),
+
+            Instruction::Malloc { size, align, .. } => {
+                let rt = self.gen.path_to_rt();
+                self.needs_memory = true;
+                self.needs_realloc = true;
+                let tmp = self.tmp();
+                uwriteln!(
+                    self.src,
+                    "let ptr{tmp} = {rt}::alloc(&mut {store}, &realloc, {size}, {align})?;"
+                );
+                results.push(format!("ptr{tmp}"));
+            }
+
+            Instruction::CallWasm { sig, .. } => {
+                let tmp = self.tmp();
+                let params = sig
+                    .params
+                    .iter()
+                    .map(|ty| format!("{}, ", wasm_type(*ty)))
+                    .collect::<String>();
+                let result_tys = sig
+                    .results
+                    .iter()
+                    .map(|ty| format!("{}, ", wasm_type(*ty)))
+                    .collect::<String>();
+                uwriteln!(
+                    self.src,
+                    "let func{tmp} = instance.get_typed_func::<({params}), ({result_tys})>(&mut {store}, \"{}\")?;",
+                    self.callee
+                );
+                if !sig.results.is_empty() {
+                    self.push_str("let (");
+                    for (i, ty) in sig.results.iter().enumerate() {
+                        let ret = format!("ret{tmp}_{i}");
+                        uwrite!(self.src, "{ret}, ");
+                        results.push(ret.clone());
+                        self.wasm_results.push((ret, *ty));
+                    }
+                    self.push_str(") = ");
+                }
+                let args = operands
+                    .iter()
+                    .map(|op| format!("{op}, "))
+                    .collect::<String>();
+                uwriteln!(self.src, "func{tmp}.call(&mut {store}, ({args}))?;");
+            }
+
+            Instruction::CallInterface { func, .. } => {
+                // Arguments are bound first since lifting them may borrow the
+                // store which the host's state is borrowed from.
+                let mut args = String::new();
+                for (i, operand) in operands.iter().enumerate() {
+                    uwriteln!(self.src, "let param{i} = {operand};");
+                    uwrite!(args, ", param{i}");
+                }
+                self.let_results(func.results.len(), results);
+                uwriteln!(self.src, "{}(get({store}.data_mut()){args})?;", self.callee);
+            }
+
+            Instruction::Return { amt, .. } => {
+                let ret = match amt {
+                    0 => "()".to_string(),
+                    1 => operands[0].clone(),
+                    _ => format!("({})", operands.join(", ")),
+                };
+                match self.post_return.take() {
+                    Some(post_return) => {
+                        uwriteln!(self.src, "let ret = {ret};");
+                        let tys = self
+                            .wasm_results
+                            .iter()
+                            .map(|(_, ty)| format!("{}, ", wasm_type(*ty)))
+                            .collect::<String>();
+                        let args = self
+                            .wasm_results
+                            .iter()
+                            .map(|(name, _)| format!("{name}, "))
+                            .collect::<String>();
+                        uwriteln!(
+                            self.src,
+                            "let post_return = instance.get_typed_func::<({tys}), ()>(&mut {store}, \"{post_return}\")?;"
+                        );
+                        uwriteln!(self.src, "post_return.call(&mut {store}, ({args}))?;");
+                        uwriteln!(self.src, "Ok(ret)");
+                    }
+                    None => uwriteln!(self.src, "Ok({ret})"),
+                }
+            }
+
+            Instruction::I32Load { offset }
+            | Instruction::PointerLoad { offset }
+            | Instruction::LengthLoad { offset } => {
+                results.push(self.load("i32", "i32", *offset, operands));
+            }
+            Instruction::I32Load8U { offset } => {
+                results.push(self.load("u8", "i32", *offset, operands));
+            }
+            Instruction::I32Load8S { offset } => {
+                results.push(self.load("i8", "i32", *offset, operands));
+            }
+            Instruction::I32Load16U { offset } => {
+                results.push(self.load("u16", "i32", *offset, operands));
+            }
+            Instruction::I32Load16S { offset } => {
+                results.push(self.load("i16", "i32", *offset, operands));
+            }
+            Instruction::I64Load { offset } => {
+                results.push(self.load("i64", "i64", *offset, operands));
+            }
+            Instruction::F32Load { offset } => {
+                results.push(self.load("f32", "f32", *offset, operands));
+            }
+            Instruction::F64Load { offset } => {
+                results.push(self.load("f64", "f64", *offset, operands));
+            }
+
+            Instruction::I32Store { offset }
+            | Instruction::I64Store { offset }
+            | Instruction::F32Store { offset }
+            | Instruction::F64Store { offset }
+            | Instruction::PointerStore { offset }
+            | Instruction::LengthStore { offset } => {
+                self.store(None, *offset, operands);
+            }
+            Instruction::I32Store8 { offset } => {
+                self.store(Some("u8"), *offset, operands);
+            }
+            Instruction::I32Store16 { offset } => {
+                self.store(Some("u16"), *offset, operands);
+            }
+
+            Instruction::GuestDeallocate { .. }
+            | Instruction::GuestDeallocateString
+            | Instruction::GuestDeallocateVariant { .. }
+            | Instruction::GuestDeallocateList { .. } => {
+                unreachable!("hosts never deallocate guest memory")
+            }
+        }
+    }
+}
+
+/// Returns the Rust type of primitives which are stored in linear memory as
+/// is, making lists of them canonical.
+fn primitive(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::U8 => Some("u8"),
+        Type::S8 => Some("i8"),
+        Type::U16 => Some("u16"),
+        Type::S16 => Some("i16"),
+        Type::U32 => Some("u32"),
+        Type::S32 => Some("i32"),
+        Type::U64 => Some("u64"),
+        Type::S64 => Some("i64"),
+        Type::F32 => Some("f32"),
+        Type::F64 => Some("f64"),
+        _ => None,
+    }
+}
@@ -0,0 +1,619 @@
+use crate::bindgen::FunctionBindgen;
+use crate::{to_upper_camel_case, wasm_type, world_struct_name, HostRust, Ownership};
+use heck::*;
+use std::fmt::Write as _;
+use std::mem;
+use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
+use wit_bindgen_core::{uwrite, uwriteln, wit_parser::*, Source, TypeInfo};
+use wit_bindgen_rust::common::{
+    compute_module_path, group_by_resource, Identifier, InterfaceNames, RustGenerator, TypeMode,
+    TypeOwnershipStyle,
+};
+use wit_bindgen_rust::to_rust_ident;
+
+pub struct InterfaceGenerator<'a> {
+    pub src: Source,
+    pub(super) identifier: Identifier<'a>,
+    pub in_import: bool,
+    pub sizes: SizeAlign,
+    pub(super) gen: &'a mut HostRust,
+    pub resolve: &'a Resolve,
+    pub(super) needs_runtime_module: bool,
+}
+
+impl InterfaceGenerator<'_> {
+    /// Generates the `Host` traits for the functions and resources imported
+    /// through `module`, along with the function adding them to a linker.
+    pub(super) fn generate_imports<'a>(
+        &mut self,
+        module: &str,
+        trait_name: &str,
+        funcs: impl Iterator<Item = &'a Function>,
+    ) {
+        let by_resource = group_by_resource(funcs);
+        let resources = self.resources();
+
+        let mut supertraits = Vec::new();
+        for (name, id) in resources.iter() {
+            let rt = self.path_to_rt();
+            let camel = to_upper_camel_case(name);
+            uwriteln!(self.src, "pub trait Host{camel} {{");
+            for func in by_resource.get(&Some(*id)).into_iter().flatten() {
+                self.print_host_method(func);
+            }
+            uwriteln!(
+                self.src,
+                "fn drop(&mut self, rep: {rt}::Resource<{camel}>) -> wasmtime::Result<()>;"
+            );
+            uwriteln!(self.src, "}}");
+            supertraits.push(format!("Host{camel}"));
+        }
+
+        uwrite!(self.src, "pub trait {trait_name}");
+        if !supertraits.is_empty() {
+            uwrite!(self.src, ": {}", supertraits.join(" + "));
+        }
+        uwriteln!(self.src, " {{");
+        for func in by_resource.get(&None).into_iter().flatten() {
+            self.print_host_method(func);
+        }
+        uwriteln!(self.src, "}}");
+
+        let fn_name = match self.identifier {
+            Identifier::Interface(..) => "add_to_linker",
+            Identifier::World(world) => {
+                let camel = world_struct_name(self.resolve, world);
+                uwriteln!(self.src, "impl {camel} {{");
+                "add_root_to_linker"
+            }
+        };
+        uwriteln!(
+            self.src,
+            "
+                pub fn {fn_name}<T, U>(
+                    linker: &mut wasmtime::Linker<T>,
+                    get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                    where U: {trait_name},
+                {{
+            "
+        );
+        for (name, _) in resources.iter() {
+            let rt = self.path_to_rt();
+            let camel = to_upper_camel_case(name);
+            uwriteln!(
+                self.src,
+                "
+                    linker.func_wrap(
+                        \"{module}\",
+                        \"[resource-drop]{name}\",
+                        move |mut caller: wasmtime::Caller<'_, T>, arg0: i32| -> wasmtime::Result<()> {{
+                            Host{camel}::drop(get(caller.data_mut()), {rt}::Resource::new(arg0 as u32))
+                        }},
+                    )?;
+                "
+            );
+        }
+        let mut any_funcs = false;
+        for funcs in by_resource.values() {
+            for func in funcs {
+                any_funcs = true;
+                self.generate_host_import(module, trait_name, func);
+            }
+        }
+        if resources.is_empty() && !any_funcs {
+            uwriteln!(self.src, "let _ = (linker, get);");
+        }
+        uwriteln!(self.src, "Ok(())");
+        uwriteln!(self.src, "}}");
+        if let Identifier::World(_) = self.identifier {
+            uwriteln!(self.src, "}}");
+        }
+    }
+
+    /// Generates wrappers calling the functions exported by the guest.
+    ///
+    /// Exports of an interface, identified by `module`, are methods of a
+    /// `Guest` struct while exports of the world itself are methods of the
+    /// world's struct.
+    pub(super) fn generate_exports<'a>(
+        &mut self,
+        module: Option<&str>,
+        funcs: impl Iterator<Item = &'a Function>,
+    ) {
+        if module.is_some() {
+            uwriteln!(
+                self.src,
+                "
+                    /// The functions exported by the guest for this interface.
+                    #[derive(Clone, Copy)]
+                    pub struct Guest {{
+                        instance: wasmtime::Instance,
+                    }}
+
+                    impl Guest {{
+                        pub fn new(instance: wasmtime::Instance) -> Self {{
+                            Self {{ instance }}
+                        }}
+                "
+            );
+        }
+
+        for func in funcs {
+            self.generate_host_export(module, func);
+        }
+
+        let module = match module {
+            Some(module) => module,
+            None => return,
+        };
+        let resources = self.resources();
+        for (name, _) in resources.iter() {
+            let rt = self.path_to_rt();
+            let camel = to_upper_camel_case(name);
+            let snake = to_rust_ident(name);
+            uwriteln!(
+                self.src,
+                "
+                    /// Drops the resource `rep` by calling its destructor in the guest.
+                    pub fn drop_{snake}(
+                        &self,
+                        mut store: impl wasmtime::AsContextMut,
+                        rep: {rt}::Resource<{camel}>,
+                    ) -> wasmtime::Result<()> {{
+                        let func = self.instance.get_typed_func::<(i32,), ()>(
+                            &mut store,
+                            \"{module}#[dtor]{name}\",
+                        )?;
+                        func.call(&mut store, (rep.rep() as i32,))
+                    }}
+                "
+            );
+        }
+        uwriteln!(self.src, "}}");
+
+        if resources.is_empty() {
+            return;
+        }
+
+        // A resource's handle is its representation, so the intrinsics the
+        // guest imports for its own resources are trivial except for drops
+        // which run the destructor exported by the guest.
+        uwriteln!(
+            self.src,
+            "
+                pub fn add_resource_intrinsics_to_linker<T>(
+                    linker: &mut wasmtime::Linker<T>,
+                ) -> wasmtime::Result<()> {{
+            "
+        );
+        for (name, _) in resources.iter() {
+            let rt = self.path_to_rt();
+            uwriteln!(
+                self.src,
+                "
+                    linker.func_wrap(\"[export]{module}\", \"[resource-new]{name}\", |rep: i32| rep)?;
+                    linker.func_wrap(\"[export]{module}\", \"[resource-rep]{name}\", |handle: i32| handle)?;
+                    linker.func_wrap(
+                        \"[export]{module}\",
+                        \"[resource-drop]{name}\",
+                        |mut caller: wasmtime::Caller<'_, T>, handle: i32| {{
+                            {rt}::call_dtor(&mut caller, \"{module}#[dtor]{name}\", handle)
+                        }},
+                    )?;
+                "
+            );
+        }
+        uwriteln!(self.src, "Ok(())");
+        uwriteln!(self.src, "}}");
+    }
+
+    /// Returns the resources defined by the interface or world being
+    /// generated.
+    fn resources(&self) -> Vec<(String, TypeId)> {
+        let types: Vec<(String, TypeId)> = match self.identifier {
+            Identifier::Interface(id, _) => self.resolve.interfaces[id]
+                .types
+                .iter()
+                .map(|(name, id)| (name.clone(), *id))
+                .collect(),
+            Identifier::World(world) => self.resolve.worlds[world]
+                .imports
+                .iter()
+                .filter_map(|(key, item)| match (key, item) {
+                    (WorldKey::Name(name), WorldItem::Type(id)) => Some((name.clone(), *id)),
+                    _ => None,
+                })
+                .collect(),
+        };
+        types
+            .into_iter()
+            .filter(|(_, id)| matches!(self.resolve.types[*id].kind, TypeDefKind::Resource))
+            .collect()
+    }
+
+    pub fn finish(&mut self) -> String {
+        let src = mem::take(&mut self.src).into();
+
+        if self.needs_runtime_module {
+            let root = self.path_to_root();
+            if !root.is_empty() {
+                return format!("use {root}_rt;\n{src}");
+            }
+        }
+        src
+    }
+
+    pub fn start_append_submodule(&mut self, name: &WorldKey) -> (String, Vec<String>) {
+        let snake = match name {
+            WorldKey::Name(name) => to_rust_ident(name),
+            WorldKey::Interface(id) => {
+                to_rust_ident(self.resolve.interfaces[*id].name.as_ref().unwrap())
+            }
+        };
+        let module_path = compute_module_path(name, self.resolve, !self.in_import);
+        (snake, module_path)
+    }
+
+    pub fn finish_append_submodule(mut self, snake: &str, module_path: Vec<String>) {
+        let module = self.finish();
+        let module = format!(
+            "\
+                #[allow(dead_code, clippy::all)]
+                pub mod {snake} {{
+                    {module}
+                }}
+",
+        );
+        let map = if self.in_import {
+            &mut self.gen.import_modules
+        } else {
+            &mut self.gen.export_modules
+        };
+        map.push((module, module_path))
+    }
+
+    /// Returns the name of the host trait method implementing `func`.
+    fn host_method_name(func: &Function) -> String {
+        match &func.kind {
+            FunctionKind::Freestanding => to_rust_ident(&func.name),
+            FunctionKind::Constructor(_) => "new".to_string(),
+            FunctionKind::Method(_) | FunctionKind::Static(_) => to_rust_ident(func.item_name()),
+        }
+    }
+
+    /// Prints the declaration of the host trait method implementing `func`.
+    fn print_host_method(&mut self, func: &Function) {
+        self.rustdoc(&func.docs);
+        uwrite!(self.src, "fn {}(&mut self,", Self::host_method_name(func));
+        for (name, param) in func.params.iter() {
+            uwrite!(self.src, "{}: ", to_rust_ident(name));
+            self.print_ty(param, TypeMode::owned());
+            self.push_str(",");
+        }
+        self.push_str(") -> wasmtime::Result<");
+        self.print_results(&func.results);
+        self.push_str(">;\n");
+    }
+
+    /// Generates the registration of `func` with a linker, lifting its
+    /// arguments out of the guest and lowering the host's results back in.
+    fn generate_host_import(&mut self, module: &str, trait_name: &str, func: &Function) {
+        let sig = self.resolve.wasm_signature(AbiVariant::GuestImport, func);
+        let host_trait = match &func.kind {
+            FunctionKind::Freestanding => trait_name.to_string(),
+            FunctionKind::Method(id) | FunctionKind::Static(id) | FunctionKind::Constructor(id) => {
+                format!("Host{}", to_upper_camel_case(&self.type_name(*id)))
+            }
+        };
+        let callee = format!("{host_trait}::{}", Self::host_method_name(func));
+        let params = (0..sig.params.len()).map(|i| format!("arg{i}")).collect();
+
+        let mut f = FunctionBindgen::new(self, params, "caller", callee);
+        abi::call(
+            f.gen.resolve,
+            AbiVariant::GuestImport,
+            LiftLower::LiftArgsLowerResults,
+            func,
+            &mut f,
+        );
+        let FunctionBindgen {
+            src,
+            needs_memory,
+            needs_realloc,
+            ..
+        } = f;
+
+        uwrite!(
+            self.src,
+            "linker.func_wrap(\"{module}\", \"{}\", move |mut caller: wasmtime::Caller<'_, T>",
+            func.name
+        );
+        for (i, ty) in sig.params.iter().enumerate() {
+            uwrite!(self.src, ", arg{i}: {}", wasm_type(*ty));
+        }
+        let ret = match sig.results.as_slice() {
+            [] => "()",
+            [ty] => wasm_type(*ty),
+            // Results which don't fit in a single core wasm value are
+            // returned through memory instead.
+            _ => unreachable!("core wasm signatures have at most one result"),
+        };
+        uwriteln!(self.src, "| -> wasmtime::Result<{ret}> {{");
+        if needs_memory {
+            let rt = self.path_to_rt();
+            uwriteln!(self.src, "let memory = {rt}::memory(&mut caller)?;");
+        }
+        if needs_realloc {
+            let rt = self.path_to_rt();
+            uwriteln!(self.src, "let realloc = {rt}::realloc(&mut caller)?;");
+        }
+        self.src.push_str(&src);
+        uwriteln!(self.src, "}})?;");
+    }
+
+    /// Generates a method calling the guest's export of `func`, lowering its
+    /// arguments into the guest and lifting the results back out.
+    fn generate_host_export(&mut self, module: Option<&str>, func: &Function) {
+        let name = match &func.kind {
+            FunctionKind::Freestanding => format!("call_{}", func.name.to_snake_case()),
+            FunctionKind::Constructor(id) => {
+                format!("call_{}_constructor", self.type_name(*id).to_snake_case())
+            }
+            FunctionKind::Method(id) | FunctionKind::Static(id) => format!(
+                "call_{}_{}",
+                self.type_name(*id).to_snake_case(),
+                func.item_name().to_snake_case()
+            ),
+        };
+
+        self.rustdoc(&func.docs);
+        uwrite!(
+            self.src,
+            "pub fn {name}(&self, mut store: impl wasmtime::AsContextMut,"
+        );
+        let params = self.print_export_params(func);
+        self.push_str(") -> wasmtime::Result<");
+        self.print_results(&func.results);
+        self.push_str("> {\n");
+
+        let callee = func.core_export_name(module).into_owned();
+        let post_return = if abi::guest_export_needs_post_return(self.resolve, func) {
+            Some(format!("cabi_post_{callee}"))
+        } else {
+            None
+        };
+        let mut f = FunctionBindgen::new(self, params, "store", callee);
+        f.post_return = post_return;
+        abi::call(
+            f.gen.resolve,
+            AbiVariant::GuestExport,
+            LiftLower::LowerArgsLiftResults,
+            func,
+            &mut f,
+        );
+        let FunctionBindgen {
+            src,
+            needs_memory,
+            needs_realloc,
+            ..
+        } = f;
+
+        uwriteln!(self.src, "let instance = self.instance;");
+        if needs_memory {
+            let rt = self.path_to_rt();
+            uwriteln!(
+                self.src,
+                "let memory = {rt}::instance_memory(&mut store, &instance)?;"
+            );
+        }
+        if needs_realloc {
+            let rt = self.path_to_rt();
+            uwriteln!(
+                self.src,
+                "let realloc = {rt}::instance_realloc(&mut store, &instance)?;"
+            );
+        }
+        self.src.push_str(&src);
+        self.push_str("}\n");
+    }
+
+    /// Prints the parameters of a call to an export, returning the
+    /// expressions the lowering code uses for each parameter.
+    fn print_export_params(&mut self, func: &Function) -> Vec<String> {
+        let mut params = Vec::new();
+        for (name, param) in func.params.iter() {
+            let name = to_rust_ident(name);
+            self.push_str(&name);
+            self.push_str(": ");
+
+            // Parameters of exports are only read by the host while they're
+            // lowered into the guest, so they're borrowed according to the
+            // configured `ownership`, as the parameters of imports are in the
+            // guest Rust generator.
+            let style = match self.gen.opts.ownership {
+                Ownership::Owning => TypeOwnershipStyle::OnlyTopBorrowed,
+                Ownership::Borrowing { .. } => TypeOwnershipStyle::Borrowed,
+            };
+            let mode = self.type_mode_for(param, style, "'_");
+            self.print_ty(param, mode);
+            self.push_str(",");
+
+            // If a borrowed mode was requested but an owned one was selected
+            // then the argument is lowered through a borrow anyway, keeping the
+            // lowering code the same for both.
+            if mode.style == style {
+                params.push(name);
+            } else {
+                params.push(format!("&{name}"));
+            }
+        }
+        params
+    }
+
+    fn print_results(&mut self, results: &Results) {
+        match results.len() {
+            0 => self.push_str("()"),
+            1 => {
+                let ty = results.iter_types().next().unwrap();
+                self.print_ty(ty, TypeMode::owned());
+            }
+            _ => {
+                self.push_str("(");
+                for ty in results.iter_types() {
+                    self.print_ty(ty, TypeMode::owned());
+                    self.push_str(", ")
+                }
+                self.push_str(")")
+            }
+        }
+    }
+
+    /// Returns the path to the runtime module of the generated bindings.
+    pub fn path_to_rt(&mut self) -> &'static str {
+        self.needs_runtime_module = true;
+        "_rt"
+    }
+}
+
+impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn info(&self, ty: TypeId) -> TypeInfo {
+        self.gen.types.get(ty)
+    }
+
+    fn identifier(&self) -> Identifier<'a> {
+        self.identifier
+    }
+
+    fn in_import(&self) -> bool {
+        self.in_import
+    }
+
+    fn interface_names(&self) -> &InterfaceNames {
+        &self.gen.interface_names
+    }
+
+    fn ownership(&self) -> Ownership {
+        self.gen.opts.ownership
+    }
+
+    fn additional_derives(&self) -> &[String] {
+        &self.gen.opts.additional_derive_attributes
+    }
+
+    fn std_feature(&self) -> bool {
+        false
+    }
+
+    fn raw_strings(&self) -> bool {
+        false
+    }
+
+    /// Handles are always plain `Resource<T>` values on the host, so only
+    /// lists introduce lifetime parameters.
+    fn borrow_handles_need_lifetime(&self) -> bool {
+        false
+    }
+
+    fn upper_camel_case(&self, name: &str) -> String {
+        to_upper_camel_case(name)
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.src.push_str(s);
+    }
+
+    fn push_vec_name(&mut self) {
+        self.push_str("Vec");
+    }
+
+    fn push_string_name(&mut self) {
+        self.push_str("String");
+    }
+
+    fn print_handle(&mut self, handle: &Handle, _mode: TypeMode) {
+        let (Handle::Own(ty) | Handle::Borrow(ty)) = handle;
+        let rt = self.path_to_rt();
+        self.push_str(&format!("{rt}::Resource<"));
+        self.print_ty(&Type::Id(*ty), TypeMode::owned());
+        self.push_str(">");
+    }
+
+    // Futures and streams are passed through as their raw handles.
+    fn print_future(&mut self, _ty: Option<&Type>) {
+        self.push_str("u32");
+    }
+
+    fn print_stream(&mut self, _stream: &Stream) {
+        self.push_str("u32");
+    }
+}
+
+impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
+        self.print_typedef_record(id, record, docs);
+    }
+
+    fn type_resource(&mut self, _id: TypeId, name: &str, docs: &Docs) {
+        // Resources are only ever referred to through `Resource<T>` handles,
+        // so their type is an uninhabited marker.
+        self.rustdoc(docs);
+        let camel = to_upper_camel_case(name);
+        uwriteln!(self.src, "pub enum {camel} {{}}");
+    }
+
+    fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
+        self.print_typedef_tuple(id, tuple, docs);
+    }
+
+    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let name = to_upper_camel_case(name);
+        self.print_flags_words(&name, flags, docs, flags.repr().count());
+    }
+
+    fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
+        self.print_typedef_variant(id, variant, docs);
+    }
+
+    fn type_option(&mut self, id: TypeId, _name: &str, payload: &Type, docs: &Docs) {
+        self.print_typedef_option(id, payload, docs);
+    }
+
+    fn type_result(&mut self, id: TypeId, _name: &str, result: &Result_, docs: &Docs) {
+        self.print_typedef_result(id, result, docs);
+    }
+
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        self.print_typedef_enum(id, name, enum_, docs);
+    }
+
+    fn type_alias(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_alias(id, ty, docs);
+    }
+
+    fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_list(id, ty, docs);
+    }
+
+    fn type_builtin(&mut self, _id: TypeId, name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_builtin(name, ty, docs);
+    }
+
+    fn type_future(&mut self, _id: TypeId, name: &str, _ty: &Option<Type>, docs: &Docs) {
+        self.rustdoc(docs);
+        uwriteln!(self.src, "pub type {} = u32;", name.to_upper_camel_case());
+    }
+
+    fn type_stream(&mut self, _id: TypeId, name: &str, _stream: &Stream, docs: &Docs) {
+        self.rustdoc(docs);
+        uwriteln!(self.src, "pub type {} = u32;", name.to_upper_camel_case());
+    }
+}
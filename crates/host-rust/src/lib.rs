@@ -0,0 +1,812 @@
+use crate::interface::InterfaceGenerator;
+use anyhow::Result;
+use heck::*;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::mem;
+use std::process::{Command, Stdio};
+use wit_bindgen_core::abi::{Bitcast, WasmType};
+use wit_bindgen_core::{
+    uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Source, Types, WorldGenerator,
+};
+use wit_bindgen_rust::common::{emit_modules, Identifier, InterfaceNames};
+use wit_bindgen_rust::to_rust_ident;
+pub use wit_bindgen_rust::Ownership;
+
+mod bindgen;
+mod interface;
+
+#[derive(Default)]
+struct HostRust {
+    types: Types,
+    src: Source,
+    opts: Opts,
+    import_modules: Vec<(String, Vec<String>)>,
+    export_modules: Vec<(String, Vec<String>)>,
+    interface_names: InterfaceNames,
+    import_funcs_called: bool,
+
+    /// Paths to the modules of imported interfaces, each of which has a
+    /// `Host` trait and an `add_to_linker` function.
+    imported_interfaces: Vec<String>,
+    /// Whether the world imports functions or resources of its own, which
+    /// are provided through a `{World}Imports` trait.
+    has_root_imports: bool,
+    /// Names and module paths of exported interfaces, each of which has a
+    /// `Guest` struct.
+    exported_interfaces: Vec<(String, String)>,
+    /// Module paths of exported interfaces which define resources, each of
+    /// which has an `add_resource_intrinsics_to_linker` function.
+    exported_resource_interfaces: Vec<String>,
+    /// Methods of the world's struct which call functions exported directly
+    /// from the world.
+    world_exports: Source,
+}
+
+#[cfg(feature = "clap")]
+fn parse_with(s: &str) -> Result<(String, String), String> {
+    let (k, v) = s.split_once('=').ok_or_else(|| {
+        format!("expected string of form `<key>=<value>[,<key>=<value>...]`; got `{s}`")
+    })?;
+    Ok((k.to_string(), v.to_string()))
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Whether or not `rustfmt` is executed to format generated code.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub rustfmt: bool,
+
+    /// Whether to generate owning or borrowing type definitions for the
+    /// parameters of exported functions, which the host passes to the guest.
+    ///
+    /// Valid values are the same as those of the guest Rust generator:
+    /// `owning`, `borrowing`, and `borrowing-duplicate-if-necessary`.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = Ownership::Owning))]
+    pub ownership: Ownership,
+
+    /// Additional derive attributes to add to generated types. If using in a CLI, this flag can be
+    /// specified multiple times to add multiple attributes.
+    ///
+    /// These derive attributes will be added to any generated structs or enums
+    #[cfg_attr(feature = "clap", arg(long = "additional_derive_attribute", short = 'd', default_values_t = Vec::<String>::new()))]
+    pub additional_derive_attributes: Vec<String>,
+
+    /// Remapping of interface names to rust module names.
+    ///
+    /// Argument must be of the form `k=v` and this option can be passed
+    /// multiple times or one option can be comma separated, for example
+    /// `k1=v1,k2=v2`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_with, value_delimiter = ','))]
+    pub with: Vec<(String, String)>,
+}
+
+impl Opts {
+    pub fn build(self) -> Box<dyn WorldGenerator> {
+        let mut r = HostRust::new();
+        r.opts = self;
+        Box::new(r)
+    }
+}
+
+impl HostRust {
+    fn new() -> HostRust {
+        HostRust::default()
+    }
+
+    fn interface<'a>(
+        &'a mut self,
+        identifier: Identifier<'a>,
+        resolve: &'a Resolve,
+        in_import: bool,
+    ) -> InterfaceGenerator<'a> {
+        let mut sizes = SizeAlign::default();
+        sizes.fill(resolve);
+
+        InterfaceGenerator {
+            identifier,
+            src: Source::default(),
+            in_import,
+            gen: self,
+            sizes,
+            resolve,
+            needs_runtime_module: false,
+        }
+    }
+
+    /// Generates the struct representing an instance of the world, which is
+    /// the entry point for linking and instantiating a module.
+    fn finish_world_struct(&mut self, resolve: &Resolve, world: WorldId) {
+        let world_name = &resolve.worlds[world].name;
+        let camel = world_struct_name(resolve, world);
+
+        let mut bounds = self
+            .imported_interfaces
+            .iter()
+            .map(|path| format!("{path}::Host"))
+            .collect::<Vec<_>>();
+        if self.has_root_imports {
+            bounds.push(format!("{}Imports", to_upper_camel_case(world_name)));
+        }
+
+        uwriteln!(
+            self.src,
+            "
+                /// An instance of the `{world_name}` world, created from a
+                /// core wasm module instance.
+                #[derive(Clone, Copy)]
+                pub struct {camel} {{
+                    instance: wasmtime::Instance,
+                }}
+
+                impl {camel} {{
+                    /// Adds the imports of this world, along with the
+                    /// intrinsics of its exported resources, to `linker`.
+                    ///
+                    /// The `get` closure projects the host state `U`
+                    /// implementing the imports out of the store's data.
+                    pub fn add_to_linker<T, U>(
+                        linker: &mut wasmtime::Linker<T>,
+                        get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                    ) -> wasmtime::Result<()>
+            "
+        );
+        if !bounds.is_empty() {
+            uwriteln!(self.src, "where U: {},", bounds.join(" + "));
+        }
+        uwriteln!(self.src, "{{");
+        for path in self.imported_interfaces.iter() {
+            uwriteln!(self.src, "{path}::add_to_linker(linker, get)?;");
+        }
+        if self.has_root_imports {
+            uwriteln!(self.src, "Self::add_root_to_linker(linker, get)?;");
+        }
+        if bounds.is_empty() && self.exported_resource_interfaces.is_empty() {
+            uwriteln!(self.src, "let _ = (linker, get);");
+        } else if bounds.is_empty() {
+            uwriteln!(self.src, "let _ = get;");
+        }
+        for path in self.exported_resource_interfaces.iter() {
+            uwriteln!(
+                self.src,
+                "{path}::add_resource_intrinsics_to_linker(linker)?;"
+            );
+        }
+        uwriteln!(
+            self.src,
+            "
+                    Ok(())
+                }}
+
+                /// Instantiates `module` with the definitions in `linker`.
+                pub fn instantiate<T>(
+                    mut store: impl wasmtime::AsContextMut<Data = T>,
+                    module: &wasmtime::Module,
+                    linker: &wasmtime::Linker<T>,
+                ) -> wasmtime::Result<Self> {{
+                    let instance = linker.instantiate(&mut store, module)?;
+                    Ok(Self::new(instance))
+                }}
+
+                /// Wraps an already instantiated module.
+                pub fn new(instance: wasmtime::Instance) -> Self {{
+                    Self {{ instance }}
+                }}
+
+                /// Returns the underlying core wasm instance.
+                pub fn instance(&self) -> wasmtime::Instance {{
+                    self.instance
+                }}
+            "
+        );
+        for (name, path) in self.exported_interfaces.iter() {
+            uwriteln!(
+                self.src,
+                "
+                    pub fn {name}(&self) -> {path}::Guest {{
+                        {path}::Guest::new(self.instance)
+                    }}
+                "
+            );
+        }
+        let world_exports = mem::take(&mut self.world_exports);
+        self.src.push_str(&world_exports);
+        uwriteln!(self.src, "}}");
+    }
+}
+
+impl WorldGenerator for HostRust {
+    fn supports_async_types(&self) -> bool {
+        true
+    }
+
+    fn preprocess(&mut self, resolve: &Resolve, _world: WorldId) {
+        wit_bindgen_core::generated_preamble(&mut self.src, env!("CARGO_PKG_VERSION"));
+
+        // Render some generator options to assist with debugging and/or to help
+        // recreate it if the original generation command is lost.
+        let mut options = Vec::new();
+        if !matches!(self.opts.ownership, Ownership::Owning) {
+            options.push(format!("ownership: {:?}", self.opts.ownership));
+        }
+        if !self.opts.additional_derive_attributes.is_empty() {
+            options.push(format!(
+                "additional derives {:?}",
+                self.opts.additional_derive_attributes
+            ));
+        }
+        for (k, v) in self.opts.with.iter() {
+            options.push(format!("with {k:?} = {v:?}"));
+        }
+        if !options.is_empty() {
+            uwriteln!(self.src, "// Options used:");
+            for option in options {
+                uwriteln!(self.src, "//   * {option}");
+            }
+        }
+
+        // Type information is computed from the guest's point of view, where
+        // "borrowed" types are the parameters the guest lowers. On the host
+        // it's the other way around, so analyze a copy of the worlds with
+        // their imports and exports swapped.
+        let mut mirrored = resolve.clone();
+        for (_, world) in mirrored.worlds.iter_mut() {
+            mem::swap(&mut world.imports, &mut world.exports);
+        }
+        self.types.analyze(&mirrored);
+
+        for (k, v) in self.opts.with.iter() {
+            self.interface_names.remap(k, v);
+        }
+    }
+
+    fn import_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) {
+        let mut gen = self.interface(Identifier::Interface(id, name), resolve, true);
+        let (snake, module_path) = gen.start_append_submodule(name);
+        let remapped =
+            gen.gen
+                .interface_names
+                .name_interface(&mut gen.gen.src, resolve, id, name, false);
+        let path = gen.gen.interface_names[&id].path.clone();
+        gen.gen.imported_interfaces.push(path);
+        if remapped {
+            return;
+        }
+        gen.types(id);
+
+        let module = resolve.name_world_key(name);
+        gen.generate_imports(&module, "Host", resolve.interfaces[id].functions.values());
+
+        gen.finish_append_submodule(&snake, module_path);
+    }
+
+    fn import_funcs(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) {
+        self.import_funcs_called = true;
+
+        let has_resources = resolve.worlds[world].imports.values().any(|item| {
+            matches!(item, WorldItem::Type(id) if matches!(resolve.types[*id].kind, TypeDefKind::Resource))
+        });
+        if funcs.is_empty() && !has_resources {
+            return;
+        }
+        self.has_root_imports = true;
+
+        let trait_name = format!(
+            "{}Imports",
+            to_upper_camel_case(&resolve.worlds[world].name)
+        );
+        let mut gen = self.interface(Identifier::World(world), resolve, true);
+        gen.generate_imports("$root", &trait_name, funcs.iter().map(|(_, func)| *func));
+
+        let src = gen.finish();
+        self.src.push_str(&src);
+    }
+
+    fn export_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<()> {
+        let mut gen = self.interface(Identifier::Interface(id, name), resolve, false);
+        let (snake, module_path) = gen.start_append_submodule(name);
+        let remapped =
+            gen.gen
+                .interface_names
+                .name_interface(&mut gen.gen.src, resolve, id, name, true);
+        let accessor = match name {
+            WorldKey::Name(name) => to_rust_ident(name),
+            WorldKey::Interface(_) => module_path[1..].join("_"),
+        };
+        let path = gen.gen.interface_names[&id].path.clone();
+        let has_resources = resolve.interfaces[id]
+            .types
+            .values()
+            .any(|ty| matches!(resolve.types[*ty].kind, TypeDefKind::Resource));
+        if has_resources {
+            gen.gen.exported_resource_interfaces.push(path.clone());
+        }
+        gen.gen.exported_interfaces.push((accessor, path));
+        if remapped {
+            return Ok(());
+        }
+        gen.types(id);
+
+        let module = resolve.name_world_key(name);
+        gen.generate_exports(Some(&module), resolve.interfaces[id].functions.values());
+
+        gen.finish_append_submodule(&snake, module_path);
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<()> {
+        let mut gen = self.interface(Identifier::World(world), resolve, false);
+        gen.generate_exports(None, funcs.iter().map(|f| f.1));
+        let src = gen.finish();
+        self.world_exports.push_str(&src);
+        Ok(())
+    }
+
+    fn import_types(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) {
+        let mut gen = self.interface(Identifier::World(world), resolve, true);
+        for (name, ty) in types {
+            gen.define_type(name, *ty);
+        }
+        let src = gen.finish();
+        self.src.push_str(&src);
+    }
+
+    fn finish_imports(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) {
+        if !self.import_funcs_called {
+            // We call `import_funcs` even if the world doesn't import any
+            // functions since one of the side effects of that method is to
+            // generate a trait for any imported resources.
+            self.import_funcs(resolve, world, &[], files);
+        }
+    }
+
+    fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()> {
+        let name = &resolve.worlds[world].name;
+
+        self.finish_world_struct(resolve, world);
+
+        let imports = mem::take(&mut self.import_modules);
+        emit_modules(&mut self.src, imports);
+        let exports = mem::take(&mut self.export_modules);
+        emit_modules(&mut self.src, exports);
+
+        self.src.push_str("pub use _rt::Resource;\n");
+        self.src.push_str(RUNTIME_MODULE);
+
+        let mut src = mem::take(&mut self.src);
+        if self.opts.rustfmt {
+            let mut child = Command::new("rustfmt")
+                .arg("--edition=2018")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("failed to spawn `rustfmt`");
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(src.as_bytes())
+                .unwrap();
+            src.as_mut_string().truncate(0);
+            child
+                .stdout
+                .take()
+                .unwrap()
+                .read_to_string(src.as_mut_string())
+                .unwrap();
+            let status = child.wait().unwrap();
+            assert!(status.success());
+        }
+
+        let module_name = name.to_snake_case();
+        files.push(&format!("{module_name}.rs"), src.as_bytes());
+
+        self.interface_names.check_unused_remappings()
+    }
+}
+
+/// Helpers used by the generated bindings to move values in and out of the
+/// linear memory of a guest.
+const RUNTIME_MODULE: &str = r#"
+#[allow(dead_code, clippy::all)]
+mod _rt {
+    use wasmtime::{AsContext, AsContextMut, Caller, Extern, Instance, Memory, TypedFunc};
+
+    /// The signature of the guest's `cabi_realloc` export.
+    pub type Realloc = TypedFunc<(i32, i32, i32, i32), i32>;
+
+    /// A handle to a resource, identified by its representation in the
+    /// module defining it.
+    pub struct Resource<T> {
+        rep: u32,
+        _marker: core::marker::PhantomData<fn() -> T>,
+    }
+
+    impl<T> Resource<T> {
+        pub fn new(rep: u32) -> Self {
+            Self {
+                rep,
+                _marker: core::marker::PhantomData,
+            }
+        }
+
+        pub fn rep(&self) -> u32 {
+            self.rep
+        }
+    }
+
+    impl<T> Clone for Resource<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for Resource<T> {}
+
+    impl<T> PartialEq for Resource<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.rep == other.rep
+        }
+    }
+
+    impl<T> Eq for Resource<T> {}
+
+    impl<T> core::hash::Hash for Resource<T> {
+        fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+            self.rep.hash(state)
+        }
+    }
+
+    impl<T> core::fmt::Debug for Resource<T> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("Resource").field("rep", &self.rep).finish()
+        }
+    }
+
+    pub fn memory<T>(caller: &mut Caller<'_, T>) -> wasmtime::Result<Memory> {
+        match caller.get_export("memory") {
+            Some(Extern::Memory(memory)) => Ok(memory),
+            _ => Err(wasmtime::Error::msg("guest does not export `memory`")),
+        }
+    }
+
+    pub fn realloc<T>(caller: &mut Caller<'_, T>) -> wasmtime::Result<Realloc> {
+        match caller.get_export("cabi_realloc") {
+            Some(Extern::Func(func)) => func.typed(&*caller),
+            _ => Err(wasmtime::Error::msg("guest does not export `cabi_realloc`")),
+        }
+    }
+
+    pub fn instance_memory(
+        store: impl AsContextMut,
+        instance: &Instance,
+    ) -> wasmtime::Result<Memory> {
+        instance
+            .get_memory(store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("guest does not export `memory`"))
+    }
+
+    pub fn instance_realloc(
+        store: impl AsContextMut,
+        instance: &Instance,
+    ) -> wasmtime::Result<Realloc> {
+        instance.get_typed_func(store, "cabi_realloc")
+    }
+
+    pub fn call_dtor<T>(caller: &mut Caller<'_, T>, name: &str, rep: i32) -> wasmtime::Result<()> {
+        let dtor = match caller.get_export(name) {
+            Some(Extern::Func(func)) => func.typed::<(i32,), ()>(&*caller)?,
+            _ => return Err(wasmtime::Error::msg(format!("guest does not export `{name}`"))),
+        };
+        dtor.call(caller, (rep,))
+    }
+
+    /// Primitive values stored in little-endian order in linear memory.
+    pub trait Le: Copy {
+        const SIZE: usize;
+        fn from_le(bytes: &[u8]) -> Self;
+        fn to_le(self, dst: &mut [u8]);
+    }
+
+    macro_rules! le {
+        ($($t:ident)*) => ($(
+            impl Le for $t {
+                const SIZE: usize = core::mem::size_of::<$t>();
+
+                fn from_le(bytes: &[u8]) -> Self {
+                    $t::from_le_bytes(bytes.try_into().unwrap())
+                }
+
+                fn to_le(self, dst: &mut [u8]) {
+                    dst.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*)
+    }
+
+    le!(u8 i8 u16 i16 u32 i32 u64 i64 f32 f64);
+
+    fn range(
+        ptr: i32,
+        offset: i32,
+        len: usize,
+        memory_len: usize,
+    ) -> wasmtime::Result<core::ops::Range<usize>> {
+        let start = (ptr as u32 as usize).checked_add(offset as u32 as usize);
+        match start.and_then(|start| Some(start..start.checked_add(len)?)) {
+            Some(range) if range.end <= memory_len => Ok(range),
+            _ => Err(wasmtime::Error::msg("pointer out of bounds of linear memory")),
+        }
+    }
+
+    pub fn load<V: Le>(
+        store: impl AsContext,
+        memory: Memory,
+        ptr: i32,
+        offset: i32,
+    ) -> wasmtime::Result<V> {
+        let data = memory.data(store.as_context());
+        let range = range(ptr, offset, V::SIZE, data.len())?;
+        Ok(V::from_le(&data[range]))
+    }
+
+    pub fn store<V: Le>(
+        mut store: impl AsContextMut,
+        memory: Memory,
+        ptr: i32,
+        offset: i32,
+        val: V,
+    ) -> wasmtime::Result<()> {
+        let data = memory.data_mut(store.as_context_mut());
+        let range = range(ptr, offset, V::SIZE, data.len())?;
+        val.to_le(&mut data[range]);
+        Ok(())
+    }
+
+    pub fn load_list<V: Le>(
+        store: impl AsContext,
+        memory: Memory,
+        ptr: i32,
+        len: i32,
+    ) -> wasmtime::Result<Vec<V>> {
+        let data = memory.data(store.as_context());
+        let size = (len as u32 as usize)
+            .checked_mul(V::SIZE)
+            .ok_or_else(|| wasmtime::Error::msg("list length overflow"))?;
+        let range = range(ptr, 0, size, data.len())?;
+        Ok(data[range].chunks_exact(V::SIZE).map(V::from_le).collect())
+    }
+
+    pub fn load_string(
+        store: impl AsContext,
+        memory: Memory,
+        ptr: i32,
+        len: i32,
+    ) -> wasmtime::Result<String> {
+        String::from_utf8(load_list(store, memory, ptr, len)?)
+            .map_err(|_| wasmtime::Error::msg("invalid utf-8 string"))
+    }
+
+    pub fn alloc(
+        mut store: impl AsContextMut,
+        realloc: &Realloc,
+        size: usize,
+        align: usize,
+    ) -> wasmtime::Result<i32> {
+        realloc.call(&mut store, (0, 0, align as i32, size as i32))
+    }
+
+    pub fn store_list<V: Le>(
+        mut store: impl AsContextMut,
+        memory: Memory,
+        realloc: &Realloc,
+        list: &[V],
+    ) -> wasmtime::Result<(i32, i32)> {
+        let ptr = alloc(&mut store, realloc, list.len() * V::SIZE, V::SIZE)?;
+        let data = memory.data_mut(store.as_context_mut());
+        let range = range(ptr, 0, list.len() * V::SIZE, data.len())?;
+        for (dst, val) in data[range].chunks_exact_mut(V::SIZE).zip(list) {
+            val.to_le(dst);
+        }
+        Ok((ptr, list.len() as i32))
+    }
+
+    pub fn store_string(
+        mut store: impl AsContextMut,
+        memory: Memory,
+        realloc: &Realloc,
+        s: &str,
+    ) -> wasmtime::Result<(i32, i32)> {
+        store_list(&mut store, memory, realloc, s.as_bytes())
+    }
+
+    pub fn as_i32<T: AsI32>(t: T) -> i32 {
+        t.as_i32()
+    }
+
+    pub trait AsI32 {
+        fn as_i32(self) -> i32;
+    }
+
+    impl<'a, T: Copy + AsI32> AsI32 for &'a T {
+        fn as_i32(self) -> i32 {
+            (*self).as_i32()
+        }
+    }
+
+    pub fn as_i64<T: AsI64>(t: T) -> i64 {
+        t.as_i64()
+    }
+
+    pub trait AsI64 {
+        fn as_i64(self) -> i64;
+    }
+
+    impl<'a, T: Copy + AsI64> AsI64 for &'a T {
+        fn as_i64(self) -> i64 {
+            (*self).as_i64()
+        }
+    }
+
+    pub fn as_f32<T: AsF32>(t: T) -> f32 {
+        t.as_f32()
+    }
+
+    pub trait AsF32 {
+        fn as_f32(self) -> f32;
+    }
+
+    impl<'a, T: Copy + AsF32> AsF32 for &'a T {
+        fn as_f32(self) -> f32 {
+            (*self).as_f32()
+        }
+    }
+
+    pub fn as_f64<T: AsF64>(t: T) -> f64 {
+        t.as_f64()
+    }
+
+    pub trait AsF64 {
+        fn as_f64(self) -> f64;
+    }
+
+    impl<'a, T: Copy + AsF64> AsF64 for &'a T {
+        fn as_f64(self) -> f64 {
+            (*self).as_f64()
+        }
+    }
+
+    macro_rules! as_traits {
+        ($($tr:ident $method:ident $ty:ident => $($from:ident)*;)*) => ($($(
+            impl $tr for $from {
+                #[inline]
+                fn $method(self) -> $ty {
+                    self as $ty
+                }
+            }
+        )*)*)
+    }
+
+    as_traits! {
+        AsI32 as_i32 i32 => char i8 u8 i16 u16 i32 u32 usize;
+        AsI64 as_i64 i64 => i64 u64;
+        AsF32 as_f32 f32 => f32;
+        AsF64 as_f64 f64 => f64;
+    }
+
+    pub fn char_lift(val: u32) -> wasmtime::Result<char> {
+        char::from_u32(val).ok_or_else(|| wasmtime::Error::msg("invalid `char` value"))
+    }
+
+    pub fn invalid_discriminant() -> wasmtime::Error {
+        wasmtime::Error::msg("invalid discriminant")
+    }
+}
+"#;
+
+/// Returns the name of the struct generated for `world`, which is suffixed
+/// with `World` if the world defines a type of the same name.
+fn world_struct_name(resolve: &Resolve, world: WorldId) -> String {
+    let world = &resolve.worlds[world];
+    let camel = to_upper_camel_case(&world.name);
+    let clashes = world.imports.iter().any(|(key, item)| match (key, item) {
+        (WorldKey::Name(name), WorldItem::Type(_)) => to_upper_camel_case(name) == camel,
+        _ => false,
+    });
+    if clashes {
+        format!("{camel}World")
+    } else {
+        camel
+    }
+}
+
+fn to_upper_camel_case(name: &str) -> String {
+    match name {
+        // The names "Guest" and "Host" are reserved for the struct and trait
+        // generated for interfaces, so remap types defined in wit to
+        // something else.
+        "guest" => "Guest_".to_string(),
+        "host" => "Host_".to_string(),
+        s => s.to_upper_camel_case(),
+    }
+}
+
+/// Returns the type of a core wasm value as seen by the host, where pointers
+/// and lengths are 32-bit integers.
+fn wasm_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 | WasmType::Pointer | WasmType::Length => "i32",
+        WasmType::I64 | WasmType::PointerOrI64 => "i64",
+        WasmType::F32 => "f32",
+        WasmType::F64 => "f64",
+    }
+}
+
+fn bitcast(casts: &[Bitcast], operands: &[String], results: &mut Vec<String>) {
+    for (cast, operand) in casts.iter().zip(operands) {
+        results.push(perform_cast(operand, cast));
+    }
+}
+
+fn perform_cast(operand: &str, cast: &Bitcast) -> String {
+    match cast {
+        Bitcast::I32ToI64 | Bitcast::PToP64 | Bitcast::LToI64 => {
+            format!("i64::from({})", operand)
+        }
+        Bitcast::F32ToI32 => format!("({}).to_bits() as i32", operand),
+        Bitcast::F64ToI64 => format!("({}).to_bits() as i64", operand),
+        Bitcast::I64ToI32 | Bitcast::P64ToP | Bitcast::I64ToL => format!("{} as i32", operand),
+        Bitcast::I32ToF32 => format!("f32::from_bits({} as u32)", operand),
+        Bitcast::I64ToF64 => format!("f64::from_bits({} as u64)", operand),
+        Bitcast::F32ToI64 => format!("i64::from(({}).to_bits())", operand),
+        Bitcast::I64ToF32 => format!("f32::from_bits({} as u32)", operand),
+
+        // Pointers and lengths are plain `i32` values on the host, and values
+        // which may hold a pointer are plain `i64` values.
+        Bitcast::None
+        | Bitcast::P64ToI64
+        | Bitcast::I64ToP64
+        | Bitcast::I32ToP
+        | Bitcast::PToI32
+        | Bitcast::PToL
+        | Bitcast::LToP
+        | Bitcast::I32ToL
+        | Bitcast::LToI32 => operand.to_owned(),
+
+        Bitcast::Sequence(sequence) => {
+            let [first, second] = &**sequence;
+            perform_cast(&perform_cast(operand, first), second)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use heck::*;
+use std::path::Path;
+use std::process::Command;
+
+macro_rules! codegen_test {
+    ($id:ident $name:tt $test:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "host-rust",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_host_rust::Opts::default()
+                        .build()
+                        .generate(resolve, world, files)
+                        .unwrap()
+                },
+                verify,
+            );
+            test_helpers::run_world_codegen_test(
+                "host-rust-borrowing",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_host_rust::Opts {
+                        ownership: wit_bindgen_host_rust::Ownership::Borrowing {
+                            duplicate_if_necessary: true,
+                        },
+                        ..Default::default()
+                    }
+                    .build()
+                    .generate(resolve, world, files)
+                    .unwrap()
+                },
+                verify,
+            );
+        }
+    };
+}
+
+test_helpers::codegen_tests!();
+
+/// Type-checks the generated bindings in a crate of their own depending on
+/// `wasmtime`.
+fn verify(dir: &Path, name: &str) {
+    let name = name.to_snake_case();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        r#"
+[package]
+name = "host-rust-codegen"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[lib]
+path = "lib.rs"
+
+[dependencies]
+wasmtime = { version = "19.0.0", default-features = false, features = ["runtime"] }
+
+[workspace]
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib.rs"), format!("include!(\"{name}.rs\");\n")).unwrap();
+
+    // Share one target directory across all tests to avoid rebuilding
+    // `wasmtime` for each of them.
+    let target_dir = dir
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("host-rust-target");
+    let mut cmd = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    cmd.arg("check")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir);
+    test_helpers::run_command(&mut cmd);
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the guest in `tests/runtime` through the bindings generated for it,
+/// checking the generated lifting, lowering, post-return and resource
+/// handling against an actual instance.
+#[test]
+fn runtime() {
+    let wit = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/runtime/world.wit");
+    test_helpers::run_world_codegen_test(
+        "host-rust-runtime",
+        &wit,
+        |resolve, world, files| {
+            wit_bindgen_host_rust::Opts::default()
+                .build()
+                .generate(resolve, world, files)
+                .unwrap()
+        },
+        run,
+    );
+}
+
+/// Builds the host in `tests/runtime/host.rs` along with the generated
+/// bindings, and runs it on `tests/runtime/guest.wat`.
+fn run(dir: &Path, _name: &str) {
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        r#"
+[package]
+name = "host-rust-runtime"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[[bin]]
+name = "host"
+path = "host.rs"
+
+[dependencies]
+wasmtime = { version = "19.0.0", default-features = false, features = ["runtime", "cranelift", "wat"] }
+
+[workspace]
+"#,
+    )
+    .unwrap();
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/runtime");
+    for file in ["host.rs", "guest.wat"] {
+        std::fs::copy(fixtures.join(file), dir.join(file)).unwrap();
+    }
+
+    // Share the target directory of the codegen tests to avoid rebuilding
+    // `wasmtime` more than necessary.
+    let target_dir = dir
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("host-rust-target");
+    let mut cmd = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    cmd.arg("run")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(dir.join("Cargo.toml"))
+        .current_dir(dir)
+        .env("CARGO_TARGET_DIR", target_dir);
+    test_helpers::run_command(&mut cmd);
+}
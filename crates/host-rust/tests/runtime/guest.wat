@@ -0,0 +1,84 @@
+;; A guest for `world.wit` written directly against the canonical ABI, so the
+;; generated host bindings can be run without a compiler for wasm.
+(module
+  (import "test:host/imports" "double" (func $double (param i32 i32 i32)))
+  (import "[export]test:host/exports" "[resource-new]counter"
+    (func $counter_new (param i32) (result i32)))
+  (import "[export]test:host/exports" "[resource-drop]counter"
+    (func $counter_drop (param i32)))
+
+  (memory (export "memory") 1)
+
+  ;; results which don't fit in a single core wasm value are returned here
+  (global $ret i32 (i32.const 16))
+  ;; the next free byte of memory for `cabi_realloc`
+  (global $heap (mut i32) (i32.const 1024))
+  (global $dropped (mut i32) (i32.const 0))
+  (global $post_returns (mut i32) (i32.const 0))
+
+  ;; a bump allocator which never frees memory
+  (func $realloc (export "cabi_realloc")
+    (param $old i32) (param $old_size i32) (param $align i32) (param $new_size i32)
+    (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get $align))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $new_size)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add (i32.div_u (local.get $new_size) (i32.const 65536)) (i32.const 1))))))
+    (memory.copy (local.get $ptr) (local.get $old) (local.get $old_size))
+    (local.get $ptr))
+
+  (func (export "test:host/exports#[constructor]counter") (param $start i32) (result i32)
+    (local $rep i32)
+    (local.set $rep (call $realloc (i32.const 0) (i32.const 0) (i32.const 4) (i32.const 4)))
+    (i32.store (local.get $rep) (local.get $start))
+    (call $counter_new (local.get $rep)))
+
+  (func (export "test:host/exports#[method]counter.bump")
+    (param $rep i32) (param $by i32) (result i32)
+    (i32.store (local.get $rep) (i32.add (i32.load (local.get $rep)) (local.get $by)))
+    (i32.load (local.get $rep)))
+
+  (func (export "test:host/exports#[dtor]counter") (param $rep i32)
+    (global.set $dropped (i32.add (global.get $dropped) (i32.const 1))))
+
+  (func (export "test:host/exports#discard") (param $handle i32)
+    (call $counter_drop (local.get $handle)))
+
+  (func (export "test:host/exports#num-dropped") (result i32)
+    (global.get $dropped))
+
+  (func (export "test:host/exports#concat")
+    (param $a i32) (param $a_len i32) (param $b i32) (param $b_len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (call $realloc
+        (i32.const 0) (i32.const 0) (i32.const 1)
+        (i32.add (local.get $a_len) (local.get $b_len))))
+    (memory.copy (local.get $ptr) (local.get $a) (local.get $a_len))
+    (memory.copy
+      (i32.add (local.get $ptr) (local.get $a_len)) (local.get $b) (local.get $b_len))
+    (i32.store (global.get $ret) (local.get $ptr))
+    (i32.store offset=4 (global.get $ret) (i32.add (local.get $a_len) (local.get $b_len)))
+    (global.get $ret))
+
+  (func (export "cabi_post_test:host/exports#concat") (param i32)
+    (global.set $post_returns (i32.add (global.get $post_returns) (i32.const 1))))
+
+  ;; the import writes its result to the return area, which is passed along as
+  ;; is
+  (func (export "test:host/exports#call-double") (param $s i32) (param $len i32) (result i32)
+    (call $double (local.get $s) (local.get $len) (global.get $ret))
+    (global.get $ret))
+
+  (func (export "cabi_post_test:host/exports#call-double") (param i32)
+    (global.set $post_returns (i32.add (global.get $post_returns) (i32.const 1))))
+
+  (func (export "test:host/exports#num-post-returns") (result i32)
+    (global.get $post_returns))
+)
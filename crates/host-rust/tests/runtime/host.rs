@@ -0,0 +1,65 @@
+//! Runs `guest.wat` through the host bindings generated for `world.wit`.
+
+#[allow(dead_code)]
+mod bindings {
+    include!("runtime.rs");
+}
+
+use bindings::Runtime;
+
+struct Host {
+    doubled: u32,
+}
+
+impl bindings::test::host::imports::Host for Host {
+    fn double(&mut self, s: String) -> wasmtime::Result<String> {
+        self.doubled += 1;
+        Ok(format!("{s}{s}"))
+    }
+}
+
+fn get(host: &mut Host) -> &mut Host {
+    host
+}
+
+fn main() -> wasmtime::Result<()> {
+    let engine = wasmtime::Engine::default();
+    let module = wasmtime::Module::from_file(&engine, "guest.wat")?;
+    let mut linker = wasmtime::Linker::new(&engine);
+    Runtime::add_to_linker(&mut linker, get)?;
+    let mut store = wasmtime::Store::new(&engine, Host { doubled: 0 });
+    let runtime = Runtime::instantiate(&mut store, &module, &linker)?;
+    let guest = runtime.test_host_exports();
+
+    // Arguments are lowered into memory allocated by the guest, and results
+    // are lifted out of it before the post-return function runs.
+    assert_eq!(
+        guest.call_concat(&mut store, "hello, ", "world")?,
+        "hello, world"
+    );
+    assert_eq!(guest.call_concat(&mut store, "", "")?, "");
+    assert_eq!(guest.call_num_post_returns(&mut store)?, 2);
+
+    // Imports lift their arguments out of the guest's memory and lower their
+    // results into its return area.
+    assert_eq!(guest.call_call_double(&mut store, "ab")?, "abab");
+    assert_eq!(store.data().doubled, 1);
+    assert_eq!(guest.call_num_post_returns(&mut store)?, 3);
+
+    // Resources are identified by their representation in the guest.
+    let a = guest.call_counter_constructor(&mut store, 10)?;
+    let b = guest.call_counter_constructor(&mut store, 20)?;
+    assert_ne!(a, b);
+    assert_eq!(guest.call_counter_bump(&mut store, a, 1)?, 11);
+    assert_eq!(guest.call_counter_bump(&mut store, b, 2)?, 22);
+    assert_eq!(guest.call_counter_bump(&mut store, a, 3)?, 14);
+    guest.drop_counter(&mut store, a)?;
+    assert_eq!(guest.call_num_dropped(&mut store)?, 1);
+
+    // Handing ownership back to the guest lets it drop the resource itself,
+    // which runs the destructor through the `[resource-drop]` intrinsic.
+    guest.call_discard(&mut store, b)?;
+    assert_eq!(guest.call_num_dropped(&mut store)?, 2);
+
+    Ok(())
+}
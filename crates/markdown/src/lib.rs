@@ -37,6 +37,10 @@ impl Opts {
 }
 
 impl WorldGenerator for Markdown {
+    fn supports_async_types(&self) -> bool {
+        true
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         self.sizes.fill(resolve);
 
@@ -671,4 +675,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs) {
         self.type_alias(id, name, ty, docs)
     }
+
+    fn type_future(&mut self, id: TypeId, name: &str, _ty: &Option<Type>, docs: &Docs) {
+        self.type_alias(id, name, &Type::Id(id), docs);
+    }
+
+    fn type_stream(&mut self, id: TypeId, name: &str, _stream: &Stream, docs: &Docs) {
+        self.type_alias(id, name, &Type::Id(id), docs);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::{int_repr, to_rust_ident, wasm_type, InterfaceGenerator, RustFlagsRepr};
+use crate::common::{int_repr, RustFunctionGenerator, RustGenerator};
+use crate::{to_rust_ident, wasm_type, InterfaceGenerator, RustFlagsRepr};
 use heck::*;
 use std::fmt::Write as _;
 use std::mem;
@@ -17,6 +18,12 @@ pub(super) struct FunctionBindgen<'a, 'b> {
     pub import_return_pointer_area_size: usize,
     pub import_return_pointer_area_align: usize,
     pub handle_decls: Vec<String>,
+    /// Lists of boxed handles of borrows lifted within list elements, which
+    /// are dropped once the exported function returns.
+    handle_lists: Vec<String>,
+    /// Whether each block in `block_storage` lifts or lowers the elements
+    /// of a list.
+    list_blocks: Vec<bool>,
 }
 
 impl<'a, 'b> FunctionBindgen<'a, 'b> {
@@ -36,6 +43,8 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             import_return_pointer_area_size: 0,
             import_return_pointer_area_align: 0,
             handle_decls: Vec::new(),
+            handle_lists: Vec::new(),
+            list_blocks: Vec::new(),
         }
     }
 
@@ -94,111 +103,9 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         );
         "wit_import".to_string()
     }
+}
 
-    fn let_results(&mut self, amt: usize, results: &mut Vec<String>) {
-        match amt {
-            0 => {}
-            1 => {
-                let tmp = self.tmp();
-                let res = format!("result{}", tmp);
-                self.push_str("let ");
-                self.push_str(&res);
-                results.push(res);
-                self.push_str(" = ");
-            }
-            n => {
-                let tmp = self.tmp();
-                self.push_str("let (");
-                for i in 0..n {
-                    let arg = format!("result{}_{}", tmp, i);
-                    self.push_str(&arg);
-                    self.push_str(",");
-                    results.push(arg);
-                }
-                self.push_str(") = ");
-            }
-        }
-    }
-
-    fn record_lower(
-        &mut self,
-        id: TypeId,
-        record: &Record,
-        operand: &str,
-        results: &mut Vec<String>,
-    ) {
-        let tmp = self.tmp();
-        self.push_str("let ");
-        let name = self.typename_lower(id);
-        self.push_str(&name);
-        self.push_str("{ ");
-        for field in record.fields.iter() {
-            let name = to_rust_ident(&field.name);
-            let arg = format!("{}{}", name, tmp);
-            self.push_str(&name);
-            self.push_str(":");
-            self.push_str(&arg);
-            self.push_str(", ");
-            results.push(arg);
-        }
-        self.push_str("} = ");
-        self.push_str(operand);
-        self.push_str(";\n");
-    }
-
-    fn record_lift(
-        &mut self,
-        id: TypeId,
-        ty: &Record,
-        operands: &[String],
-        results: &mut Vec<String>,
-    ) {
-        let mut result = self.typename_lift(id);
-        result.push_str("{\n");
-        for (field, val) in ty.fields.iter().zip(operands) {
-            result.push_str(&to_rust_ident(&field.name));
-            result.push_str(": ");
-            result.push_str(val);
-            result.push_str(",\n");
-        }
-        result.push('}');
-        results.push(result);
-    }
-
-    fn tuple_lower(&mut self, tuple: &Tuple, operand: &str, results: &mut Vec<String>) {
-        let tmp = self.tmp();
-        self.push_str("let (");
-        for i in 0..tuple.types.len() {
-            let arg = format!("t{}_{}", tmp, i);
-            self.push_str(&arg);
-            self.push_str(", ");
-            results.push(arg);
-        }
-        self.push_str(") = ");
-        self.push_str(operand);
-        self.push_str(";\n");
-    }
-
-    fn tuple_lift(&mut self, operands: &[String], results: &mut Vec<String>) {
-        if operands.len() == 1 {
-            results.push(format!("({},)", operands[0]));
-        } else {
-            results.push(format!("({})", operands.join(", ")));
-        }
-    }
-
-    fn typename_lower(&self, id: TypeId) -> String {
-        let owned = match self.lift_lower() {
-            LiftLower::LowerArgsLiftResults => false,
-            LiftLower::LiftArgsLowerResults => true,
-        };
-        self.gen.type_path(id, owned)
-    }
-
-    fn typename_lift(&self, id: TypeId) -> String {
-        self.gen.type_path(id, true)
-    }
-
+impl RustFunctionGenerator for FunctionBindgen<'_, '_> {
     fn push_str(&mut self, s: &str) {
         self.src.push_str(s);
     }
@@ -216,6 +123,10 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             LiftLower::LiftArgsLowerResults
         }
     }
+
+    fn type_path(&self, id: TypeId, owned: bool) -> String {
+        self.gen.type_path(id, owned)
+    }
 }
 
 impl Bindgen for FunctionBindgen<'_, '_> {
@@ -225,6 +136,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         let prev_src = mem::take(&mut self.src);
         let prev_cleanup = mem::take(&mut self.cleanup);
         self.block_storage.push((prev_src, prev_cleanup));
+        self.list_blocks.push(false);
     }
 
     fn finish_block(&mut self, operands: &mut Vec<String>) {
@@ -241,6 +153,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             self.push_str("]);\n");
         }
         let (prev_src, prev_cleanup) = self.block_storage.pop().unwrap();
+        self.list_blocks.pop();
         let src = mem::replace(&mut self.src, prev_src);
         self.cleanup = prev_cleanup;
         let expr = match operands.len() {
@@ -389,20 +302,42 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             Instruction::FlagsLower { flags, .. } => {
                 let tmp = self.tmp();
                 self.push_str(&format!("let flags{} = {};\n", tmp, operands[0]));
-                for i in 0..flags.repr().count() {
-                    results.push(format!("(flags{}.bits() >> {}) as i32", tmp, i * 32));
+                match RustFlagsRepr::new(flags) {
+                    RustFlagsRepr::Words(n) => {
+                        for i in 0..n {
+                            results.push(format!("flags{tmp}.bits()[{i}] as i32"));
+                        }
+                    }
+                    _ => {
+                        for i in 0..flags.repr().count() {
+                            results.push(format!("(flags{}.bits() >> {}) as i32", tmp, i * 32));
+                        }
+                    }
                 }
             }
             Instruction::FlagsLift { flags, ty, .. } => {
                 let repr = RustFlagsRepr::new(flags);
                 let name = self.gen.type_path(*ty, true);
-                let mut result = format!("{name}::empty()");
-                for (i, op) in operands.iter().enumerate() {
-                    result.push_str(&format!(
-                        " | {name}::from_bits_retain((({op} as {repr}) << {}) as _)",
-                        i * 32
-                    ));
-                }
+                let result = match repr {
+                    RustFlagsRepr::Words(_) => {
+                        let words = operands
+                            .iter()
+                            .map(|op| format!("{op} as u32"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!("{name}::from_bits_retain([{words}])")
+                    }
+                    _ => {
+                        let mut result = format!("{name}::empty()");
+                        for (i, op) in operands.iter().enumerate() {
+                            result.push_str(&format!(
+                                " | {name}::from_bits_retain((({op} as {repr}) << {}) as _)",
+                                i * 32
+                            ));
+                        }
+                        result
+                    }
+                };
                 results.push(result);
             }
 
@@ -423,6 +358,23 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 results.push(format!("({op}).handle() as i32"))
             }
 
+            Instruction::FutureLower { .. } | Instruction::StreamLower { .. } => {
+                let op = &operands[0];
+                results.push(format!("({op}).take_handle() as i32"));
+            }
+
+            Instruction::FutureLift { .. } => {
+                let op = &operands[0];
+                let name = self.gen.path_to_future_reader();
+                results.push(format!("{name}::from_handle({op} as u32)"));
+            }
+
+            Instruction::StreamLift { .. } => {
+                let op = &operands[0];
+                let name = self.gen.path_to_stream_reader();
+                results.push(format!("{name}::from_handle({op} as u32)"));
+            }
+
             Instruction::HandleLift { handle, .. } => {
                 let op = &operands[0];
                 let (is_own, resource) = match handle {
@@ -436,12 +388,26 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     let name = self.gen.type_path(dealiased_resource, true);
                     format!("{name}::from_handle({op} as u32)")
                 } else if self.gen.is_exported_resource(*resource) {
-                    let name = resolve.types[*resource]
-                        .name
-                        .as_deref()
-                        .unwrap()
-                        .to_upper_camel_case();
+                    let name = self.gen.type_name(*resource).to_upper_camel_case();
                     format!("{name}Borrow::lift({op} as u32 as usize)")
+                } else if self.list_blocks.contains(&true) {
+                    // Borrows lifted within the elements of a list can't
+                    // share a single local, so each one is boxed to give it
+                    // a stable address until the call returns.
+                    let tmp = format!("handles{}", self.tmp());
+                    let vec = self.gen.path_to_vec();
+                    self.handle_decls
+                        .push(format!("let mut {tmp} = {vec}::new();"));
+                    self.handle_lists.push(tmp.clone());
+                    let name = self.gen.type_path(dealiased_resource, true);
+                    let box_ = self.gen.path_to_box();
+                    format!(
+                        "{{\n
+                            let handle = {box_}::into_raw({box_}::new({name}::from_handle({op} as u32)));
+                            {tmp}.push(handle);
+                            &*handle
+                        }}"
+                    )
                 } else {
                     let tmp = format!("handle{}", self.tmp());
                     self.handle_decls.push(format!("let {tmp};"));
@@ -673,7 +639,12 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 if realloc.is_none() {
                     self.push_str(&format!("let {} = {};\n", val, operands[0]));
                 } else {
-                    let op0 = format!("{}.into_bytes()", operands[0]);
+                    // raw strings are already owned as bytes
+                    let op0 = if self.gen.gen.opts.raw_strings {
+                        operands[0].clone()
+                    } else {
+                        format!("{}.into_bytes()", operands[0])
+                    };
                     self.push_str(&format!("let {} = ({}).into_boxed_slice();\n", val, op0));
                 }
                 self.push_str(&format!("let {} = {}.as_ptr().cast::<u8>();\n", ptr, val));
@@ -776,7 +747,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::IterElem { .. } => results.push("e".to_string()),
 
-            Instruction::IterBasePointer => results.push("base".to_string()),
+            Instruction::IterBasePointer => {
+                *self.list_blocks.last_mut().unwrap() = true;
+                results.push("base".to_string());
+            }
 
             Instruction::CallWasm { name, sig, .. } => {
                 let func = self.declare_import(
@@ -799,21 +773,23 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::CallInterface { func, .. } => {
                 self.let_results(func.results.len(), results);
+                let block_on = if self.gen.gen.opts.async_exports {
+                    let rt = self.gen.gen.runtime_path().to_string();
+                    format!("{rt}::block_on(")
+                } else {
+                    String::new()
+                };
                 match &func.kind {
                     FunctionKind::Freestanding => {
-                        self.push_str(&format!("T::{}", to_rust_ident(&func.name)));
+                        self.push_str(&format!("{block_on}T::{}", to_rust_ident(&func.name)));
                     }
                     FunctionKind::Method(_) | FunctionKind::Static(_) => {
-                        self.push_str(&format!("T::{}", to_rust_ident(func.item_name())));
+                        self.push_str(&format!("{block_on}T::{}", to_rust_ident(func.item_name())));
                     }
                     FunctionKind::Constructor(ty) => {
                         self.push_str(&format!(
-                            "{}::new(T::new",
-                            resolve.types[*ty]
-                                .name
-                                .as_deref()
-                                .unwrap()
-                                .to_upper_camel_case()
+                            "{}::new({block_on}T::new",
+                            self.gen.type_name(*ty).to_upper_camel_case()
                         ));
                     }
                 }
@@ -833,10 +809,20 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     }
                 }
                 self.push_str(")");
+                if !block_on.is_empty() {
+                    self.push_str(")");
+                }
                 if let FunctionKind::Constructor(_) = &func.kind {
                     self.push_str(")");
                 }
                 self.push_str(";\n");
+                for list in mem::take(&mut self.handle_lists) {
+                    let box_ = self.gen.path_to_box();
+                    uwriteln!(
+                        self.src,
+                        "for handle in {list} {{ drop({box_}::from_raw(handle)); }}"
+                    );
+                }
             }
 
             Instruction::Return { amt, .. } => {
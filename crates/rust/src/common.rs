@@ -0,0 +1,1627 @@
+//! Pieces of the Rust generator which are shared with the host Rust
+//! generator, `wit-bindgen-host-rust`.
+//!
+//! Both generators render WIT types as Rust types the same way and only
+//! differ in how handles, futures, streams and strings are represented, which
+//! is what the hooks of [`RustGenerator`] are for.
+
+use crate::{to_rust_ident, Ownership};
+use anyhow::{bail, Result};
+use heck::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::ops::Index;
+use wit_bindgen_core::abi::LiftLower;
+use wit_bindgen_core::{uwriteln, wit_parser::*, Source, TypeInfo};
+
+pub struct InterfaceName {
+    /// True when this interface name has been remapped through the use of `with` in the `bindgen!`
+    /// macro invocation.
+    pub remapped: bool,
+
+    /// The string name for this interface.
+    pub path: String,
+}
+
+/// The Rust paths of all interfaces seen so far, taking remappings provided
+/// through `with` into account.
+#[derive(Default)]
+pub struct InterfaceNames {
+    names: HashMap<InterfaceId, InterfaceName>,
+    with: HashMap<String, String>,
+    with_name_counter: usize,
+    // Track the with options that were used. Remapped interfaces provided via `with`
+    // are required to be used.
+    used_with_opts: HashSet<String>,
+}
+
+impl InterfaceNames {
+    /// Registers the remapping of the interface named `key` to the Rust path
+    /// `path`.
+    pub fn remap(&mut self, key: &str, path: &str) {
+        self.with.insert(key.to_string(), path.to_string());
+    }
+
+    /// Records the path of the interface `id`, returning whether it was
+    /// remapped, in which case no bindings should be generated for it.
+    ///
+    /// Remapped interfaces are imported into the root module, so the `use`
+    /// for them is written to `src`.
+    pub fn name_interface(
+        &mut self,
+        src: &mut Source,
+        resolve: &Resolve,
+        id: InterfaceId,
+        name: &WorldKey,
+        is_export: bool,
+    ) -> bool {
+        let with_name = resolve.name_world_key(name);
+        let entry = if let Some(remapped_path) = self.with.get(&with_name) {
+            let name = format!("__with_name{}", self.with_name_counter);
+            self.used_with_opts.insert(with_name);
+            self.with_name_counter += 1;
+            uwriteln!(src, "use {remapped_path} as {name};");
+            InterfaceName {
+                remapped: true,
+                path: name,
+            }
+        } else {
+            let path = compute_module_path(name, resolve, is_export).join("::");
+
+            InterfaceName {
+                remapped: false,
+                path,
+            }
+        };
+
+        let remapped = entry.remapped;
+        self.names.insert(id, entry);
+
+        remapped
+    }
+
+    /// Returns an error if any remapping provided via `with` didn't match an
+    /// interface of the world.
+    pub fn check_unused_remappings(&self) -> Result<()> {
+        let remapping_keys = self.with.keys().cloned().collect::<HashSet<String>>();
+
+        let mut unused_keys = remapping_keys
+            .difference(&self.used_with_opts)
+            .collect::<Vec<&String>>();
+
+        unused_keys.sort();
+
+        if !unused_keys.is_empty() {
+            bail!("unused remappings provided via `with`: {unused_keys:?}");
+        }
+
+        Ok(())
+    }
+}
+
+impl Index<&InterfaceId> for InterfaceNames {
+    type Output = InterfaceName;
+
+    fn index(&self, id: &InterfaceId) -> &InterfaceName {
+        &self.names[id]
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Identifier<'a> {
+    World(WorldId),
+    Interface(InterfaceId, &'a WorldKey),
+}
+
+/// Emits each module at its path, nesting it within `pub mod` items for each
+/// of the path's components.
+pub fn emit_modules(src: &mut Source, modules: Vec<(String, Vec<String>)>) {
+    #[derive(Default)]
+    struct Module {
+        submodules: BTreeMap<String, Module>,
+        contents: Vec<String>,
+    }
+    let mut map = Module::default();
+    for (module, path) in modules {
+        let mut cur = &mut map;
+        for name in path[..path.len() - 1].iter() {
+            cur = cur
+                .submodules
+                .entry(name.clone())
+                .or_insert(Module::default());
+        }
+        cur.contents.push(module);
+    }
+    emit(src, map);
+    fn emit(me: &mut Source, module: Module) {
+        for (name, submodule) in module.submodules {
+            // Ignore dead-code warnings. If the bindings are only used
+            // within a crate, and not exported to a different crate, some
+            // parts may be unused, and that's ok.
+            uwriteln!(me, "#[allow(dead_code)]");
+
+            uwriteln!(me, "pub mod {name} {{");
+            emit(me, submodule);
+            uwriteln!(me, "}}");
+        }
+        for submodule in module.contents {
+            uwriteln!(me, "{submodule}");
+        }
+    }
+}
+
+pub fn compute_module_path(name: &WorldKey, resolve: &Resolve, is_export: bool) -> Vec<String> {
+    let mut path = Vec::new();
+    if is_export {
+        path.push("exports".to_string());
+    }
+    match name {
+        WorldKey::Name(name) => {
+            path.push(to_rust_ident(name));
+        }
+        WorldKey::Interface(id) => {
+            let iface = &resolve.interfaces[*id];
+            let pkg = iface.package.unwrap();
+            let pkgname = resolve.packages[pkg].name.clone();
+            path.push(to_rust_ident(&pkgname.namespace));
+            path.push(name_package_module(resolve, pkg));
+            path.push(to_rust_ident(iface.name.as_ref().unwrap()));
+        }
+    }
+    path
+}
+
+/// If the package `id` is the only package with its namespace/name combo
+/// then pass through the name unmodified. If, however, there are multiple
+/// versions of this package then the package module is going to get version
+/// information.
+fn name_package_module(resolve: &Resolve, id: PackageId) -> String {
+    let pkg = &resolve.packages[id];
+    let versions_with_same_name = resolve
+        .packages
+        .iter()
+        .filter_map(|(_, p)| {
+            if p.name.namespace == pkg.name.namespace && p.name.name == pkg.name.name {
+                Some(&p.name.version)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let base = pkg.name.name.to_snake_case();
+    if versions_with_same_name.len() == 1 {
+        return base;
+    }
+
+    let version = match &pkg.name.version {
+        Some(version) => version,
+        // If this package didn't have a version then don't mangle its name
+        // and other packages with the same name but with versions present
+        // will have their names mangled.
+        None => return base,
+    };
+
+    // Here there's multiple packages with the same name that differ only in
+    // version, so the version needs to be mangled into the Rust module name
+    // that we're generating. This in theory could look at all of
+    // `versions_with_same_name` and produce a minimal diff, e.g. for 0.1.0
+    // and 0.2.0 this could generate "foo1" and "foo2", but for now
+    // a simpler path is chosen to generate "foo0_1_0" and "foo0_2_0".
+    let version = version
+        .to_string()
+        .replace('.', "_")
+        .replace('-', "_")
+        .replace('+', "_")
+        .to_snake_case();
+    format!("{base}{version}")
+}
+
+pub fn group_by_resource<'a>(
+    funcs: impl Iterator<Item = &'a Function>,
+) -> BTreeMap<Option<TypeId>, Vec<&'a Function>> {
+    let mut by_resource = BTreeMap::<_, Vec<_>>::new();
+    for func in funcs {
+        match &func.kind {
+            FunctionKind::Freestanding => by_resource.entry(None).or_default().push(func),
+            FunctionKind::Method(ty) | FunctionKind::Static(ty) | FunctionKind::Constructor(ty) => {
+                by_resource.entry(Some(*ty)).or_default().push(func);
+            }
+        }
+    }
+    by_resource
+}
+
+pub fn int_repr(repr: Int) -> &'static str {
+    match repr {
+        Int::U8 => "u8",
+        Int::U16 => "u16",
+        Int::U32 => "u32",
+        Int::U64 => "u64",
+    }
+}
+
+/// A description of the "mode" in which a type is printed.
+///
+/// Rust types can either be "borrowed" or "owned". This primarily has to do
+/// with lists and imports where arguments to imports can be borrowed lists in
+/// theory as ownership is not taken from the caller. This structure is used to
+/// help with this fact in addition to the various codegen options of this
+/// generator. Namely types in WIT can be reflected into Rust as two separate
+/// types, one "owned" and one "borrowed" (aka one with `Vec` and one with
+/// `&[T]`).
+///
+/// This structure is used in conjunction with `modes_of` and `type_mode_for*`
+/// primarily. That enables creating a programmatic description of what a type
+/// is rendered as along with various options.
+///
+/// Note that a `TypeMode` is a description of a single "level" of a type. This
+/// means that there's one mode for `Vec<T>` and one mode for `T` internally.
+/// This is mostly used for things like records where some fields have lifetime
+/// parameters, for example, and others don't.
+///
+/// This type is intended to simplify generation of types and encapsulate all
+/// the knowledge about whether lifetime parameters are used and how lists are
+/// rendered.
+///
+/// There are currently two users of lifetime parameters:
+///
+/// * Lists - when borrowed these are rendered as either `&[T]` or `&str`.
+/// * Borrowed resources - for resources owned by the current module they're
+///   represented as `&T` and for borrows of imported resources they're
+///   represented, more-or-less, as `&Resource<T>`.
+///
+/// Lists have a choice of being rendered as borrowed or not but resources are
+/// required to be borrowed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TypeMode {
+    /// The lifetime parameter, if any, for this type. If present this type is
+    /// required to have a lifetime parameter.
+    pub lifetime: Option<&'static str>,
+
+    /// Whether or not lists are borrowed in this type.
+    ///
+    /// If this field is `true` then lists are rendered as `&[T]` and `&str`
+    /// rather than their owned equivalent. If this field is `false` than the
+    /// owned equivalents are used instead.
+    pub lists_borrowed: bool,
+
+    /// The "style" of ownership that this mode was created with.
+    ///
+    /// This information is used to determine what mode the next layer deep int
+    /// he type tree is rendered with. For example if this layer is owned so is
+    /// the next layer. This is primarily used for the "OnlyTopBorrowed"
+    /// ownership style where all further layers beneath that are `Owned`.
+    pub style: TypeOwnershipStyle,
+}
+
+/// The style of ownership of a type, used to initially create a `TypeMode` and
+/// stored internally within it as well.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TypeOwnershipStyle {
+    /// This style means owned things are printed such as `Vec<T>` and `String`.
+    ///
+    /// Note that this primarily applies to lists.
+    Owned,
+
+    /// This style means that lists/strings are `&[T]` and `&str`.
+    ///
+    /// Note that this primarily applies to lists.
+    Borrowed,
+
+    /// This style means that the top-level of a type is borrowed but all other
+    /// layers are `Owned`.
+    ///
+    /// This is used for parameters in the "owning" mode of generation to
+    /// imports. It's easy enough to create a `&T` at the root layer but it's
+    /// more difficult to create `&T` stored within a `U`, for example.
+    OnlyTopBorrowed,
+}
+
+impl TypeMode {
+    /// Returns a mode where everything is indicated that it's supposed to be
+    /// rendered as an "owned" type.
+    pub fn owned() -> TypeMode {
+        TypeMode {
+            lifetime: None,
+            lists_borrowed: false,
+            style: TypeOwnershipStyle::Owned,
+        }
+    }
+}
+
+impl TypeOwnershipStyle {
+    /// Preserves this mode except for `OnlyTopBorrowed` where it switches it to
+    /// `Owned`.
+    pub fn next(&self) -> TypeOwnershipStyle {
+        match self {
+            TypeOwnershipStyle::Owned => TypeOwnershipStyle::Owned,
+            TypeOwnershipStyle::Borrowed => TypeOwnershipStyle::Borrowed,
+            TypeOwnershipStyle::OnlyTopBorrowed => TypeOwnershipStyle::Owned,
+        }
+    }
+}
+
+/// The rendering of WIT types as Rust types, implemented by the interface
+/// generators of both the guest and the host.
+pub trait RustGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve;
+
+    fn info(&self, ty: TypeId) -> TypeInfo;
+
+    /// The module the generated code is placed in.
+    fn identifier(&self) -> Identifier<'a>;
+
+    fn in_import(&self) -> bool;
+
+    fn interface_names(&self) -> &InterfaceNames;
+
+    fn ownership(&self) -> Ownership;
+
+    fn additional_derives(&self) -> &[String];
+
+    /// Whether `std::error::Error` implementations are gated on a `std`
+    /// feature of the crate the bindings are placed in.
+    fn std_feature(&self) -> bool;
+
+    /// Whether strings are rendered as lists of bytes.
+    fn raw_strings(&self) -> bool;
+
+    /// Whether `borrow<T>` handles are rendered with a lifetime, which then
+    /// needs to be threaded through every type containing one.
+    fn borrow_handles_need_lifetime(&self) -> bool;
+
+    /// Converts the WIT name of a type into the name of its Rust item.
+    fn upper_camel_case(&self, name: &str) -> String;
+
+    fn push_str(&mut self, s: &str);
+
+    fn push_vec_name(&mut self);
+
+    fn push_string_name(&mut self);
+
+    fn print_handle(&mut self, handle: &Handle, mode: TypeMode);
+
+    fn print_future(&mut self, ty: Option<&Type>);
+
+    fn print_stream(&mut self, stream: &Stream);
+
+    /// Returns the WIT name of `ty`.
+    fn type_name(&self, ty: TypeId) -> String {
+        self.resolve().types[ty]
+            .name
+            .clone()
+            .unwrap_or_else(|| panic!("type {ty:?} doesn't have a name"))
+    }
+
+    fn rustdoc(&mut self, docs: &Docs) {
+        let docs = match &docs.contents {
+            Some(docs) => docs,
+            None => return,
+        };
+        for line in docs.trim().lines() {
+            self.push_str("///");
+            if !line.is_empty() {
+                self.push_str(" ");
+                self.push_str(line);
+            }
+            self.push_str("\n");
+        }
+    }
+
+    fn path_to_root(&self) -> String {
+        let mut path_to_root = String::new();
+
+        if let Identifier::Interface(_, key) = self.identifier() {
+            // Escape the submodule for this interface
+            path_to_root.push_str("super::");
+
+            // Escape the `exports` top-level submodule
+            if !self.in_import() {
+                path_to_root.push_str("super::");
+            }
+
+            // Escape the namespace/package submodules for interface-based ids
+            match key {
+                WorldKey::Name(_) => {}
+                WorldKey::Interface(_) => {
+                    path_to_root.push_str("super::super::");
+                }
+            }
+        }
+        path_to_root
+    }
+
+    fn path_to_interface(&self, interface: InterfaceId) -> Option<String> {
+        let InterfaceName { path, remapped } = &self.interface_names()[&interface];
+        if *remapped {
+            let mut path_to_root = self.path_to_root();
+            path_to_root.push_str(path);
+            Some(path_to_root)
+        } else {
+            let mut full_path = String::new();
+            if let Identifier::Interface(cur, name) = self.identifier() {
+                if cur == interface {
+                    return None;
+                }
+                if !self.in_import() {
+                    full_path.push_str("super::");
+                }
+                match name {
+                    WorldKey::Name(_) => {
+                        full_path.push_str("super::");
+                    }
+                    WorldKey::Interface(_) => {
+                        full_path.push_str("super::super::super::");
+                    }
+                }
+            }
+            full_path.push_str(path);
+            Some(full_path)
+        }
+    }
+
+    /// Calculates the `TypeMode` to be used for the `ty` specified.
+    ///
+    /// This takes a `style` argument which is the requested style of ownership
+    /// for this type. Note that the returned `TypeMode` may have a different
+    /// `style`.
+    ///
+    /// This additionally takes a `lt` parameter which, if needed, is what will
+    /// be used to render lifetimes.
+    fn type_mode_for(&self, ty: &Type, style: TypeOwnershipStyle, lt: &'static str) -> TypeMode {
+        match ty {
+            Type::Id(id) => self.type_mode_for_id(*id, style, lt),
+
+            // Borrowed strings are handled specially here since they're the
+            // only list-like primitive.
+            Type::String if style != TypeOwnershipStyle::Owned => TypeMode {
+                lifetime: Some(lt),
+                lists_borrowed: true,
+                style,
+            },
+
+            _ => TypeMode::owned(),
+        }
+    }
+
+    /// Same as `type_mode_for`, but specifically for `TypeId` which refers to a
+    /// type.
+    fn type_mode_for_id(
+        &self,
+        ty: TypeId,
+        style: TypeOwnershipStyle,
+        lt: &'static str,
+    ) -> TypeMode {
+        // NB: This method is the heart of determining how to render types.
+        // There's a lot of permutations and corner cases to handle, especially
+        // with being able to configure at the generator level how types are
+        // generated. Long story short this is a subtle and complicated method.
+        //
+        // The hope is that most of the complexity around type generation in
+        // Rust is largely centered here where everything else can lean on this.
+        // This has gone through so many refactors I've lost count at this
+        // point, but maybe this one is the one that'll stick!
+        //
+        // The general idea is that there's some clear-and-fast rules for how
+        // `TypeMode` must be returned here. For example borrowed handles are
+        // required to have a lifetime parameter. Everything else though is here
+        // to handle the various levels of configuration and semantics for each
+        // level of types.
+        //
+        // As a reminder a `TypeMode` is generated for each "level" of a type
+        // hierarchy, for example there's one mode for `Vec<T>` and another mode
+        // for `T`. This enables, for example, rendering the outer layer as
+        // either `Vec<T>` or `&[T]` but the inner `T` may or may not have a
+        // type parameter.
+
+        let info = self.info(ty);
+        let lifetime = if info.has_borrow_handle && self.borrow_handles_need_lifetime() {
+            // Borrowed handles always have a lifetime associated with them so
+            // thread it through.
+            Some(lt)
+        } else if style == TypeOwnershipStyle::Owned {
+            // If this type is being rendered as an "owned" type, and it
+            // doesn't have any borrowed handles, then no lifetimes are needed
+            // since any internal lists will be their owned version.
+            None
+        } else if info.has_own_handle || !info.has_list {
+            // At this point there are no borrowed handles and a borrowed style
+            // of type is requested. In this situation there's two cases where a
+            // lifetime is never used:
+            //
+            // * Owned handles are present - in this situation ownership is used
+            //   to statically reflect how a value is lost when passed to an
+            //   import. This means that no lifetime is used for internal lists
+            //   since they must be rendered in an owned mode.
+            //
+            // * There are no lists present - here the lifetime parameter won't
+            //   be used for anything because there's no borrows or lists, so
+            //   it's skipped.
+            None
+        } else if !info.owned || self.uses_two_names(&info) {
+            // This next layer things get a little more interesting. To recap,
+            // so far we know that there's no borrowed handles, a borrowed mode
+            // is requested, there's no own handles, and there's a list. In that
+            // situation if `info` shows that this type is never used in an
+            // owned position, or if two types are explicitly requested for
+            // owned/borrowed values, then a lifetime is used.
+            Some(lt)
+        } else {
+            // ... and finally, here at the end we know:
+            //
+            // * No borrowed handles
+            // * Borrowing mode is requested
+            // * No owned handles
+            // * A list is somewhere
+            // * This type is used somewhere in an owned position
+            // * This type does not used "two names" meaning that we must use
+            //   the owned version of the type.
+            //
+            // If the configured ownership mode for generating types of this
+            // generator is "owning" then that's the only type that can be used.
+            // If borrowing is requested then this means that `&T` is going to
+            // be rendered, so thread it through.
+            //
+            // If the configured ownership mode uses borrowing by default, then
+            // things get a little weird. This means that a lifetime is going to
+            // be used an any lists should be borrowed, but we specifically
+            // switch to only borrowing the top layer of the type rather than
+            // the entire hierarchy. This situation can happen in
+            // `duplicate_if_necessary: false` mode for example where we're
+            // borrowing a type which is used in an owned position elsewhere.
+            // The only possibility at that point is to borrow it at the root
+            // but everything else internally is required to be owned from then
+            // on.
+            match self.ownership() {
+                Ownership::Owning => Some(lt),
+                Ownership::Borrowing { .. } => {
+                    return TypeMode {
+                        lifetime: Some(lt),
+                        lists_borrowed: true,
+                        style: TypeOwnershipStyle::OnlyTopBorrowed,
+                    };
+                }
+            }
+        };
+        TypeMode {
+            lifetime,
+
+            // If a lifetime is present and ownership isn't requested, then make
+            // sure any lists show up as `&str` or `&[T]`.
+            lists_borrowed: lifetime.is_some() && style != TypeOwnershipStyle::Owned,
+
+            // Switch the style to `Owned` if an `own<T>` handle is present
+            // because there's no option but to take interior types by ownership
+            // as that statically shows that the ownership of the value is being
+            // lost.
+            style: if info.has_own_handle {
+                TypeOwnershipStyle::Owned
+            } else {
+                style
+            },
+        }
+    }
+
+    /// Generates the "next" mode for a type.
+    ///
+    /// The `ty` specified is the type that a mode is being generated for, and
+    /// the `mode` argument is the "parent" mode that the previous outer layer
+    /// of type was rendered with. The returned mode should be used to render
+    /// `ty`.
+    fn filter_mode(&self, ty: &Type, mode: TypeMode) -> TypeMode {
+        match mode.lifetime {
+            Some(lt) => self.type_mode_for(ty, mode.style.next(), lt),
+            None => TypeMode::owned(),
+        }
+    }
+
+    /// Same as `filder_mode` except if `mode` has the type `OnlyTopBorrowed`
+    /// the `mode` is specifically preserved as-is.
+    ///
+    /// This is used for types like `Option<T>` to render as `Option<&T>`
+    /// instead of `&Option<T>` for example.
+    fn filter_mode_preserve_top(&self, ty: &Type, mode: TypeMode) -> TypeMode {
+        if mode.style == TypeOwnershipStyle::OnlyTopBorrowed {
+            mode
+        } else {
+            self.filter_mode(ty, mode)
+        }
+    }
+
+    fn print_ty(&mut self, ty: &Type, mode: TypeMode) {
+        match ty {
+            Type::Id(t) => self.print_tyid(*t, mode),
+            Type::Bool => self.push_str("bool"),
+            Type::U8 => self.push_str("u8"),
+            Type::U16 => self.push_str("u16"),
+            Type::U32 => self.push_str("u32"),
+            Type::U64 => self.push_str("u64"),
+            Type::S8 => self.push_str("i8"),
+            Type::S16 => self.push_str("i16"),
+            Type::S32 => self.push_str("i32"),
+            Type::S64 => self.push_str("i64"),
+            Type::F32 => self.push_str("f32"),
+            Type::F64 => self.push_str("f64"),
+            Type::Char => self.push_str("char"),
+            Type::String => {
+                assert_eq!(mode.lists_borrowed, mode.lifetime.is_some());
+                match mode.lifetime {
+                    Some(lt) => self.print_borrowed_str(lt),
+                    None => {
+                        if self.raw_strings() {
+                            self.push_vec_name();
+                            self.push_str("::<u8>");
+                        } else {
+                            self.push_string_name();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn print_optional_ty(&mut self, ty: Option<&Type>, mode: TypeMode) {
+        match ty {
+            Some(ty) => {
+                let mode = self.filter_mode_preserve_top(ty, mode);
+                self.print_ty(ty, mode)
+            }
+            None => self.push_str("()"),
+        }
+    }
+
+    fn type_path(&self, id: TypeId, owned: bool) -> String {
+        self.type_path_with_name(
+            id,
+            if owned {
+                self.result_name(id)
+            } else {
+                self.param_name(id)
+            },
+        )
+    }
+
+    fn type_path_with_name(&self, id: TypeId, name: String) -> String {
+        match self.resolve().types[id].owner {
+            TypeOwner::Interface(id) => {
+                if let Some(path) = self.path_to_interface(id) {
+                    return format!("{path}::{name}");
+                }
+            }
+            // Types without an owner are defined in the root module, as are
+            // those of the world.
+            TypeOwner::World(_) | TypeOwner::None => {
+                return format!("{}{name}", self.path_to_root())
+            }
+        }
+        name
+    }
+
+    fn print_tyid(&mut self, id: TypeId, mode: TypeMode) {
+        let ty = &self.resolve().types[id];
+        let needs_item = matches!(
+            ty.kind,
+            TypeDefKind::Variant(_)
+                | TypeDefKind::Resource
+                | TypeDefKind::Record(_)
+                | TypeDefKind::Flags(_)
+                | TypeDefKind::Enum(_)
+        );
+        if ty.name.is_some() || needs_item {
+            // NB: Most of the heavy lifting of `TypeMode` and what to do here
+            // has already happened in `type_mode_for*`. Here though a little
+            // more happens because this is where `OnlyTopBorrowed` is
+            // processed.
+            //
+            // Specifically what should happen is that in the case of an
+            // argument to an imported function if only the top value is
+            // borrowed then we want to render it as `&T`. If this all is
+            // applicable then the lifetime is rendered here before the type.
+            // The `mode` is then switched to `Owned` and recalculated for the
+            // type we're rendering here to avoid accidentally giving it a
+            // lifetime type parameter when it otherwise doesn't have it.
+            let mode = if mode.style == TypeOwnershipStyle::OnlyTopBorrowed {
+                if let Some(lt) = mode.lifetime {
+                    self.push_str("&");
+                    if lt != "'_" {
+                        self.push_str(lt);
+                        self.push_str(" ");
+                    }
+                    self.type_mode_for_id(id, TypeOwnershipStyle::Owned, lt)
+                } else {
+                    mode
+                }
+            } else {
+                mode
+            };
+            let name = self.type_path(
+                id,
+                match mode.style {
+                    TypeOwnershipStyle::Owned => true,
+                    TypeOwnershipStyle::OnlyTopBorrowed | TypeOwnershipStyle::Borrowed => false,
+                },
+            );
+            self.push_str(&name);
+            self.print_generics(mode.lifetime);
+            return;
+        }
+
+        match &ty.kind {
+            TypeDefKind::List(t) => self.print_list(t, mode),
+
+            TypeDefKind::Option(t) => {
+                self.push_str("Option<");
+                let mode = self.filter_mode_preserve_top(t, mode);
+                self.print_ty(t, mode);
+                self.push_str(">");
+            }
+
+            TypeDefKind::Result(r) => {
+                self.push_str("Result<");
+                self.print_optional_ty(r.ok.as_ref(), mode);
+                self.push_str(",");
+                self.print_optional_ty(r.err.as_ref(), mode);
+                self.push_str(">");
+            }
+
+            // Tuple-like records are mapped directly to Rust tuples of
+            // types. Note the trailing comma after each member to
+            // appropriately handle 1-tuples.
+            TypeDefKind::Tuple(t) => {
+                self.push_str("(");
+                for ty in t.types.iter() {
+                    let mode = self.filter_mode_preserve_top(ty, mode);
+                    self.print_ty(ty, mode);
+                    self.push_str(",");
+                }
+                self.push_str(")");
+            }
+            // Types which need a named item in Rust are handled above.
+            TypeDefKind::Variant(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Record(_)
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_) => unreachable!(),
+            TypeDefKind::Future(ty) => self.print_future(ty.as_ref()),
+            TypeDefKind::Stream(stream) => self.print_stream(stream),
+
+            TypeDefKind::Handle(handle) => self.print_handle(handle, mode),
+
+            TypeDefKind::Type(t) => self.print_ty(t, mode),
+
+            TypeDefKind::Unknown => unreachable!(),
+        }
+    }
+
+    fn print_list(&mut self, ty: &Type, mode: TypeMode) {
+        let next_mode = self.filter_mode(ty, mode);
+        if mode.lists_borrowed {
+            let lifetime = mode.lifetime.unwrap();
+            self.push_str("&");
+            if lifetime != "'_" {
+                self.push_str(lifetime);
+                self.push_str(" ");
+            }
+            self.push_str("[");
+            self.print_ty(ty, next_mode);
+            self.push_str("]");
+        } else {
+            self.push_vec_name();
+            self.push_str("::<");
+            self.print_ty(ty, next_mode);
+            self.push_str(">");
+        }
+    }
+
+    fn print_generics(&mut self, lifetime: Option<&str>) {
+        if lifetime.is_none() {
+            return;
+        }
+        self.push_str("<");
+        if let Some(lt) = lifetime {
+            self.push_str(lt);
+            self.push_str(",");
+        }
+        self.push_str(">");
+    }
+
+    fn print_borrowed_str(&mut self, lifetime: &'static str) {
+        self.push_str("&");
+        if lifetime != "'_" {
+            self.push_str(lifetime);
+            self.push_str(" ");
+        }
+        if self.raw_strings() {
+            self.push_str("[u8]");
+        } else {
+            self.push_str("str");
+        }
+    }
+
+    fn modes_of(&self, ty: TypeId) -> Vec<(String, TypeMode)> {
+        let info = self.info(ty);
+        // If this type isn't actually used, no need to generate it.
+        if !info.owned && !info.borrowed {
+            return Vec::new();
+        }
+        let mut result = Vec::new();
+
+        // Generate one mode for when the type is owned and another for when
+        // it's borrowed.
+        let a = self.type_mode_for_id(ty, TypeOwnershipStyle::Owned, "'a");
+        let b = self.type_mode_for_id(ty, TypeOwnershipStyle::Borrowed, "'a");
+
+        if self.uses_two_names(&info) {
+            // If this type uses two names then, well, it uses two names. In
+            // this situation both modes are returned.
+            assert!(a != b);
+            result.push((self.result_name(ty), a));
+            result.push((self.param_name(ty), b));
+        } else if a == b {
+            // If the modes are the same then there's only one result.
+            result.push((self.result_name(ty), a));
+        } else if info.owned || matches!(self.ownership(), Ownership::Owning) {
+            // If this type is owned or if ownership is preferred then the owned
+            // variant is used as a priority. This is where the generator's
+            // configuration comes into play.
+            result.push((self.result_name(ty), a));
+        } else {
+            // And finally, failing all that, the borrowed variant is used.
+            assert!(!info.owned);
+            result.push((self.param_name(ty), b));
+        }
+        result
+    }
+
+    fn param_name(&self, ty: TypeId) -> String {
+        let info = self.info(ty);
+        let name = self.upper_camel_case(&self.type_name(ty));
+        if self.uses_two_names(&info) {
+            format!("{}Param", name)
+        } else {
+            name
+        }
+    }
+
+    fn result_name(&self, ty: TypeId) -> String {
+        let info = self.info(ty);
+        let name = self.upper_camel_case(&self.type_name(ty));
+        if self.uses_two_names(&info) {
+            format!("{}Result", name)
+        } else {
+            name
+        }
+    }
+
+    fn uses_two_names(&self, info: &TypeInfo) -> bool {
+        // Types are only duplicated if explicitly requested ...
+        matches!(
+            self.ownership(),
+            Ownership::Borrowing {
+                duplicate_if_necessary: true
+            }
+        )
+            // ... and if they're both used in a borrowed/owned context
+            && info.borrowed
+            && info.owned
+            // ... and they have a list ...
+            && info.has_list
+            // ... and if there's NOT an `own` handle since those are always
+            // done by ownership.
+            && !info.has_own_handle
+    }
+
+    /// Prints the `#[derive]` attribute for a type with the `info` specified,
+    /// in addition to the `extra` derives requested.
+    fn print_derives(&mut self, info: &TypeInfo, extra: &[&str]) {
+        // We use a BTree set to make sure we don't have any duplicates and we have a stable order
+        let mut derives: BTreeSet<String> = self.additional_derives().iter().cloned().collect();
+        derives.extend(extra.iter().map(|s| s.to_string()));
+        if info.is_copy() {
+            derives.extend(["Copy", "Clone"].into_iter().map(|s| s.to_string()));
+        } else if info.is_clone() {
+            derives.insert("Clone".to_string());
+        }
+        if !derives.is_empty() {
+            self.push_str("#[derive(");
+            self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
+            self.push_str(")]\n")
+        }
+    }
+
+    fn print_typedef_record(&mut self, id: TypeId, record: &Record, docs: &Docs) {
+        let info = self.info(id);
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            if info.is_copy() {
+                self.push_str("#[repr(C)]\n");
+            }
+            self.print_derives(&info, &[]);
+            self.push_str(&format!("pub struct {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str(" {\n");
+            for field in record.fields.iter() {
+                self.rustdoc(&field.docs);
+                self.push_str("pub ");
+                self.push_str(&to_rust_ident(&field.name));
+                self.push_str(": ");
+                let mode = self.filter_mode(&field.ty, mode);
+                self.print_ty(&field.ty, mode);
+                self.push_str(",\n");
+            }
+            self.push_str("}\n");
+
+            self.push_str("impl");
+            self.print_generics(mode.lifetime);
+            self.push_str(" ::core::fmt::Debug for ");
+            self.push_str(&name);
+            self.print_generics(mode.lifetime);
+            self.push_str(" {\n");
+            self.push_str(
+                "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+            );
+            self.push_str(&format!("f.debug_struct(\"{}\")", name));
+            for field in record.fields.iter() {
+                self.push_str(&format!(
+                    ".field(\"{}\", &self.{})",
+                    field.name,
+                    to_rust_ident(&field.name)
+                ));
+            }
+            self.push_str(".finish()\n");
+            self.push_str("}\n");
+            self.push_str("}\n");
+
+            if info.error {
+                self.push_str("impl");
+                self.print_generics(mode.lifetime);
+                self.push_str(" ::core::fmt::Display for ");
+                self.push_str(&name);
+                self.print_generics(mode.lifetime);
+                self.push_str(" {\n");
+                self.push_str(
+                    "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+                );
+                self.push_str("write!(f, \"{:?}\", self)\n");
+                self.push_str("}\n");
+                self.push_str("}\n");
+                if self.std_feature() {
+                    self.push_str("#[cfg(feature = \"std\")]\n");
+                }
+                self.push_str("impl std::error::Error for ");
+                self.push_str(&name);
+                self.push_str(" {}\n");
+            }
+        }
+    }
+
+    fn print_typedef_tuple(&mut self, id: TypeId, tuple: &Tuple, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str(" = (");
+            for ty in tuple.types.iter() {
+                let mode = self.filter_mode(ty, mode);
+                self.print_ty(ty, mode);
+                self.push_str(",");
+            }
+            self.push_str(");\n");
+        }
+    }
+
+    fn print_typedef_variant(&mut self, id: TypeId, variant: &Variant, docs: &Docs) {
+        self.print_rust_enum(
+            id,
+            variant
+                .cases
+                .iter()
+                .map(|c| (c.name.to_upper_camel_case(), &c.docs, c.ty.as_ref())),
+            docs,
+        );
+    }
+
+    fn print_rust_enum<'b>(
+        &mut self,
+        id: TypeId,
+        cases: impl IntoIterator<Item = (String, &'b Docs, Option<&'b Type>)> + Clone,
+        docs: &Docs,
+    ) {
+        let info = self.info(id);
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.print_derives(&info, &[]);
+            self.push_str(&format!("pub enum {name}"));
+            self.print_generics(mode.lifetime);
+            self.push_str(" {\n");
+            for (case_name, docs, payload) in cases.clone() {
+                self.rustdoc(docs);
+                self.push_str(&case_name);
+                if let Some(ty) = payload {
+                    self.push_str("(");
+                    let mode = self.filter_mode(ty, mode);
+                    self.print_ty(ty, mode);
+                    self.push_str(")")
+                }
+                self.push_str(",\n");
+            }
+            self.push_str("}\n");
+
+            self.print_rust_enum_debug(
+                mode,
+                &name,
+                cases
+                    .clone()
+                    .into_iter()
+                    .map(|(name, _docs, ty)| (name, ty)),
+            );
+
+            if info.error {
+                self.push_str("impl");
+                self.print_generics(mode.lifetime);
+                self.push_str(" ::core::fmt::Display for ");
+                self.push_str(&name);
+                self.print_generics(mode.lifetime);
+                self.push_str(" {\n");
+                self.push_str(
+                    "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+                );
+                self.push_str("write!(f, \"{:?}\", self)\n");
+                self.push_str("}\n");
+                self.push_str("}\n");
+                self.push_str("\n");
+
+                if self.std_feature() {
+                    self.push_str("#[cfg(feature = \"std\")]\n");
+                }
+                self.push_str("impl");
+                self.print_generics(mode.lifetime);
+                self.push_str(" std::error::Error for ");
+                self.push_str(&name);
+                self.print_generics(mode.lifetime);
+                self.push_str(" {}\n");
+            }
+        }
+    }
+
+    fn print_rust_enum_debug<'b>(
+        &mut self,
+        mode: TypeMode,
+        name: &str,
+        cases: impl IntoIterator<Item = (String, Option<&'b Type>)>,
+    ) {
+        self.push_str("impl");
+        self.print_generics(mode.lifetime);
+        self.push_str(" ::core::fmt::Debug for ");
+        self.push_str(name);
+        self.print_generics(mode.lifetime);
+        self.push_str(" {\n");
+        self.push_str(
+            "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+        );
+        self.push_str("match self {\n");
+        for (case_name, payload) in cases {
+            self.push_str(name);
+            self.push_str("::");
+            self.push_str(&case_name);
+            if payload.is_some() {
+                self.push_str("(e)");
+            }
+            self.push_str(" => {\n");
+            self.push_str(&format!("f.debug_tuple(\"{}::{}\")", name, case_name));
+            if payload.is_some() {
+                self.push_str(".field(e)");
+            }
+            self.push_str(".finish()\n");
+            self.push_str("}\n");
+        }
+        self.push_str("}\n");
+        self.push_str("}\n");
+        self.push_str("}\n");
+    }
+
+    fn print_typedef_option(&mut self, id: TypeId, payload: &Type, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str("= Option<");
+            self.print_ty(payload, mode);
+            self.push_str(">;\n");
+        }
+    }
+
+    fn print_typedef_result(&mut self, id: TypeId, result: &Result_, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str("= Result<");
+            self.print_optional_ty(result.ok.as_ref(), mode);
+            self.push_str(",");
+            self.print_optional_ty(result.err.as_ref(), mode);
+            self.push_str(">;\n");
+        }
+    }
+
+    fn print_typedef_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        let info = self.info(id);
+
+        let name = self.upper_camel_case(name);
+        self.rustdoc(docs);
+        self.push_str("#[repr(");
+        self.push_str(int_repr(enum_.tag()));
+        self.push_str(")]\n");
+        // We use a BTree set to make sure we don't have any duplicates and a stable order
+        let mut derives: BTreeSet<String> = self.additional_derives().iter().cloned().collect();
+        derives.extend(
+            ["Clone", "Copy", "PartialEq", "Eq"]
+                .into_iter()
+                .map(|s| s.to_string()),
+        );
+        self.push_str("#[derive(");
+        self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
+        self.push_str(")]\n");
+        self.push_str(&format!("pub enum {name} {{\n"));
+        for case in enum_.cases.iter() {
+            self.rustdoc(&case.docs);
+            self.push_str(&case.name.to_upper_camel_case());
+            self.push_str(",\n");
+        }
+        self.push_str("}\n");
+
+        // Auto-synthesize an implementation of the standard `Error` trait for
+        // error-looking types based on their name.
+        if info.error {
+            self.push_str("impl ");
+            self.push_str(&name);
+            self.push_str("{\n");
+
+            self.push_str("pub fn name(&self) -> &'static str {\n");
+            self.push_str("match self {\n");
+            for case in enum_.cases.iter() {
+                self.push_str(&name);
+                self.push_str("::");
+                self.push_str(&case.name.to_upper_camel_case());
+                self.push_str(" => \"");
+                self.push_str(case.name.as_str());
+                self.push_str("\",\n");
+            }
+            self.push_str("}\n");
+            self.push_str("}\n");
+
+            self.push_str("pub fn message(&self) -> &'static str {\n");
+            self.push_str("match self {\n");
+            for case in enum_.cases.iter() {
+                self.push_str(&name);
+                self.push_str("::");
+                self.push_str(&case.name.to_upper_camel_case());
+                self.push_str(" => \"");
+                if let Some(contents) = &case.docs.contents {
+                    self.push_str(contents.trim());
+                }
+                self.push_str("\",\n");
+            }
+            self.push_str("}\n");
+            self.push_str("}\n");
+
+            self.push_str("}\n");
+
+            self.push_str("impl ::core::fmt::Debug for ");
+            self.push_str(&name);
+            self.push_str(
+                "{\nfn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+            );
+            self.push_str("f.debug_struct(\"");
+            self.push_str(&name);
+            self.push_str("\")\n");
+            self.push_str(".field(\"code\", &(*self as i32))\n");
+            self.push_str(".field(\"name\", &self.name())\n");
+            self.push_str(".field(\"message\", &self.message())\n");
+            self.push_str(".finish()\n");
+            self.push_str("}\n");
+            self.push_str("}\n");
+
+            self.push_str("impl ::core::fmt::Display for ");
+            self.push_str(&name);
+            self.push_str(
+                "{\nfn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+            );
+            self.push_str("write!(f, \"{} (error {})\", self.name(), *self as i32)\n");
+            self.push_str("}\n");
+            self.push_str("}\n");
+            self.push_str("\n");
+            if self.std_feature() {
+                self.push_str("#[cfg(feature = \"std\")]\n");
+            }
+            self.push_str("impl std::error::Error for ");
+            self.push_str(&name);
+            self.push_str(" {}\n");
+        } else {
+            self.print_rust_enum_debug(
+                TypeMode::owned(),
+                &name,
+                enum_
+                    .cases
+                    .iter()
+                    .map(|c| (c.name.to_upper_camel_case(), None)),
+            )
+        }
+    }
+
+    fn print_typedef_alias(&mut self, id: TypeId, ty: &Type, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {name}"));
+            self.print_generics(mode.lifetime);
+            self.push_str(" = ");
+            self.print_ty(ty, mode);
+            self.push_str(";\n");
+        }
+    }
+
+    fn print_typedef_list(&mut self, id: TypeId, ty: &Type, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str(" = ");
+            self.print_list(ty, mode);
+            self.push_str(";\n");
+        }
+    }
+
+    fn print_typedef_builtin(&mut self, name: &str, ty: &Type, docs: &Docs) {
+        self.rustdoc(docs);
+        self.push_str(&format!("pub type {}", name.to_upper_camel_case()));
+        self.push_str(" = ");
+        self.print_ty(ty, TypeMode::owned());
+        self.push_str(";\n");
+    }
+
+    /// Prints a flags type with more flags than the widest integer `bitflags`
+    /// supports as a bitset of `n` words, mirroring the API of `bitflags`.
+    ///
+    /// The `name` is the name of the Rust item to define.
+    fn print_flags_words(&mut self, name: &str, flags: &Flags, docs: &Docs, n: usize) {
+        self.rustdoc(docs);
+        let mut src = Source::default();
+        uwriteln!(
+            src,
+            "#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+            pub struct {name} {{
+                bits: [u32; {n}],
+            }}"
+        );
+        self.push_str(&src);
+
+        self.push_str(&format!("impl {name} {{\n"));
+        for (i, flag) in flags.flags.iter().enumerate() {
+            self.rustdoc(&flag.docs);
+            self.push_str(&format!(
+                "pub const {}: Self = Self::from_bit({i});\n",
+                flag.name.to_shouty_snake_case(),
+            ));
+        }
+
+        let all = (0..n)
+            .map(|word| {
+                let bits = flags.flags.len().saturating_sub(word * 32).min(32);
+                format!("{:#x}", u32::MAX >> (32 - bits))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut src = Source::default();
+        uwriteln!(
+            src,
+            "
+                const fn from_bit(bit: usize) -> Self {{
+                    let mut bits = [0; {n}];
+                    bits[bit / 32] = 1 << (bit % 32);
+                    Self {{ bits }}
+                }}
+
+                fn zip_with(self, other: Self, f: impl Fn(u32, u32) -> u32) -> Self {{
+                    let mut bits = self.bits;
+                    for (a, b) in bits.iter_mut().zip(other.bits) {{
+                        *a = f(*a, b);
+                    }}
+                    Self {{ bits }}
+                }}
+
+                /// Get a flags value with all bits unset.
+                #[inline]
+                pub const fn empty() -> Self {{
+                    Self {{ bits: [0; {n}] }}
+                }}
+
+                /// Get a flags value with all known bits set.
+                #[inline]
+                pub const fn all() -> Self {{
+                    Self {{ bits: [{all}] }}
+                }}
+
+                /// Get the underlying bits value.
+                #[inline]
+                pub const fn bits(&self) -> [u32; {n}] {{
+                    self.bits
+                }}
+
+                /// Convert from a bits value, unsetting any unknown bits.
+                #[inline]
+                pub fn from_bits_truncate(bits: [u32; {n}]) -> Self {{
+                    Self::from_bits_retain(bits).intersection(Self::all())
+                }}
+
+                /// Convert from a bits value exactly, returning `None` if any
+                /// unknown bits are set.
+                #[inline]
+                pub fn from_bits(bits: [u32; {n}]) -> Option<Self> {{
+                    let truncated = Self::from_bits_truncate(bits);
+                    if truncated.bits == bits {{
+                        Some(truncated)
+                    }} else {{
+                        None
+                    }}
+                }}
+
+                /// Convert from a bits value exactly.
+                #[inline]
+                pub const fn from_bits_retain(bits: [u32; {n}]) -> Self {{
+                    Self {{ bits }}
+                }}
+
+                /// Whether all bits in this flags value are unset.
+                #[inline]
+                pub fn is_empty(&self) -> bool {{
+                    self.bits.iter().all(|word| *word == 0)
+                }}
+
+                /// Whether all known bits in this flags value are set.
+                #[inline]
+                pub fn is_all(&self) -> bool {{
+                    self.contains(Self::all())
+                }}
+
+                /// Whether any set bits in `other` are also set in `self`.
+                #[inline]
+                pub fn intersects(&self, other: Self) -> bool {{
+                    !self.intersection(other).is_empty()
+                }}
+
+                /// Whether all set bits in `other` are also set in `self`.
+                #[inline]
+                pub fn contains(&self, other: Self) -> bool {{
+                    self.intersection(other) == other
+                }}
+
+                /// The bitwise or (`|`) of the bits in `self` and `other`.
+                #[inline]
+                pub fn insert(&mut self, other: Self) {{
+                    *self = self.union(other);
+                }}
+
+                /// The intersection of `self` with the complement of `other`.
+                #[inline]
+                pub fn remove(&mut self, other: Self) {{
+                    *self = self.difference(other);
+                }}
+
+                /// The bitwise exclusive-or (`^`) of the bits in `self` and `other`.
+                #[inline]
+                pub fn toggle(&mut self, other: Self) {{
+                    *self = self.symmetric_difference(other);
+                }}
+
+                /// Call `insert` when `value` is `true` or `remove` when `value` is `false`.
+                #[inline]
+                pub fn set(&mut self, other: Self, value: bool) {{
+                    if value {{
+                        self.insert(other);
+                    }} else {{
+                        self.remove(other);
+                    }}
+                }}
+
+                /// The bitwise and (`&`) of the bits in `self` and `other`.
+                #[inline]
+                #[must_use]
+                pub fn intersection(self, other: Self) -> Self {{
+                    self.zip_with(other, |a, b| a & b)
+                }}
+
+                /// The bitwise or (`|`) of the bits in `self` and `other`.
+                #[inline]
+                #[must_use]
+                pub fn union(self, other: Self) -> Self {{
+                    self.zip_with(other, |a, b| a | b)
+                }}
+
+                /// The intersection of `self` with the complement of `other`.
+                #[inline]
+                #[must_use]
+                pub fn difference(self, other: Self) -> Self {{
+                    self.zip_with(other, |a, b| a & !b)
+                }}
+
+                /// The bitwise exclusive-or (`^`) of the bits in `self` and `other`.
+                #[inline]
+                #[must_use]
+                pub fn symmetric_difference(self, other: Self) -> Self {{
+                    self.zip_with(other, |a, b| a ^ b)
+                }}
+
+                /// The bitwise negation (`!`) of the bits in `self`, truncating the result.
+                #[inline]
+                #[must_use]
+                pub fn complement(self) -> Self {{
+                    Self::all().difference(self)
+                }}
+            }}
+            "
+        );
+
+        for (op, op_fn, method) in [
+            ("BitOr", "bitor", "union"),
+            ("BitAnd", "bitand", "intersection"),
+            ("BitXor", "bitxor", "symmetric_difference"),
+            ("Sub", "sub", "difference"),
+        ] {
+            uwriteln!(
+                src,
+                "
+                impl ::core::ops::{op} for {name} {{
+                    type Output = Self;
+
+                    #[inline]
+                    fn {op_fn}(self, other: Self) -> Self {{
+                        self.{method}(other)
+                    }}
+                }}
+
+                impl ::core::ops::{op}Assign for {name} {{
+                    #[inline]
+                    fn {op_fn}_assign(&mut self, other: Self) {{
+                        *self = self.{method}(other);
+                    }}
+                }}
+                "
+            );
+        }
+
+        uwriteln!(
+            src,
+            "
+            impl ::core::ops::Not for {name} {{
+                type Output = Self;
+
+                #[inline]
+                fn not(self) -> Self {{
+                    self.complement()
+                }}
+            }}
+            "
+        );
+        self.push_str(&src);
+    }
+}
+
+/// The pieces of lifting and lowering values which are the same for the
+/// function bindings of both the guest and the host.
+pub trait RustFunctionGenerator {
+    fn push_str(&mut self, s: &str);
+
+    fn tmp(&mut self) -> usize;
+
+    fn lift_lower(&self) -> LiftLower;
+
+    /// Returns the path of the type `id`, owned if `owned` is true.
+    fn type_path(&self, id: TypeId, owned: bool) -> String;
+
+    fn let_results(&mut self, amt: usize, results: &mut Vec<String>) {
+        match amt {
+            0 => {}
+            1 => {
+                let tmp = self.tmp();
+                let res = format!("result{}", tmp);
+                self.push_str("let ");
+                self.push_str(&res);
+                results.push(res);
+                self.push_str(" = ");
+            }
+            n => {
+                let tmp = self.tmp();
+                self.push_str("let (");
+                for i in 0..n {
+                    let arg = format!("result{}_{}", tmp, i);
+                    self.push_str(&arg);
+                    self.push_str(",");
+                    results.push(arg);
+                }
+                self.push_str(") = ");
+            }
+        }
+    }
+
+    fn record_lower(
+        &mut self,
+        id: TypeId,
+        record: &Record,
+        operand: &str,
+        results: &mut Vec<String>,
+    ) {
+        let tmp = self.tmp();
+        self.push_str("let ");
+        let name = self.typename_lower(id);
+        self.push_str(&name);
+        self.push_str("{ ");
+        for field in record.fields.iter() {
+            let name = to_rust_ident(&field.name);
+            let arg = format!("{}{}", name, tmp);
+            self.push_str(&name);
+            self.push_str(":");
+            self.push_str(&arg);
+            self.push_str(", ");
+            results.push(arg);
+        }
+        self.push_str("} = ");
+        self.push_str(operand);
+        self.push_str(";\n");
+    }
+
+    fn record_lift(
+        &mut self,
+        id: TypeId,
+        ty: &Record,
+        operands: &[String],
+        results: &mut Vec<String>,
+    ) {
+        let mut result = self.typename_lift(id);
+        result.push_str("{\n");
+        for (field, val) in ty.fields.iter().zip(operands) {
+            result.push_str(&to_rust_ident(&field.name));
+            result.push_str(": ");
+            result.push_str(val);
+            result.push_str(",\n");
+        }
+        result.push('}');
+        results.push(result);
+    }
+
+    fn tuple_lower(&mut self, tuple: &Tuple, operand: &str, results: &mut Vec<String>) {
+        let tmp = self.tmp();
+        self.push_str("let (");
+        for i in 0..tuple.types.len() {
+            let arg = format!("t{}_{}", tmp, i);
+            self.push_str(&arg);
+            self.push_str(", ");
+            results.push(arg);
+        }
+        self.push_str(") = ");
+        self.push_str(operand);
+        self.push_str(";\n");
+    }
+
+    fn tuple_lift(&mut self, operands: &[String], results: &mut Vec<String>) {
+        if operands.len() == 1 {
+            results.push(format!("({},)", operands[0]));
+        } else {
+            results.push(format!("({})", operands.join(", ")));
+        }
+    }
+
+    /// Returns the path of the type `id` for values which are lowered, which
+    /// are borrowed when they're arguments.
+    fn typename_lower(&self, id: TypeId) -> String {
+        let owned = match self.lift_lower() {
+            LiftLower::LowerArgsLiftResults => false,
+            LiftLower::LiftArgsLowerResults => true,
+        };
+        self.type_path(id, owned)
+    }
+
+    fn typename_lift(&self, id: TypeId) -> String {
+        self.type_path(id, true)
+    }
+}
@@ -1,11 +1,15 @@
 use crate::bindgen::FunctionBindgen;
+use crate::common::{
+    compute_module_path, group_by_resource, int_repr, Identifier, InterfaceNames, RustGenerator,
+    TypeMode, TypeOwnershipStyle,
+};
 use crate::{
-    int_repr, to_rust_ident, to_upper_camel_case, wasm_type, FnSig, Identifier, InterfaceName,
-    Ownership, RuntimeItem, RustFlagsRepr, RustWasm,
+    anonymous_type_name, to_rust_ident, to_upper_camel_case, wasm_type, FnSig, Ownership,
+    RuntimeItem, RustFlagsRepr, RustWasm,
 };
 use anyhow::Result;
 use heck::*;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 use std::fmt::Write as _;
 use std::mem;
 use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
@@ -24,107 +28,6 @@ pub struct InterfaceGenerator<'a> {
     pub(super) needs_runtime_module: bool,
 }
 
-/// A description of the "mode" in which a type is printed.
-///
-/// Rust types can either be "borrowed" or "owned". This primarily has to do
-/// with lists and imports where arguments to imports can be borrowed lists in
-/// theory as ownership is not taken from the caller. This structure is used to
-/// help with this fact in addition to the various codegen options of this
-/// generator. Namely types in WIT can be reflected into Rust as two separate
-/// types, one "owned" and one "borrowed" (aka one with `Vec` and one with
-/// `&[T]`).
-///
-/// This structure is used in conjunction with `modes_of` and `type_mode_for*`
-/// primarily. That enables creating a programmatic description of what a type
-/// is rendered as along with various options.
-///
-/// Note that a `TypeMode` is a description of a single "level" of a type. This
-/// means that there's one mode for `Vec<T>` and one mode for `T` internally.
-/// This is mostly used for things like records where some fields have lifetime
-/// parameters, for example, and others don't.
-///
-/// This type is intended to simplify generation of types and encapsulate all
-/// the knowledge about whether lifetime parameters are used and how lists are
-/// rendered.
-///
-/// There are currently two users of lifetime parameters:
-///
-/// * Lists - when borrowed these are rendered as either `&[T]` or `&str`.
-/// * Borrowed resources - for resources owned by the current module they're
-///   represented as `&T` and for borrows of imported resources they're
-///   represented, more-or-less, as `&Resource<T>`.
-///
-/// Lists have a choice of being rendered as borrowed or not but resources are
-/// required to be borrowed.
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct TypeMode {
-    /// The lifetime parameter, if any, for this type. If present this type is
-    /// required to have a lifetime parameter.
-    lifetime: Option<&'static str>,
-
-    /// Whether or not lists are borrowed in this type.
-    ///
-    /// If this field is `true` then lists are rendered as `&[T]` and `&str`
-    /// rather than their owned equivalent. If this field is `false` than the
-    /// owned equivalents are used instead.
-    lists_borrowed: bool,
-
-    /// The "style" of ownership that this mode was created with.
-    ///
-    /// This information is used to determine what mode the next layer deep int
-    /// he type tree is rendered with. For example if this layer is owned so is
-    /// the next layer. This is primarily used for the "OnlyTopBorrowed"
-    /// ownership style where all further layers beneath that are `Owned`.
-    style: TypeOwnershipStyle,
-}
-
-/// The style of ownership of a type, used to initially create a `TypeMode` and
-/// stored internally within it as well.
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum TypeOwnershipStyle {
-    /// This style means owned things are printed such as `Vec<T>` and `String`.
-    ///
-    /// Note that this primarily applies to lists.
-    Owned,
-
-    /// This style means that lists/strings are `&[T]` and `&str`.
-    ///
-    /// Note that this primarily applies to lists.
-    Borrowed,
-
-    /// This style means that the top-level of a type is borrowed but all other
-    /// layers are `Owned`.
-    ///
-    /// This is used for parameters in the "owning" mode of generation to
-    /// imports. It's easy enough to create a `&T` at the root layer but it's
-    /// more difficult to create `&T` stored within a `U`, for example.
-    OnlyTopBorrowed,
-}
-
-impl TypeMode {
-    /// Returns a mode where everything is indicated that it's supposed to be
-    /// rendered as an "owned" type.
-    fn owned() -> TypeMode {
-        TypeMode {
-            lifetime: None,
-            lists_borrowed: false,
-            style: TypeOwnershipStyle::Owned,
-        }
-    }
-}
-
-impl TypeOwnershipStyle {
-    /// Preserves this mode except for `OnlyTopBorrowed` where it switches it to
-    /// `Owned`.
-    fn next(&self) -> TypeOwnershipStyle {
-        match self {
-            TypeOwnershipStyle::Owned => TypeOwnershipStyle::Owned,
-            TypeOwnershipStyle::Borrowed => TypeOwnershipStyle::Borrowed,
-            TypeOwnershipStyle::OnlyTopBorrowed => TypeOwnershipStyle::Owned,
-        }
-    }
-}
-
 impl InterfaceGenerator<'_> {
     pub(super) fn generate_exports<'a>(
         &mut self,
@@ -138,14 +41,20 @@ impl InterfaceGenerator<'_> {
         traits.insert(None, ("Guest".to_string(), Vec::new()));
 
         if let Some((id, _)) = interface {
-            for (name, id) in self.resolve.interfaces[id].types.iter() {
-                match self.resolve.types[*id].kind {
+            let types = self.resolve.interfaces[id]
+                .types
+                .iter()
+                .map(|(name, id)| (name.clone(), *id))
+                .chain(self.anonymous_types(TypeOwner::Interface(id)))
+                .collect::<Vec<_>>();
+            for (name, id) in types {
+                match self.resolve.types[id].kind {
                     TypeDefKind::Resource => {}
                     _ => continue,
                 }
-                resources_to_drop.push(name);
                 let camel = name.to_upper_camel_case();
-                traits.insert(Some(*id), (format!("Guest{camel}"), Vec::new()));
+                resources_to_drop.push(name);
+                traits.insert(Some(id), (format!("Guest{camel}"), Vec::new()));
             }
         }
 
@@ -167,6 +76,7 @@ impl InterfaceGenerator<'_> {
 
             let prev = mem::take(&mut self.src);
             let mut sig = FnSig {
+                async_: self.gen.opts.async_exports,
                 use_item_name: true,
                 private: true,
                 ..Default::default()
@@ -175,6 +85,12 @@ impl InterfaceGenerator<'_> {
                 sig.self_arg = Some("&self".into());
                 sig.self_is_first_param = true;
             }
+            if sig.async_ {
+                // Bounds on the returned future can't be expressed with
+                // `async fn`, but the futures are only ever polled to
+                // completion in place by the generated bindings.
+                self.src.push_str("#[allow(async_fn_in_trait)]\n");
+            }
             self.print_signature(func, true, &sig);
             self.src.push_str(";\n");
             let trait_method = mem::replace(&mut self.src, prev);
@@ -195,7 +111,7 @@ impl InterfaceGenerator<'_> {
         for (resource, (trait_name, methods)) in traits.iter() {
             uwriteln!(self.src, "pub trait {trait_name}: 'static {{");
             let resource = resource.unwrap();
-            let resource_name = self.resolve.types[resource].name.as_ref().unwrap();
+            let resource_name = self.type_name(resource);
             let (_, interface_name) = interface.unwrap();
             let module = self.resolve.name_world_key(interface_name);
             uwriteln!(
@@ -294,11 +210,7 @@ macro_rules! {macro_name} {{
             let ty = match resource {
                 None => "$ty".to_string(),
                 Some(id) => {
-                    let name = self.resolve.types[id]
-                        .name
-                        .as_ref()
-                        .unwrap()
-                        .to_upper_camel_case();
+                    let name = self.type_name(id).to_upper_camel_case();
                     format!("<$ty as $($path_to_types)*::Guest>::{name}")
                 }
             };
@@ -342,11 +254,7 @@ macro_rules! {macro_name} {{
     ) {
         uwriteln!(self.src, "pub trait {trait_name} {{");
         for (id, trait_name) in resource_traits {
-            let name = self.resolve.types[id]
-                .name
-                .as_ref()
-                .unwrap()
-                .to_upper_camel_case();
+            let name = self.type_name(id).to_upper_camel_case();
             uwriteln!(self.src, "type {name}: {trait_name};");
         }
         for method in methods {
@@ -386,29 +294,6 @@ macro_rules! {macro_name} {{
         src
     }
 
-    fn path_to_root(&self) -> String {
-        let mut path_to_root = String::new();
-
-        if let Identifier::Interface(_, key) = self.identifier {
-            // Escape the submodule for this interface
-            path_to_root.push_str("super::");
-
-            // Escape the `exports` top-level submodule
-            if !self.in_import {
-                path_to_root.push_str("super::");
-            }
-
-            // Escape the namespace/package submodules for interface-based ids
-            match key {
-                WorldKey::Name(_) => {}
-                WorldKey::Interface(_) => {
-                    path_to_root.push_str("super::super::");
-                }
-            }
-        }
-        path_to_root
-    }
-
     pub fn start_append_submodule(&mut self, name: &WorldKey) -> (String, Vec<String>) {
         let snake = match name {
             WorldKey::Name(name) => to_rust_ident(name),
@@ -416,7 +301,7 @@ macro_rules! {macro_name} {{
                 to_rust_ident(self.resolve.interfaces[*id].name.as_ref().unwrap())
             }
         };
-        let module_path = crate::compute_module_path(name, &self.resolve, !self.in_import);
+        let module_path = compute_module_path(name, &self.resolve, !self.in_import);
         (snake, module_path)
     }
 
@@ -452,8 +337,7 @@ macro_rules! {macro_name} {{
         match func.kind {
             FunctionKind::Freestanding => {}
             FunctionKind::Method(id) | FunctionKind::Static(id) | FunctionKind::Constructor(id) => {
-                let name = self.resolve.types[id].name.as_ref().unwrap();
-                let name = to_upper_camel_case(name);
+                let name = to_upper_camel_case(&self.type_name(id));
                 uwriteln!(self.src, "impl {name} {{");
                 sig.use_item_name = true;
                 if let FunctionKind::Method(_) = &func.kind {
@@ -674,7 +558,7 @@ macro_rules! {macro_name} {{
         interface: Option<(InterfaceId, &WorldKey)>,
         funcs: impl Iterator<Item = &'a Function> + Clone,
     ) {
-        let mut funcs = super::group_by_resource(funcs.clone());
+        let mut funcs = group_by_resource(funcs.clone());
 
         let root_methods = funcs.remove(&None).unwrap_or(Vec::new());
 
@@ -682,15 +566,20 @@ macro_rules! {macro_name} {{
         let guest_trait = match interface {
             Some((id, _)) => {
                 let path = self.path_to_interface(id).unwrap();
-                for (name, id) in self.resolve.interfaces[id].types.iter() {
-                    match self.resolve.types[*id].kind {
+                let types = self.resolve.interfaces[id]
+                    .types
+                    .iter()
+                    .map(|(name, id)| (name.clone(), *id))
+                    .chain(self.anonymous_types(TypeOwner::Interface(id)));
+                for (name, id) in types.collect::<Vec<_>>() {
+                    match self.resolve.types[id].kind {
                         TypeDefKind::Resource => {}
                         _ => continue,
                     }
                     let camel = name.to_upper_camel_case();
                     uwriteln!(extra_trait_items, "type {camel} = Stub;");
 
-                    let resource_methods = funcs.remove(&Some(*id)).unwrap_or(Vec::new());
+                    let resource_methods = funcs.remove(&Some(id)).unwrap_or(Vec::new());
                     let trait_name = format!("{path}::Guest{camel}");
                     self.generate_stub_impl(&trait_name, "", &resource_methods);
                 }
@@ -721,6 +610,7 @@ macro_rules! {macro_name} {{
                 continue;
             }
             let mut sig = FnSig {
+                async_: self.gen.opts.async_exports,
                 use_item_name: true,
                 private: true,
                 ..Default::default()
@@ -736,21 +626,6 @@ macro_rules! {macro_name} {{
         self.src.push_str("}\n");
     }
 
-    fn rustdoc(&mut self, docs: &Docs) {
-        let docs = match &docs.contents {
-            Some(docs) => docs,
-            None => return,
-        };
-        for line in docs.trim().lines() {
-            self.push_str("///");
-            if !line.is_empty() {
-                self.push_str(" ");
-                self.push_str(line);
-            }
-            self.push_str("\n");
-        }
-    }
-
     fn rustdoc_params(&mut self, docs: &[(String, Type)], header: &str) {
         let _ = (docs, header);
         // let docs = docs
@@ -927,901 +802,25 @@ macro_rules! {macro_name} {{
         }
     }
 
-    /// Calculates the `TypeMode` to be used for the `ty` specified.
-    ///
-    /// This takes a `style` argument which is the requested style of ownership
-    /// for this type. Note that the returned `TypeMode` may have a different
-    /// `style`.
-    ///
-    /// This additionally takes a `lt` parameter which, if needed, is what will
-    /// be used to render lifetimes.
-    fn type_mode_for(&self, ty: &Type, style: TypeOwnershipStyle, lt: &'static str) -> TypeMode {
-        match ty {
-            Type::Id(id) => self.type_mode_for_id(*id, style, lt),
-
-            // Borrowed strings are handled specially here since they're the
-            // only list-like primitive.
-            Type::String if style != TypeOwnershipStyle::Owned => TypeMode {
-                lifetime: Some(lt),
-                lists_borrowed: true,
-                style,
-            },
-
-            _ => TypeMode::owned(),
-        }
-    }
-
-    /// Same as `type_mode_for`, but specifically for `TypeId` which refers to a
-    /// type.
-    fn type_mode_for_id(
-        &self,
-        ty: TypeId,
-        style: TypeOwnershipStyle,
-        lt: &'static str,
-    ) -> TypeMode {
-        // NB: This method is the heart of determining how to render types.
-        // There's a lot of permutations and corner cases to handle, especially
-        // with being able to configure at the generator level how types are
-        // generated. Long story short this is a subtle and complicated method.
-        //
-        // The hope is that most of the complexity around type generation in
-        // Rust is largely centered here where everything else can lean on this.
-        // This has gone through so many refactors I've lost count at this
-        // point, but maybe this one is the one that'll stick!
-        //
-        // The general idea is that there's some clear-and-fast rules for how
-        // `TypeMode` must be returned here. For example borrowed handles are
-        // required to have a lifetime parameter. Everything else though is here
-        // to handle the various levels of configuration and semantics for each
-        // level of types.
-        //
-        // As a reminder a `TypeMode` is generated for each "level" of a type
-        // hierarchy, for example there's one mode for `Vec<T>` and another mode
-        // for `T`. This enables, for example, rendering the outer layer as
-        // either `Vec<T>` or `&[T]` but the inner `T` may or may not have a
-        // type parameter.
-
-        let info = self.info(ty);
-        let lifetime = if info.has_borrow_handle {
-            // Borrowed handles always have a lifetime associated with them so
-            // thread it through.
-            Some(lt)
-        } else if style == TypeOwnershipStyle::Owned {
-            // If this type is being rendered as an "owned" type, and it
-            // doesn't have any borrowed handles, then no lifetimes are needed
-            // since any internal lists will be their owned version.
-            None
-        } else if info.has_own_handle || !info.has_list {
-            // At this point there are no borrowed handles and a borrowed style
-            // of type is requested. In this situation there's two cases where a
-            // lifetime is never used:
-            //
-            // * Owned handles are present - in this situation ownership is used
-            //   to statically reflect how a value is lost when passed to an
-            //   import. This means that no lifetime is used for internal lists
-            //   since they must be rendered in an owned mode.
-            //
-            // * There are no lists present - here the lifetime parameter won't
-            //   be used for anything because there's no borrows or lists, so
-            //   it's skipped.
-            None
-        } else if !info.owned || self.uses_two_names(&info) {
-            // This next layer things get a little more interesting. To recap,
-            // so far we know that there's no borrowed handles, a borrowed mode
-            // is requested, there's no own handles, and there's a list. In that
-            // situation if `info` shows that this type is never used in an
-            // owned position, or if two types are explicitly requested for
-            // owned/borrowed values, then a lifetime is used.
-            Some(lt)
-        } else {
-            // ... and finally, here at the end we know:
-            //
-            // * No borrowed handles
-            // * Borrowing mode is requested
-            // * No owned handles
-            // * A list is somewhere
-            // * This type is used somewhere in an owned position
-            // * This type does not used "two names" meaning that we must use
-            //   the owned version of the type.
-            //
-            // If the configured ownership mode for generating types of this
-            // generator is "owning" then that's the only type that can be used.
-            // If borrowing is requested then this means that `&T` is going to
-            // be rendered, so thread it through.
-            //
-            // If the configured ownership mode uses borrowing by default, then
-            // things get a little weird. This means that a lifetime is going to
-            // be used an any lists should be borrowed, but we specifically
-            // switch to only borrowing the top layer of the type rather than
-            // the entire hierarchy. This situation can happen in
-            // `duplicate_if_necessary: false` mode for example where we're
-            // borrowing a type which is used in an owned position elsewhere.
-            // The only possibility at that point is to borrow it at the root
-            // but everything else internally is required to be owned from then
-            // on.
-            match self.gen.opts.ownership {
-                Ownership::Owning => Some(lt),
-                Ownership::Borrowing { .. } => {
-                    return TypeMode {
-                        lifetime: Some(lt),
-                        lists_borrowed: true,
-                        style: TypeOwnershipStyle::OnlyTopBorrowed,
-                    };
-                }
-            }
-        };
-        TypeMode {
-            lifetime,
-
-            // If a lifetime is present and ownership isn't requested, then make
-            // sure any lists show up as `&str` or `&[T]`.
-            lists_borrowed: lifetime.is_some() && style != TypeOwnershipStyle::Owned,
-
-            // Switch the style to `Owned` if an `own<T>` handle is present
-            // because there's no option but to take interior types by ownership
-            // as that statically shows that the ownership of the value is being
-            // lost.
-            style: if info.has_own_handle {
-                TypeOwnershipStyle::Owned
-            } else {
-                style
-            },
-        }
-    }
-
-    /// Generates the "next" mode for a type.
-    ///
-    /// The `ty` specified is the type that a mode is being generated for, and
-    /// the `mode` argument is the "parent" mode that the previous outer layer
-    /// of type was rendered with. The returned mode should be used to render
-    /// `ty`.
-    fn filter_mode(&self, ty: &Type, mode: TypeMode) -> TypeMode {
-        match mode.lifetime {
-            Some(lt) => self.type_mode_for(ty, mode.style.next(), lt),
-            None => TypeMode::owned(),
-        }
-    }
-
-    /// Same as `filder_mode` except if `mode` has the type `OnlyTopBorrowed`
-    /// the `mode` is specifically preserved as-is.
-    ///
-    /// This is used for types like `Option<T>` to render as `Option<&T>`
-    /// instead of `&Option<T>` for example.
-    fn filter_mode_preserve_top(&self, ty: &Type, mode: TypeMode) -> TypeMode {
-        if mode.style == TypeOwnershipStyle::OnlyTopBorrowed {
-            mode
-        } else {
-            self.filter_mode(ty, mode)
-        }
-    }
-
-    fn print_ty(&mut self, ty: &Type, mode: TypeMode) {
-        match ty {
-            Type::Id(t) => self.print_tyid(*t, mode),
-            Type::Bool => self.push_str("bool"),
-            Type::U8 => self.push_str("u8"),
-            Type::U16 => self.push_str("u16"),
-            Type::U32 => self.push_str("u32"),
-            Type::U64 => self.push_str("u64"),
-            Type::S8 => self.push_str("i8"),
-            Type::S16 => self.push_str("i16"),
-            Type::S32 => self.push_str("i32"),
-            Type::S64 => self.push_str("i64"),
-            Type::F32 => self.push_str("f32"),
-            Type::F64 => self.push_str("f64"),
-            Type::Char => self.push_str("char"),
-            Type::String => {
-                assert_eq!(mode.lists_borrowed, mode.lifetime.is_some());
-                match mode.lifetime {
-                    Some(lt) => self.print_borrowed_str(lt),
-                    None => {
-                        if self.gen.opts.raw_strings {
-                            self.push_vec_name();
-                            self.push_str("::<u8>");
-                        } else {
-                            self.push_string_name();
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    fn print_optional_ty(&mut self, ty: Option<&Type>, mode: TypeMode) {
-        match ty {
-            Some(ty) => {
-                let mode = self.filter_mode_preserve_top(ty, mode);
-                self.print_ty(ty, mode)
-            }
-            None => self.push_str("()"),
-        }
-    }
-
-    pub fn type_path(&self, id: TypeId, owned: bool) -> String {
-        self.type_path_with_name(
-            id,
-            if owned {
-                self.result_name(id)
-            } else {
-                self.param_name(id)
-            },
-        )
-    }
-
-    fn type_path_with_name(&self, id: TypeId, name: String) -> String {
-        if let TypeOwner::Interface(id) = self.resolve.types[id].owner {
-            if let Some(path) = self.path_to_interface(id) {
-                return format!("{path}::{name}");
-            }
-        }
-        name
-    }
-
-    fn print_tyid(&mut self, id: TypeId, mode: TypeMode) {
-        let ty = &self.resolve.types[id];
-        if ty.name.is_some() {
-            // NB: Most of the heavy lifting of `TypeMode` and what to do here
-            // has already happened in `type_mode_for*`. Here though a little
-            // more happens because this is where `OnlyTopBorrowed` is
-            // processed.
-            //
-            // Specifically what should happen is that in the case of an
-            // argument to an imported function if only the top value is
-            // borrowed then we want to render it as `&T`. If this all is
-            // applicable then the lifetime is rendered here before the type.
-            // The `mode` is then switched to `Owned` and recalculated for the
-            // type we're rendering here to avoid accidentally giving it a
-            // lifetime type parameter when it otherwise doesn't have it.
-            let mode = if mode.style == TypeOwnershipStyle::OnlyTopBorrowed {
-                if let Some(lt) = mode.lifetime {
-                    self.push_str("&");
-                    if lt != "'_" {
-                        self.push_str(lt);
-                        self.push_str(" ");
-                    }
-                    self.type_mode_for_id(id, TypeOwnershipStyle::Owned, lt)
-                } else {
-                    mode
-                }
-            } else {
-                mode
-            };
-            let name = self.type_path(
-                id,
-                match mode.style {
-                    TypeOwnershipStyle::Owned => true,
-                    TypeOwnershipStyle::OnlyTopBorrowed | TypeOwnershipStyle::Borrowed => false,
-                },
-            );
-            self.push_str(&name);
-            self.print_generics(mode.lifetime);
-            return;
-        }
-
-        match &ty.kind {
-            TypeDefKind::List(t) => self.print_list(t, mode),
-
-            TypeDefKind::Option(t) => {
-                self.push_str("Option<");
-                let mode = self.filter_mode_preserve_top(t, mode);
-                self.print_ty(t, mode);
-                self.push_str(">");
-            }
-
-            TypeDefKind::Result(r) => {
-                self.push_str("Result<");
-                self.print_optional_ty(r.ok.as_ref(), mode);
-                self.push_str(",");
-                self.print_optional_ty(r.err.as_ref(), mode);
-                self.push_str(">");
-            }
-
-            TypeDefKind::Variant(_) => panic!("unsupported anonymous variant"),
-
-            // Tuple-like records are mapped directly to Rust tuples of
-            // types. Note the trailing comma after each member to
-            // appropriately handle 1-tuples.
-            TypeDefKind::Tuple(t) => {
-                self.push_str("(");
-                for ty in t.types.iter() {
-                    let mode = self.filter_mode_preserve_top(ty, mode);
-                    self.print_ty(ty, mode);
-                    self.push_str(",");
-                }
-                self.push_str(")");
-            }
-            TypeDefKind::Resource => {
-                panic!("unsupported anonymous type reference: resource")
-            }
-            TypeDefKind::Record(_) => {
-                panic!("unsupported anonymous type reference: record")
-            }
-            TypeDefKind::Flags(_) => {
-                panic!("unsupported anonymous type reference: flags")
-            }
-            TypeDefKind::Enum(_) => {
-                panic!("unsupported anonymous type reference: enum")
-            }
-            TypeDefKind::Future(ty) => {
-                self.push_str("Future<");
-                self.print_optional_ty(ty.as_ref(), mode);
-                self.push_str(">");
-            }
-            TypeDefKind::Stream(stream) => {
-                self.push_str("Stream<");
-                self.print_optional_ty(stream.element.as_ref(), mode);
-                self.push_str(",");
-                self.print_optional_ty(stream.end.as_ref(), mode);
-                self.push_str(">");
-            }
-
-            TypeDefKind::Handle(Handle::Own(ty)) => {
-                self.print_ty(&Type::Id(*ty), mode);
-            }
-
-            TypeDefKind::Handle(Handle::Borrow(ty)) => {
-                assert!(mode.lifetime.is_some());
-                let lt = mode.lifetime.unwrap();
-                if self.is_exported_resource(*ty) {
-                    let camel = self.resolve.types[*ty]
-                        .name
-                        .as_deref()
-                        .unwrap()
-                        .to_upper_camel_case();
-                    let name = format!("{camel}Borrow");
-                    self.push_str(&self.type_path_with_name(*ty, name));
-                    self.push_str("<");
-                    self.push_str(lt);
-                    self.push_str(">");
-                } else {
-                    self.push_str("&");
-                    if lt != "'_" {
-                        self.push_str(lt);
-                        self.push_str(" ");
-                    }
-                    let ty = &Type::Id(*ty);
-                    let mode = self.filter_mode(ty, mode);
-                    self.print_ty(ty, mode);
-                }
-            }
-
-            TypeDefKind::Type(t) => self.print_ty(t, mode),
-
-            TypeDefKind::Unknown => unreachable!(),
-        }
-    }
-
-    fn print_list(&mut self, ty: &Type, mode: TypeMode) {
-        let next_mode = self.filter_mode(ty, mode);
-        if mode.lists_borrowed {
-            let lifetime = mode.lifetime.unwrap();
-            self.push_str("&");
-            if lifetime != "'_" {
-                self.push_str(lifetime);
-                self.push_str(" ");
-            }
-            self.push_str("[");
-            self.print_ty(ty, next_mode);
-            self.push_str("]");
-        } else {
-            self.push_vec_name();
-            self.push_str("::<");
-            self.print_ty(ty, next_mode);
-            self.push_str(">");
-        }
-    }
-
-    fn print_generics(&mut self, lifetime: Option<&str>) {
-        if lifetime.is_none() {
-            return;
-        }
-        self.push_str("<");
-        if let Some(lt) = lifetime {
-            self.push_str(lt);
-            self.push_str(",");
-        }
-        self.push_str(">");
-    }
-
-    fn int_repr(&mut self, repr: Int) {
-        self.push_str(int_repr(repr));
-    }
-
-    fn modes_of(&self, ty: TypeId) -> Vec<(String, TypeMode)> {
-        let info = self.info(ty);
-        // If this type isn't actually used, no need to generate it.
-        if !info.owned && !info.borrowed {
-            return Vec::new();
-        }
-        let mut result = Vec::new();
-
-        // Generate one mode for when the type is owned and another for when
-        // it's borrowed.
-        let a = self.type_mode_for_id(ty, TypeOwnershipStyle::Owned, "'a");
-        let b = self.type_mode_for_id(ty, TypeOwnershipStyle::Borrowed, "'a");
-
-        if self.uses_two_names(&info) {
-            // If this type uses two names then, well, it uses two names. In
-            // this situation both modes are returned.
-            assert!(a != b);
-            result.push((self.result_name(ty), a));
-            result.push((self.param_name(ty), b));
-        } else if a == b {
-            // If the modes are the same then there's only one result.
-            result.push((self.result_name(ty), a));
-        } else if info.owned || matches!(self.gen.opts.ownership, Ownership::Owning) {
-            // If this type is owned or if ownership is preferred then the owned
-            // variant is used as a priority. This is where the generator's
-            // configuration comes into play.
-            result.push((self.result_name(ty), a));
-        } else {
-            // And finally, failing all that, the borrowed variant is used.
-            assert!(!info.owned);
-            result.push((self.param_name(ty), b));
-        }
-        result
-    }
-
-    fn print_typedef_record(&mut self, id: TypeId, record: &Record, docs: &Docs) {
-        let info = self.info(id);
-        // We use a BTree set to make sure we don't have any duplicates and we have a stable order
-        let additional_derives: BTreeSet<String> = self
-            .gen
-            .opts
-            .additional_derive_attributes
-            .iter()
-            .cloned()
-            .collect();
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            let mut derives = additional_derives.clone();
-            if info.is_copy() {
-                self.push_str("#[repr(C)]\n");
-                derives.extend(["Copy", "Clone"].into_iter().map(|s| s.to_string()));
-            } else if info.is_clone() {
-                derives.insert("Clone".to_string());
-            }
-            if !derives.is_empty() {
-                self.push_str("#[derive(");
-                self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
-                self.push_str(")]\n")
-            }
-            self.push_str(&format!("pub struct {}", name));
-            self.print_generics(mode.lifetime);
-            self.push_str(" {\n");
-            for field in record.fields.iter() {
-                self.rustdoc(&field.docs);
-                self.push_str("pub ");
-                self.push_str(&to_rust_ident(&field.name));
-                self.push_str(": ");
-                let mode = self.filter_mode(&field.ty, mode);
-                self.print_ty(&field.ty, mode);
-                self.push_str(",\n");
-            }
-            self.push_str("}\n");
-
-            self.push_str("impl");
-            self.print_generics(mode.lifetime);
-            self.push_str(" ::core::fmt::Debug for ");
-            self.push_str(&name);
-            self.print_generics(mode.lifetime);
-            self.push_str(" {\n");
-            self.push_str(
-                "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-            );
-            self.push_str(&format!("f.debug_struct(\"{}\")", name));
-            for field in record.fields.iter() {
-                self.push_str(&format!(
-                    ".field(\"{}\", &self.{})",
-                    field.name,
-                    to_rust_ident(&field.name)
-                ));
-            }
-            self.push_str(".finish()\n");
-            self.push_str("}\n");
-            self.push_str("}\n");
-
-            if info.error {
-                self.push_str("impl");
-                self.print_generics(mode.lifetime);
-                self.push_str(" ::core::fmt::Display for ");
-                self.push_str(&name);
-                self.print_generics(mode.lifetime);
-                self.push_str(" {\n");
-                self.push_str(
-                    "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-                );
-                self.push_str("write!(f, \"{:?}\", self)\n");
-                self.push_str("}\n");
-                self.push_str("}\n");
-                if self.gen.opts.std_feature {
-                    self.push_str("#[cfg(feature = \"std\")]\n");
-                }
-                self.push_str("impl std::error::Error for ");
-                self.push_str(&name);
-                self.push_str(" {}\n");
-            }
-        }
-    }
-
-    fn print_typedef_variant(&mut self, id: TypeId, variant: &Variant, docs: &Docs)
-    where
-        Self: Sized,
-    {
-        self.print_rust_enum(
-            id,
-            variant
-                .cases
-                .iter()
-                .map(|c| (c.name.to_upper_camel_case(), &c.docs, c.ty.as_ref())),
-            docs,
-        );
-    }
-
-    fn print_rust_enum<'b>(
-        &mut self,
-        id: TypeId,
-        cases: impl IntoIterator<Item = (String, &'b Docs, Option<&'b Type>)> + Clone,
-        docs: &Docs,
-    ) where
-        Self: Sized,
-    {
-        let info = self.info(id);
-        // We use a BTree set to make sure we don't have any duplicates and have a stable order
-        let additional_derives: BTreeSet<String> = self
-            .gen
-            .opts
-            .additional_derive_attributes
-            .iter()
-            .cloned()
-            .collect();
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            let mut derives = additional_derives.clone();
-            if info.is_copy() {
-                derives.extend(["Copy", "Clone"].into_iter().map(|s| s.to_string()));
-            } else if info.is_clone() {
-                derives.insert("Clone".to_string());
-            }
-            if !derives.is_empty() {
-                self.push_str("#[derive(");
-                self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
-                self.push_str(")]\n")
-            }
-            self.push_str(&format!("pub enum {name}"));
-            self.print_generics(mode.lifetime);
-            self.push_str(" {\n");
-            for (case_name, docs, payload) in cases.clone() {
-                self.rustdoc(docs);
-                self.push_str(&case_name);
-                if let Some(ty) = payload {
-                    self.push_str("(");
-                    let mode = self.filter_mode(ty, mode);
-                    self.print_ty(ty, mode);
-                    self.push_str(")")
-                }
-                self.push_str(",\n");
-            }
-            self.push_str("}\n");
-
-            self.print_rust_enum_debug(
-                mode,
-                &name,
-                cases
-                    .clone()
-                    .into_iter()
-                    .map(|(name, _docs, ty)| (name, ty)),
-            );
-
-            if info.error {
-                self.push_str("impl");
-                self.print_generics(mode.lifetime);
-                self.push_str(" ::core::fmt::Display for ");
-                self.push_str(&name);
-                self.print_generics(mode.lifetime);
-                self.push_str(" {\n");
-                self.push_str(
-                    "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-                );
-                self.push_str("write!(f, \"{:?}\", self)\n");
-                self.push_str("}\n");
-                self.push_str("}\n");
-                self.push_str("\n");
-
-                if self.gen.opts.std_feature {
-                    self.push_str("#[cfg(feature = \"std\")]\n");
-                }
-                self.push_str("impl");
-                self.print_generics(mode.lifetime);
-                self.push_str(" std::error::Error for ");
-                self.push_str(&name);
-                self.print_generics(mode.lifetime);
-                self.push_str(" {}\n");
-            }
-        }
-    }
-
-    fn print_rust_enum_debug<'b>(
-        &mut self,
-        mode: TypeMode,
-        name: &str,
-        cases: impl IntoIterator<Item = (String, Option<&'b Type>)>,
-    ) where
-        Self: Sized,
-    {
-        self.push_str("impl");
-        self.print_generics(mode.lifetime);
-        self.push_str(" ::core::fmt::Debug for ");
-        self.push_str(name);
-        self.print_generics(mode.lifetime);
-        self.push_str(" {\n");
-        self.push_str(
-            "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-        );
-        self.push_str("match self {\n");
-        for (case_name, payload) in cases {
-            self.push_str(name);
-            self.push_str("::");
-            self.push_str(&case_name);
-            if payload.is_some() {
-                self.push_str("(e)");
-            }
-            self.push_str(" => {\n");
-            self.push_str(&format!("f.debug_tuple(\"{}::{}\")", name, case_name));
-            if payload.is_some() {
-                self.push_str(".field(e)");
-            }
-            self.push_str(".finish()\n");
-            self.push_str("}\n");
-        }
-        self.push_str("}\n");
-        self.push_str("}\n");
-        self.push_str("}\n");
-    }
-
-    fn print_typedef_option(&mut self, id: TypeId, payload: &Type, docs: &Docs) {
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            self.push_str(&format!("pub type {}", name));
-            self.print_generics(mode.lifetime);
-            self.push_str("= Option<");
-            self.print_ty(payload, mode);
-            self.push_str(">;\n");
-        }
-    }
-
-    fn print_typedef_result(&mut self, id: TypeId, result: &Result_, docs: &Docs) {
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            self.push_str(&format!("pub type {}", name));
-            self.print_generics(mode.lifetime);
-            self.push_str("= Result<");
-            self.print_optional_ty(result.ok.as_ref(), mode);
-            self.push_str(",");
-            self.print_optional_ty(result.err.as_ref(), mode);
-            self.push_str(">;\n");
-        }
-    }
-
-    fn print_typedef_enum(
-        &mut self,
-        id: TypeId,
-        name: &str,
-        enum_: &Enum,
-        docs: &Docs,
-        attrs: &[String],
-        case_attr: Box<dyn Fn(&EnumCase) -> String>,
-    ) where
-        Self: Sized,
-    {
-        let info = self.info(id);
-
-        let name = to_upper_camel_case(name);
-        self.rustdoc(docs);
-        for attr in attrs {
-            self.push_str(&format!("{}\n", attr));
-        }
-        self.push_str("#[repr(");
-        self.int_repr(enum_.tag());
-        self.push_str(")]\n");
-        // We use a BTree set to make sure we don't have any duplicates and a stable order
-        let mut derives: BTreeSet<String> = self
-            .gen
-            .opts
-            .additional_derive_attributes
+    /// Returns the anonymous types owned by `owner` which are given a
+    /// synthesized name.
+    fn anonymous_types(&self, owner: TypeOwner) -> Vec<(String, TypeId)> {
+        self.resolve
+            .types
             .iter()
-            .cloned()
-            .collect();
-        derives.extend(
-            ["Clone", "Copy", "PartialEq", "Eq"]
-                .into_iter()
-                .map(|s| s.to_string()),
-        );
-        self.push_str("#[derive(");
-        self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
-        self.push_str(")]\n");
-        self.push_str(&format!("pub enum {name} {{\n"));
-        for case in enum_.cases.iter() {
-            self.rustdoc(&case.docs);
-            self.push_str(&case_attr(case));
-            self.push_str(&case.name.to_upper_camel_case());
-            self.push_str(",\n");
-        }
-        self.push_str("}\n");
-
-        // Auto-synthesize an implementation of the standard `Error` trait for
-        // error-looking types based on their name.
-        if info.error {
-            self.push_str("impl ");
-            self.push_str(&name);
-            self.push_str("{\n");
-
-            self.push_str("pub fn name(&self) -> &'static str {\n");
-            self.push_str("match self {\n");
-            for case in enum_.cases.iter() {
-                self.push_str(&name);
-                self.push_str("::");
-                self.push_str(&case.name.to_upper_camel_case());
-                self.push_str(" => \"");
-                self.push_str(case.name.as_str());
-                self.push_str("\",\n");
-            }
-            self.push_str("}\n");
-            self.push_str("}\n");
-
-            self.push_str("pub fn message(&self) -> &'static str {\n");
-            self.push_str("match self {\n");
-            for case in enum_.cases.iter() {
-                self.push_str(&name);
-                self.push_str("::");
-                self.push_str(&case.name.to_upper_camel_case());
-                self.push_str(" => \"");
-                if let Some(contents) = &case.docs.contents {
-                    self.push_str(contents.trim());
-                }
-                self.push_str("\",\n");
-            }
-            self.push_str("}\n");
-            self.push_str("}\n");
-
-            self.push_str("}\n");
-
-            self.push_str("impl ::core::fmt::Debug for ");
-            self.push_str(&name);
-            self.push_str(
-                "{\nfn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-            );
-            self.push_str("f.debug_struct(\"");
-            self.push_str(&name);
-            self.push_str("\")\n");
-            self.push_str(".field(\"code\", &(*self as i32))\n");
-            self.push_str(".field(\"name\", &self.name())\n");
-            self.push_str(".field(\"message\", &self.message())\n");
-            self.push_str(".finish()\n");
-            self.push_str("}\n");
-            self.push_str("}\n");
-
-            self.push_str("impl ::core::fmt::Display for ");
-            self.push_str(&name);
-            self.push_str(
-                "{\nfn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
-            );
-            self.push_str("write!(f, \"{} (error {})\", self.name(), *self as i32)\n");
-            self.push_str("}\n");
-            self.push_str("}\n");
-            self.push_str("\n");
-            if self.gen.opts.std_feature {
-                self.push_str("#[cfg(feature = \"std\")]\n");
-            }
-            self.push_str("impl std::error::Error for ");
-            self.push_str(&name);
-            self.push_str(" {}\n");
-        } else {
-            self.print_rust_enum_debug(
-                TypeMode::owned(),
-                &name,
-                enum_
-                    .cases
-                    .iter()
-                    .map(|c| (c.name.to_upper_camel_case(), None)),
-            )
-        }
-    }
-
-    fn print_typedef_alias(&mut self, id: TypeId, ty: &Type, docs: &Docs) {
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            self.push_str(&format!("pub type {name}"));
-            self.print_generics(mode.lifetime);
-            self.push_str(" = ");
-            self.print_ty(ty, mode);
-            self.push_str(";\n");
-        }
-
-        if self.is_exported_resource(id) {
-            self.rustdoc(docs);
-            let name = self.resolve.types[id].name.as_ref().unwrap();
-            let name = name.to_upper_camel_case();
-            self.push_str(&format!("pub type {name}Borrow<'a>"));
-            self.push_str(" = ");
-            self.print_ty(ty, TypeMode::owned());
-            self.push_str("Borrow<'a>");
-            self.push_str(";\n");
-        }
-    }
-
-    fn param_name(&self, ty: TypeId) -> String {
-        let info = self.info(ty);
-        let name = to_upper_camel_case(self.resolve.types[ty].name.as_ref().unwrap());
-        if self.uses_two_names(&info) {
-            format!("{}Param", name)
-        } else {
-            name
-        }
+            .filter(|(_, ty)| ty.owner == owner)
+            .filter_map(|(id, _)| Some((anonymous_type_name(self.resolve, id)?, id)))
+            .collect()
     }
 
-    fn result_name(&self, ty: TypeId) -> String {
-        let info = self.info(ty);
-        let name = to_upper_camel_case(self.resolve.types[ty].name.as_ref().unwrap());
-        if self.uses_two_names(&info) {
-            format!("{}Result", name)
-        } else {
-            name
-        }
-    }
-
-    fn uses_two_names(&self, info: &TypeInfo) -> bool {
-        // Types are only duplicated if explicitly requested ...
-        matches!(
-            self.gen.opts.ownership,
-            Ownership::Borrowing {
-                duplicate_if_necessary: true
-            }
-        )
-            // ... and if they're both used in a borrowed/owned context
-            && info.borrowed
-            && info.owned
-            // ... and they have a list ...
-            && info.has_list
-            // ... and if there's NOT an `own` handle since those are always
-            // done by ownership.
-            && !info.has_own_handle
-    }
-
-    fn path_to_interface(&self, interface: InterfaceId) -> Option<String> {
-        let InterfaceName { path, remapped } = &self.gen.interface_names[&interface];
-        if *remapped {
-            let mut path_to_root = self.path_to_root();
-            path_to_root.push_str(path);
-            return Some(path_to_root);
-        } else {
-            let mut full_path = String::new();
-            if let Identifier::Interface(cur, name) = self.identifier {
-                if cur == interface {
-                    return None;
-                }
-                if !self.in_import {
-                    full_path.push_str("super::");
-                }
-                match name {
-                    WorldKey::Name(_) => {
-                        full_path.push_str("super::");
-                    }
-                    WorldKey::Interface(_) => {
-                        full_path.push_str("super::super::super::");
-                    }
-                }
-            }
-            full_path.push_str(&path);
-            Some(full_path)
+    /// Defines the anonymous types owned by `owner` alongside the named types
+    /// of the same owner.
+    pub(crate) fn define_anonymous_types(&mut self, owner: TypeOwner) {
+        for (name, id) in self.anonymous_types(owner) {
+            wit_bindgen_core::InterfaceGenerator::define_type(self, &name, id);
         }
     }
 
-    fn push_vec_name(&mut self) {
-        let path = self.path_to_vec();
-        self.push_str(&path);
-    }
-
     pub fn is_exported_resource(&self, ty: TypeId) -> bool {
         let ty = dealias(self.resolve, ty);
         let ty = &self.resolve.types[ty];
@@ -1838,39 +837,22 @@ macro_rules! {macro_name} {{
             // them as dictates whether it's exported or not.
             TypeOwner::Interface(i) => !self.gen.interface_last_seen_as_import[&i],
 
-            // Shouldn't be the case for resources
-            TypeOwner::None => unreachable!(),
+            // Only possible for anonymous resources which are always
+            // generated as imports in the root module.
+            TypeOwner::None => false,
         }
     }
 
-    fn push_string_name(&mut self) {
-        let path = self.path_to_string();
-        self.push_str(&path);
-    }
-
-    fn push_str(&mut self, s: &str) {
-        self.src.push_str(s);
-    }
-
-    fn info(&self, ty: TypeId) -> TypeInfo {
-        self.gen.types.get(ty)
+    pub fn path_to_resource(&mut self) -> String {
+        self.path_from_runtime_module(RuntimeItem::ResourceType, "Resource")
     }
 
-    fn print_borrowed_str(&mut self, lifetime: &'static str) {
-        self.push_str("&");
-        if lifetime != "'_" {
-            self.push_str(lifetime);
-            self.push_str(" ");
-        }
-        if self.gen.opts.raw_strings {
-            self.push_str("[u8]");
-        } else {
-            self.push_str("str");
-        }
+    pub fn path_to_future_reader(&mut self) -> String {
+        self.path_from_runtime_module(RuntimeItem::FutureReaderType, "FutureReader")
     }
 
-    pub fn path_to_resource(&mut self) -> String {
-        self.path_from_runtime_module(RuntimeItem::ResourceType, "Resource")
+    pub fn path_to_stream_reader(&mut self) -> String {
+        self.path_from_runtime_module(RuntimeItem::StreamReaderType, "StreamReader")
     }
 
     fn path_to_wasm_resource(&mut self) -> String {
@@ -1947,6 +929,126 @@ macro_rules! {macro_name} {{
     }
 }
 
+impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn info(&self, ty: TypeId) -> TypeInfo {
+        self.gen.types.get(ty)
+    }
+
+    fn identifier(&self) -> Identifier<'a> {
+        self.identifier
+    }
+
+    fn in_import(&self) -> bool {
+        self.in_import
+    }
+
+    fn interface_names(&self) -> &InterfaceNames {
+        &self.gen.interface_names
+    }
+
+    fn ownership(&self) -> Ownership {
+        self.gen.opts.ownership
+    }
+
+    fn additional_derives(&self) -> &[String] {
+        &self.gen.opts.additional_derive_attributes
+    }
+
+    fn std_feature(&self) -> bool {
+        self.gen.opts.std_feature
+    }
+
+    fn raw_strings(&self) -> bool {
+        self.gen.opts.raw_strings
+    }
+
+    fn borrow_handles_need_lifetime(&self) -> bool {
+        true
+    }
+
+    fn upper_camel_case(&self, name: &str) -> String {
+        to_upper_camel_case(name)
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.src.push_str(s);
+    }
+
+    fn push_vec_name(&mut self) {
+        let path = self.path_to_vec();
+        self.push_str(&path);
+    }
+
+    fn push_string_name(&mut self) {
+        let path = self.path_to_string();
+        self.push_str(&path);
+    }
+
+    fn print_handle(&mut self, handle: &Handle, mode: TypeMode) {
+        match handle {
+            Handle::Own(ty) => {
+                self.print_ty(&Type::Id(*ty), mode);
+            }
+
+            Handle::Borrow(ty) => {
+                assert!(mode.lifetime.is_some());
+                let lt = mode.lifetime.unwrap();
+                if self.is_exported_resource(*ty) {
+                    let camel = self.type_name(*ty).to_upper_camel_case();
+                    let name = format!("{camel}Borrow");
+                    self.push_str(&self.type_path_with_name(*ty, name));
+                    self.push_str("<");
+                    self.push_str(lt);
+                    self.push_str(">");
+                } else {
+                    self.push_str("&");
+                    if lt != "'_" {
+                        self.push_str(lt);
+                        self.push_str(" ");
+                    }
+                    let ty = &Type::Id(*ty);
+                    let mode = self.filter_mode(ty, mode);
+                    self.print_ty(ty, mode);
+                }
+            }
+        }
+    }
+
+    fn print_future(&mut self, ty: Option<&Type>) {
+        let path = self.path_to_future_reader();
+        self.push_str(&path);
+        self.push_str("<");
+        self.print_optional_ty(ty, TypeMode::owned());
+        self.push_str(">");
+    }
+
+    fn print_stream(&mut self, stream: &Stream) {
+        let path = self.path_to_stream_reader();
+        self.push_str(&path);
+        self.push_str("<");
+        self.print_optional_ty(stream.element.as_ref(), TypeMode::owned());
+        if let Some(end) = &stream.end {
+            self.push_str(", ");
+            self.print_ty(end, TypeMode::owned());
+        }
+        self.push_str(">");
+    }
+
+    /// Returns the WIT name of `ty`, synthesizing one if it's an anonymous
+    /// type which Rust can only represent as a named item.
+    fn type_name(&self, ty: TypeId) -> String {
+        match &self.resolve.types[ty].name {
+            Some(name) => name.clone(),
+            None => anonymous_type_name(self.resolve, ty)
+                .unwrap_or_else(|| panic!("type {ty:?} doesn't have a name")),
+        }
+    }
+}
+
 impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     fn resolve(&self) -> &'a Resolve {
         self.resolve
@@ -2155,21 +1257,14 @@ impl<'a> {camel}Borrow<'a>{{
     }
 
     fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            self.push_str(&format!("pub type {}", name));
-            self.print_generics(mode.lifetime);
-            self.push_str(" = (");
-            for ty in tuple.types.iter() {
-                let mode = self.filter_mode(ty, mode);
-                self.print_ty(ty, mode);
-                self.push_str(",");
-            }
-            self.push_str(");\n");
-        }
+        self.print_typedef_tuple(id, tuple, docs);
     }
 
     fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        if let RustFlagsRepr::Words(n) = RustFlagsRepr::new(flags) {
+            self.print_flags_words(&name.to_upper_camel_case(), flags, docs, n);
+            return;
+        }
         self.src.push_str(&format!(
             "{bitflags}::bitflags! {{\n",
             bitflags = self.gen.bitflags_path()
@@ -2205,7 +1300,7 @@ impl<'a> {camel}Borrow<'a>{{
     }
 
     fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
-        self.print_typedef_enum(id, name, enum_, docs, &[], Box::new(|_| String::new()));
+        self.print_typedef_enum(id, name, enum_, docs);
 
         let name = to_upper_camel_case(name);
         let mut cases = String::new();
@@ -2235,25 +1330,41 @@ impl<'a> {camel}Borrow<'a>{{
 
     fn type_alias(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
         self.print_typedef_alias(id, ty, docs);
-    }
 
-    fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
-        for (name, mode) in self.modes_of(id) {
+        if self.is_exported_resource(id) {
             self.rustdoc(docs);
-            self.push_str(&format!("pub type {}", name));
-            self.print_generics(mode.lifetime);
+            let name = self.type_name(id).to_upper_camel_case();
+            self.push_str(&format!("pub type {name}Borrow<'a>"));
             self.push_str(" = ");
-            self.print_list(ty, mode);
+            self.print_ty(ty, TypeMode::owned());
+            self.push_str("Borrow<'a>");
             self.push_str(";\n");
         }
     }
 
+    fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_list(id, ty, docs);
+    }
+
     fn type_builtin(&mut self, _id: TypeId, name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_builtin(name, ty, docs);
+    }
+
+    fn type_future(&mut self, _id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs) {
+        self.rustdoc(docs);
+        self.src
+            .push_str(&format!("pub type {}", name.to_upper_camel_case()));
+        self.src.push_str(" = ");
+        self.print_future(ty.as_ref());
+        self.src.push_str(";\n");
+    }
+
+    fn type_stream(&mut self, _id: TypeId, name: &str, stream: &Stream, docs: &Docs) {
         self.rustdoc(docs);
         self.src
             .push_str(&format!("pub type {}", name.to_upper_camel_case()));
         self.src.push_str(" = ");
-        self.print_ty(ty, TypeMode::owned());
+        self.print_stream(stream);
         self.src.push_str(";\n");
     }
 }
@@ -1,8 +1,9 @@
+use crate::common::{emit_modules, Identifier, InterfaceNames};
 use crate::interface::InterfaceGenerator;
-use anyhow::{bail, Result};
+use anyhow::Result;
 use heck::*;
 use indexmap::IndexSet;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Write as _};
 use std::io::{Read, Write};
 use std::mem;
@@ -10,21 +11,14 @@ use std::process::{Command, Stdio};
 use std::str::FromStr;
 use wit_bindgen_core::abi::{Bitcast, WasmType};
 use wit_bindgen_core::{
-    uwrite, uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Source, Types, WorldGenerator,
+    async_type_used_by, uwrite, uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Source,
+    Types, WorldGenerator,
 };
 
 mod bindgen;
+pub mod common;
 mod interface;
 
-struct InterfaceName {
-    /// True when this interface name has been remapped through the use of `with` in the `bindgen!`
-    /// macro invocation.
-    remapped: bool,
-
-    /// The string name for this interface.
-    path: String,
-}
-
 #[derive(Default)]
 struct RustWasm {
     types: Types,
@@ -33,19 +27,14 @@ struct RustWasm {
     import_modules: Vec<(String, Vec<String>)>,
     export_modules: Vec<(String, Vec<String>)>,
     skip: HashSet<String>,
-    interface_names: HashMap<InterfaceId, InterfaceName>,
+    interface_names: InterfaceNames,
     /// Each imported and exported interface is stored in this map. Value indicates if last use was import.
     interface_last_seen_as_import: HashMap<InterfaceId, bool>,
     import_funcs_called: bool,
-    with_name_counter: usize,
-    // Track the with options that were used. Remapped interfaces provided via `with`
-    // are required to be used.
-    used_with_opts: HashSet<String>,
     world: Option<WorldId>,
 
     rt_module: IndexSet<RuntimeItem>,
     export_macros: Vec<(String, String)>,
-    with: HashMap<String, String>,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
@@ -66,6 +55,8 @@ enum RuntimeItem {
     AsF64,
     ResourceType,
     BoxType,
+    FutureReaderType,
+    StreamReaderType,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -182,6 +173,21 @@ pub struct Opts {
     /// candidate for being exported outside of the crate.
     #[cfg_attr(feature = "clap", arg(long))]
     pub pub_export_macro: bool,
+
+    /// Generate exported functions as `async fn` in their traits.
+    ///
+    /// The generated export glue runs each call to completion with the
+    /// executor in the runtime module before returning to the caller.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub async_exports: bool,
+
+    /// Generate bindings for worlds which use `future` and `stream` types.
+    ///
+    /// This is unstable and not yet usable: `wit-component` can't encode
+    /// these types, so the generated bindings fail to compile for wasm. Worlds
+    /// using them are rejected unless this is set.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub unstable_async_types: bool,
 }
 
 impl Opts {
@@ -222,41 +228,6 @@ impl RustWasm {
         }
     }
 
-    fn emit_modules(&mut self, modules: Vec<(String, Vec<String>)>) {
-        #[derive(Default)]
-        struct Module {
-            submodules: BTreeMap<String, Module>,
-            contents: Vec<String>,
-        }
-        let mut map = Module::default();
-        for (module, path) in modules {
-            let mut cur = &mut map;
-            for name in path[..path.len() - 1].iter() {
-                cur = cur
-                    .submodules
-                    .entry(name.clone())
-                    .or_insert(Module::default());
-            }
-            cur.contents.push(module);
-        }
-        emit(&mut self.src, map);
-        fn emit(me: &mut Source, module: Module) {
-            for (name, submodule) in module.submodules {
-                // Ignore dead-code warnings. If the bindings are only used
-                // within a crate, and not exported to a different crate, some
-                // parts may be unused, and that's ok.
-                uwriteln!(me, "#[allow(dead_code)]");
-
-                uwriteln!(me, "pub mod {name} {{");
-                emit(me, submodule);
-                uwriteln!(me, "}}");
-            }
-            for submodule in module.contents {
-                uwriteln!(me, "{submodule}");
-            }
-        }
-    }
-
     fn runtime_path(&self) -> &str {
         self.opts
             .runtime_path
@@ -271,38 +242,6 @@ impl RustWasm {
             .unwrap_or(format!("{}::bitflags", self.runtime_path()))
     }
 
-    fn name_interface(
-        &mut self,
-        resolve: &Resolve,
-        id: InterfaceId,
-        name: &WorldKey,
-        is_export: bool,
-    ) -> bool {
-        let with_name = resolve.name_world_key(name);
-        let entry = if let Some(remapped_path) = self.with.get(&with_name) {
-            let name = format!("__with_name{}", self.with_name_counter);
-            self.used_with_opts.insert(with_name);
-            self.with_name_counter += 1;
-            uwriteln!(self.src, "use {remapped_path} as {name};");
-            InterfaceName {
-                remapped: true,
-                path: name,
-            }
-        } else {
-            let path = compute_module_path(name, resolve, is_export).join("::");
-
-            InterfaceName {
-                remapped: false,
-                path,
-            }
-        };
-
-        let remapped = entry.remapped;
-        self.interface_names.insert(id, entry);
-
-        remapped
-    }
-
     fn finish_runtime_module(&mut self) {
         if self.rt_module.is_empty() {
             return;
@@ -544,6 +483,158 @@ impl<T: WasmResource> Drop for Resource<T> {
             }
         }
     }
+}
+                    "#,
+                );
+            }
+
+            RuntimeItem::FutureReaderType => {
+                self.src.push_str(
+                    r#"
+
+/// An owned handle to a component model `future<T>` value.
+///
+/// Values of this type are produced when a `future` is received from another
+/// component and are consumed when passed back out to another component.
+#[repr(transparent)]
+pub struct FutureReader<T> {
+    // Like `Resource<T>` this is stored as an atomic to support taking the
+    // handle through a shared reference, and `u32::MAX` indicates that the
+    // handle has been taken.
+    handle: core::sync::atomic::AtomicU32,
+    _marker: core::marker::PhantomData<T>,
+}
+
+impl<T> FutureReader<T> {
+    #[doc(hidden)]
+    pub unsafe fn from_handle(handle: u32) -> Self {
+        debug_assert!(handle != u32::MAX);
+        Self {
+            handle: core::sync::atomic::AtomicU32::new(handle),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn take_handle(&self) -> u32 {
+        self.handle.swap(u32::MAX, core::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[doc(hidden)]
+    pub fn handle(&self) -> u32 {
+        self.handle.load(core::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl<T> core::fmt::Debug for FutureReader<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FutureReader")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+impl<T> Drop for FutureReader<T> {
+    fn drop(&mut self) {
+        match self.handle() {
+            // If this handle was "taken" then it's no longer owned here.
+            u32::MAX => {}
+
+            // ... but otherwise it's dropped with the component model
+            // intrinsic for this kind of handle.
+            _handle => {
+                #[cfg(not(target_arch = "wasm32"))]
+                unreachable!();
+
+                #[cfg(target_arch = "wasm32")]
+                {
+                    #[link(wasm_import_module = "$root")]
+                    extern "C" {
+                        #[link_name = "[future-drop-readable]"]
+                        fn drop(_: u32);
+                    }
+
+                    unsafe { drop(_handle) }
+                }
+            }
+        }
+    }
+}
+                    "#,
+                );
+            }
+
+            RuntimeItem::StreamReaderType => {
+                self.src.push_str(
+                    r#"
+
+/// An owned handle to a component model `stream<T, E>` value.
+///
+/// The `T` parameter is the type of the elements of the stream and `E` is
+/// the type of the value the stream ends with. Values of this type are
+/// produced when a `stream` is received from another component and are
+/// consumed when passed back out to another component.
+#[repr(transparent)]
+pub struct StreamReader<T, E = ()> {
+    // See `FutureReader<T>` for why this is an atomic.
+    handle: core::sync::atomic::AtomicU32,
+    _marker: core::marker::PhantomData<(T, E)>,
+}
+
+impl<T, E> StreamReader<T, E> {
+    #[doc(hidden)]
+    pub unsafe fn from_handle(handle: u32) -> Self {
+        debug_assert!(handle != u32::MAX);
+        Self {
+            handle: core::sync::atomic::AtomicU32::new(handle),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn take_handle(&self) -> u32 {
+        self.handle.swap(u32::MAX, core::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[doc(hidden)]
+    pub fn handle(&self) -> u32 {
+        self.handle.load(core::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl<T, E> core::fmt::Debug for StreamReader<T, E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+impl<T, E> Drop for StreamReader<T, E> {
+    fn drop(&mut self) {
+        match self.handle() {
+            // If this handle was "taken" then it's no longer owned here.
+            u32::MAX => {}
+
+            // ... but otherwise it's dropped with the component model
+            // intrinsic for this kind of handle.
+            _handle => {
+                #[cfg(not(target_arch = "wasm32"))]
+                unreachable!();
+
+                #[cfg(target_arch = "wasm32")]
+                {
+                    #[link(wasm_import_module = "$root")]
+                    extern "C" {
+                        #[link_name = "[stream-drop-readable]"]
+                        fn drop(_: u32);
+                    }
+
+                    unsafe { drop(_handle) }
+                }
+            }
+        }
+    }
 }
                     "#,
                 );
@@ -689,6 +780,40 @@ macro_rules! __export_{world_name}_impl {{
         section_suffix: &str,
         func_name: Option<&str>,
     ) {
+        // `wit-component` is not yet able to encode `future` and `stream`
+        // types, so worlds which use them can't get their type information
+        // embedded. Building such bindings for wasm is an error rather than
+        // producing a module which can't be turned into a component.
+        if async_type_used_by(resolve, world).is_some() {
+            self.src.push_str(
+                "
+                    #[cfg(target_arch = \"wasm32\")]
+                    compile_error!(\"these bindings use `future` or `stream` types, \\
+                        which can't yet be encoded in the component type custom \\
+                        section needed to create a component\");
+                ",
+            );
+        } else {
+            self.emit_component_type(resolve, world, section_suffix);
+        }
+
+        if let Some(func_name) = func_name {
+            let rt = self.runtime_path().to_string();
+            uwriteln!(
+                self.src,
+                "
+                #[inline(never)]
+                #[doc(hidden)]
+                #[cfg(target_arch = \"wasm32\")]
+                pub fn {func_name}() {{
+                    {rt}::maybe_link_cabi_realloc();
+                }}
+            ",
+            );
+        }
+    }
+
+    fn emit_component_type(&mut self, resolve: &Resolve, world: WorldId, section_suffix: &str) {
         self.src.push_str("\n#[cfg(target_arch = \"wasm32\")]\n");
 
         // The custom section name here must start with "component-type" but
@@ -710,6 +835,19 @@ macro_rules! __export_{world_name}_impl {{
             env!("CARGO_PKG_VERSION"),
         );
 
+        // The component type refers to anonymous types by the same names that
+        // the generated bindings use for them.
+        let named;
+        let resolve = if resolve
+            .types
+            .iter()
+            .any(|(id, _)| anonymous_type_name(resolve, id).is_some())
+        {
+            named = name_anonymous_types(resolve);
+            &named
+        } else {
+            resolve
+        };
         let component_type = wit_component::metadata::encode(
             resolve,
             world,
@@ -757,70 +895,14 @@ macro_rules! __export_{world_name}_impl {{
 
         self.src.push_str("\";\n");
         self.src.set_indent(old_indent);
-
-        if let Some(func_name) = func_name {
-            let rt = self.runtime_path().to_string();
-            uwriteln!(
-                self.src,
-                "
-                #[inline(never)]
-                #[doc(hidden)]
-                #[cfg(target_arch = \"wasm32\")]
-                pub fn {func_name}() {{
-                    {rt}::maybe_link_cabi_realloc();
-                }}
-            ",
-            );
-        }
     }
 }
 
-/// If the package `id` is the only package with its namespace/name combo
-/// then pass through the name unmodified. If, however, there are multiple
-/// versions of this package then the package module is going to get version
-/// information.
-fn name_package_module(resolve: &Resolve, id: PackageId) -> String {
-    let pkg = &resolve.packages[id];
-    let versions_with_same_name = resolve
-        .packages
-        .iter()
-        .filter_map(|(_, p)| {
-            if p.name.namespace == pkg.name.namespace && p.name.name == pkg.name.name {
-                Some(&p.name.version)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    let base = pkg.name.name.to_snake_case();
-    if versions_with_same_name.len() == 1 {
-        return base;
+impl WorldGenerator for RustWasm {
+    fn supports_async_types(&self) -> bool {
+        self.opts.unstable_async_types
     }
 
-    let version = match &pkg.name.version {
-        Some(version) => version,
-        // If this package didn't have a version then don't mangle its name
-        // and other packages with the same name but with versions present
-        // will have their names mangled.
-        None => return base,
-    };
-
-    // Here there's multiple packages with the same name that differ only in
-    // version, so the version needs to be mangled into the Rust module name
-    // that we're generating. This in theory could look at all of
-    // `versions_with_same_name` and produce a minimal diff, e.g. for 0.1.0
-    // and 0.2.0 this could generate "foo1" and "foo2", but for now
-    // a simpler path is chosen to generate "foo0_1_0" and "foo0_2_0".
-    let version = version
-        .to_string()
-        .replace('.', "_")
-        .replace('-', "_")
-        .replace('+', "_")
-        .to_snake_case();
-    format!("{base}{version}")
-}
-
-impl WorldGenerator for RustWasm {
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         wit_bindgen_core::generated_preamble(&mut self.src, env!("CARGO_PKG_VERSION"));
 
@@ -861,11 +943,14 @@ impl WorldGenerator for RustWasm {
         if self.opts.pub_export_macro {
             uwriteln!(self.src, "//   * pub-export-macro");
         }
+        if self.opts.async_exports {
+            uwriteln!(self.src, "//   * async-exports");
+        }
         self.types.analyze(resolve);
         self.world = Some(world);
 
         for (k, v) in self.opts.with.iter() {
-            self.with.insert(k.clone(), v.clone());
+            self.interface_names.remap(k, v);
         }
     }
 
@@ -885,10 +970,15 @@ impl WorldGenerator for RustWasm {
             true,
         );
         let (snake, module_path) = gen.start_append_submodule(name);
-        if gen.gen.name_interface(resolve, id, name, false) {
+        if gen
+            .gen
+            .interface_names
+            .name_interface(&mut gen.gen.src, resolve, id, name, false)
+        {
             return;
         }
         gen.types(id);
+        gen.define_anonymous_types(TypeOwner::Interface(id));
 
         gen.generate_imports(resolve.interfaces[id].functions.values());
 
@@ -906,6 +996,11 @@ impl WorldGenerator for RustWasm {
 
         let mut gen = self.interface(Identifier::World(world), Some("$root"), resolve, true);
 
+        // Anonymous types owned by the world, or by nothing at all, are
+        // defined once in the root module.
+        gen.define_anonymous_types(TypeOwner::World(world));
+        gen.define_anonymous_types(TypeOwner::None);
+
         gen.generate_imports(funcs.iter().map(|(_, func)| *func));
 
         let src = gen.finish();
@@ -922,10 +1017,15 @@ impl WorldGenerator for RustWasm {
         self.interface_last_seen_as_import.insert(id, false);
         let mut gen = self.interface(Identifier::Interface(id, name), None, resolve, false);
         let (snake, module_path) = gen.start_append_submodule(name);
-        if gen.gen.name_interface(resolve, id, name, true) {
+        if gen
+            .gen
+            .interface_names
+            .name_interface(&mut gen.gen.src, resolve, id, name, true)
+        {
             return Ok(());
         }
         gen.types(id);
+        gen.define_anonymous_types(TypeOwner::Interface(id));
         let macro_name =
             gen.generate_exports(Some((id, name)), resolve.interfaces[id].functions.values())?;
         gen.finish_append_submodule(&snake, module_path);
@@ -992,9 +1092,9 @@ impl WorldGenerator for RustWasm {
         let name = &resolve.worlds[world].name;
 
         let imports = mem::take(&mut self.import_modules);
-        self.emit_modules(imports);
+        emit_modules(&mut self.src, imports);
         let exports = mem::take(&mut self.export_modules);
-        self.emit_modules(exports);
+        emit_modules(&mut self.src, exports);
 
         self.finish_runtime_module();
         self.finish_export_macro(resolve, world);
@@ -1088,63 +1188,10 @@ impl WorldGenerator for RustWasm {
         let module_name = name.to_snake_case();
         files.push(&format!("{module_name}.rs"), src.as_bytes());
 
-        let remapping_keys = self.with.keys().cloned().collect::<HashSet<String>>();
-
-        let mut unused_keys = remapping_keys
-            .difference(&self.used_with_opts)
-            .collect::<Vec<&String>>();
-
-        unused_keys.sort();
-
-        if !unused_keys.is_empty() {
-            bail!("unused remappings provided via `with`: {unused_keys:?}");
-        }
-
-        Ok(())
+        self.interface_names.check_unused_remappings()
     }
 }
 
-fn compute_module_path(name: &WorldKey, resolve: &Resolve, is_export: bool) -> Vec<String> {
-    let mut path = Vec::new();
-    if is_export {
-        path.push("exports".to_string());
-    }
-    match name {
-        WorldKey::Name(name) => {
-            path.push(to_rust_ident(name));
-        }
-        WorldKey::Interface(id) => {
-            let iface = &resolve.interfaces[*id];
-            let pkg = iface.package.unwrap();
-            let pkgname = resolve.packages[pkg].name.clone();
-            path.push(to_rust_ident(&pkgname.namespace));
-            path.push(name_package_module(resolve, pkg));
-            path.push(to_rust_ident(iface.name.as_ref().unwrap()));
-        }
-    }
-    path
-}
-
-enum Identifier<'a> {
-    World(WorldId),
-    Interface(InterfaceId, &'a WorldKey),
-}
-
-fn group_by_resource<'a>(
-    funcs: impl Iterator<Item = &'a Function>,
-) -> BTreeMap<Option<TypeId>, Vec<&'a Function>> {
-    let mut by_resource = BTreeMap::<_, Vec<_>>::new();
-    for func in funcs {
-        match &func.kind {
-            FunctionKind::Freestanding => by_resource.entry(None).or_default().push(func),
-            FunctionKind::Method(ty) | FunctionKind::Static(ty) | FunctionKind::Constructor(ty) => {
-                by_resource.entry(Some(*ty)).or_default().push(func);
-            }
-        }
-    }
-    by_resource
-}
-
 #[derive(Default, Debug, Clone, Copy)]
 pub enum Ownership {
     /// Generated types will be composed entirely of owning fields, regardless
@@ -1267,6 +1314,51 @@ pub fn to_rust_ident(name: &str) -> String {
     }
 }
 
+/// Records, variants, enums, flags and resources all need a named item in
+/// Rust. They're always named when parsed from WIT, but a `Resolve` built in
+/// memory may contain anonymous ones, so a name is derived here from the kind
+/// of the type and its index.
+fn anonymous_type_name(resolve: &Resolve, id: TypeId) -> Option<String> {
+    let ty = &resolve.types[id];
+    if ty.name.is_some() {
+        return None;
+    }
+    match &ty.kind {
+        TypeDefKind::Record(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Resource => Some(format!("anonymous-{}-{}", ty.kind.as_str(), id.index())),
+        _ => None,
+    }
+}
+
+/// Returns a copy of `resolve` where anonymous types are given their
+/// synthesized names and registered with the interface or world that owns
+/// them.
+fn name_anonymous_types(resolve: &Resolve) -> Resolve {
+    let mut resolve = resolve.clone();
+    let ids = resolve.types.iter().map(|(id, _)| id).collect::<Vec<_>>();
+    for id in ids {
+        let Some(name) = anonymous_type_name(&resolve, id) else {
+            continue;
+        };
+        resolve.types[id].name = Some(name.clone());
+        match resolve.types[id].owner {
+            TypeOwner::Interface(i) => {
+                resolve.interfaces[i].types.insert(name, id);
+            }
+            TypeOwner::World(w) => {
+                resolve.worlds[w]
+                    .imports
+                    .insert(WorldKey::Name(name), WorldItem::Type(id));
+            }
+            TypeOwner::None => {}
+        }
+    }
+    resolve
+}
+
 fn to_upper_camel_case(name: &str) -> String {
     match name {
         // The name "Guest" is reserved for traits generated by exported
@@ -1294,15 +1386,6 @@ fn wasm_type(ty: WasmType) -> &'static str {
     }
 }
 
-fn int_repr(repr: Int) -> &'static str {
-    match repr {
-        Int::U8 => "u8",
-        Int::U16 => "u16",
-        Int::U32 => "u32",
-        Int::U64 => "u64",
-    }
-}
-
 fn bitcast(casts: &[Bitcast], operands: &[String], results: &mut Vec<String>) {
     for (cast, operand) in casts.iter().zip(operands) {
         results.push(perform_cast(operand, cast));
@@ -1372,6 +1455,9 @@ enum RustFlagsRepr {
     U32,
     U64,
     U128,
+    /// Flags which don't fit in a primitive integer are stored as an array of
+    /// `u32` words, one per core wasm parameter.
+    Words(usize),
 }
 
 impl RustFlagsRepr {
@@ -1382,7 +1468,7 @@ impl RustFlagsRepr {
             FlagsRepr::U32(1) => RustFlagsRepr::U32,
             FlagsRepr::U32(2) => RustFlagsRepr::U64,
             FlagsRepr::U32(3 | 4) => RustFlagsRepr::U128,
-            FlagsRepr::U32(n) => panic!("unsupported number of flags: {}", n * 32),
+            FlagsRepr::U32(n) => RustFlagsRepr::Words(n),
         }
     }
 }
@@ -1395,6 +1481,7 @@ impl fmt::Display for RustFlagsRepr {
             RustFlagsRepr::U32 => "u32".fmt(f),
             RustFlagsRepr::U64 => "u64".fmt(f),
             RustFlagsRepr::U128 => "u128".fmt(f),
+            RustFlagsRepr::Words(n) => write!(f, "[u32; {n}]"),
         }
     }
 }
@@ -3,6 +3,19 @@
 
 mod codegen_tests {
     macro_rules! codegen_test {
+        // `future` and `stream` types are only generated when opted into.
+        (futures_streams $name:tt $test:tt) => {
+            mod futures_streams {
+                wit_bindgen::generate!({
+                    path: $test,
+                    stubs,
+                    unstable_async_types: true,
+                });
+
+                #[test]
+                fn works() {}
+            }
+        };
         ($id:ident $name:tt $test:tt) => {
             mod $id {
                 wit_bindgen::generate!({
@@ -225,6 +238,172 @@ mod alternative_bitflags_path {
     }
 }
 
+mod wide_flags {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline;
+            world foo {
+                flags bar {
+                    b0, b1, b2, b3, b4, b5, b6, b7, b8, b9,
+                    b10, b11, b12, b13, b14, b15, b16, b17, b18, b19,
+                    b20, b21, b22, b23, b24, b25, b26, b27, b28, b29,
+                    b30, b31, b32, b33, b34, b35, b36, b37, b38, b39,
+                    b40, b41, b42, b43, b44, b45, b46, b47, b48, b49,
+                    b50, b51, b52, b53, b54, b55, b56, b57, b58, b59,
+                    b60, b61, b62, b63, b64, b65, b66, b67, b68, b69,
+                    b70, b71, b72, b73, b74, b75, b76, b77, b78, b79,
+                    b80, b81, b82, b83, b84, b85, b86, b87, b88, b89,
+                    b90, b91, b92, b93, b94, b95, b96, b97, b98, b99,
+                    b100, b101, b102, b103, b104, b105, b106, b107, b108, b109,
+                    b110, b111, b112, b113, b114, b115, b116, b117, b118, b119,
+                    b120, b121, b122, b123, b124, b125, b126, b127, b128, b129,
+                }
+                import roundtrip-wide: func(a: bar) -> bar;
+                export get-wide-flags: func() -> bar;
+            }
+        ",
+    });
+
+    struct Component;
+
+    export!(Component);
+
+    impl Guest for Component {
+        fn get_wide_flags() -> Bar {
+            Bar::B0 | Bar::B64 | Bar::B129
+        }
+    }
+
+    #[test]
+    fn bitset() {
+        let mut flags = Bar::B0 | Bar::B129;
+        assert_eq!(flags.bits(), [1, 0, 0, 0, 2]);
+        assert!(flags.contains(Bar::B129));
+        assert!(!flags.contains(Bar::B64));
+        assert!(flags.intersects(Bar::B0 | Bar::B64));
+
+        flags.insert(Bar::B64);
+        flags.remove(Bar::B0);
+        assert_eq!(flags, Bar::B64 | Bar::B129);
+        assert_eq!(flags - Bar::B64, Bar::B129);
+
+        assert!(Bar::empty().is_empty());
+        assert!(Bar::all().is_all());
+        assert_eq!(Bar::all().bits(), [!0, !0, !0, !0, 0b11]);
+        assert_eq!(!Bar::empty(), Bar::all());
+        assert_eq!(Bar::from_bits([0, 0, 0, 0, 0b100]), None);
+        assert_eq!(
+            Bar::from_bits_truncate([1, 0, 0, 0, 0b110]),
+            Bar::B0 | Bar::B129
+        );
+    }
+}
+
+mod anonymous_types {
+    use wit_bindgen_core::wit_parser::{Resolve, UnresolvedPackage};
+
+    // A `Resolve` built in memory may contain records, variants, enums,
+    // flags and resources without a name, which can't be written in WIT, so
+    // strip the names from a parsed package to simulate that.
+    #[test]
+    fn synthesized_names() -> anyhow::Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push(UnresolvedPackage::parse(
+            "input.wit".as_ref(),
+            r#"
+                package my:anonymous;
+
+                interface foo {
+                    record r { a: u32, b: string }
+                    variant v { a(r), b }
+                    enum e { a, b }
+                    flags f { a, b }
+                    resource res {
+                        constructor();
+                        get: func() -> u32;
+                    }
+
+                    a: func(x: r, y: v) -> tuple<e, f>;
+                    b: func(x: borrow<res>) -> res;
+                }
+
+                world anonymous {
+                    import foo;
+                    export foo;
+                }
+            "#,
+        )?)?;
+        let world = resolve.select_world(pkg, None)?;
+        for (_, iface) in resolve.interfaces.iter_mut() {
+            iface.types.clear();
+        }
+        for (_, ty) in resolve.types.iter_mut() {
+            ty.name = None;
+        }
+
+        let mut files = Default::default();
+        wit_bindgen_rust::Opts::default()
+            .build()
+            .generate(&resolve, world, &mut files)?;
+        let (_, contents) = files.iter().next().unwrap();
+        let contents = std::str::from_utf8(contents)?;
+        for name in [
+            "AnonymousRecord",
+            "AnonymousVariant",
+            "AnonymousEnum",
+            "AnonymousFlags",
+            "AnonymousResource",
+        ] {
+            assert!(
+                contents.contains(&format!("pub struct {name}"))
+                    || contents.contains(&format!("pub enum {name}"))
+            );
+        }
+        Ok(())
+    }
+}
+
+mod world_resource_exports {
+    use wit_bindgen_core::wit_parser::{Resolve, UnresolvedPackage};
+
+    // Resources can't be exported directly from a world in WIT, so parse an
+    // imported one and move it, along with its functions, to the world's
+    // exports. `wit-component` can't encode such a world, so this is an error
+    // rather than a panic.
+    #[test]
+    fn rejected() -> anyhow::Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push(UnresolvedPackage::parse(
+            "input.wit".as_ref(),
+            r#"
+                package my:resources;
+
+                world resources {
+                    resource r {
+                        constructor();
+                        get: func() -> u32;
+                    }
+                    export consume: func(x: r) -> u32;
+                }
+            "#,
+        )?)?;
+        let world = resolve.select_world(pkg, None)?;
+        let imports = std::mem::take(&mut resolve.worlds[world].imports);
+        let exports = std::mem::take(&mut resolve.worlds[world].exports);
+        resolve.worlds[world].exports = imports.into_iter().chain(exports).collect();
+
+        let err = wit_bindgen_rust::Opts::default()
+            .build()
+            .generate(&resolve, world, &mut Default::default())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "world `resources` exports types, which aren't supported by this generator"
+        );
+        Ok(())
+    }
+}
+
 mod owned_resource_deref_mut {
     wit_bindgen::generate!({
         inline: "
@@ -452,3 +631,163 @@ mod with_and_resources {
         });
     }
 }
+
+mod async_exports {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline;
+
+            interface foo {
+                resource counter {
+                    constructor(start: u32);
+                    increment: func() -> u32;
+                    merge: static func(a: borrow<counter>, b: borrow<counter>) -> counter;
+                }
+
+                add: func(a: u32, b: u32) -> u32;
+                greet: func(name: string) -> string;
+            }
+
+            world baz {
+                import log: func(msg: string);
+                export foo;
+                export run: func();
+            }
+        ",
+        async_exports: true,
+    });
+
+    use exports::my::inline::foo::{Counter, CounterBorrow, Guest as FooGuest, GuestCounter};
+    use std::cell::Cell;
+
+    struct Yield(bool);
+
+    impl std::future::Future for Yield {
+        type Output = ();
+
+        fn poll(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.0 {
+                std::task::Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        }
+    }
+
+    pub struct MyCounter(Cell<u32>);
+
+    impl GuestCounter for MyCounter {
+        async fn new(start: u32) -> Self {
+            MyCounter(Cell::new(start))
+        }
+
+        async fn increment(&self) -> u32 {
+            Yield(false).await;
+            self.0.set(self.0.get() + 1);
+            self.0.get()
+        }
+
+        async fn merge(a: CounterBorrow<'_>, b: CounterBorrow<'_>) -> Counter {
+            let a = a.get::<MyCounter>().0.get();
+            let b = b.get::<MyCounter>().0.get();
+            Counter::new(MyCounter(Cell::new(a + b)))
+        }
+    }
+
+    struct Component;
+
+    impl FooGuest for Component {
+        type Counter = MyCounter;
+
+        async fn add(a: u32, b: u32) -> u32 {
+            Yield(false).await;
+            a + b
+        }
+
+        async fn greet(name: String) -> String {
+            format!("hello {name}")
+        }
+    }
+
+    impl Guest for Component {
+        async fn run() {
+            log("hello");
+        }
+    }
+
+    export!(Component);
+
+    #[test]
+    fn export_runs_to_completion() {
+        let sum = unsafe { exports::my::inline::foo::_export_add_cabi::<Component>(1, 2) };
+        assert_eq!(sum, 3);
+    }
+}
+
+mod async_type_handles {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline;
+
+            world baz {
+                record pair {
+                    f: future<u32>,
+                    s: stream<u8, string>,
+                }
+
+                export swap-future: func(x: future<u32>, y: future<u32>) -> future<u32>;
+                export pair-up: func(f: future<u32>, s: stream<u8, string>) -> pair;
+                export last: func(x: list<stream<u8, string>>) -> option<stream<u8, string>>;
+            }
+        ",
+        unstable_async_types: true,
+    });
+
+    use _rt::{FutureReader, StreamReader};
+
+    struct Component;
+
+    impl Guest for Component {
+        fn swap_future(x: FutureReader<u32>, y: FutureReader<u32>) -> FutureReader<u32> {
+            // Dropping a handle isn't possible outside of wasm, so hand it
+            // back instead.
+            std::mem::forget(x);
+            y
+        }
+
+        fn pair_up(f: FutureReader<u32>, s: StreamReader<u8, String>) -> Pair {
+            Pair { f, s }
+        }
+
+        fn last(mut x: Vec<StreamReader<u8, String>>) -> Option<StreamReader<u8, String>> {
+            let last = x.pop();
+            std::mem::forget(x);
+            last
+        }
+    }
+
+    export!(Component);
+
+    // Handles are lifted from their `i32` representation when passed in and
+    // lowered back to it, either directly or through memory, when returned.
+    #[test]
+    fn handles_round_trip() {
+        unsafe {
+            assert_eq!(_export_swap_future_cabi::<Component>(1, 2), 2);
+
+            let ret = _export_pair_up_cabi::<Component>(3, 4);
+            assert_eq!(*ret.cast::<i32>(), 3);
+            assert_eq!(*ret.add(4).cast::<i32>(), 4);
+
+            let mut list = std::mem::ManuallyDrop::new(vec![5i32, 6, 7]);
+            let ret = _export_last_cabi::<Component>(list.as_mut_ptr().cast(), list.len());
+            assert_eq!(*ret, 1);
+            assert_eq!(*ret.add(4).cast::<i32>(), 7);
+        }
+    }
+}
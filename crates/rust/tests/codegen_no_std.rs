@@ -14,6 +14,20 @@ extern crate alloc;
 
 mod codegen_tests {
     macro_rules! codegen_test {
+        // `future` and `stream` types are only generated when opted into.
+        (futures_streams $name:tt $test:tt) => {
+            mod futures_streams {
+                wit_bindgen::generate!({
+                    path: $test,
+                    std_feature,
+                    stubs,
+                    unstable_async_types: true,
+                });
+
+                #[test]
+                fn works() {}
+            }
+        };
         ($id:ident $name:tt $test:tt) => {
             mod $id {
                 wit_bindgen::generate!({
@@ -8,11 +8,11 @@ use std::{
 };
 use wit_bindgen_core::{
     abi::{self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType},
-    uwrite, uwriteln,
+    dealias, resource_functions, uwrite, uwriteln,
     wit_parser::{
-        Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Int, InterfaceId, Record, Resolve,
-        Result_, SizeAlign, Tuple, Type, TypeDef, TypeDefKind, TypeId, TypeOwner, Variant, WorldId,
-        WorldKey,
+        Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle, Int, InterfaceId, Record,
+        Resolve, Result_, SizeAlign, Stream, Tuple, Type, TypeDef, TypeDefKind, TypeId, TypeOwner,
+        Variant, WorldId, WorldKey,
     },
     Direction, Files, InterfaceGenerator as _, Ns, Source, WorldGenerator,
 };
@@ -58,10 +58,13 @@ pub struct TeaVmJava {
     tuple_counts: HashSet<usize>,
     needs_cleanup: bool,
     needs_result: bool,
+    needs_wasm_handle: bool,
+    needs_rep_table: bool,
     interface_fragments: HashMap<String, Vec<InterfaceFragment>>,
     world_fragments: Vec<InterfaceFragment>,
     sizes: SizeAlign,
     interface_names: HashMap<InterfaceId, String>,
+    exported_resources: HashSet<TypeId>,
 }
 
 impl TeaVmJava {
@@ -69,13 +72,21 @@ impl TeaVmJava {
         format!("{}.", self.name)
     }
 
-    fn interface<'a>(&'a mut self, resolve: &'a Resolve, name: &'a str) -> InterfaceGenerator<'a> {
+    fn interface<'a>(
+        &'a mut self,
+        resolve: &'a Resolve,
+        name: &'a str,
+        module: &'a str,
+        direction: Direction,
+    ) -> InterfaceGenerator<'a> {
         InterfaceGenerator {
             src: String::new(),
             stub: String::new(),
             gen: self,
             resolve,
             name,
+            module,
+            direction,
         }
     }
 }
@@ -95,11 +106,12 @@ impl WorldGenerator for TeaVmJava {
     ) {
         let name = interface_name(resolve, key, Direction::Import);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name);
+        let module = resolve.name_world_key(key);
+        let mut gen = self.interface(resolve, &name, &module, Direction::Import);
         gen.types(id);
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
-            gen.import(&resolve.name_world_key(key), func);
+            gen.import(&module, func);
         }
 
         gen.add_interface_fragment();
@@ -113,7 +125,7 @@ impl WorldGenerator for TeaVmJava {
         _files: &mut Files,
     ) {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, &name, "$root", Direction::Import);
 
         for (_, func) in funcs {
             gen.import("$root", func);
@@ -131,11 +143,12 @@ impl WorldGenerator for TeaVmJava {
     ) -> Result<()> {
         let name = interface_name(resolve, key, Direction::Export);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name);
+        let module = resolve.name_world_key(key);
+        let mut gen = self.interface(resolve, &name, &module, Direction::Export);
         gen.types(id);
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
-            gen.export(Some(&resolve.name_world_key(key)), func);
+            gen.export(Some(&module), func);
         }
 
         gen.add_interface_fragment();
@@ -150,7 +163,7 @@ impl WorldGenerator for TeaVmJava {
         _files: &mut Files,
     ) -> Result<()> {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, &name, "$root", Direction::Export);
 
         for (_, func) in funcs {
             gen.export(None, func);
@@ -168,7 +181,7 @@ impl WorldGenerator for TeaVmJava {
         _files: &mut Files,
     ) {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, &name, "$root", Direction::Import);
 
         for (ty_name, ty) in types {
             gen.define_type(ty_name, *ty);
@@ -343,6 +356,67 @@ impl WorldGenerator for TeaVmJava {
             );
         }
 
+        if self.needs_wasm_handle {
+            src.push_str(
+                "
+                public static final class WasmHandle {
+                    public final int value;
+
+                    public WasmHandle(int value) {
+                        this.value = value;
+                    }
+                }
+                ",
+            );
+        }
+
+        if self.needs_rep_table {
+            src.push_str(
+                r#"
+                public static final class RepTable<T> {
+                    private final ArrayList<Object> list = new ArrayList<>();
+                    private int firstVacant = -1;
+
+                    private static final class Vacant {
+                        public final int next;
+
+                        public Vacant(int next) {
+                            this.next = next;
+                        }
+                    }
+
+                    public int add(T value) {
+                        int rep;
+                        if (firstVacant >= 0) {
+                            rep = firstVacant;
+                            firstVacant = ((Vacant) list.get(rep)).next;
+                            list.set(rep, value);
+                        } else {
+                            rep = list.size();
+                            list.add(value);
+                        }
+                        return rep;
+                    }
+
+                    public T get(int rep) {
+                        Object value = list.get(rep);
+                        if (value instanceof Vacant) {
+                            throw new RuntimeException("invalid rep " + rep);
+                        }
+                        return (T) value;
+                    }
+
+                    public T remove(int rep) {
+                        T value = get(rep);
+                        list.set(rep, new Vacant(firstVacant));
+                        firstVacant = rep;
+                        return value;
+                    }
+                }
+                "#,
+            );
+        }
+
         if self.return_area_align > 0 {
             let size = self.return_area_size;
             let align = self.return_area_align;
@@ -442,6 +516,8 @@ struct InterfaceGenerator<'a> {
     gen: &'a mut TeaVmJava,
     resolve: &'a Resolve,
     name: &'a str,
+    module: &'a str,
+    direction: Direction,
 }
 
 impl InterfaceGenerator<'_> {
@@ -480,10 +556,6 @@ impl InterfaceGenerator<'_> {
     }
 
     fn import(&mut self, module: &str, func: &Function) {
-        if func.kind != FunctionKind::Freestanding {
-            todo!("resources");
-        }
-
         let mut bindgen = FunctionBindgen::new(
             self,
             &func.name,
@@ -524,7 +596,7 @@ impl InterfaceGenerator<'_> {
             _ => unreachable!(),
         };
 
-        let camel_name = func.name.to_upper_camel_case();
+        let camel_name = camel_func_name(&func.name);
 
         let params = sig
             .params
@@ -580,7 +652,7 @@ impl InterfaceGenerator<'_> {
             _ => unreachable!(),
         };
 
-        let camel_name = func.name.to_upper_camel_case();
+        let camel_name = camel_func_name(&func.name);
 
         let params = sig
             .params
@@ -636,14 +708,16 @@ impl InterfaceGenerator<'_> {
             );
         }
 
-        if self.gen.opts.generate_stub {
+        // Resource functions are stubbed out as part of the resource's class, see
+        // `export_resource`.
+        if self.gen.opts.generate_stub && func.kind == FunctionKind::Freestanding {
             let sig = self.sig_string(func, true);
 
             uwrite!(
                 self.stub,
                 r#"
                 {sig} {{
-                    throw new RuntimeException("todo");
+                    throw new UnsupportedOperationException("not implemented");
                 }}
                 "#
             );
@@ -696,6 +770,9 @@ impl InterfaceGenerator<'_> {
                         format!("{}Tuple{count}{params}", self.gen.qualifier())
                     }
                     TypeDefKind::Option(ty) => self.type_name_boxed(ty, qualifier),
+                    TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => {
+                        self.type_name_with_qualifier(&Type::Id(*id), qualifier)
+                    }
                     TypeDefKind::Result(result) => {
                         self.gen.needs_result = true;
                         let mut name = |ty: &Option<Type>| {
@@ -786,7 +863,7 @@ impl InterfaceGenerator<'_> {
     }
 
     fn sig_string(&mut self, func: &Function, qualifier: bool) -> String {
-        let name = func.name.to_java_ident();
+        let name = java_func_name(&func.name);
 
         let result_type = match func.results.len() {
             0 => "void".into(),
@@ -818,7 +895,347 @@ impl InterfaceGenerator<'_> {
             .collect::<Vec<_>>()
             .join(", ");
 
-        format!("public static {result_type} {name}({params})")
+        // Resource functions are only called from the methods of the resource's class.
+        let visibility = if func.kind == FunctionKind::Freestanding {
+            "public"
+        } else {
+            "private"
+        };
+
+        format!("{visibility} static {result_type} {name}({params})")
+    }
+
+    fn resource_direction(&self, id: TypeId) -> Direction {
+        match self.direction {
+            Direction::Export if self.gen.exported_resources.contains(&id) => Direction::Export,
+            _ => Direction::Import,
+        }
+    }
+
+    fn resource_member_sig(
+        &mut self,
+        func: &Function,
+        qualifier: bool,
+    ) -> (String, String, Vec<String>) {
+        let result_type = match func.kind {
+            FunctionKind::Constructor(_) => String::new(),
+            _ => match func.results.len() {
+                0 => "void".into(),
+                1 => self
+                    .type_name_with_qualifier(func.results.iter_types().next().unwrap(), qualifier),
+                count => {
+                    self.gen.tuple_counts.insert(count);
+                    format!(
+                        "{}Tuple{count}<{}>",
+                        self.gen.qualifier(),
+                        func.results
+                            .iter_types()
+                            .map(|ty| self.type_name_boxed(ty, qualifier))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+            },
+        };
+
+        let skip = if let FunctionKind::Method(_) = func.kind {
+            1
+        } else {
+            0
+        };
+
+        let (params, args): (Vec<_>, Vec<_>) = func
+            .params
+            .iter()
+            .skip(skip)
+            .map(|(name, ty)| {
+                let name = name.to_java_ident();
+                let ty = self.type_name_with_qualifier(ty, qualifier);
+                (format!("{ty} {name}"), name)
+            })
+            .unzip();
+
+        (result_type, params.join(", "), args)
+    }
+
+    fn import_resource(&mut self, id: TypeId, name: &str) {
+        self.gen.needs_wasm_handle = true;
+
+        let camel_name = name.to_upper_camel_case();
+        let module = self.module;
+        let wasm_handle = format!("{}WasmHandle", self.gen.qualifier());
+
+        let mut members = String::new();
+        for func in resource_functions(self.resolve, id) {
+            let (result_type, params, mut args) = self.resource_member_sig(func, false);
+            let target = java_func_name(&func.name);
+            let member_name = func.item_name().to_java_ident();
+            let ret = if result_type == "void" { "" } else { "return " };
+
+            match func.kind {
+                FunctionKind::Constructor(_) => {
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        "
+                        public {camel_name}({params}) {{
+                            {camel_name} resource = {target}({args});
+                            this.handle = resource.handle;
+                            resource.handle = 0;
+                        }}
+                        "
+                    );
+                }
+                FunctionKind::Method(_) => {
+                    args.insert(0, "this".to_owned());
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        "
+                        public {result_type} {member_name}({params}) {{
+                            {ret}{target}({args});
+                        }}
+                        "
+                    );
+                }
+                FunctionKind::Static(_) => {
+                    let args = args.join(", ");
+                    uwrite!(
+                        members,
+                        "
+                        public static {result_type} {member_name}({params}) {{
+                            {ret}{target}({args});
+                        }}
+                        "
+                    );
+                }
+                FunctionKind::Freestanding => unreachable!(),
+            }
+        }
+
+        uwrite!(
+            self.src,
+            r#"
+            public static final class {camel_name} implements AutoCloseable {{
+                public int handle;
+
+                public {camel_name}({wasm_handle} handle) {{
+                    this.handle = handle.value;
+                }}
+
+                @Import(name = "[resource-drop]{name}", module = "{module}")
+                private static native void wasmImportResourceDrop(int p0);
+
+                @Override
+                public void close() {{
+                    if (this.handle != 0) {{
+                        int handle = this.handle;
+                        this.handle = 0;
+                        wasmImportResourceDrop(handle);
+                    }}
+                }}
+
+                {members}
+            }}
+            "#
+        );
+    }
+
+    fn export_resource(&mut self, id: TypeId, name: &str) {
+        self.gen.needs_rep_table = true;
+        self.gen.exported_resources.insert(id);
+
+        let camel_name = name.to_upper_camel_case();
+        let module = self.module;
+        let qualifier = self.gen.qualifier();
+
+        let mut members = String::new();
+        let mut stub_members = String::new();
+        for func in resource_functions(self.resolve, id) {
+            let (result_type, params, _) = self.resource_member_sig(func, true);
+            let member_name = func.item_name().to_java_ident();
+
+            // Constructors and static functions are provided by the implementation
+            // class, see `CallInterface`.
+            match func.kind {
+                FunctionKind::Constructor(_) => uwrite!(
+                    stub_members,
+                    r#"
+                    public {camel_name}({params}) {{
+                        throw new UnsupportedOperationException("not implemented");
+                    }}
+                    "#
+                ),
+                FunctionKind::Method(_) => {
+                    uwrite!(
+                        members,
+                        "
+                        public abstract {result_type} {member_name}({params});
+                        "
+                    );
+                    uwrite!(
+                        stub_members,
+                        r#"
+                        @Override
+                        public {result_type} {member_name}({params}) {{
+                            throw new UnsupportedOperationException("not implemented");
+                        }}
+                        "#
+                    );
+                }
+                FunctionKind::Static(_) => uwrite!(
+                    stub_members,
+                    r#"
+                    public static {result_type} {member_name}({params}) {{
+                        throw new UnsupportedOperationException("not implemented");
+                    }}
+                    "#
+                ),
+                FunctionKind::Freestanding => unreachable!(),
+            }
+        }
+
+        uwrite!(
+            self.src,
+            r#"
+            public static abstract class {camel_name} implements AutoCloseable {{
+                public static final {qualifier}RepTable<{camel_name}> REP_TABLE = new {qualifier}RepTable<>();
+
+                public int handle;
+
+                @Import(name = "[resource-drop]{name}", module = "[export]{module}")
+                private static native void wasmImportResourceDrop(int p0);
+
+                @Import(name = "[resource-new]{name}", module = "[export]{module}")
+                public static native int wasmImportResourceNew(int p0);
+
+                @Import(name = "[resource-rep]{name}", module = "[export]{module}")
+                public static native int wasmImportResourceRep(int p0);
+
+                @Override
+                public void close() {{
+                    if (this.handle != 0) {{
+                        int handle = this.handle;
+                        this.handle = 0;
+                        wasmImportResourceDrop(handle);
+                    }}
+                }}
+
+                {members}
+            }}
+
+            @Export(name = "{module}#[dtor]{name}")
+            private static void wasmExportResourceDtor{camel_name}(int rep) {{
+                {camel_name} resource = {camel_name}.REP_TABLE.remove(rep);
+                resource.handle = 0;
+                resource.close();
+            }}
+            "#
+        );
+
+        if self.gen.opts.generate_stub {
+            let qualified_name = self.type_name_with_qualifier(&Type::Id(id), true);
+
+            uwrite!(
+                self.stub,
+                "
+                public static class {camel_name} extends {qualified_name} {{
+                    {stub_members}
+                }}
+                "
+            );
+        }
+    }
+
+    /// Flags which don't fit in a `long` are stored as one `int` per 32 flags.
+    ///
+    /// Arrays can't be made immutable, so the words are copied on the way in
+    /// and out and `or`, `and` and `contains` stand in for the bitwise
+    /// operators used with narrower flags.
+    fn type_wide_flags(&mut self, name: &str, flags: &Flags, words: usize) {
+        let flags = flags
+            .flags
+            .iter()
+            .enumerate()
+            .map(|(i, flag)| {
+                let flag_name = flag.name.to_shouty_snake_case();
+                let value = (0..words)
+                    .map(|word| {
+                        if word == i / 32 {
+                            format!("1 << {}", i % 32)
+                        } else {
+                            "0".to_owned()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "public static final {name} {flag_name} = new {name}(new int[] {{ {value} }});"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        uwrite!(
+            self.src,
+            r#"
+            public static final class {name} {{
+                private final int[] value;
+
+                public {name}(int[] value) {{
+                    if (value.length != {words}) {{
+                        throw new IllegalArgumentException("expected {words} words, got " + value.length);
+                    }}
+                    this.value = value.clone();
+                }}
+
+                public int[] value() {{
+                    return this.value.clone();
+                }}
+
+                public int word(int index) {{
+                    return this.value[index];
+                }}
+
+                public {name} or({name} other) {{
+                    int[] value = new int[{words}];
+                    for (int i = 0; i < {words}; ++i) {{
+                        value[i] = this.value[i] | other.value[i];
+                    }}
+                    return new {name}(value);
+                }}
+
+                public {name} and({name} other) {{
+                    int[] value = new int[{words}];
+                    for (int i = 0; i < {words}; ++i) {{
+                        value[i] = this.value[i] & other.value[i];
+                    }}
+                    return new {name}(value);
+                }}
+
+                public boolean contains({name} other) {{
+                    for (int i = 0; i < {words}; ++i) {{
+                        if ((this.value[i] & other.value[i]) != other.value[i]) {{
+                            return false;
+                        }}
+                    }}
+                    return true;
+                }}
+
+                @Override
+                public boolean equals(Object other) {{
+                    return other instanceof {name} && java.util.Arrays.equals(this.value, (({name}) other).value);
+                }}
+
+                @Override
+                public int hashCode() {{
+                    return java.util.Arrays.hashCode(this.value);
+                }}
+
+                {flags}
+            }}
+            "#
+        );
     }
 }
 
@@ -887,8 +1304,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     }
 
     fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
-        _ = (id, name, docs);
-        todo!()
+        self.print_docs(docs);
+
+        match self.direction {
+            Direction::Import => self.import_resource(id, name),
+            Direction::Export => self.export_resource(id, name),
+        }
     }
 
     fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
@@ -896,13 +1317,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
         let name = name.to_upper_camel_case();
 
-        let ty = match flags.repr() {
-            FlagsRepr::U8 => "byte",
-            FlagsRepr::U16 => "short",
-            FlagsRepr::U32(1) => "int",
-            FlagsRepr::U32(2) => "long",
-            repr => todo!("flags {repr:?}"),
-        };
+        if let FlagsRepr::U32(words @ 3..) = flags.repr() {
+            self.type_wide_flags(&name, flags, words);
+            return;
+        }
+
+        let ty = flags_type(flags);
 
         let flags = flags
             .flags
@@ -1069,6 +1489,14 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     fn type_builtin(&mut self, _id: TypeId, _name: &str, _ty: &Type, _docs: &Docs) {
         unimplemented!();
     }
+
+    fn type_future(&mut self, _id: TypeId, _name: &str, _ty: &Option<Type>, _docs: &Docs) {
+        unreachable!("`future` types are rejected by `WorldGenerator::generate`")
+    }
+
+    fn type_stream(&mut self, _id: TypeId, _name: &str, _stream: &Stream, _docs: &Docs) {
+        unreachable!("`stream` types are rejected by `WorldGenerator::generate`")
+    }
 }
 
 struct Block {
@@ -1341,38 +1769,138 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
             Instruction::BoolFromI32 => results.push(format!("({} != 0)", operands[0])),
 
-            // TODO: checked
-            Instruction::FlagsLower { flags, .. } => match flags_repr(flags) {
-                Int::U8 | Int::U16 | Int::U32 => {
-                    results.push(format!("({}).value", operands[0]));
+            Instruction::FlagsLower { flags, .. } => {
+                let op = &operands[0];
+                match flags.repr() {
+                    FlagsRepr::U8 | FlagsRepr::U16 | FlagsRepr::U32(1) => {
+                        results.push(format!("({op}).value"));
+                    }
+                    FlagsRepr::U32(2) => {
+                        results.push(format!("(int) (({op}).value & 0xffffffffL)"));
+                        results.push(format!("(int) ((({op}).value >>> 32) & 0xffffffffL)"));
+                    }
+                    // Flags wider than 64 bits are stored as one `int` per 32
+                    // flags.
+                    // TODO: checked
+                    FlagsRepr::U32(n) => {
+                        for i in 0..n {
+                            results.push(format!("({op}).word({i})"));
+                        }
+                    }
                 }
-                Int::U64 => {
-                    let op = &operands[0];
-                    results.push(format!("(int) (({op}).value & 0xffffffffL)"));
-                    results.push(format!("(int) ((({op}).value >>> 32) & 0xffffffffL)"));
+            }
+
+            Instruction::FlagsLift { flags, ty, .. } => {
+                let name = self.gen.type_name(&Type::Id(*ty));
+                match flags.repr() {
+                    FlagsRepr::U8 | FlagsRepr::U16 | FlagsRepr::U32(1) => {
+                        results.push(format!(
+                            "new {name}(({}) {})",
+                            flags_type(flags),
+                            operands[0]
+                        ));
+                    }
+                    FlagsRepr::U32(2) => {
+                        results.push(format!(
+                            "new {name}((((long) ({})) & 0xffffffffL) | (((long) ({})) << 32))",
+                            operands[0], operands[1]
+                        ));
+                    }
+                    FlagsRepr::U32(_) => {
+                        results.push(format!(
+                            "new {name}(new int[] {{ {} }})",
+                            operands.join(", ")
+                        ));
+                    }
                 }
-            },
+            }
+
+            Instruction::HandleLower { handle, .. } => {
+                let (Handle::Own(ty) | Handle::Borrow(ty)) = handle;
+                let is_own = matches!(handle, Handle::Own(_));
+                let id = dealias(self.gen.resolve, *ty);
+                let op = &operands[0];
+                let lowered = self.locals.tmp("handle");
+
+                uwriteln!(self.src, "int {lowered} = ({op}).handle;");
 
-            Instruction::FlagsLift { flags, ty, .. } => match flags_repr(flags) {
-                Int::U8 | Int::U16 | Int::U32 => {
-                    results.push(format!(
-                        "new {}(({}) {})",
-                        self.gen.type_name(&Type::Id(*ty)),
-                        int_type(flags_repr(flags)),
-                        operands[0]
-                    ));
+                match self.gen.resource_direction(id) {
+                    Direction::Import => {
+                        // Zeroing `handle` turns a later `close()` into a no-op
+                        // now that the callee owns the resource.
+                        if is_own {
+                            uwriteln!(self.src, "({op}).handle = 0;");
+                        }
+                    }
+                    Direction::Export => {
+                        let class = self.gen.type_name_with_qualifier(&Type::Id(id), true);
+                        let rep = self.locals.tmp("rep");
+
+                        uwrite!(
+                            self.src,
+                            "
+                            if ({lowered} == 0) {{
+                                int {rep} = {class}.REP_TABLE.add({op});
+                                {lowered} = {class}.wasmImportResourceNew({rep});
+                            "
+                        );
+                        // The first time an instance leaves the guest it's added to
+                        // `REP_TABLE` and given a handle. Handing out an `own` means
+                        // `close()` must not drop that handle, whereas a `borrow`
+                        // remembers it so the instance can be passed again.
+                        if is_own {
+                            uwriteln!(self.src, "}}\n({op}).handle = 0;");
+                        } else {
+                            uwriteln!(self.src, "({op}).handle = {lowered};\n}}");
+                        }
+                    }
                 }
-                Int::U64 => {
-                    results.push(format!(
-                        "new {}(((long) ({})) | (((long) ({})) << 32))",
-                        self.gen.type_name(&Type::Id(*ty)),
-                        operands[0],
-                        operands[1]
-                    ));
+
+                results.push(lowered);
+            }
+
+            Instruction::HandleLift { handle, .. } => {
+                let (Handle::Own(ty) | Handle::Borrow(ty)) = handle;
+                let is_own = matches!(handle, Handle::Own(_));
+                let id = dealias(self.gen.resolve, *ty);
+                let class = self.gen.type_name_with_qualifier(&Type::Id(id), true);
+                let op = &operands[0];
+
+                match self.gen.resource_direction(id) {
+                    Direction::Import => {
+                        results.push(format!(
+                            "new {class}(new {}WasmHandle({op}))",
+                            self.gen.gen.qualifier()
+                        ));
+                    }
+                    Direction::Export => {
+                        if is_own {
+                            let handle = self.locals.tmp("handle");
+                            let resource = self.locals.tmp("resource");
+                            uwrite!(
+                                self.src,
+                                "
+                                int {handle} = {op};
+                                {class} {resource} = {class}.REP_TABLE.get({class}.wasmImportResourceRep({handle}));
+                                {resource}.handle = {handle};
+                                "
+                            );
+                            results.push(resource);
+                        } else {
+                            results.push(format!("{class}.REP_TABLE.get({op})"));
+                        }
+                    }
                 }
-            },
+            }
 
-            Instruction::HandleLower { .. } | Instruction::HandleLift { .. } => todo!(),
+            Instruction::FutureLower { .. }
+            | Instruction::FutureLift { .. }
+            | Instruction::StreamLower { .. }
+            | Instruction::StreamLift { .. } => {
+                unreachable!(
+                    "`future` and `stream` types are rejected by `WorldGenerator::generate`"
+                )
+            }
 
             Instruction::RecordLower { record, .. } => {
                 let op = &operands[0];
@@ -1754,7 +2282,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     _ => unreachable!(),
                 };
 
-                let func_name = self.func_name.to_upper_camel_case();
+                let func_name = camel_func_name(self.func_name);
 
                 let operands = operands.join(", ");
 
@@ -1807,14 +2335,37 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 };
 
                 let module = self.gen.name;
-                let name = func.name.to_java_ident();
+                let name = func.item_name().to_java_ident();
 
-                let args = operands.join(", ");
+                let call = match func.kind {
+                    FunctionKind::Freestanding => {
+                        format!("{module}Impl.{name}({})", operands.join(", "))
+                    }
+                    FunctionKind::Method(_) => {
+                        format!("({}).{name}({})", operands[0], operands[1..].join(", "))
+                    }
+                    FunctionKind::Constructor(id) => {
+                        let resource = self.gen.resolve.types[id]
+                            .name
+                            .as_ref()
+                            .unwrap()
+                            .to_upper_camel_case();
+                        format!("new {module}Impl.{resource}({})", operands.join(", "))
+                    }
+                    FunctionKind::Static(id) => {
+                        let resource = self.gen.resolve.types[id]
+                            .name
+                            .as_ref()
+                            .unwrap()
+                            .to_upper_camel_case();
+                        format!("{module}Impl.{resource}.{name}({})", operands.join(", "))
+                    }
+                };
 
                 uwrite!(
                     self.src,
                     "
-                    {assignment}{module}Impl.{name}({args});
+                    {assignment}{call};
                     {destructure}
                     "
                 );
@@ -2146,13 +2697,13 @@ fn wasm_type(ty: WasmType) -> &'static str {
     }
 }
 
-fn flags_repr(flags: &Flags) -> Int {
+fn flags_type(flags: &Flags) -> &'static str {
     match flags.repr() {
-        FlagsRepr::U8 => Int::U8,
-        FlagsRepr::U16 => Int::U16,
-        FlagsRepr::U32(1) => Int::U32,
-        FlagsRepr::U32(2) => Int::U64,
-        repr => panic!("unimplemented flags {repr:?}"),
+        FlagsRepr::U8 => "byte",
+        FlagsRepr::U16 => "short",
+        FlagsRepr::U32(1) => "int",
+        FlagsRepr::U32(2) => "long",
+        FlagsRepr::U32(_) => "int[]",
     }
 }
 
@@ -2196,6 +2747,16 @@ fn indent(code: &str) -> String {
     indented
 }
 
+/// Converts a core function name, e.g. `[method]x.get`, into an UpperCamelCase Java identifier.
+fn camel_func_name(name: &str) -> String {
+    name.replace('.', "-").to_upper_camel_case()
+}
+
+/// Converts a core function name, e.g. `[method]x.get`, into a lowerCamelCase Java identifier.
+fn java_func_name(name: &str) -> String {
+    name.replace('.', "-").to_java_ident()
+}
+
 fn is_primitive(ty: &Type) -> bool {
     matches!(
         ty,
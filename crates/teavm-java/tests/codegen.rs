@@ -3,24 +3,32 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 macro_rules! codegen_test {
-    // todo: implement resource support and then remove the following lines:
-    (resources $name:tt $test:tt) => {};
-    (resource_alias $name:tt $test:tt) => {};
-    (return_resource_from_export $name:tt $test:tt) => {};
-    (import_and_export_resource $name:tt $test:tt) => {};
-    (import_and_export_resource_alias $name:tt $test:tt) => {};
-    (resources_with_lists $name:tt $test:tt) => {};
-    (resource_local_alias $name:tt $test:tt) => {};
-    (resource_local_alias_borrow $name:tt $test:tt) => {};
-    (resource_local_alias_borrow_import $name:tt $test:tt) => {};
-    (resource_borrow_in_record $name:tt $test:tt) => {};
-    (resource_borrow_in_record_export $name:tt $test:tt) => {};
-    (resource_own_in_other_interface $name:tt $test:tt) => {};
+    // todo: support the following tests and then remove these lines:
     (same_names5 $name:tt $test:tt) => {};
-    (resources_in_aggregates $name:tt $test:tt) => {};
-    (issue668 $name:tt $test:tt) => {};
     (multiversion $name:tt $test:tt) => {};
 
+    // `future` and `stream` types aren't supported yet, so make sure they're
+    // reported as an error.
+    (futures_streams $name:tt $test:tt) => {
+        #[test]
+        fn futures_streams() {
+            test_helpers::run_world_codegen_test(
+                "guest-teavm-java",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    let err = wit_bindgen_teavm_java::Opts {
+                        generate_stub: true,
+                    }
+                    .build()
+                    .generate(resolve, world, files)
+                    .unwrap_err();
+                    assert!(err.to_string().contains("aren't supported"), "{err}");
+                },
+                |_, _| {},
+            );
+        }
+    };
+
     ($id:ident $name:tt $test:tt) => {
         #[test]
         fn $id() {
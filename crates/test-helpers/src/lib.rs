@@ -10,6 +10,8 @@ use wit_bindgen_core::Files;
 use wit_component::StringEncoding;
 use wit_parser::{Resolve, WorldId};
 
+pub mod smith;
+
 /// Returns a suitable directory to place output for tests within.
 ///
 /// This tries to pick a location in the `target` directory that can be
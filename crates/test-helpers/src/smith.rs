@@ -0,0 +1,310 @@
+//! Generation of random WIT packages, used to fuzz generators.
+//!
+//! The package is generated as WIT source so it goes through the same
+//! parsing and validation as handwritten WIT. Every type it defines may only
+//! refer to types defined before it, so the result is always valid.
+
+use std::fmt::Write;
+
+use arbitrary::{Result, Unstructured};
+use wit_bindgen_core::uwriteln;
+
+const MAX_INTERFACES: u32 = 4;
+const MAX_ITEMS: u32 = 8;
+const MAX_FIELDS: u32 = 6;
+const MAX_FLAGS: u32 = 100;
+const MAX_PARAMS: u32 = 5;
+const MAX_RESULTS: u32 = 3;
+const MAX_METHODS: u32 = 3;
+const MAX_DEPTH: u32 = 3;
+
+/// The name of the world in the package returned by [`smith`].
+pub const WORLD: &str = "fuzz";
+
+/// Returns the source of a random WIT package with a world named [`WORLD`].
+///
+/// The world imports and exports interfaces which define records, variants,
+/// enums, flags with up to 100 flags, resources and functions using them, as
+/// well as functions of its own.
+pub fn smith(u: &mut Unstructured<'_>) -> Result<String> {
+    Smith {
+        u,
+        src: String::new(),
+        names: 0,
+    }
+    .package()
+}
+
+struct Smith<'a, 'b> {
+    u: &'a mut Unstructured<'b>,
+    src: String,
+    // the number of names handed out, which keeps all names unique
+    names: u32,
+}
+
+/// The named types which can be referred to.
+#[derive(Default, Clone)]
+struct Scope {
+    types: Vec<String>,
+    resources: Vec<String>,
+}
+
+impl Smith<'_, '_> {
+    fn package(mut self) -> Result<String> {
+        self.src.push_str("package fuzz:smith;\n\n");
+
+        let mut interfaces: Vec<(String, Scope)> = Vec::new();
+        // the interfaces each interface transitively depends on
+        let mut deps: Vec<Vec<usize>> = Vec::new();
+        for _ in 0..self.u.int_in_range(1..=MAX_INTERFACES)? {
+            let name = self.name("i");
+            uwriteln!(self.src, "interface {name} {{");
+            let (scope, used) = self.uses(&interfaces)?;
+            let scope = self.interface(scope)?;
+            self.src.push_str("}\n\n");
+            interfaces.push((name, scope));
+            deps.push(match used {
+                Some(used) => {
+                    let mut used_deps = deps[used].clone();
+                    used_deps.push(used);
+                    used_deps
+                }
+                None => Vec::new(),
+            });
+        }
+
+        // The dependencies of an exported interface are imported, so an
+        // interface may only be exported if none of its dependencies is.
+        uwriteln!(self.src, "world {WORLD} {{");
+        let mut exported = vec![false; interfaces.len()];
+        for (i, (name, _)) in interfaces.iter().enumerate() {
+            if self.u.arbitrary()? {
+                uwriteln!(self.src, "  import {name};");
+            }
+            if !deps[i].iter().any(|dep| exported[*dep]) && self.u.arbitrary()? {
+                uwriteln!(self.src, "  export {name};");
+                exported[i] = true;
+            }
+        }
+        let imported = interfaces
+            .iter()
+            .zip(&exported)
+            .filter(|(_, exported)| !**exported)
+            .map(|(interface, _)| interface.clone())
+            .collect::<Vec<_>>();
+        let (scope, _) = self.uses(&imported)?;
+        for _ in 0..self.u.int_in_range(0..=MAX_ITEMS)? {
+            let name = self.name("func");
+            let dir = if self.u.arbitrary()? {
+                "import"
+            } else {
+                "export"
+            };
+            let func = self.func(&scope)?;
+            uwriteln!(self.src, "  {dir} {name}: {func};");
+        }
+        self.src.push_str("}\n");
+        Ok(self.src)
+    }
+
+    fn name(&mut self, prefix: &str) -> String {
+        self.names += 1;
+        format!("{prefix}{}", self.names)
+    }
+
+    /// Generates a `use` of some of the types of one of `interfaces`,
+    /// returning the types it brings into scope and the index of the
+    /// interface, if any was used.
+    fn uses(&mut self, interfaces: &[(String, Scope)]) -> Result<(Scope, Option<usize>)> {
+        let mut scope = Scope::default();
+        if interfaces.is_empty() || !self.u.arbitrary()? {
+            return Ok((scope, None));
+        }
+        let index = self.u.choose_index(interfaces.len())?;
+        let (name, used) = &interfaces[index];
+        let mut names = Vec::new();
+        for ty in used.types.iter() {
+            if self.u.arbitrary()? {
+                names.push(ty.clone());
+                scope.types.push(ty.clone());
+            }
+        }
+        for resource in used.resources.iter() {
+            if self.u.arbitrary()? {
+                names.push(resource.clone());
+                scope.resources.push(resource.clone());
+            }
+        }
+        if names.is_empty() {
+            return Ok((scope, None));
+        }
+        let names = names.join(", ");
+        uwriteln!(self.src, "  use {name}.{{{names}}};");
+        Ok((scope, Some(index)))
+    }
+
+    /// Generates the items of an interface, returning the types it defines.
+    fn interface(&mut self, mut scope: Scope) -> Result<Scope> {
+        let mut defined = Scope::default();
+        for _ in 0..self.u.int_in_range(0..=MAX_ITEMS)? {
+            match self.u.int_in_range(0..=6)? {
+                0 => {
+                    let name = self.name("r");
+                    let mut fields = Vec::new();
+                    for _ in 0..self.u.int_in_range(1..=MAX_FIELDS)? {
+                        let field = self.name("field");
+                        let ty = self.ty(&scope, MAX_DEPTH)?;
+                        fields.push(format!("{field}: {ty}"));
+                    }
+                    let fields = fields.join(", ");
+                    uwriteln!(self.src, "  record {name} {{ {fields} }}");
+                    scope.types.push(name.clone());
+                    defined.types.push(name);
+                }
+                1 => {
+                    let name = self.name("v");
+                    let mut cases = Vec::new();
+                    for _ in 0..self.u.int_in_range(1..=MAX_FIELDS)? {
+                        let case = self.name("c");
+                        if self.u.arbitrary()? {
+                            let ty = self.ty(&scope, MAX_DEPTH)?;
+                            cases.push(format!("{case}({ty})"));
+                        } else {
+                            cases.push(case);
+                        }
+                    }
+                    let cases = cases.join(", ");
+                    uwriteln!(self.src, "  variant {name} {{ {cases} }}");
+                    scope.types.push(name.clone());
+                    defined.types.push(name);
+                }
+                2 => {
+                    let name = self.name("e");
+                    let cases = (0..self.u.int_in_range(1..=MAX_FIELDS)?)
+                        .map(|_| self.name("c"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    uwriteln!(self.src, "  enum {name} {{ {cases} }}");
+                    scope.types.push(name.clone());
+                    defined.types.push(name);
+                }
+                3 => {
+                    let name = self.name("fl");
+                    let flags = (0..self.u.int_in_range(1..=MAX_FLAGS)?)
+                        .map(|_| self.name("b"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    uwriteln!(self.src, "  flags {name} {{ {flags} }}");
+                    scope.types.push(name.clone());
+                    defined.types.push(name);
+                }
+                4 => {
+                    let name = self.name("t");
+                    let ty = self.ty(&scope, MAX_DEPTH)?;
+                    uwriteln!(self.src, "  type {name} = {ty};");
+                    scope.types.push(name.clone());
+                    defined.types.push(name);
+                }
+                5 => {
+                    // the resource is in scope for its own methods
+                    let name = self.name("res");
+                    scope.resources.push(name.clone());
+                    defined.resources.push(name.clone());
+                    uwriteln!(self.src, "  resource {name} {{");
+                    if self.u.arbitrary()? {
+                        let params = self.params(&scope)?;
+                        uwriteln!(self.src, "    constructor({params});");
+                    }
+                    for _ in 0..self.u.int_in_range(0..=MAX_METHODS)? {
+                        let method = self.name("m");
+                        let func = self.func(&scope)?;
+                        let kind = if self.u.arbitrary()? { "static " } else { "" };
+                        uwriteln!(self.src, "    {method}: {kind}{func};");
+                    }
+                    self.src.push_str("  }\n");
+                }
+                _ => {
+                    let name = self.name("func");
+                    let func = self.func(&scope)?;
+                    uwriteln!(self.src, "  {name}: {func};");
+                }
+            }
+        }
+        Ok(defined)
+    }
+
+    fn func(&mut self, scope: &Scope) -> Result<String> {
+        let params = self.params(scope)?;
+        let results = match self.u.int_in_range(0..=2)? {
+            0 => String::new(),
+            1 => format!(" -> {}", self.ty(scope, MAX_DEPTH)?),
+            _ => {
+                let mut results = Vec::new();
+                for _ in 0..self.u.int_in_range(0..=MAX_RESULTS)? {
+                    let name = self.name("p");
+                    let ty = self.ty(scope, MAX_DEPTH)?;
+                    results.push(format!("{name}: {ty}"));
+                }
+                format!(" -> ({})", results.join(", "))
+            }
+        };
+        Ok(format!("func({params}){results}"))
+    }
+
+    /// Generates parameters, which unlike other types may borrow resources.
+    fn params(&mut self, scope: &Scope) -> Result<String> {
+        let mut params = Vec::new();
+        for _ in 0..self.u.int_in_range(0..=MAX_PARAMS)? {
+            let name = self.name("p");
+            let ty = if !scope.resources.is_empty() && self.u.arbitrary()? {
+                format!("borrow<{}>", self.u.choose(&scope.resources)?)
+            } else {
+                self.ty(scope, MAX_DEPTH)?
+            };
+            params.push(format!("{name}: {ty}"));
+        }
+        Ok(params.join(", "))
+    }
+
+    fn ty(&mut self, scope: &Scope, depth: u32) -> Result<String> {
+        const PRIMITIVES: &[&str] = &[
+            "bool", "u8", "s8", "u16", "s16", "u32", "s32", "u64", "s64", "f32", "f64", "char",
+            "string",
+        ];
+
+        let max = if depth == 0 { 2 } else { 6 };
+        Ok(match self.u.int_in_range(0..=max)? {
+            1 if !scope.types.is_empty() => self.u.choose(&scope.types)?.clone(),
+            2 if !scope.resources.is_empty() => self.u.choose(&scope.resources)?.clone(),
+            3 => format!("list<{}>", self.ty(scope, depth - 1)?),
+            4 => format!("option<{}>", self.ty(scope, depth - 1)?),
+            5 => {
+                let ok = self.maybe_ty(scope, depth - 1)?;
+                let err = self.maybe_ty(scope, depth - 1)?;
+                match (ok, err) {
+                    (None, None) => "result".to_string(),
+                    (Some(ok), None) => format!("result<{ok}>"),
+                    (ok, Some(err)) => {
+                        format!("result<{}, {err}>", ok.as_deref().unwrap_or("_"))
+                    }
+                }
+            }
+            6 => {
+                let mut types = Vec::new();
+                for _ in 0..self.u.int_in_range(1..=MAX_FIELDS)? {
+                    types.push(self.ty(scope, depth - 1)?);
+                }
+                format!("tuple<{}>", types.join(", "))
+            }
+            _ => self.u.choose(PRIMITIVES)?.to_string(),
+        })
+    }
+
+    fn maybe_ty(&mut self, scope: &Scope, depth: u32) -> Result<Option<String>> {
+        if self.u.arbitrary()? {
+            Ok(Some(self.ty(scope, depth)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use arbitrary::Unstructured;
+use wit_parser::{Resolve, UnresolvedPackage};
+
+/// Checks that random WIT packages are always valid, whatever the input.
+#[test]
+fn smith_is_valid() {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for i in 0..1000 {
+        let data = (0..(i % 20) * 100)
+            .map(|_| {
+                // xorshift, to get the same inputs each run
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let wit = test_helpers::smith::smith(&mut Unstructured::new(&data)).unwrap();
+
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("smith.wit".as_ref(), &wit)
+            .and_then(|pkg| resolve.push(pkg))
+            .unwrap_or_else(|e| panic!("invalid WIT: {e:?}\n{wit}"));
+        resolve
+            .select_world(pkg, Some(test_helpers::smith::WORLD))
+            .unwrap();
+    }
+}
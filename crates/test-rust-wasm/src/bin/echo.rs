@@ -0,0 +1,3 @@
+include!("../../../../tests/runtime/echo/wasm.rs");
+
+fn main() {}
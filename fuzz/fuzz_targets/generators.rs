@@ -0,0 +1,204 @@
+//! Runs every generator over random WIT worlds, checking that none of them
+//! panic and that the Rust and C bindings compile.
+//!
+//! The C bindings are compiled with the WASI SDK if `WASI_SDK_PATH` is set,
+//! and otherwise only syntax-checked with the host's `cc`.
+
+#![no_main]
+
+use arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use test_helpers::smith::{smith, WORLD};
+use wit_bindgen_core::{Files, WorldGenerator};
+use wit_component::StringEncoding;
+use wit_parser::{Resolve, TypeDefKind, UnresolvedPackage, WorldId};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = run(&mut Unstructured::new(data));
+});
+
+fn run(u: &mut Unstructured<'_>) -> Result<()> {
+    let wit = smith(u)?;
+    let mut resolve = Resolve::default();
+    let pkg = UnresolvedPackage::parse("smith.wit".as_ref(), &wit)
+        .and_then(|pkg| resolve.push(pkg))
+        .unwrap_or_else(|e| panic!("invalid WIT: {e:?}\n{wit}"));
+    let world = resolve.select_world(pkg, Some(WORLD)).unwrap();
+
+    let rust = wit_bindgen_rust::Opts {
+        stubs: true,
+        std_feature: u.arbitrary()?,
+        raw_strings: u.arbitrary()?,
+        // Borrowing types aren't fuzzed yet, as they don't always match
+        // between a type and its `use`s, or a value and its lowering.
+        ownership: wit_bindgen_rust::Ownership::Owning,
+        ..Default::default()
+    };
+    let files = generate(rust.build(), &resolve, world);
+    check_rust(&files);
+
+    // The C generator, which the C++ and cgo bindings wrap, and the C#
+    // generator don't support more than 64 flags yet.
+    let wide_flags = resolve.types.iter().any(|(_, ty)| match &ty.kind {
+        TypeDefKind::Flags(flags) => flags.flags.len() > 64,
+        _ => false,
+    });
+
+    let c = wit_bindgen_c::Opts {
+        no_sig_flattening: u.arbitrary()?,
+        autodrop_borrows: if u.arbitrary()? {
+            wit_bindgen_c::Enabled::Yes
+        } else {
+            wit_bindgen_c::Enabled::No
+        },
+        string_encoding: *u.choose(&[
+            StringEncoding::UTF8,
+            StringEncoding::UTF16,
+            StringEncoding::CompactUTF16,
+        ])?,
+        split_interfaces: u.arbitrary()?,
+        ..Default::default()
+    };
+    if !wide_flags {
+        let files = generate(c.build(), &resolve, world);
+        check_c(&files);
+
+        generate(wit_bindgen_cpp::Opts::default().build(), &resolve, world);
+    }
+
+    generate(
+        wit_bindgen_host_rust::Opts::default().build(),
+        &resolve,
+        world,
+    );
+
+    let java = wit_bindgen_teavm_java::Opts {
+        generate_stub: u.arbitrary()?,
+    };
+    generate(java.build(), &resolve, world);
+
+    let go = wit_bindgen_go::Opts {
+        gofmt: false,
+        pure_go: wide_flags || u.arbitrary()?,
+        generate_stub: u.arbitrary()?,
+        ..Default::default()
+    };
+    generate(go.build(), &resolve, world);
+
+    let csharp = wit_bindgen_csharp::Opts {
+        generate_stub: u.arbitrary()?,
+        ..Default::default()
+    };
+    if !wide_flags {
+        generate(csharp.build(), &resolve, world);
+    }
+
+    generate(
+        wit_bindgen_markdown::Opts::default().build(),
+        &resolve,
+        world,
+    );
+    Ok(())
+}
+
+fn generate(mut gen: Box<dyn WorldGenerator>, resolve: &Resolve, world: WorldId) -> Files {
+    let mut files = Files::default();
+    gen.generate(resolve, world, &mut files).unwrap();
+    files
+}
+
+/// Returns the directory, which persists between runs, where the bindings of
+/// `gen_name` are compiled.
+fn out_dir(gen_name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target/generated")
+        .join(gen_name);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, files: &Files) {
+    for (name, contents) in files.iter() {
+        let dst = dir.join(name);
+        fs::create_dir_all(dst.parent().unwrap()).unwrap();
+        fs::write(dst, contents).unwrap();
+    }
+}
+
+/// Checks the Rust bindings in a crate of their own, which keeps its target
+/// directory between runs so only the bindings are checked each time.
+fn check_rust(files: &Files) {
+    let dir = out_dir("rust");
+    let guest_rust = Path::new(env!("CARGO_MANIFEST_DIR")).join("../crates/guest-rust");
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "\
+[package]
+name = \"smith\"
+version = \"0.0.0\"
+edition = \"2021\"
+
+[lib]
+path = \"{WORLD}.rs\"
+
+[dependencies]
+wit-bindgen = {{ path = {guest_rust:?} }}
+
+[workspace]
+"
+        ),
+    )
+    .unwrap();
+    write(&dir, files);
+    test_helpers::run_command(
+        Command::new("cargo")
+            .arg("check")
+            .arg("--offline")
+            .arg("--quiet")
+            .current_dir(&dir),
+    );
+}
+
+fn check_c(files: &Files) {
+    let dir = out_dir("c");
+    drop(fs::remove_dir_all(&dir));
+    fs::create_dir_all(&dir).unwrap();
+    write(&dir, files);
+
+    let mut cmd = match env::var_os("WASI_SDK_PATH") {
+        Some(sdk_path) => {
+            let sdk_path = PathBuf::from(sdk_path);
+            let mut cmd = Command::new(sdk_path.join("bin/clang"));
+            cmd.arg("--sysroot")
+                .arg(sdk_path.join("share/wasi-sysroot"));
+            cmd
+        }
+        // The host's pointers may be wider than wasm32's, which the
+        // bindings cast to and from `int32_t`.
+        None => {
+            let mut cmd = Command::new("cc");
+            cmd.arg("-Wno-attributes")
+                .arg("-Wno-pointer-to-int-cast")
+                .arg("-Wno-int-to-pointer-cast");
+            cmd
+        }
+    };
+    cmd.arg("-fsyntax-only")
+        .arg("-I")
+        .arg(&dir)
+        .arg("-Wall")
+        .arg("-Wextra")
+        .arg("-Werror")
+        .arg("-Wno-unused-parameter");
+    for (name, _) in files.iter() {
+        if name.ends_with(".c") {
+            cmd.arg(dir.join(name));
+        }
+    }
+    test_helpers::run_command(&mut cmd);
+}
@@ -30,7 +30,15 @@ enum Opt {
         #[clap(flatten)]
         args: Common,
     },
-    /// Generates bindings for C/CPP guest modules.
+    /// Generates host-side bindings for Rust embedders using `wasmtime`.
+    #[cfg(feature = "host-rust")]
+    HostRust {
+        #[clap(flatten)]
+        opts: wit_bindgen_host_rust::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
+    /// Generates bindings for C guest modules.
     #[cfg(feature = "c")]
     C {
         #[clap(flatten)]
@@ -38,6 +46,14 @@ enum Opt {
         #[clap(flatten)]
         args: Common,
     },
+    /// Generates bindings for C++ guest modules.
+    #[cfg(feature = "cpp")]
+    Cpp {
+        #[clap(flatten)]
+        opts: wit_bindgen_cpp::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
 
     /// Generates bindings for TeaVM-based Java guest modules.
     #[cfg(feature = "teavm-java")]
@@ -96,8 +112,12 @@ fn main() -> Result<()> {
         Opt::Markdown { opts, args } => (opts.build(), args),
         #[cfg(feature = "c")]
         Opt::C { opts, args } => (opts.build(), args),
+        #[cfg(feature = "cpp")]
+        Opt::Cpp { opts, args } => (opts.build(), args),
         #[cfg(feature = "rust")]
         Opt::Rust { opts, args } => (opts.build(), args),
+        #[cfg(feature = "host-rust")]
+        Opt::HostRust { opts, args } => (opts.build(), args),
         #[cfg(feature = "teavm-java")]
         Opt::TeavmJava { opts, args } => (opts.build(), args),
         #[cfg(feature = "go")]
@@ -0,0 +1,263 @@
+use anyhow::Result;
+use wasmtime::Store;
+
+wasmtime::component::bindgen!(in "tests/runtime/echo");
+
+use exports::test::echo::test::*;
+
+const ITERATIONS: usize = 100;
+
+const WIDE: [Wide; 40] = [
+    Wide::B0,
+    Wide::B1,
+    Wide::B2,
+    Wide::B3,
+    Wide::B4,
+    Wide::B5,
+    Wide::B6,
+    Wide::B7,
+    Wide::B8,
+    Wide::B9,
+    Wide::B10,
+    Wide::B11,
+    Wide::B12,
+    Wide::B13,
+    Wide::B14,
+    Wide::B15,
+    Wide::B16,
+    Wide::B17,
+    Wide::B18,
+    Wide::B19,
+    Wide::B20,
+    Wide::B21,
+    Wide::B22,
+    Wide::B23,
+    Wide::B24,
+    Wide::B25,
+    Wide::B26,
+    Wide::B27,
+    Wide::B28,
+    Wide::B29,
+    Wide::B30,
+    Wide::B31,
+    Wide::B32,
+    Wide::B33,
+    Wide::B34,
+    Wide::B35,
+    Wide::B36,
+    Wide::B37,
+    Wide::B38,
+    Wide::B39,
+];
+
+#[test]
+fn run() -> Result<()> {
+    crate::run_test(
+        "echo",
+        |_| Ok(()),
+        |store, component, linker| {
+            let (u, e) = Echo::instantiate(store, component, linker)?;
+            Ok((u.interface0, e))
+        },
+        run_test,
+    )
+}
+
+/// A small xorshift generator, seeded identically for every component so
+/// that a failure in one language can be reproduced in the others.
+struct Rng(u64);
+
+impl Rng {
+    fn u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bool(&mut self) -> bool {
+        self.u64() & 1 == 1
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.u64() % n as u64) as usize
+    }
+
+    // NaN is left out since its payload isn't preserved by every language.
+    fn f32(&mut self) -> f32 {
+        loop {
+            let f = f32::from_bits(self.u64() as u32);
+            if !f.is_nan() {
+                return f;
+            }
+        }
+    }
+
+    fn f64(&mut self) -> f64 {
+        loop {
+            let f = f64::from_bits(self.u64());
+            if !f.is_nan() {
+                return f;
+            }
+        }
+    }
+
+    fn char(&mut self) -> char {
+        loop {
+            // Favor ASCII, but also produce multi-byte encodings.
+            let max = if self.bool() { 0x80 } else { 0x110000 };
+            if let Some(c) = char::from_u32(self.below(max) as u32) {
+                return c;
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        (0..self.below(20)).map(|_| self.char()).collect()
+    }
+
+    fn list<T>(&mut self, mut f: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        (0..self.below(20)).map(|_| f(self)).collect()
+    }
+
+    fn aligned(&mut self) -> Aligned {
+        Aligned {
+            a: self.u64() as u8,
+            b: self.u64(),
+            c: self.u64() as u16,
+            d: self.f32(),
+            e: self.u64() as i8,
+        }
+    }
+
+    fn small(&mut self) -> Small {
+        [Small::READ, Small::WRITE, Small::EXEC]
+            .into_iter()
+            .filter(|_| self.bool())
+            .fold(Small::empty(), |a, b| a | b)
+    }
+
+    fn wide(&mut self) -> Wide {
+        WIDE.into_iter()
+            .filter(|_| self.bool())
+            .fold(Wide::empty(), |a, b| a | b)
+    }
+
+    fn kind(&mut self) -> Kind {
+        [Kind::First, Kind::Second, Kind::Third][self.below(3)]
+    }
+
+    fn payload(&mut self) -> Payload {
+        match self.below(5) {
+            0 => Payload::Nothing,
+            1 => Payload::Small(self.u64() as u8),
+            2 => Payload::Big(self.f64()),
+            3 => Payload::Text(self.string()),
+            _ => Payload::Pair((self.u64() as u32, self.u64() as i64)),
+        }
+    }
+}
+
+fn run_test(exports: Guest, store: &mut Store<crate::Wasi<()>>) -> Result<()> {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..ITERATIONS {
+        let a = rng.bool();
+        assert_eq!(exports.call_echo_bool(&mut *store, a)?, a);
+        let a = rng.u64() as u8;
+        assert_eq!(exports.call_echo_u8(&mut *store, a)?, a);
+        let a = rng.u64() as i8;
+        assert_eq!(exports.call_echo_s8(&mut *store, a)?, a);
+        let a = rng.u64() as u16;
+        assert_eq!(exports.call_echo_u16(&mut *store, a)?, a);
+        let a = rng.u64() as i16;
+        assert_eq!(exports.call_echo_s16(&mut *store, a)?, a);
+        let a = rng.u64() as u32;
+        assert_eq!(exports.call_echo_u32(&mut *store, a)?, a);
+        let a = rng.u64() as i32;
+        assert_eq!(exports.call_echo_s32(&mut *store, a)?, a);
+        let a = rng.u64();
+        assert_eq!(exports.call_echo_u64(&mut *store, a)?, a);
+        let a = rng.u64() as i64;
+        assert_eq!(exports.call_echo_s64(&mut *store, a)?, a);
+        let a = rng.f32();
+        assert_eq!(
+            exports.call_echo_f32(&mut *store, a)?.to_bits(),
+            a.to_bits()
+        );
+        let a = rng.f64();
+        assert_eq!(
+            exports.call_echo_f64(&mut *store, a)?.to_bits(),
+            a.to_bits()
+        );
+        let a = rng.char();
+        assert_eq!(exports.call_echo_char(&mut *store, a)?, a);
+        let a = rng.string();
+        assert_eq!(exports.call_echo_string(&mut *store, &a)?, a);
+
+        let a = rng.list(|r| r.u64() as u8);
+        assert_eq!(exports.call_echo_bytes(&mut *store, &a)?, a);
+        let a = rng.list(|r| r.u64() as i64);
+        assert_eq!(exports.call_echo_numbers(&mut *store, &a)?, a);
+        let a = rng.list(|r| r.string());
+        assert_eq!(exports.call_echo_strings(&mut *store, &a)?, a);
+
+        // Records and variants don't implement `PartialEq`, so they're
+        // compared through their `Debug` representations instead.
+        let a = rng.aligned();
+        let expected = format!("{a:?}");
+        assert_eq!(
+            format!("{:?}", exports.call_echo_aligned(&mut *store, a)?),
+            expected,
+        );
+        let a = rng.list(|r| r.aligned());
+        assert_eq!(
+            format!("{:?}", exports.call_echo_aligned_list(&mut *store, &a)?),
+            format!("{a:?}"),
+        );
+        let a = (rng.aligned(), rng.aligned(), rng.aligned(), rng.aligned());
+        let expected = format!("{a:?}");
+        assert_eq!(
+            format!(
+                "{:?}",
+                exports.call_echo_spilled(&mut *store, a.0, a.1, a.2, a.3)?
+            ),
+            expected,
+        );
+        let (x, y, z) = (rng.u64() as u8, rng.string(), rng.f64());
+        let (x2, y2, z2) = exports.call_echo_tuple(&mut *store, (x, &y[..], z))?;
+        assert_eq!((x2, y2, z2.to_bits()), (x, y, z.to_bits()));
+
+        let a = rng.small();
+        assert_eq!(exports.call_echo_small(&mut *store, a)?, a);
+        let a = rng.wide();
+        assert_eq!(exports.call_echo_wide(&mut *store, a)?, a);
+        let a = rng.kind();
+        assert_eq!(exports.call_echo_kind(&mut *store, a)?, a);
+
+        let a = rng.payload();
+        assert_eq!(
+            format!("{:?}", exports.call_echo_payload(&mut *store, &a)?),
+            format!("{a:?}"),
+        );
+        let a = rng.list(|r| r.payload());
+        assert_eq!(
+            format!("{:?}", exports.call_echo_payloads(&mut *store, &a)?),
+            format!("{a:?}"),
+        );
+
+        let a = if rng.bool() { Some(rng.string()) } else { None };
+        assert_eq!(exports.call_echo_option(&mut *store, a.as_deref())?, a);
+        let a = if rng.bool() {
+            Ok(rng.string())
+        } else {
+            Err(rng.u64() as u16)
+        };
+        assert_eq!(
+            exports.call_echo_result(&mut *store, a.as_deref().map_err(|e| *e))?,
+            a
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+#include <echo.h>
+
+// Ownership of each parameter moves to the return value, which the bindings
+// free once it has been returned.
+
+bool exports_test_echo_test_echo_bool(bool a) {
+  return a;
+}
+
+uint8_t exports_test_echo_test_echo_u8(uint8_t a) {
+  return a;
+}
+
+int8_t exports_test_echo_test_echo_s8(int8_t a) {
+  return a;
+}
+
+uint16_t exports_test_echo_test_echo_u16(uint16_t a) {
+  return a;
+}
+
+int16_t exports_test_echo_test_echo_s16(int16_t a) {
+  return a;
+}
+
+uint32_t exports_test_echo_test_echo_u32(uint32_t a) {
+  return a;
+}
+
+int32_t exports_test_echo_test_echo_s32(int32_t a) {
+  return a;
+}
+
+uint64_t exports_test_echo_test_echo_u64(uint64_t a) {
+  return a;
+}
+
+int64_t exports_test_echo_test_echo_s64(int64_t a) {
+  return a;
+}
+
+float exports_test_echo_test_echo_f32(float a) {
+  return a;
+}
+
+double exports_test_echo_test_echo_f64(double a) {
+  return a;
+}
+
+uint32_t exports_test_echo_test_echo_char(uint32_t a) {
+  return a;
+}
+
+void exports_test_echo_test_echo_string(echo_string_t *a, echo_string_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_bytes(echo_list_u8_t *a, echo_list_u8_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_numbers(echo_list_s64_t *a, echo_list_s64_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_strings(echo_list_string_t *a, echo_list_string_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_aligned(
+    exports_test_echo_test_aligned_t *a,
+    exports_test_echo_test_aligned_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_aligned_list(
+    exports_test_echo_test_list_aligned_t *a,
+    exports_test_echo_test_list_aligned_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_spilled(
+    exports_test_echo_test_aligned_t *a,
+    exports_test_echo_test_aligned_t *b,
+    exports_test_echo_test_aligned_t *c,
+    exports_test_echo_test_aligned_t *d,
+    exports_test_echo_test_tuple4_aligned_aligned_aligned_aligned_t *ret) {
+  ret->f0 = *a;
+  ret->f1 = *b;
+  ret->f2 = *c;
+  ret->f3 = *d;
+}
+
+void exports_test_echo_test_echo_tuple(
+    echo_tuple3_u8_string_float64_t *a,
+    echo_tuple3_u8_string_float64_t *ret) {
+  *ret = *a;
+}
+
+exports_test_echo_test_small_t exports_test_echo_test_echo_small(exports_test_echo_test_small_t a) {
+  return a;
+}
+
+exports_test_echo_test_wide_t exports_test_echo_test_echo_wide(exports_test_echo_test_wide_t a) {
+  return a;
+}
+
+exports_test_echo_test_kind_t exports_test_echo_test_echo_kind(exports_test_echo_test_kind_t a) {
+  return a;
+}
+
+void exports_test_echo_test_echo_payload(
+    exports_test_echo_test_payload_t *a,
+    exports_test_echo_test_payload_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_echo_test_echo_payloads(
+    exports_test_echo_test_list_payload_t *a,
+    exports_test_echo_test_list_payload_t *ret) {
+  *ret = *a;
+}
+
+bool exports_test_echo_test_echo_option(echo_string_t *maybe_a, echo_string_t *ret) {
+  if (maybe_a) {
+    *ret = *maybe_a;
+  }
+  return maybe_a != NULL;
+}
+
+bool exports_test_echo_test_echo_result(
+    exports_test_echo_test_result_string_u16_t *a,
+    echo_string_t *ret,
+    uint16_t *err) {
+  if (a->is_err) {
+    *err = a->val.err;
+    return false;
+  }
+  *ret = a->val.ok;
+  return true;
+}
@@ -0,0 +1,127 @@
+#include <echo.hpp>
+
+namespace exports::test::echo::test {
+
+class Echo : public Guest {
+public:
+  bool echo_bool(bool a) override {
+    return a;
+  }
+
+  uint8_t echo_u8(uint8_t a) override {
+    return a;
+  }
+
+  int8_t echo_s8(int8_t a) override {
+    return a;
+  }
+
+  uint16_t echo_u16(uint16_t a) override {
+    return a;
+  }
+
+  int16_t echo_s16(int16_t a) override {
+    return a;
+  }
+
+  uint32_t echo_u32(uint32_t a) override {
+    return a;
+  }
+
+  int32_t echo_s32(int32_t a) override {
+    return a;
+  }
+
+  uint64_t echo_u64(uint64_t a) override {
+    return a;
+  }
+
+  int64_t echo_s64(int64_t a) override {
+    return a;
+  }
+
+  float echo_f32(float a) override {
+    return a;
+  }
+
+  double echo_f64(double a) override {
+    return a;
+  }
+
+  char32_t echo_char(char32_t a) override {
+    return a;
+  }
+
+  std::string echo_string(std::string_view a) override {
+    return std::string(a);
+  }
+
+  std::vector<uint8_t> echo_bytes(std::span<const uint8_t> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  std::vector<int64_t> echo_numbers(std::span<const int64_t> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  std::vector<std::string> echo_strings(std::span<const std::string> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  Aligned echo_aligned(const Aligned& a) override {
+    return a;
+  }
+
+  std::vector<Aligned> echo_aligned_list(std::span<const Aligned> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  std::tuple<Aligned, Aligned, Aligned, Aligned> echo_spilled(
+      const Aligned& a,
+      const Aligned& b,
+      const Aligned& c,
+      const Aligned& d) override {
+    return {a, b, c, d};
+  }
+
+  std::tuple<uint8_t, std::string, double> echo_tuple(
+      const std::tuple<uint8_t, std::string, double>& a) override {
+    return a;
+  }
+
+  Small echo_small(Small a) override {
+    return a;
+  }
+
+  Wide echo_wide(Wide a) override {
+    return a;
+  }
+
+  Kind echo_kind(Kind a) override {
+    return a;
+  }
+
+  Payload echo_payload(const Payload& a) override {
+    return a;
+  }
+
+  std::vector<Payload> echo_payloads(std::span<const Payload> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  std::optional<std::string> echo_option(const std::optional<std::string>& a) override {
+    return a;
+  }
+
+  std::expected<std::string, uint16_t> echo_result(
+      const std::expected<std::string, uint16_t>& a) override {
+    return a;
+  }
+};
+
+Guest& Guest::instance() {
+  static Echo echo;
+  return echo;
+}
+
+}
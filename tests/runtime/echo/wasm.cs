@@ -0,0 +1,146 @@
+using System.Collections.Generic;
+
+namespace EchoWorld.wit.exports.test.echo
+{
+    public class TestImpl : ITest
+    {
+        public static bool EchoBool(bool a)
+        {
+            return a;
+        }
+
+        public static byte EchoU8(byte a)
+        {
+            return a;
+        }
+
+        public static sbyte EchoS8(sbyte a)
+        {
+            return a;
+        }
+
+        public static ushort EchoU16(ushort a)
+        {
+            return a;
+        }
+
+        public static short EchoS16(short a)
+        {
+            return a;
+        }
+
+        public static uint EchoU32(uint a)
+        {
+            return a;
+        }
+
+        public static int EchoS32(int a)
+        {
+            return a;
+        }
+
+        public static ulong EchoU64(ulong a)
+        {
+            return a;
+        }
+
+        public static long EchoS64(long a)
+        {
+            return a;
+        }
+
+        public static float EchoF32(float a)
+        {
+            return a;
+        }
+
+        public static double EchoF64(double a)
+        {
+            return a;
+        }
+
+        public static uint EchoChar(uint a)
+        {
+            return a;
+        }
+
+        public static string EchoString(string a)
+        {
+            return a;
+        }
+
+        public static byte[] EchoBytes(byte[] a)
+        {
+            return a;
+        }
+
+        public static long[] EchoNumbers(long[] a)
+        {
+            return a;
+        }
+
+        public static List<string> EchoStrings(List<string> a)
+        {
+            return a;
+        }
+
+        public static ITest.Aligned EchoAligned(ITest.Aligned a)
+        {
+            return a;
+        }
+
+        public static List<ITest.Aligned> EchoAlignedList(List<ITest.Aligned> a)
+        {
+            return a;
+        }
+
+        public static (ITest.Aligned, ITest.Aligned, ITest.Aligned, ITest.Aligned) EchoSpilled(
+            ITest.Aligned a,
+            ITest.Aligned b,
+            ITest.Aligned c,
+            ITest.Aligned d)
+        {
+            return (a, b, c, d);
+        }
+
+        public static (byte, string, double) EchoTuple((byte, string, double) a)
+        {
+            return a;
+        }
+
+        public static ITest.Small EchoSmall(ITest.Small a)
+        {
+            return a;
+        }
+
+        public static ITest.Wide EchoWide(ITest.Wide a)
+        {
+            return a;
+        }
+
+        public static ITest.Kind EchoKind(ITest.Kind a)
+        {
+            return a;
+        }
+
+        public static ITest.Payload EchoPayload(ITest.Payload a)
+        {
+            return a;
+        }
+
+        public static List<ITest.Payload> EchoPayloads(List<ITest.Payload> a)
+        {
+            return a;
+        }
+
+        public static ITest.Option_string EchoOption(ITest.Option_string a)
+        {
+            return a;
+        }
+
+        public static Result<string, ushort> EchoResult(Result<string, ushort> a)
+        {
+            return a;
+        }
+    }
+}
@@ -0,0 +1,121 @@
+package main
+
+import (
+	. "wit_echo_go/gen"
+)
+
+func init() {
+	SetExportsTestEchoTest(&EchoImpl{})
+}
+
+type EchoImpl struct{}
+
+func (e *EchoImpl) EchoBool(a bool) bool {
+	return a
+}
+
+func (e *EchoImpl) EchoU8(a uint8) uint8 {
+	return a
+}
+
+func (e *EchoImpl) EchoS8(a int8) int8 {
+	return a
+}
+
+func (e *EchoImpl) EchoU16(a uint16) uint16 {
+	return a
+}
+
+func (e *EchoImpl) EchoS16(a int16) int16 {
+	return a
+}
+
+func (e *EchoImpl) EchoU32(a uint32) uint32 {
+	return a
+}
+
+func (e *EchoImpl) EchoS32(a int32) int32 {
+	return a
+}
+
+func (e *EchoImpl) EchoU64(a uint64) uint64 {
+	return a
+}
+
+func (e *EchoImpl) EchoS64(a int64) int64 {
+	return a
+}
+
+func (e *EchoImpl) EchoF32(a float32) float32 {
+	return a
+}
+
+func (e *EchoImpl) EchoF64(a float64) float64 {
+	return a
+}
+
+func (e *EchoImpl) EchoChar(a rune) rune {
+	return a
+}
+
+func (e *EchoImpl) EchoString(a string) string {
+	return a
+}
+
+func (e *EchoImpl) EchoBytes(a []uint8) []uint8 {
+	return a
+}
+
+func (e *EchoImpl) EchoNumbers(a []int64) []int64 {
+	return a
+}
+
+func (e *EchoImpl) EchoStrings(a []string) []string {
+	return a
+}
+
+func (e *EchoImpl) EchoAligned(a ExportsTestEchoTestAligned) ExportsTestEchoTestAligned {
+	return a
+}
+
+func (e *EchoImpl) EchoAlignedList(a []ExportsTestEchoTestAligned) []ExportsTestEchoTestAligned {
+	return a
+}
+
+func (e *EchoImpl) EchoSpilled(a ExportsTestEchoTestAligned, b ExportsTestEchoTestAligned, c ExportsTestEchoTestAligned, d ExportsTestEchoTestAligned) ExportsTestEchoTestTuple4AlignedAlignedAlignedAlignedT {
+	return ExportsTestEchoTestTuple4AlignedAlignedAlignedAlignedT{a, b, c, d}
+}
+
+func (e *EchoImpl) EchoTuple(a ExportsTestEchoTestTuple3U8StringF64T) ExportsTestEchoTestTuple3U8StringF64T {
+	return a
+}
+
+func (e *EchoImpl) EchoSmall(a ExportsTestEchoTestSmall) ExportsTestEchoTestSmall {
+	return a
+}
+
+func (e *EchoImpl) EchoWide(a ExportsTestEchoTestWide) ExportsTestEchoTestWide {
+	return a
+}
+
+func (e *EchoImpl) EchoKind(a ExportsTestEchoTestKind) ExportsTestEchoTestKind {
+	return a
+}
+
+func (e *EchoImpl) EchoPayload(a ExportsTestEchoTestPayload) ExportsTestEchoTestPayload {
+	return a
+}
+
+func (e *EchoImpl) EchoPayloads(a []ExportsTestEchoTestPayload) []ExportsTestEchoTestPayload {
+	return a
+}
+
+func (e *EchoImpl) EchoOption(a Option[string]) Option[string] {
+	return a
+}
+
+func (e *EchoImpl) EchoResult(a Result[string, uint16]) Result[string, uint16] {
+	return a
+}
+
+func main() {}
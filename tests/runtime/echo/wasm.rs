@@ -0,0 +1,124 @@
+wit_bindgen::generate!({
+    path: "../../tests/runtime/echo",
+});
+
+use exports::test::echo::test::*;
+
+struct Component;
+
+export!(Component);
+
+impl Guest for Component {
+    fn echo_bool(a: bool) -> bool {
+        a
+    }
+
+    fn echo_u8(a: u8) -> u8 {
+        a
+    }
+
+    fn echo_s8(a: i8) -> i8 {
+        a
+    }
+
+    fn echo_u16(a: u16) -> u16 {
+        a
+    }
+
+    fn echo_s16(a: i16) -> i16 {
+        a
+    }
+
+    fn echo_u32(a: u32) -> u32 {
+        a
+    }
+
+    fn echo_s32(a: i32) -> i32 {
+        a
+    }
+
+    fn echo_u64(a: u64) -> u64 {
+        a
+    }
+
+    fn echo_s64(a: i64) -> i64 {
+        a
+    }
+
+    fn echo_f32(a: f32) -> f32 {
+        a
+    }
+
+    fn echo_f64(a: f64) -> f64 {
+        a
+    }
+
+    fn echo_char(a: char) -> char {
+        a
+    }
+
+    fn echo_string(a: String) -> String {
+        a
+    }
+
+    fn echo_bytes(a: Vec<u8>) -> Vec<u8> {
+        a
+    }
+
+    fn echo_numbers(a: Vec<i64>) -> Vec<i64> {
+        a
+    }
+
+    fn echo_strings(a: Vec<String>) -> Vec<String> {
+        a
+    }
+
+    fn echo_aligned(a: Aligned) -> Aligned {
+        a
+    }
+
+    fn echo_aligned_list(a: Vec<Aligned>) -> Vec<Aligned> {
+        a
+    }
+
+    fn echo_spilled(
+        a: Aligned,
+        b: Aligned,
+        c: Aligned,
+        d: Aligned,
+    ) -> (Aligned, Aligned, Aligned, Aligned) {
+        (a, b, c, d)
+    }
+
+    fn echo_tuple(a: (u8, String, f64)) -> (u8, String, f64) {
+        a
+    }
+
+    fn echo_small(a: Small) -> Small {
+        a
+    }
+
+    fn echo_wide(a: Wide) -> Wide {
+        a
+    }
+
+    fn echo_kind(a: Kind) -> Kind {
+        a
+    }
+
+    fn echo_payload(a: Payload) -> Payload {
+        a
+    }
+
+    fn echo_payloads(a: Vec<Payload>) -> Vec<Payload> {
+        a
+    }
+
+    fn echo_option(a: Option<String>) -> Option<String> {
+        a
+    }
+
+    fn echo_result(a: Result<String, u16>) -> Result<String, u16> {
+        a
+    }
+}
@@ -0,0 +1,119 @@
+package wit.exports.test.echo;
+
+import java.util.ArrayList;
+
+import wit.worlds.Echo.Result;
+import wit.worlds.Echo.Tuple3;
+import wit.worlds.Echo.Tuple4;
+
+public class TestImpl {
+    public static boolean echoBool(boolean a) {
+        return a;
+    }
+
+    public static byte echoU8(byte a) {
+        return a;
+    }
+
+    public static byte echoS8(byte a) {
+        return a;
+    }
+
+    public static short echoU16(short a) {
+        return a;
+    }
+
+    public static short echoS16(short a) {
+        return a;
+    }
+
+    public static int echoU32(int a) {
+        return a;
+    }
+
+    public static int echoS32(int a) {
+        return a;
+    }
+
+    public static long echoU64(long a) {
+        return a;
+    }
+
+    public static long echoS64(long a) {
+        return a;
+    }
+
+    public static float echoF32(float a) {
+        return a;
+    }
+
+    public static double echoF64(double a) {
+        return a;
+    }
+
+    public static int echoChar(int a) {
+        return a;
+    }
+
+    public static String echoString(String a) {
+        return a;
+    }
+
+    public static byte[] echoBytes(byte[] a) {
+        return a;
+    }
+
+    public static long[] echoNumbers(long[] a) {
+        return a;
+    }
+
+    public static ArrayList<String> echoStrings(ArrayList<String> a) {
+        return a;
+    }
+
+    public static Test.Aligned echoAligned(Test.Aligned a) {
+        return a;
+    }
+
+    public static ArrayList<Test.Aligned> echoAlignedList(ArrayList<Test.Aligned> a) {
+        return a;
+    }
+
+    public static Tuple4<Test.Aligned, Test.Aligned, Test.Aligned, Test.Aligned> echoSpilled
+        (Test.Aligned a, Test.Aligned b, Test.Aligned c, Test.Aligned d)
+    {
+        return new Tuple4<>(a, b, c, d);
+    }
+
+    public static Tuple3<Byte, String, Double> echoTuple(Tuple3<Byte, String, Double> a) {
+        return a;
+    }
+
+    public static Test.Small echoSmall(Test.Small a) {
+        return a;
+    }
+
+    public static Test.Wide echoWide(Test.Wide a) {
+        return a;
+    }
+
+    public static Test.Kind echoKind(Test.Kind a) {
+        return a;
+    }
+
+    public static Test.Payload echoPayload(Test.Payload a) {
+        return a;
+    }
+
+    public static ArrayList<Test.Payload> echoPayloads(ArrayList<Test.Payload> a) {
+        return a;
+    }
+
+    public static String echoOption(String a) {
+        return a;
+    }
+
+    public static Result<String, Short> echoResult(Result<String, Short> a) {
+        return a;
+    }
+}
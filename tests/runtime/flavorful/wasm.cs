@@ -0,0 +1,126 @@
+using System.Collections.Generic;
+using System.Diagnostics;
+using System.Linq;
+using System.Text;
+using FlavorfulWorld.wit.imports.test.flavorful;
+
+namespace FlavorfulWorld
+{
+    public class FlavorfulWorldImpl : IFlavorfulWorld
+    {
+        public static void TestImports()
+        {
+            TestInterop.FListInRecord1(new ITest.ListInRecord1("list_in_record1"));
+            Debug.Assert(TestInterop.FListInRecord2().a == "list_in_record2");
+
+            Debug.Assert(TestInterop.FListInRecord3(new ITest.ListInRecord3("list_in_record3 input")).a
+                == "list_in_record3 output");
+
+            Debug.Assert(TestInterop.FListInRecord4(new ITest.ListInRecord4("input4")).a == "result4");
+
+            TestInterop.FListInVariant1(
+                new ITest.ListInVariant1V1("foo"),
+                Result<None, string>.err("bar")
+            );
+
+            {
+                var result = TestInterop.FListInVariant2();
+                Debug.Assert(result.HasValue && result.Value == "list_in_variant2");
+            }
+
+            {
+                var result = TestInterop.FListInVariant3(new ITest.ListInVariant3("input3"));
+                Debug.Assert(result.HasValue && result.Value == "output3");
+            }
+
+            {
+                var result = TestInterop.ErrnoResult();
+                Debug.Assert(result.IsErr && result.AsErr == ITest.MyErrno.B);
+            }
+
+            Debug.Assert(TestInterop.ErrnoResult().IsOk);
+
+            {
+                var (a, b) = TestInterop.ListTypedefs("typedef1", new List<string>() { "typedef2" });
+                Debug.Assert(a.SequenceEqual(Encoding.UTF8.GetBytes("typedef3")));
+                Debug.Assert(b.SequenceEqual(new List<string>() { "typedef4" }));
+            }
+
+            {
+                var (a, b, c) = TestInterop.ListOfVariants(
+                    new List<bool>() { true, false },
+                    new List<Result<None, None>>() { Result<None, None>.ok(new None()), Result<None, None>.err(new None()) },
+                    new List<ITest.MyErrno>() { ITest.MyErrno.SUCCESS, ITest.MyErrno.A }
+                );
+                Debug.Assert(a.SequenceEqual(new List<bool>() { false, true }));
+                Debug.Assert(b.Count == 2 && b[0].IsErr && b[1].IsOk);
+                Debug.Assert(c.SequenceEqual(new List<ITest.MyErrno>() { ITest.MyErrno.A, ITest.MyErrno.B }));
+            }
+        }
+    }
+}
+
+namespace FlavorfulWorld.wit.exports.test.flavorful
+{
+    public class TestImpl : ITest
+    {
+        public static void FListInRecord1(ITest.ListInRecord1 a)
+        {
+            Debug.Assert(a.a == "list_in_record1");
+        }
+
+        public static ITest.ListInRecord2 FListInRecord2()
+        {
+            return new ITest.ListInRecord2("list_in_record2");
+        }
+
+        public static ITest.ListInRecord3 FListInRecord3(ITest.ListInRecord3 a)
+        {
+            Debug.Assert(a.a == "list_in_record3 input");
+            return new ITest.ListInRecord3("list_in_record3 output");
+        }
+
+        public static ITest.ListInRecord4 FListInRecord4(ITest.ListInRecord4 a)
+        {
+            Debug.Assert(a.a == "input4");
+            return new ITest.ListInRecord4("result4");
+        }
+
+        public static void FListInVariant1(ITest.ListInVariant1V1 a, Result<None, string> b)
+        {
+            Debug.Assert(a.HasValue && a.Value == "foo");
+            Debug.Assert(b.IsErr && b.AsErr == "bar");
+        }
+
+        public static ITest.ListInVariant2 FListInVariant2()
+        {
+            return new ITest.ListInVariant2("list_in_variant2");
+        }
+
+        public static ITest.ListInVariant3 FListInVariant3(ITest.ListInVariant3 a)
+        {
+            Debug.Assert(a.HasValue && a.Value == "input3");
+            return new ITest.ListInVariant3("output3");
+        }
+
+        public static Result<None, ITest.MyErrno> ErrnoResult()
+        {
+            return Result<None, ITest.MyErrno>.err(ITest.MyErrno.B);
+        }
+
+        public static (byte[], List<string>) ListTypedefs(string a, List<string> c)
+        {
+            Debug.Assert(a == "typedef1");
+            Debug.Assert(c.SequenceEqual(new List<string>() { "typedef2" }));
+            return (Encoding.UTF8.GetBytes("typedef3"), new List<string>() { "typedef4" });
+        }
+
+        public static (List<bool>, List<Result<None, None>>, List<ITest.MyErrno>) ListOfVariants(
+            List<bool> a,
+            List<Result<None, None>> b,
+            List<ITest.MyErrno> c)
+        {
+            return (a, b, c);
+        }
+    }
+}
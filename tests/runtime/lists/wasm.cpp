@@ -0,0 +1,191 @@
+#include <algorithm>
+#include <cassert>
+#include <cfloat>
+#include <climits>
+#include <cmath>
+#include <lists.hpp>
+
+namespace exports::lists {
+
+class Lists : public Guest {
+public:
+  uint32_t allocated_bytes() override {
+    return 0;
+  }
+
+  void test_imports() override {
+    using namespace ::test::lists::test;
+
+    empty_list_param({});
+    empty_string_param("");
+    assert(empty_list_result().empty());
+    assert(empty_string_result().empty());
+
+    const uint8_t bytes[] = {1, 2, 3, 4};
+    list_param(bytes);
+    list_param2("foo");
+
+    const std::string strings[] = {"foo", "bar", "baz"};
+    list_param3(strings);
+
+    const std::vector<std::string> nested[] = {{"foo", "bar"}, {"baz"}};
+    list_param4(nested);
+
+    assert((list_result() == std::vector<uint8_t>{1, 2, 3, 4, 5}));
+    assert(list_result2() == "hello!");
+    assert((list_result3() == std::vector<std::string>{"hello,", "world!"}));
+
+    for (std::string_view s : {"", "x", "hello"}) {
+      auto bytes = std::span(reinterpret_cast<const uint8_t*>(s.data()), s.size());
+      auto ret = list_roundtrip(bytes);
+      assert(std::equal(ret.begin(), ret.end(), bytes.begin(), bytes.end()));
+    }
+
+    for (std::string_view s : {"x", "", "hello", "hello ⚑ world"}) {
+      assert(string_roundtrip(s) == s);
+    }
+
+    {
+      const uint8_t u8[] = {0, UCHAR_MAX};
+      const int8_t s8[] = {SCHAR_MIN, SCHAR_MAX};
+      auto [u, s] = list_minmax8(u8, s8);
+      assert(std::equal(u.begin(), u.end(), std::begin(u8), std::end(u8)));
+      assert(std::equal(s.begin(), s.end(), std::begin(s8), std::end(s8)));
+    }
+
+    {
+      const uint16_t u16[] = {0, USHRT_MAX};
+      const int16_t s16[] = {SHRT_MIN, SHRT_MAX};
+      auto [u, s] = list_minmax16(u16, s16);
+      assert(std::equal(u.begin(), u.end(), std::begin(u16), std::end(u16)));
+      assert(std::equal(s.begin(), s.end(), std::begin(s16), std::end(s16)));
+    }
+
+    {
+      const uint32_t u32[] = {0, UINT_MAX};
+      const int32_t s32[] = {INT_MIN, INT_MAX};
+      auto [u, s] = list_minmax32(u32, s32);
+      assert(std::equal(u.begin(), u.end(), std::begin(u32), std::end(u32)));
+      assert(std::equal(s.begin(), s.end(), std::begin(s32), std::end(s32)));
+    }
+
+    {
+      const uint64_t u64[] = {0, ULLONG_MAX};
+      const int64_t s64[] = {LLONG_MIN, LLONG_MAX};
+      auto [u, s] = list_minmax64(u64, s64);
+      assert(std::equal(u.begin(), u.end(), std::begin(u64), std::end(u64)));
+      assert(std::equal(s.begin(), s.end(), std::begin(s64), std::end(s64)));
+    }
+
+    {
+      const float f32[] = {-FLT_MAX, FLT_MAX, -INFINITY, INFINITY};
+      const double f64[] = {-DBL_MAX, DBL_MAX, -INFINITY, INFINITY};
+      auto [f, d] = list_minmax_float(f32, f64);
+      assert(std::equal(f.begin(), f.end(), std::begin(f32), std::end(f32)));
+      assert(std::equal(d.begin(), d.end(), std::begin(f64), std::end(f64)));
+    }
+  }
+};
+
+Guest& Guest::instance() {
+  static Lists lists;
+  return lists;
+}
+
+}
+
+namespace exports::test::lists::test {
+
+class Test : public Guest {
+public:
+  void empty_list_param(std::span<const uint8_t> a) override {
+    assert(a.empty());
+  }
+
+  void empty_string_param(std::string_view a) override {
+    assert(a.empty());
+  }
+
+  std::vector<uint8_t> empty_list_result() override {
+    return {};
+  }
+
+  std::string empty_string_result() override {
+    return "";
+  }
+
+  void list_param(std::span<const uint8_t> a) override {
+    assert((std::vector(a.begin(), a.end()) == std::vector<uint8_t>{1, 2, 3, 4}));
+  }
+
+  void list_param2(std::string_view a) override {
+    assert(a == "foo");
+  }
+
+  void list_param3(std::span<const std::string> a) override {
+    assert((std::vector(a.begin(), a.end()) == std::vector<std::string>{"foo", "bar", "baz"}));
+  }
+
+  void list_param4(std::span<const std::vector<std::string>> a) override {
+    assert(a.size() == 2);
+    assert((a[0] == std::vector<std::string>{"foo", "bar"}));
+    assert((a[1] == std::vector<std::string>{"baz"}));
+  }
+
+  std::vector<uint8_t> list_result() override {
+    return {1, 2, 3, 4, 5};
+  }
+
+  std::string list_result2() override {
+    return "hello!";
+  }
+
+  std::vector<std::string> list_result3() override {
+    return {"hello,", "world!"};
+  }
+
+  std::tuple<std::vector<uint8_t>, std::vector<int8_t>> list_minmax8(
+      std::span<const uint8_t> a,
+      std::span<const int8_t> b) override {
+    return {std::vector(a.begin(), a.end()), std::vector(b.begin(), b.end())};
+  }
+
+  std::tuple<std::vector<uint16_t>, std::vector<int16_t>> list_minmax16(
+      std::span<const uint16_t> a,
+      std::span<const int16_t> b) override {
+    return {std::vector(a.begin(), a.end()), std::vector(b.begin(), b.end())};
+  }
+
+  std::tuple<std::vector<uint32_t>, std::vector<int32_t>> list_minmax32(
+      std::span<const uint32_t> a,
+      std::span<const int32_t> b) override {
+    return {std::vector(a.begin(), a.end()), std::vector(b.begin(), b.end())};
+  }
+
+  std::tuple<std::vector<uint64_t>, std::vector<int64_t>> list_minmax64(
+      std::span<const uint64_t> a,
+      std::span<const int64_t> b) override {
+    return {std::vector(a.begin(), a.end()), std::vector(b.begin(), b.end())};
+  }
+
+  std::tuple<std::vector<float>, std::vector<double>> list_minmax_float(
+      std::span<const float> a,
+      std::span<const double> b) override {
+    return {std::vector(a.begin(), a.end()), std::vector(b.begin(), b.end())};
+  }
+
+  std::vector<uint8_t> list_roundtrip(std::span<const uint8_t> a) override {
+    return std::vector(a.begin(), a.end());
+  }
+
+  std::string string_roundtrip(std::string_view a) override {
+    return std::string(a);
+  }
+};
+
+Guest& Guest::instance() {
+  static Test test;
+  return test;
+}
+
+}
@@ -0,0 +1,188 @@
+using System;
+using System.Collections.Generic;
+using System.Diagnostics;
+using System.Linq;
+using ListsWorld.wit.imports.test.lists;
+
+namespace ListsWorld
+{
+    public class ListsWorldImpl : IListsWorld
+    {
+        public static uint AllocatedBytes()
+        {
+            return 0;
+        }
+
+        public static void TestImports()
+        {
+            TestInterop.EmptyListParam(new byte[0]);
+            TestInterop.EmptyStringParam("");
+            Debug.Assert(TestInterop.EmptyListResult().Length == 0);
+            Debug.Assert(TestInterop.EmptyStringResult().Length == 0);
+
+            TestInterop.ListParam(new byte[] { 1, 2, 3, 4 });
+            TestInterop.ListParam2("foo");
+            TestInterop.ListParam3(new List<string>() { "foo", "bar", "baz" });
+            TestInterop.ListParam4(new List<List<string>>() {
+                new List<string>() { "foo", "bar" },
+                new List<string>() { "baz" }
+            });
+
+            Debug.Assert(TestInterop.ListResult().SequenceEqual(new byte[] { 1, 2, 3, 4, 5 }));
+            Debug.Assert(TestInterop.ListResult2() == "hello!");
+            Debug.Assert(TestInterop.ListResult3().SequenceEqual(new List<string>() { "hello,", "world!" }));
+
+            Debug.Assert(TestInterop.ListRoundtrip(new byte[0]).Length == 0);
+            Debug.Assert(TestInterop.ListRoundtrip(new byte[] { 1 }).SequenceEqual(new byte[] { 1 }));
+            Debug.Assert(TestInterop.ListRoundtrip(new byte[] { 1, 2, 3, 4, 5 }).SequenceEqual(new byte[] { 1, 2, 3, 4, 5 }));
+
+            Debug.Assert(TestInterop.StringRoundtrip("x") == "x");
+            Debug.Assert(TestInterop.StringRoundtrip("") == "");
+            Debug.Assert(TestInterop.StringRoundtrip("hello") == "hello");
+            Debug.Assert(TestInterop.StringRoundtrip("hello ⚑ world") == "hello ⚑ world");
+
+            {
+                var (u, s) = TestInterop.ListMinmax8(
+                    new byte[] { byte.MinValue, byte.MaxValue },
+                    new sbyte[] { sbyte.MinValue, sbyte.MaxValue }
+                );
+                Debug.Assert(u.SequenceEqual(new byte[] { byte.MinValue, byte.MaxValue }));
+                Debug.Assert(s.SequenceEqual(new sbyte[] { sbyte.MinValue, sbyte.MaxValue }));
+            }
+
+            {
+                var (u, s) = TestInterop.ListMinmax16(
+                    new ushort[] { ushort.MinValue, ushort.MaxValue },
+                    new short[] { short.MinValue, short.MaxValue }
+                );
+                Debug.Assert(u.SequenceEqual(new ushort[] { ushort.MinValue, ushort.MaxValue }));
+                Debug.Assert(s.SequenceEqual(new short[] { short.MinValue, short.MaxValue }));
+            }
+
+            {
+                var (u, s) = TestInterop.ListMinmax32(
+                    new uint[] { uint.MinValue, uint.MaxValue },
+                    new int[] { int.MinValue, int.MaxValue }
+                );
+                Debug.Assert(u.SequenceEqual(new uint[] { uint.MinValue, uint.MaxValue }));
+                Debug.Assert(s.SequenceEqual(new int[] { int.MinValue, int.MaxValue }));
+            }
+
+            {
+                var (u, s) = TestInterop.ListMinmax64(
+                    new ulong[] { ulong.MinValue, ulong.MaxValue },
+                    new long[] { long.MinValue, long.MaxValue }
+                );
+                Debug.Assert(u.SequenceEqual(new ulong[] { ulong.MinValue, ulong.MaxValue }));
+                Debug.Assert(s.SequenceEqual(new long[] { long.MinValue, long.MaxValue }));
+            }
+
+            {
+                var (u, s) = TestInterop.ListMinmaxFloat(
+                    new float[] { float.MinValue, float.MaxValue, float.NegativeInfinity, float.PositiveInfinity },
+                    new double[] { double.MinValue, double.MaxValue, double.NegativeInfinity, double.PositiveInfinity }
+                );
+                Debug.Assert(u.SequenceEqual(new float[] { float.MinValue, float.MaxValue, float.NegativeInfinity, float.PositiveInfinity }));
+                Debug.Assert(s.SequenceEqual(new double[] { double.MinValue, double.MaxValue, double.NegativeInfinity, double.PositiveInfinity }));
+            }
+        }
+    }
+}
+
+namespace ListsWorld.wit.exports.test.lists
+{
+    public class TestImpl : ITest
+    {
+        public static void EmptyListParam(byte[] a)
+        {
+            Debug.Assert(a.Length == 0);
+        }
+
+        public static void EmptyStringParam(string a)
+        {
+            Debug.Assert(a.Length == 0);
+        }
+
+        public static byte[] EmptyListResult()
+        {
+            return new byte[0];
+        }
+
+        public static string EmptyStringResult()
+        {
+            return "";
+        }
+
+        public static void ListParam(byte[] a)
+        {
+            Debug.Assert(a.SequenceEqual(new byte[] { 1, 2, 3, 4 }));
+        }
+
+        public static void ListParam2(string a)
+        {
+            Debug.Assert(a == "foo");
+        }
+
+        public static void ListParam3(List<string> a)
+        {
+            Debug.Assert(a.SequenceEqual(new List<string>() { "foo", "bar", "baz" }));
+        }
+
+        public static void ListParam4(List<List<string>> a)
+        {
+            Debug.Assert(a.Count == 2);
+            Debug.Assert(a[0].SequenceEqual(new List<string>() { "foo", "bar" }));
+            Debug.Assert(a[1].SequenceEqual(new List<string>() { "baz" }));
+        }
+
+        public static byte[] ListResult()
+        {
+            return new byte[] { 1, 2, 3, 4, 5 };
+        }
+
+        public static string ListResult2()
+        {
+            return "hello!";
+        }
+
+        public static List<string> ListResult3()
+        {
+            return new List<string>() { "hello,", "world!" };
+        }
+
+        public static byte[] ListRoundtrip(byte[] a)
+        {
+            return a;
+        }
+
+        public static string StringRoundtrip(string a)
+        {
+            return a;
+        }
+
+        public static (byte[], sbyte[]) ListMinmax8(byte[] a, sbyte[] b)
+        {
+            return (a, b);
+        }
+
+        public static (ushort[], short[]) ListMinmax16(ushort[] a, short[] b)
+        {
+            return (a, b);
+        }
+
+        public static (uint[], int[]) ListMinmax32(uint[] a, int[] b)
+        {
+            return (a, b);
+        }
+
+        public static (ulong[], long[]) ListMinmax64(ulong[] a, long[] b)
+        {
+            return (a, b);
+        }
+
+        public static (float[], double[]) ListMinmaxFloat(float[] a, double[] b)
+        {
+            return (a, b);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+package main
+
+import (
+	. "wit_resource_aggregates_go/gen"
+)
+
+func init() {
+	n := &Test{}
+	SetExportsTestResourceAggregatesTest(n)
+}
+
+type Test struct{}
+
+type MyThing struct {
+	value TestResourceAggregatesTestThing
+}
+
+func inner(t ExportsTestResourceAggregatesTestThing) TestResourceAggregatesTestThing {
+	return t.(*MyThing).value
+}
+
+func (e Test) ConstructorThing(v uint32) ExportsTestResourceAggregatesTestThing {
+	return &MyThing{value: NewThing(v + 1)}
+}
+
+func (e Test) Foo(r1 ExportsTestResourceAggregatesTestR1, r2 ExportsTestResourceAggregatesTestR2, r3 ExportsTestResourceAggregatesTestR3, t1 ExportsTestResourceAggregatesTestT1, t2 ExportsTestResourceAggregatesTestT2, v1 ExportsTestResourceAggregatesTestV1, v2 ExportsTestResourceAggregatesTestV2, l1 []ExportsTestResourceAggregatesTestThing, l2 []ExportsTestResourceAggregatesTestThing, o1 Option[ExportsTestResourceAggregatesTestThing], o2 Option[ExportsTestResourceAggregatesTestThing], result1 Result[ExportsTestResourceAggregatesTestThing, struct{}], result2 Result[ExportsTestResourceAggregatesTestThing, struct{}]) uint32 {
+	ir1 := TestResourceAggregatesTestR1{Thing: inner(r1.Thing)}
+	ir2 := TestResourceAggregatesTestR2{Thing: inner(r2.Thing)}
+	ir3 := TestResourceAggregatesTestR3{Thing1: inner(r3.Thing1), Thing2: inner(r3.Thing2)}
+	it1 := TestResourceAggregatesTestT1{
+		F0: inner(t1.F0),
+		F1: TestResourceAggregatesTestR1{Thing: inner(t1.F1.Thing)},
+	}
+	it2 := TestResourceAggregatesTestT2{F0: inner(t2.F0)}
+	iv1 := TestResourceAggregatesTestV1Thing(inner(v1.GetThing()))
+	iv2 := TestResourceAggregatesTestV2Thing(inner(v2.GetThing()))
+
+	il1 := make([]TestResourceAggregatesTestThing, len(l1))
+	for i, v := range l1 {
+		il1[i] = inner(v)
+	}
+	il2 := make([]TestResourceAggregatesTestThing, len(l2))
+	for i, v := range l2 {
+		il2[i] = inner(v)
+	}
+
+	io1 := None[TestResourceAggregatesTestThing]()
+	if o1.IsSome() {
+		io1 = Some(inner(o1.Unwrap()))
+	}
+	io2 := None[TestResourceAggregatesTestThing]()
+	if o2.IsSome() {
+		io2 = Some(inner(o2.Unwrap()))
+	}
+
+	iresult1 := Err[TestResourceAggregatesTestThing](struct{}{})
+	if result1.IsOk() {
+		iresult1 = Ok[TestResourceAggregatesTestThing, struct{}](inner(result1.Unwrap()))
+	}
+	iresult2 := Err[TestResourceAggregatesTestThing](struct{}{})
+	if result2.IsOk() {
+		iresult2 = Ok[TestResourceAggregatesTestThing, struct{}](inner(result2.Unwrap()))
+	}
+
+	return TestResourceAggregatesTestFoo(ir1, ir2, ir3, it1, it2, iv1, iv2, il1, il2, io1, io2, iresult1, iresult2) + 4
+}
+
+func main() {}
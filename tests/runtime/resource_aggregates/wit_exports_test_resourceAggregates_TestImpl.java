@@ -0,0 +1,94 @@
+package wit.exports.test.resourceAggregates;
+
+import java.util.ArrayList;
+
+import wit.worlds.ResourceAggregates.Result;
+import wit.worlds.ResourceAggregates.Tuple0;
+import wit.worlds.ResourceAggregates.Tuple1;
+import wit.worlds.ResourceAggregates.Tuple2;
+
+public class TestImpl {
+    public static class Thing extends Test.Thing {
+        private final wit.imports.test.resourceAggregates.Test.Thing value;
+
+        public Thing(int v) {
+            this.value = new wit.imports.test.resourceAggregates.Test.Thing(v + 1);
+        }
+
+        @Override
+        public void close() {
+            super.close();
+            this.value.close();
+        }
+    }
+
+    private static wit.imports.test.resourceAggregates.Test.Thing value(Test.Thing thing) {
+        return ((Thing) thing).value;
+    }
+
+    public static int foo(
+        Test.R1 r1,
+        Test.R2 r2,
+        Test.R3 r3,
+        Tuple2<Test.Thing, Test.R1> t1,
+        Tuple1<Test.Thing> t2,
+        Test.V1 v1,
+        Test.V2 v2,
+        ArrayList<Test.Thing> l1,
+        ArrayList<Test.Thing> l2,
+        Test.Thing o1,
+        Test.Thing o2,
+        Result<Test.Thing, Tuple0> result1,
+        Result<Test.Thing, Tuple0> result2
+    ) {
+        ArrayList<wit.imports.test.resourceAggregates.Test.Thing> il1 = new ArrayList<>();
+        for (Test.Thing thing : l1) {
+            il1.add(value(thing));
+        }
+        ArrayList<wit.imports.test.resourceAggregates.Test.Thing> il2 = new ArrayList<>();
+        for (Test.Thing thing : l2) {
+            il2.add(value(thing));
+        }
+        Result<wit.imports.test.resourceAggregates.Test.Thing, Tuple0> ir1 = result1.tag == Result.OK
+            ? Result.ok(value(result1.getOk()))
+            : Result.err(Tuple0.INSTANCE);
+        Result<wit.imports.test.resourceAggregates.Test.Thing, Tuple0> ir2 = result2.tag == Result.OK
+            ? Result.ok(value(result2.getOk()))
+            : Result.err(Tuple0.INSTANCE);
+
+        int result = wit.imports.test.resourceAggregates.Test.foo(
+            new wit.imports.test.resourceAggregates.Test.R1(value(r1.thing)),
+            new wit.imports.test.resourceAggregates.Test.R2(value(r2.thing)),
+            new wit.imports.test.resourceAggregates.Test.R3(value(r3.thing1), value(r3.thing2)),
+            new Tuple2<>(value(t1.f0), new wit.imports.test.resourceAggregates.Test.R1(value(t1.f1.thing))),
+            new Tuple1<>(value(t2.f0)),
+            wit.imports.test.resourceAggregates.Test.V1.thing(value(v1.getThing())),
+            wit.imports.test.resourceAggregates.Test.V2.thing(value(v2.getThing())),
+            il1,
+            il2,
+            o1 == null ? null : value(o1),
+            o2 == null ? null : value(o2),
+            ir1,
+            ir2
+        ) + 4;
+
+        // The owned instances were handed to this function, so release
+        // them now that their imported values have moved to the host.
+        r1.thing.close();
+        r3.thing2.close();
+        t1.f0.close();
+        t1.f1.thing.close();
+        v1.getThing().close();
+        for (Test.Thing thing : l1) {
+            thing.close();
+        }
+        if (o1 != null) {
+            o1.close();
+        }
+        if (result1.tag == Result.OK) {
+            result1.getOk().close();
+        }
+
+        return result;
+    }
+}
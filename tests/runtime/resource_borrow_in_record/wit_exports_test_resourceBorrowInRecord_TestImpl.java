@@ -0,0 +1,43 @@
+package wit.exports.test.resourceBorrowInRecord;
+
+import java.util.ArrayList;
+
+public class TestImpl {
+    public static class Thing extends Test.Thing {
+        private final wit.imports.test.resourceBorrowInRecord.Test.Thing thing;
+
+        public Thing(String s) {
+            this.thing = new wit.imports.test.resourceBorrowInRecord.Test.Thing(s + " Thing");
+        }
+
+        public Thing(wit.imports.test.resourceBorrowInRecord.Test.Thing thing) {
+            this.thing = thing;
+        }
+
+        @Override
+        public String get() {
+            return this.thing.get() + " Thing.get";
+        }
+
+        @Override
+        public void close() {
+            super.close();
+            this.thing.close();
+        }
+    }
+
+    public static ArrayList<Test.Thing> test(ArrayList<Test.Foo> a) {
+        ArrayList<wit.imports.test.resourceBorrowInRecord.Test.Foo> foos = new ArrayList<>();
+        for (Test.Foo foo : a) {
+            foos.add(new wit.imports.test.resourceBorrowInRecord.Test.Foo(((Thing) foo.thing).thing));
+        }
+
+        ArrayList<Test.Thing> result = new ArrayList<>();
+        for (wit.imports.test.resourceBorrowInRecord.Test.Thing thing
+                 : wit.imports.test.resourceBorrowInRecord.Test.test(foos))
+        {
+            result.add(new Thing(thing));
+        }
+        return result;
+    }
+}
@@ -0,0 +1,115 @@
+#include <cassert>
+#include <resources.hpp>
+
+namespace exports::exports {
+
+static uint32_t num_dropped_zs = 0;
+
+class ComponentX : public X {
+public:
+  explicit ComponentX(int32_t a) : val(a) {}
+
+  int32_t get_a() override {
+    return val;
+  }
+
+  void set_a(int32_t a) override {
+    val = a;
+  }
+
+private:
+  int32_t val;
+};
+
+class ComponentZ : public Z {
+public:
+  explicit ComponentZ(int32_t a) : val(a) {}
+
+  ~ComponentZ() override {
+    num_dropped_zs += 1;
+  }
+
+  int32_t get_a() override {
+    return val;
+  }
+
+private:
+  int32_t val;
+};
+
+class ComponentKebabCase : public KebabCase {
+public:
+  explicit ComponentKebabCase(uint32_t a) : val(a) {}
+
+  uint32_t get_a() override {
+    return val;
+  }
+
+private:
+  uint32_t val;
+};
+
+class Exports : public Guest {
+public:
+  std::unique_ptr<X> make_x(int32_t a) override {
+    return std::make_unique<ComponentX>(a);
+  }
+
+  std::unique_ptr<X> x_add(std::unique_ptr<X> x, int32_t a) override {
+    x->set_a(x->get_a() + a);
+    return x;
+  }
+
+  std::unique_ptr<Z> make_z(int32_t a) override {
+    return std::make_unique<ComponentZ>(a);
+  }
+
+  uint32_t z_num_dropped() override {
+    return num_dropped_zs + 1;
+  }
+
+  std::unique_ptr<Z> add(Z& a, Z& b) override {
+    return std::make_unique<ComponentZ>(a.get_a() + b.get_a());
+  }
+
+  std::unique_ptr<KebabCase> make_kebab_case(uint32_t a) override {
+    return std::make_unique<ComponentKebabCase>(a);
+  }
+
+  uint32_t kebab_case_take_owned(std::unique_ptr<KebabCase> k) override {
+    return k->get_a();
+  }
+
+  std::expected<void, std::string> test_imports() override {
+    using ::imports::Y;
+
+    Y y(10);
+    assert(y.get_a() == 10);
+    y.set_a(20);
+    assert(y.get_a() == 20);
+    Y y2 = Y::add(std::move(y), 20);
+    assert(y2.get_a() == 40);
+
+    // test multiple instances
+    Y y3(1);
+    Y y4(2);
+    assert(y3.get_a() == 1);
+    assert(y4.get_a() == 2);
+    y3.set_a(10);
+    y4.set_a(20);
+    assert(y3.get_a() == 10);
+    assert(y4.get_a() == 20);
+    Y y5 = Y::add(std::move(y3), 20);
+    Y y6 = Y::add(std::move(y4), 30);
+    assert(y5.get_a() == 30);
+    assert(y6.get_a() == 50);
+    return {};
+  }
+};
+
+Guest& Guest::instance() {
+  static Exports exports;
+  return exports;
+}
+
+}
@@ -0,0 +1,142 @@
+using System.Diagnostics;
+using ResourcesWorld.wit.imports;
+
+namespace ResourcesWorld.wit.exports
+{
+    public class ExportsImpl : IExports
+    {
+        public class X : IExports.X
+        {
+            private int a;
+
+            public X(int a)
+            {
+                this.a = a;
+            }
+
+            public override int GetA()
+            {
+                return a;
+            }
+
+            public override void SetA(int a)
+            {
+                this.a = a;
+            }
+        }
+
+        private static uint numDroppedZs = 0;
+
+        public class Z : IExports.Z
+        {
+            private readonly int a;
+
+            public Z(int a)
+            {
+                this.a = a;
+            }
+
+            public override int GetA()
+            {
+                return a;
+            }
+
+            protected override void Dispose(bool disposing)
+            {
+                // Instances are disposed without a handle once their
+                // destructor has run.
+                if (disposing && Handle == 0)
+                {
+                    numDroppedZs += 1;
+                }
+                base.Dispose(disposing);
+            }
+        }
+
+        public class KebabCase : IExports.KebabCase
+        {
+            private readonly uint a;
+
+            public KebabCase(uint a)
+            {
+                this.a = a;
+            }
+
+            public override uint GetA()
+            {
+                return a;
+            }
+        }
+
+        public static IExports.X ConstructorX(int a)
+        {
+            return new X(a);
+        }
+
+        public static IExports.X StaticXAdd(IExports.X x, int a)
+        {
+            x.SetA(x.GetA() + a);
+            return x;
+        }
+
+        public static IExports.Z ConstructorZ(int a)
+        {
+            return new Z(a);
+        }
+
+        public static uint StaticZNumDropped()
+        {
+            return numDroppedZs + 1;
+        }
+
+        public static IExports.Z Add(IExports.Z a, IExports.Z b)
+        {
+            return new Z(a.GetA() + b.GetA());
+        }
+
+        public static IExports.KebabCase ConstructorKebabCase(uint a)
+        {
+            return new KebabCase(a);
+        }
+
+        public static uint StaticKebabCaseTakeOwned(IExports.KebabCase k)
+        {
+            var a = k.GetA();
+            k.Dispose();
+            return a;
+        }
+
+        public static Result<None, string> TestImports()
+        {
+            {
+                var y = new IImports.Y(10);
+                Debug.Assert(y.GetA() == 10);
+                y.SetA(20);
+                Debug.Assert(y.GetA() == 20);
+                var y2 = IImports.Y.Add(y, 20);
+                Debug.Assert(y2.GetA() == 40);
+                y2.Dispose();
+            }
+
+            {
+                // test multiple instances
+                var y1 = new IImports.Y(1);
+                var y2 = new IImports.Y(2);
+                Debug.Assert(y1.GetA() == 1);
+                Debug.Assert(y2.GetA() == 2);
+                y1.SetA(10);
+                y2.SetA(20);
+                Debug.Assert(y1.GetA() == 10);
+                Debug.Assert(y2.GetA() == 20);
+                var y3 = IImports.Y.Add(y1, 20);
+                var y4 = IImports.Y.Add(y2, 30);
+                Debug.Assert(y3.GetA() == 30);
+                Debug.Assert(y4.GetA() == 50);
+                y3.Dispose();
+                y4.Dispose();
+            }
+
+            return Result<None, string>.ok(new None());
+        }
+    }
+}
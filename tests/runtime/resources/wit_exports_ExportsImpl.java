@@ -0,0 +1,116 @@
+package wit.exports;
+
+import wit.imports.Imports;
+import wit.worlds.Resources.Result;
+import wit.worlds.Resources.Tuple0;
+
+public class ExportsImpl {
+    public static class X extends Exports.X {
+        private int a;
+
+        public X(int a) {
+            this.a = a;
+        }
+
+        @Override
+        public int getA() {
+            return this.a;
+        }
+
+        @Override
+        public void setA(int a) {
+            this.a = a;
+        }
+
+        public static Exports.X add(Exports.X x, int a) {
+            x.setA(x.getA() + a);
+            return x;
+        }
+    }
+
+    private static int numDroppedZs = 0;
+
+    public static class Z extends Exports.Z {
+        private final int a;
+
+        public Z(int a) {
+            this.a = a;
+        }
+
+        @Override
+        public int getA() {
+            return this.a;
+        }
+
+        @Override
+        public void close() {
+            if (this.handle == 0) {
+                numDroppedZs += 1;
+            }
+            super.close();
+        }
+
+        public static int numDropped() {
+            return numDroppedZs + 1;
+        }
+    }
+
+    public static class KebabCase extends Exports.KebabCase {
+        private final int a;
+
+        public KebabCase(int a) {
+            this.a = a;
+        }
+
+        @Override
+        public int getA() {
+            return this.a;
+        }
+
+        public static int takeOwned(Exports.KebabCase k) {
+            return k.getA();
+        }
+    }
+
+    public static Exports.Z add(Exports.Z a, Exports.Z b) {
+        return new Z(a.getA() + b.getA());
+    }
+
+    public static Result<Tuple0, String> testImports() {
+        {
+            Imports.Y y = new Imports.Y(10);
+            expect(y.getA() == 10);
+            y.setA(20);
+            expect(y.getA() == 20);
+            Imports.Y y2 = Imports.Y.add(y, 20);
+            expect(y2.getA() == 40);
+            y2.close();
+        }
+
+        {
+            // test multiple instances
+            Imports.Y y1 = new Imports.Y(1);
+            Imports.Y y2 = new Imports.Y(2);
+            expect(y1.getA() == 1);
+            expect(y2.getA() == 2);
+            y1.setA(10);
+            y2.setA(20);
+            expect(y1.getA() == 10);
+            expect(y2.getA() == 20);
+            Imports.Y y3 = Imports.Y.add(y1, 20);
+            Imports.Y y4 = Imports.Y.add(y2, 30);
+            expect(y3.getA() == 30);
+            expect(y4.getA() == 50);
+            y3.close();
+            y4.close();
+        }
+
+        return Result.ok(Tuple0.INSTANCE);
+    }
+
+    private static void expect(boolean v) {
+        if (!v) {
+            throw new AssertionError();
+        }
+    }
+}
@@ -0,0 +1,28 @@
+#include <cassert>
+#include <strings.hpp>
+
+namespace exports::strings {
+
+class Strings : public Guest {
+public:
+  void test_imports() override {
+    ::test::strings::imports::take_basic("latin utf16");
+    assert(::test::strings::imports::return_unicode() == "🚀🚀🚀 𠈄𓀀");
+  }
+
+  std::string return_empty() override {
+    return "";
+  }
+
+  std::string roundtrip(std::string_view s) override {
+    assert(!s.empty());
+    return std::string(s);
+  }
+};
+
+Guest& Guest::instance() {
+  static Strings strings;
+  return strings;
+}
+
+}
@@ -0,0 +1,150 @@
+#include <cassert>
+#include <variants.hpp>
+
+namespace exports::variants {
+
+class Variants : public Guest {
+public:
+  void test_imports() override {
+    using namespace ::test::variants::test;
+
+    assert(roundtrip_option(1.0f) == std::optional<uint8_t>(1));
+    assert(roundtrip_option(std::nullopt) == std::nullopt);
+    assert(roundtrip_option(2.0f) == std::optional<uint8_t>(2));
+    assert((roundtrip_result(2u) == std::expected<double, uint8_t>(2.0)));
+    assert((roundtrip_result(4u) == std::expected<double, uint8_t>(4.0)));
+    assert((roundtrip_result(std::unexpected(5.3f)) == std::unexpected<uint8_t>(5)));
+
+    assert(roundtrip_enum(E1::A) == E1::A);
+    assert(roundtrip_enum(E1::B) == E1::B);
+
+    assert(invert_bool(true) == false);
+    assert(invert_bool(false) == true);
+
+    {
+      auto [a1, a2, a3, a4, a5, a6] = variant_casts({
+          C1{C1::A{1}},
+          C2{C2::A{2}},
+          C3{C3::A{3}},
+          C4{C4::A{4}},
+          C5{C5::A{5}},
+          C6{C6::A{6.0f}},
+      });
+      assert(std::get<C1::A>(a1.variant).value == 1);
+      assert(std::get<C2::A>(a2.variant).value == 2);
+      assert(std::get<C3::A>(a3.variant).value == 3);
+      assert(std::get<C4::A>(a4.variant).value == 4);
+      assert(std::get<C5::A>(a5.variant).value == 5);
+      assert(std::get<C6::A>(a6.variant).value == 6.0f);
+    }
+
+    {
+      auto [a1, a2, a3, a4, a5, a6] = variant_casts({
+          C1{C1::B{1}},
+          C2{C2::B{2.0f}},
+          C3{C3::B{3.0}},
+          C4{C4::B{4.0f}},
+          C5{C5::B{5.0}},
+          C6{C6::B{6.0}},
+      });
+      assert(std::get<C1::B>(a1.variant).value == 1);
+      assert(std::get<C2::B>(a2.variant).value == 2.0f);
+      assert(std::get<C3::B>(a3.variant).value == 3.0);
+      assert(std::get<C4::B>(a4.variant).value == 4.0f);
+      assert(std::get<C5::B>(a5.variant).value == 5.0);
+      assert(std::get<C6::B>(a6.variant).value == 6.0);
+    }
+
+    {
+      auto [a1, a2, a3, a4] = variant_zeros({
+          Z1{Z1::A{1}},
+          Z2{Z2::A{2}},
+          Z3{Z3::A{3.0f}},
+          Z4{Z4::A{4.0}},
+      });
+      assert(std::get<Z1::A>(a1.variant).value == 1);
+      assert(std::get<Z2::A>(a2.variant).value == 2);
+      assert(std::get<Z3::A>(a3.variant).value == 3.0f);
+      assert(std::get<Z4::A>(a4.variant).value == 4.0);
+    }
+
+    {
+      auto [a1, a2, a3, a4] = variant_zeros({
+          Z1{Z1::B{}},
+          Z2{Z2::B{}},
+          Z3{Z3::B{}},
+          Z4{Z4::B{}},
+      });
+      assert(std::holds_alternative<Z1::B>(a1.variant));
+      assert(std::holds_alternative<Z2::B>(a2.variant));
+      assert(std::holds_alternative<Z3::B>(a3.variant));
+      assert(std::holds_alternative<Z4::B>(a4.variant));
+    }
+
+    variant_typedefs(std::nullopt, false, std::unexpected(std::monostate{}));
+
+    auto [a, b, c] = variant_enums(true, {}, MyErrno::Success);
+    assert(a == false);
+    assert(!b.has_value());
+    assert(c == MyErrno::A);
+  }
+};
+
+Guest& Guest::instance() {
+  static Variants variants;
+  return variants;
+}
+
+}
+
+namespace exports::test::variants::test {
+
+class Test : public Guest {
+public:
+  std::optional<uint8_t> roundtrip_option(const std::optional<float>& a) override {
+    if (a) {
+      return static_cast<uint8_t>(*a);
+    }
+    return std::nullopt;
+  }
+
+  std::expected<double, uint8_t> roundtrip_result(const std::expected<uint32_t, float>& a) override {
+    if (a) {
+      return static_cast<double>(*a);
+    }
+    return std::unexpected(static_cast<uint8_t>(a.error()));
+  }
+
+  E1 roundtrip_enum(E1 a) override {
+    return a;
+  }
+
+  bool invert_bool(bool a) override {
+    return !a;
+  }
+
+  Casts variant_casts(const Casts& a) override {
+    return a;
+  }
+
+  Zeros variant_zeros(const Zeros& a) override {
+    return a;
+  }
+
+  void variant_typedefs(const OptionTypedef& a, bool b, const ResultTypedef& c) override {
+  }
+
+  std::tuple<bool, std::expected<void, std::monostate>, MyErrno> variant_enums(
+      bool a,
+      const std::expected<void, std::monostate>& b,
+      MyErrno c) override {
+    return {a, b, c};
+  }
+};
+
+Guest& Guest::instance() {
+  static Test test;
+  return test;
+}
+
+}
@@ -0,0 +1,157 @@
+using System.Diagnostics;
+using VariantsWorld.wit.imports.test.variants;
+
+namespace VariantsWorld
+{
+    public class VariantsWorldImpl : IVariantsWorld
+    {
+        public static void TestImports()
+        {
+            Debug.Assert(TestInterop.RoundtripOption(new ITest.Option_float(1.0f)).Value == 1);
+            Debug.Assert(!TestInterop.RoundtripOption(ITest.Option_float.None).HasValue);
+            Debug.Assert(TestInterop.RoundtripOption(new ITest.Option_float(2.0f)).Value == 2);
+
+            {
+                var result = TestInterop.RoundtripResult(Result<uint, float>.ok(2));
+                Debug.Assert(result.IsOk && result.AsOk == 2.0);
+            }
+
+            {
+                var result = TestInterop.RoundtripResult(Result<uint, float>.ok(4));
+                Debug.Assert(result.IsOk && result.AsOk == 4.0);
+            }
+
+            {
+                var result = TestInterop.RoundtripResult(Result<uint, float>.err(5.3f));
+                Debug.Assert(result.IsErr && result.AsErr == 5);
+            }
+
+            Debug.Assert(TestInterop.RoundtripEnum(ITest.E1.A) == ITest.E1.A);
+            Debug.Assert(TestInterop.RoundtripEnum(ITest.E1.B) == ITest.E1.B);
+
+            Debug.Assert(TestInterop.InvertBool(true) == false);
+            Debug.Assert(TestInterop.InvertBool(false) == true);
+
+            {
+                var (a1, a2, a3, a4, a5, a6) = TestInterop.VariantCasts((
+                    ITest.C1.a(1),
+                    ITest.C2.a(2),
+                    ITest.C3.a(3),
+                    ITest.C4.a(4),
+                    ITest.C5.a(5),
+                    ITest.C6.a(6.0f)
+                ));
+                Debug.Assert(a1.tag == ITest.C1.A && a1.getA() == 1);
+                Debug.Assert(a2.tag == ITest.C2.A && a2.getA() == 2);
+                Debug.Assert(a3.tag == ITest.C3.A && a3.getA() == 3);
+                Debug.Assert(a4.tag == ITest.C4.A && a4.getA() == 4);
+                Debug.Assert(a5.tag == ITest.C5.A && a5.getA() == 5);
+                Debug.Assert(a6.tag == ITest.C6.A && a6.getA() == 6.0f);
+            }
+
+            {
+                var (a1, a2, a3, a4, a5, a6) = TestInterop.VariantCasts((
+                    ITest.C1.b(1),
+                    ITest.C2.b(2.0f),
+                    ITest.C3.b(3.0),
+                    ITest.C4.b(4.0f),
+                    ITest.C5.b(5.0),
+                    ITest.C6.b(6.0)
+                ));
+                Debug.Assert(a1.tag == ITest.C1.B && a1.getB() == 1);
+                Debug.Assert(a2.tag == ITest.C2.B && a2.getB() == 2.0f);
+                Debug.Assert(a3.tag == ITest.C3.B && a3.getB() == 3.0);
+                Debug.Assert(a4.tag == ITest.C4.B && a4.getB() == 4.0f);
+                Debug.Assert(a5.tag == ITest.C5.B && a5.getB() == 5.0);
+                Debug.Assert(a6.tag == ITest.C6.B && a6.getB() == 6.0);
+            }
+
+            {
+                var (a1, a2, a3, a4) = TestInterop.VariantZeros((
+                    ITest.Z1.a(1),
+                    ITest.Z2.a(2),
+                    ITest.Z3.a(3.0f),
+                    ITest.Z4.a(4.0)
+                ));
+                Debug.Assert(a1.tag == ITest.Z1.A && a1.getA() == 1);
+                Debug.Assert(a2.tag == ITest.Z2.A && a2.getA() == 2);
+                Debug.Assert(a3.tag == ITest.Z3.A && a3.getA() == 3.0f);
+                Debug.Assert(a4.tag == ITest.Z4.A && a4.getA() == 4.0);
+            }
+
+            {
+                var (a1, a2, a3, a4) = TestInterop.VariantZeros((
+                    ITest.Z1.b(),
+                    ITest.Z2.b(),
+                    ITest.Z3.b(),
+                    ITest.Z4.b()
+                ));
+                Debug.Assert(a1.tag == ITest.Z1.B);
+                Debug.Assert(a2.tag == ITest.Z2.B);
+                Debug.Assert(a3.tag == ITest.Z3.B);
+                Debug.Assert(a4.tag == ITest.Z4.B);
+            }
+
+            TestInterop.VariantTypedefs(ITest.OptionTypedef.None, false, Result<uint, None>.err(new None()));
+
+            {
+                var (a, b, c) = TestInterop.VariantEnums(true, Result<None, None>.ok(new None()), ITest.MyErrno.SUCCESS);
+                Debug.Assert(a == false);
+                Debug.Assert(b.IsErr);
+                Debug.Assert(c == ITest.MyErrno.A);
+            }
+        }
+    }
+}
+
+namespace VariantsWorld.wit.exports.test.variants
+{
+    public class TestImpl : ITest
+    {
+        public static ITest.Option_byte RoundtripOption(ITest.Option_float a)
+        {
+            return a.HasValue ? new ITest.Option_byte((byte)a.Value) : ITest.Option_byte.None;
+        }
+
+        public static Result<double, byte> RoundtripResult(Result<uint, float> a)
+        {
+            return a.IsOk
+                ? Result<double, byte>.ok((double)a.AsOk)
+                : Result<double, byte>.err((byte)a.AsErr);
+        }
+
+        public static ITest.E1 RoundtripEnum(ITest.E1 a)
+        {
+            return a;
+        }
+
+        public static bool InvertBool(bool a)
+        {
+            return !a;
+        }
+
+        public static (ITest.C1, ITest.C2, ITest.C3, ITest.C4, ITest.C5, ITest.C6) VariantCasts(
+            (ITest.C1, ITest.C2, ITest.C3, ITest.C4, ITest.C5, ITest.C6) a)
+        {
+            return a;
+        }
+
+        public static (ITest.Z1, ITest.Z2, ITest.Z3, ITest.Z4) VariantZeros(
+            (ITest.Z1, ITest.Z2, ITest.Z3, ITest.Z4) a)
+        {
+            return a;
+        }
+
+        public static void VariantTypedefs(ITest.OptionTypedef a, bool b, Result<uint, None> c)
+        {
+        }
+
+        public static (bool, Result<None, None>, ITest.MyErrno) VariantEnums(
+            bool a,
+            Result<None, None> b,
+            ITest.MyErrno c)
+        {
+            return (a, b, c);
+        }
+    }
+}